@@ -0,0 +1,122 @@
+//! Text direction, for laying out right-to-left languages.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// The value of an HTML `dir` attribute.
+    pub fn attr(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+}
+
+/// The direction of the first letter with a strong direction, which is how
+/// the Unicode bidi algorithm picks a paragraph's direction. Links are
+/// skipped, so "see https://example.com" in Arabic still reads right to
+/// left. `None` when there is no such letter, e.g. for digits or emoji.
+pub fn first_strong(text: &str) -> Option<Direction> {
+    text.split_whitespace()
+        .filter(|word| !is_link(word))
+        .flat_map(str::chars)
+        .find_map(char_direction)
+}
+
+fn char_direction(c: char) -> Option<Direction> {
+    if is_rtl(c) {
+        Some(Direction::Rtl)
+    } else if c.is_alphabetic() {
+        Some(Direction::Ltr)
+    } else {
+        None
+    }
+}
+
+/// Letters of the scripts written right to left: Hebrew, Arabic, Syriac,
+/// Thaana, N'Ko and their presentation forms.
+fn is_rtl(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFF}'
+        | '\u{10800}'..='\u{10FFF}'
+        | '\u{1E800}'..='\u{1EFFF}'
+    ) && c.is_alphabetic()
+}
+
+fn is_link(word: &str) -> bool {
+    ["http://", "https://", "www."]
+        .iter()
+        .any(|prefix| word.starts_with(prefix))
+}
+
+/// Splits `text` into runs, marking the links. Links are always left to
+/// right; isolating them keeps their slashes and dots from being reordered
+/// into the surrounding right-to-left text.
+pub fn split_links(text: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut words = text
+        .split_whitespace()
+        .map(|word| (word.as_ptr() as usize - text.as_ptr() as usize, word));
+    for (offset, word) in &mut words {
+        if !is_link(word) {
+            continue;
+        }
+        if offset > start {
+            runs.push((&text[start..offset], false));
+        }
+        runs.push((word, true));
+        start = offset + word.len();
+    }
+    if start < text.len() {
+        runs.push((&text[start..], false));
+    }
+    runs
+}
+
+/// The first link in `text`, if it has any.
+pub fn first_link(text: &str) -> Option<&str> {
+    text.split_whitespace().find(|word| is_link(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_first_strong_letter() {
+        assert_eq!(first_strong("hello"), Some(Direction::Ltr));
+        assert_eq!(first_strong("שלום world"), Some(Direction::Rtl));
+        assert_eq!(first_strong("123 مرحبا"), Some(Direction::Rtl));
+        assert_eq!(first_strong("42 🎉"), None);
+    }
+
+    #[test]
+    fn skips_links() {
+        assert_eq!(
+            first_strong("https://example.com مرحبا"),
+            Some(Direction::Rtl)
+        );
+        assert_eq!(first_strong("www.example.com"), None);
+    }
+
+    #[test]
+    fn isolates_links() {
+        assert_eq!(
+            split_links("انظر https://example.com/a?b=1 الآن"),
+            vec![
+                ("انظر ", false),
+                ("https://example.com/a?b=1", true),
+                (" الآن", false),
+            ]
+        );
+        assert_eq!(split_links("no links"), vec![("no links", false)]);
+        assert_eq!(split_links("www.a.b"), vec![("www.a.b", true)]);
+    }
+}
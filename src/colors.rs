@@ -0,0 +1,71 @@
+//! Per-user colors. Everything is derived from the name alone, so a user
+//! looks the same to every client and across reloads.
+
+/// Which background a color has to stand out against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    Light,
+    Dark,
+}
+
+/// A hue in degrees from the name's FNV-1a hash.
+pub fn name_hue(name: &str) -> u32 {
+    let hash = name.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    hash % 360
+}
+
+/// The color a user's name is written in. Light backgrounds get dark,
+/// saturated shades; dark backgrounds get pale ones.
+pub fn name_color(name: &str, palette: Palette) -> String {
+    let (saturation, lightness) = match palette {
+        Palette::Light => (65, 35),
+        Palette::Dark => (70, 72),
+    };
+    format!("hsl({}, {}%, {}%)", name_hue(name), saturation, lightness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 24] = [
+        "alice", "bob", "carol", "dave", "eve", "frank", "grace", "heidi", "ivan", "judy",
+        "mallory", "niaj", "olivia", "peggy", "rupert", "sybil", "trent", "victor", "walter",
+        "xavier", "yvonne", "zoe", "mohammed", "wei",
+    ];
+
+    #[test]
+    fn same_name_same_color() {
+        for name in NAMES {
+            assert_eq!(name_hue(name), name_hue(name));
+            assert_eq!(
+                name_color(name, Palette::Light),
+                name_color(name, Palette::Light)
+            );
+        }
+        assert_ne!(
+            name_color("alice", Palette::Light),
+            name_color("alice", Palette::Dark)
+        );
+    }
+
+    #[test]
+    fn hues_spread_around_the_wheel() {
+        let mut buckets = [0; 6];
+        for name in NAMES {
+            buckets[(name_hue(name) / 60) as usize] += 1;
+        }
+        assert!(
+            buckets.iter().filter(|&&n| n > 0).count() >= 5,
+            "{:?}",
+            buckets
+        );
+        assert!(
+            buckets.iter().all(|&n| n <= NAMES.len() / 3),
+            "{:?}",
+            buckets
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::components::chat::MessageData;
+use crate::i18n::Locale;
+use crate::services::export::format_time;
+
+#[derive(Properties, PartialEq)]
+pub struct AnnouncementCardProps {
+    pub message: Rc<MessageData>,
+    /// Hides it from the pinned area; only offered while it's pinned.
+    #[prop_or_default]
+    pub on_dismiss: Option<Callback<()>>,
+}
+
+/// An announcement, set apart from the chat bubbles: full width, centered
+/// and edged in the accent color.
+#[function_component(AnnouncementCard)]
+pub fn announcement_card(props: &AnnouncementCardProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let m = &props.message;
+    let title = m.announcement.as_deref().unwrap_or_default();
+    // Ones from the server itself come from no one in particular.
+    let byline = match m.from.is_empty() {
+        true => format_time(m.timestamp),
+        false => format!("{} · {}", m.from, format_time(m.timestamp)),
+    };
+
+    html! {
+        <div role="note" aria-label={locale.t("announcement.title")}
+            class="relative mx-3 my-2 px-6 py-3 text-center rounded-lg border-2 border-violet-500 bg-violet-50 text-violet-950">
+            if let Some(on_dismiss) = &props.on_dismiss {
+                <button onclick={on_dismiss.reform(|_| ())} aria-label={locale.t("announcement.dismiss")} title={locale.t("announcement.dismiss")}
+                    class="absolute top-1 end-2 px-1 text-violet-700">{"✕"}</button>
+            }
+            <div class="text-xs font-semibold uppercase tracking-wide text-violet-700">
+                <span aria-hidden="true" class="me-1">{"📣"}</span>{locale.t("announcement.title")}
+            </div>
+            if !title.is_empty() {
+                <div class="mt-1 text-lg font-semibold">{title}</div>
+            }
+            <div class="mt-1 whitespace-pre-line break-words">{&m.message}</div>
+            <div class="mt-1 text-xs text-violet-700">{byline}</div>
+        </div>
+    }
+}
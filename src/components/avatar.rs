@@ -0,0 +1,104 @@
+use yew::prelude::*;
+
+use crate::colors::name_hue;
+use crate::components::lazy_avatar::LazyAvatar;
+use crate::i18n::Locale;
+
+#[derive(Properties, PartialEq)]
+pub struct AvatarProps {
+    pub name: String,
+    pub url: String,
+    /// Width and height in pixels.
+    pub size: u32,
+    #[prop_or_default]
+    pub class: Classes,
+    #[prop_or_default]
+    pub onclick: Option<Callback<MouseEvent>>,
+}
+
+/// A user's avatar image that falls back to their initials on a colored
+/// circle when the image can't be loaded. The image waits until it scrolls
+/// into view; see [`LazyAvatar`].
+#[function_component(Avatar)]
+pub fn avatar(props: &AvatarProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let failed = use_state(|| false);
+
+    // A new URL deserves a fresh attempt.
+    {
+        let failed = failed.clone();
+        use_effect_with_deps(
+            move |_| {
+                failed.set(false);
+                || ()
+            },
+            props.url.clone(),
+        );
+    }
+
+    let alt = locale.format("avatar.alt", &[("name", &props.name)]);
+    let class = classes!("rounded-full", "flex-none", props.class.clone());
+    let onclick = props.onclick.clone();
+    if *failed {
+        let style = format!(
+            "width: {size}px; height: {size}px; font-size: {font}px; background-color: {color};",
+            size = props.size,
+            font = props.size * 2 / 5,
+            color = fallback_color(&props.name),
+        );
+        return html! {
+            <div {class} {style} {onclick} role="img" aria-label={alt}>
+                <span class="flex w-full h-full items-center justify-center text-white font-bold select-none" aria-hidden="true">
+                    {initials(&props.name)}
+                </span>
+            </div>
+        };
+    }
+
+    let onerror = Callback::from(move |_: Event| failed.set(true));
+    let style = format!("width: {size}px; height: {size}px;", size = props.size);
+    html! {
+        <LazyAvatar {class} {style} {onclick} {onerror} src={props.url.clone()} {alt}/>
+    }
+}
+
+/// Up to two letters: the first letter of the first two words, splitting on
+/// spaces, dashes and underscores.
+pub fn initials(name: &str) -> String {
+    let initials: String = name
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+/// A background in the user's hue, dark enough for white initials.
+pub fn fallback_color(name: &str) -> String {
+    format!("hsl({}, 55%, 45%)", name_hue(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_initials_from_first_two_words() {
+        assert_eq!(initials("alice"), "A");
+        assert_eq!(initials("ada lovelace"), "AL");
+        assert_eq!(initials("brave-otter-42"), "BO");
+        assert_eq!(initials("émile_zola"), "ÉZ");
+        assert_eq!(initials("--"), "?");
+    }
+
+    #[test]
+    fn color_is_stable_per_name() {
+        assert_eq!(fallback_color("alice"), fallback_color("alice"));
+        assert_ne!(fallback_color("alice"), fallback_color("bob"));
+    }
+}
@@ -0,0 +1,51 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+
+#[derive(Properties, PartialEq)]
+pub struct BroadcastModalProps {
+    pub message: String,
+    /// Seconds until we are taken off to the maintenance page, when the
+    /// server is disconnecting everyone.
+    #[prop_or_default]
+    pub countdown: Option<u32>,
+    /// Closes it. Without it, there's nothing to do but wait.
+    #[prop_or_default]
+    pub on_acknowledge: Option<Callback<()>>,
+}
+
+/// A critical message from the operators, covering the whole chat until it
+/// has been acknowledged. Clicking outside or Escape doesn't close it.
+#[function_component(BroadcastModal)]
+pub fn broadcast_modal(props: &BroadcastModalProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let onkeydown = Callback::from(|e: KeyboardEvent| {
+        if e.key() == "Escape" {
+            e.stop_propagation();
+        }
+    });
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-60">
+            <div ref={dialog} tabindex="-1" role="alertdialog" aria-modal="true" aria-label={locale.t("broadcast.title").to_string()} {onkeydown}
+                class="w-96 max-w-full p-5 bg-white text-black rounded-lg shadow-lg border-t-4 border-red-600 flex flex-col gap-3">
+                <span class="text-lg font-semibold text-red-700">
+                    <span aria-hidden="true" class="me-1">{"⚠"}</span>{locale.t("broadcast.title")}
+                </span>
+                <p class="whitespace-pre-line break-words">{&props.message}</p>
+                if let Some(seconds) = props.countdown {
+                    <p role="timer" aria-live="polite" class="text-sm text-gray-600">
+                        {locale.format("broadcast.disconnecting", &[("seconds", &seconds.to_string())])}
+                    </p>
+                }
+                if let Some(on_acknowledge) = &props.on_acknowledge {
+                    <button onclick={on_acknowledge.reform(|_| ())} class="self-end px-4 py-1 rounded-full bg-red-600 text-white">
+                        {locale.t("broadcast.acknowledge")}
+                    </button>
+                }
+            </div>
+        </div>
+    }
+}
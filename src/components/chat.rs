@@ -1,228 +1,5132 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::rc::Rc;
+
+use futures::channel::mpsc::Sender;
+use gloo_events::{EventListener, EventListenerOptions};
+use gloo_timers::callback::{Interval, Timeout};
+use gloo_timers::future::TimeoutFuture;
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use web_sys::HtmlInputElement;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{DataTransfer, Element, File, HtmlElement, HtmlInputElement, MediaStream};
+use yew::context::ContextHandle;
 use yew::prelude::*;
+use yew::virtual_dom::Key;
 use yew_agent::{Bridge, Bridged};
+use yew_router::prelude::*;
 
+use crate::bidi;
+use crate::components::announcement_card::AnnouncementCard;
+use crate::components::broadcast_modal::BroadcastModal;
+use crate::components::chat_statistics::ChatStatistics;
+use crate::components::code_review_dialog::CodeReviewDialog;
+use crate::components::confetti::Confetti;
+use crate::components::confirm_dialog::ConfirmDialog;
+use crate::components::connection_status_banner::ConnectionStatusBanner;
+use crate::components::error_toasts::{ErrorEntry, ErrorLog, ErrorToasts};
+use crate::components::export_dialog::ExportDialog;
+use crate::components::file_card::human_size;
+use crate::components::gif_picker::GifPicker;
+use crate::components::keyboard_navigable_message_list::{
+    KeyboardNavigableMessageList, MessageKey,
+};
+use crate::components::lightbox::{Lightbox, LightboxImage};
+use crate::components::message_actions::MessageActions;
+use crate::components::message_content::is_image;
+use crate::components::message_filter::{Filter, MessageFilter};
+use crate::components::message_item::{MessageItem, ReactionChip, Reader, Translation};
+use crate::components::message_render::Renderer;
+use crate::components::message_search::{self, MessageSearch};
+use crate::components::pinned_message_bar::PinnedMessageBar;
+use crate::components::pinned_messages::PinnedMessages;
+use crate::components::presence_list::PresenceList;
+use crate::components::quoted_reply::{QuotedReply, ReplyPreview};
+use crate::components::reaction_picker::ReactionPicker;
+use crate::components::room_invite::RoomInviteBanner;
+use crate::components::settings_panel::SettingsPanel;
+use crate::components::shortcuts_help::ShortcutsHelp;
+use crate::components::sticker_picker::StickerPicker;
+use crate::components::thread_panel::{ThreadEntry, ThreadPanel};
+use crate::components::toast::Toast;
+use crate::components::user_popover::{Anchor, UserPopover};
+use crate::components::user_sidebar::{sort_users, UserSidebar};
+use crate::components::video_call::{IncomingCall, VideoCall as VideoCallView};
+use crate::components::virtual_list::{ListItem, RenderItem, ScrollRequest, VirtualList};
+use crate::components::welcome::WelcomeBanner;
+use crate::components::whiteboard::Draw;
+use crate::emoji;
+use crate::i18n::{self, Locale};
+use crate::services::alerts::{self, VisibilityListener};
+use crate::services::avatars::{avatar_url, AvatarStyle};
+use crate::services::bot::{ChatBot, BOT_NAME};
+use crate::services::call::PeerCall;
+use crate::services::clipboard;
+use crate::services::commands::{self, Commands, Input, Outcome};
+use crate::services::completion::{byte_index, utf16_len, Completion};
+use crate::services::confetti::Cooldowns;
+use crate::services::csrf::CsrfToken;
+use crate::services::dedupe::{SeenMessages, SendGuard};
 use crate::services::event_bus::EventBus;
-use crate::{services::websocket::WebsocketService, User};
+use crate::services::export::{self, ExportFormat, TranscriptEntry};
+use crate::services::gifs::GifConfig;
+use crate::services::history::RingBuffer;
+use crate::services::idle::IdleTracker;
+use crate::services::input_history::InputHistory;
+use crate::services::local_history;
+use crate::services::location::{self, LocationError};
+use crate::services::message::{
+    Announcement, Answer, AudioClip, Broadcast, BroadcastSeverity, CallSignal, ChatStats,
+    CodeReview, CodeReviewComment, ConfettiBurst, Delete, DiffShare, Edit, FileShare, ImageShare,
+    JoinRoom, Kick, LineComment, LinkPreview, Location, MsgTypes, Mute, OutgoingMessage, Pin,
+    PinKey, Presence, PresenceUpdate, Reaction, ReactionTally, ReadReceipt, Registration, Rename,
+    Role, RoomInvite, ServerError, Survey, SurveyResponse, ThreadId, Topic, UnpinMessage,
+    VideoCall, WebSocketMessage, Whiteboard, WhiteboardStroke,
+};
+use crate::services::names::is_valid_username;
+use crate::services::rate_limit::TokenBucket;
+use crate::services::receipts::{self, Receipts};
+use crate::services::recorder::{self, Recorder, Recording};
+use crate::services::session;
+use crate::services::settings::{SettingsHandle, Theme, ThemeContext};
+use crate::services::shortcuts::{self, Action, InputAction, InputState, KeyPress};
+use crate::services::translate::{self, TranslateConfig};
+use crate::services::transport::Transport;
+use crate::services::unread::RoomUnread;
+use crate::services::upload::{self, PastedImage};
+use crate::services::websocket::{ConnectionState, ConnectionStatus};
+use crate::services::whiteboard::Board;
+use crate::{diff, AuthToken, Route, User};
 
 pub enum Msg {
     HandleMsg(String),
     SubmitMessage,
+    InputKeyDown(KeyboardEvent),
+    StartEdit(usize),
+    CancelEdit,
+    CancelReply,
+    InputChanged(String),
     ToggleDarkMode,
+    ToggleExport,
+    SetFilter(Filter),
+    ToggleSearch,
+    SearchQuery(String),
+    /// `true` for the next hit, `false` for the previous one.
+    SearchStep(bool),
+    Shortcut(Action),
+    CloseShortcuts,
+    VisibilityChanged(bool),
+    VisibleRows(Range<usize>),
+    /// The message list has been scrolled to, or away from, the bottom.
+    AtBottom(bool),
+    /// A character typed with nothing focused, for the message box.
+    TypeIntoInput(String),
+    /// The window got focus back.
+    Focused,
+    SendReceipt,
+    JumpToUnread,
+    DismissUnread,
+    DismissWelcome,
+    ConfettiDone,
+    ToggleEmojiPicker,
+    InsertEmoji(String),
+    /// Opens the emoji picker to react to a message.
+    OpenReactions(usize),
+    CloseReactions,
+    /// Puts an emoji on a message, or takes ours back off.
+    React((usize, String)),
+    ToggleGifPicker,
+    ToggleStickers,
+    OpenImage(usize),
+    CloseLightbox,
+    Translate(usize),
+    Translated(usize, (PinKey, String), Result<String, String>),
+    ShowOriginal(usize),
+    OpenThread(usize),
+    CloseThread,
+    SendThreadReply(String),
+    SendImage(String),
+    TogglePin(usize),
+    ToggleStar(usize),
+    ToggleStarredOnly,
+    ClearStars,
+    Reply(usize),
+    CopyMessage(usize),
+    CopyLink(usize),
+    /// Shows the toast with this locale key.
+    ShowToast(&'static str),
+    HideToast,
+    Unpin(Pin),
+    ShowPinned(PinKey),
+    PinToRoom(usize),
+    UnpinFromRoom,
+    ShowRoomPin,
+    Draw(Draw),
+    PersistHistory,
+    BotReply(Box<MessageData>),
+    AcceptInvite,
+    DeclineInvite,
+    SelectRoom(Option<String>),
+    ExportChat(ExportFormat),
+    ToggleSettings,
+    TogglePeople,
+    ToggleStats,
+    RequestStats,
+    SettingsChanged(SettingsHandle),
+    ThemeChanged(ThemeContext),
+    LocaleChanged(Locale),
+    SwitchUser,
+    SetStatus(String),
+    SetPresence(Presence),
+    ToggleDoNotDisturb,
+    Idle(bool),
+    ConnectionChanged(ConnectionState),
+    ShowPopover((String, Anchor)),
+    ClosePopover,
+    MessagePrivately(String),
+    ToggleAttachMenu,
+    PickFile,
+    FileChosen,
+    /// A picked or dropped file, to share once it's checked.
+    ShareFile(File),
+    /// Files dragged over the messages, to show where they'll go.
+    DragFiles(DragEvent),
+    DragLeave,
+    DropFiles(DragEvent),
+    ShareLocation,
+    ToggleCodeReview,
+    SendCodeReview((String, String)),
+    CommentOnReview((usize, u32, String)),
+    /// Answers the survey in the message with this id.
+    AnswerSurvey((usize, Vec<Answer>)),
+    DismissPreview(usize),
+    LocationFound(Result<Location, LocationError>),
+    FileUploaded(FileShare),
+    StartRecording,
+    RecordingStarted(Recorder),
+    RecordingTick,
+    StopRecording,
+    AudioRecorded(Recording),
+    AudioUploaded(AudioClip),
+    ToggleMute(String),
+    RevealMuted(usize),
+    ToggleExpanded(usize),
+    FocusMessage(usize),
+    MessageKey((usize, MessageKey)),
+    AskDelete(usize),
+    ConfirmDelete,
+    CancelDelete,
+    StartCall(String),
+    AcceptCall,
+    DeclineCall,
+    HangUp,
+    CallConnected(PeerCall),
+    RemoteStream(MediaStream),
+    SendSignal((String, CallSignal)),
+    Rename(String),
+    PasteMessage(Event),
+    SendPastedImage,
+    /// A pasted image, encoded to send inline.
+    PastedImageEncoded(Result<String, String>),
+    /// A pasted image, uploaded to this URL.
+    PastedImageUploaded(String),
+    CancelPastedImage,
+    DismissAnnouncement(PinKey),
+    /// The oldest pinned announcement has been up long enough.
+    AnnouncementExpired,
+    DismissError(usize),
+    ToggleErrorLog,
+    ServerMute((String, u64)),
+    Kick(String),
+    MuteTick,
+    MuteExpired,
+    SlowDownTick,
+    DismissBroadcast,
+    AcknowledgeBroadcast,
+    MaintenanceTick,
+    SlowDownOver,
+    SendPauseOver,
+}
+
+const MUTED_KEY: &str = "muted";
+const STARRED_KEY: &str = "starred";
+/// Surveys we have answered, by id, so they can't be answered twice.
+const SURVEYS_KEY: &str = "surveys_answered";
+const DEFAULT_MAX_LENGTH: usize = 500;
+/// A one-line message bubble with its margins, in pixels.
+const MESSAGE_HEIGHT_ESTIMATE: f32 = 120.0;
+/// How long a server error toast stays up, in milliseconds.
+const ERROR_TOAST_MS: u32 = 8_000;
+const ERROR_LOG_LIMIT: usize = 50;
+const PERSIST_DEBOUNCE_MS: u32 = 1_000;
+/// Timestamp of the newest message the user has seen.
+const LAST_READ_KEY: &str = "last_read";
+/// Set once the welcome message has been shown.
+const FIRST_VISIT_KEY: &str = "first_visit";
+/// How long a confirmation toast stays up.
+const TOAST_MS: u32 = 2_000;
+/// How long the send button stays disabled after a send, against double
+/// clicks.
+const SEND_PAUSE_MS: u32 = 500;
+/// How long a forced disconnect is shown before the maintenance page.
+const MAINTENANCE_COUNTDOWN_SECS: u32 = 5;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct MessageData {
+    /// Assigned in arrival order by this client, used as the render key.
+    #[serde(skip)]
+    pub id: usize,
+    pub from: String,
+    pub message: String,
+    /// When the server relayed it, from the frame; local notices use our
+    /// own clock. With `from`, names the message on every client.
+    #[serde(default)]
+    pub timestamp: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<FileShare>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioClip>,
+    /// The id of a shared canvas shown in the bubble.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whiteboard: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    /// An image sent inline rather than uploaded, as a `data:` URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<ReactionTally>,
+    /// The private room the message was posted to; `None` for the lobby.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    /// The name `from` had when the message was sent, if they renamed since.
+    #[serde(skip)]
+    pub formerly: Option<String>,
+    /// Sent with `/me`, shown as "* alice waves".
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub action: bool,
+    /// Local feedback, such as `/help` output. Never sent or saved.
+    #[serde(skip)]
+    pub notice: bool,
+    /// Bookmarked by us. Stars are personal: kept apart from the message in
+    /// local storage and never sent.
+    #[serde(skip)]
+    pub starred: bool,
+    /// The message this one replies to in a thread.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<ThreadId>,
+    /// Sent as an announcement, with its title; empty if it had none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub announcement: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_review: Option<CodeReview>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub survey: Option<Survey>,
+    /// Changed by its sender since it was sent.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub edited: bool,
+}
+
+impl MessageData {
+    /// Just words, with no attachment, card or image in the bubble.
+    pub fn is_text(&self) -> bool {
+        self.file.is_none()
+            && self.diff.is_none()
+            && self.audio.is_none()
+            && self.whiteboard.is_none()
+            && self.location.is_none()
+            && self.image.is_none()
+            && self.code_review.is_none()
+            && self.survey.is_none()
+            && !self.message.trim().is_empty()
+            && !is_image(&self.message)
+    }
+}
+
+impl TranscriptEntry for MessageData {
+    fn sender(&self) -> &str {
+        &self.from
+    }
+
+    fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    fn text(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct CodeReviewData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room: Option<String>,
+    #[serde(flatten)]
+    review: CodeReview,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FileShareData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room: Option<String>,
+    #[serde(flatten)]
+    file: FileShare,
+}
+
+#[derive(Deserialize, Serialize)]
+struct AudioMessageData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room: Option<String>,
+    #[serde(flatten)]
+    audio: AudioClip,
+}
+
+#[derive(Deserialize, Serialize)]
+struct LocationMessageData {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room: Option<String>,
+    #[serde(flatten)]
+    location: Location,
+}
+
+/// The sender, which the server adds to every posted frame's payload.
+#[derive(Deserialize)]
+struct Sender {
+    from: String,
 }
 
 #[derive(Deserialize)]
-struct MessageData {
+struct StatusData {
     from: String,
-    message: String,
+    status: String,
+}
+
+#[derive(Clone, PartialEq, Deserialize, Serialize)]
+pub struct UserProfile {
+    pub name: String,
+    pub avatar: String,
+    pub avatar_style: AvatarStyle,
+    pub status: String,
+    #[serde(default)]
+    pub presence: Presence,
+    /// When this client first saw the user online, in milliseconds.
+    pub joined_at: f64,
+    /// Moderators can mute and kick other users.
+    #[serde(default)]
+    pub role: Role,
+    /// The local [`ChatBot`], listed alongside the people.
+    pub bot: bool,
+    /// When the user was last seen online, if they've left since.
+    #[serde(default)]
+    pub last_seen: Option<f64>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum MsgTypes {
-    Users,
-    Register,
-    Message,
+/// Online users keyed by name, in sidebar order.
+pub type Users = IndexMap<String, UserProfile>;
+
+/// Builds the online list from a `Users` frame. Profiles are reused by name
+/// so client-side state attached to a user survives the refresh; users who
+/// are gone move to `departed`, and come back from there if they rejoin.
+fn merge_users(
+    mut previous: Users,
+    departed: &mut HashMap<String, UserProfile>,
+    entries: &[String],
+    now: f64,
+) -> Users {
+    let users = entries
+        .iter()
+        .map(|entry| {
+            let u = Registration::parse(entry);
+            let known = previous
+                .swap_remove(&u.name)
+                .or_else(|| departed.remove(&u.name));
+            let mut profile = match known {
+                Some(mut profile) => {
+                    profile.avatar = avatar_url(&u.name, u.avatar_style);
+                    profile.avatar_style = u.avatar_style;
+                    profile
+                }
+                None => UserProfile::new(&u.name, u.avatar_style, now),
+            };
+            profile.role = u.role;
+            (u.name, profile)
+        })
+        .collect();
+    departed.extend(previous.into_iter().map(|(name, mut profile)| {
+        profile.last_seen = Some(now);
+        (name, profile)
+    }));
+    users
+}
+
+impl UserProfile {
+    pub fn new(name: &str, avatar_style: AvatarStyle, joined_at: f64) -> Self {
+        Self {
+            name: name.into(),
+            avatar: avatar_url(name, avatar_style),
+            avatar_style,
+            status: String::new(),
+            presence: Presence::Online,
+            joined_at,
+            role: Role::Member,
+            bot: false,
+            last_seen: None,
+        }
+    }
+
+    fn bot() -> Self {
+        Self {
+            bot: true,
+            ..Self::new(BOT_NAME, AvatarStyle::Bottts, 0.0)
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ChatProps {
+    #[prop_or_default]
+    pub transport: Transport,
+    /// What first-time visitors are told, in place of the built-in tips.
+    #[prop_or_default]
+    pub welcome_html: Option<Html>,
+    /// The longest message that can be sent, in characters.
+    #[prop_or(DEFAULT_MAX_LENGTH)]
+    pub max_length: usize,
+    /// Where the GIF picker searches; without it there is no picker.
+    #[prop_or_else(GifConfig::from_env)]
+    pub gif: Option<GifConfig>,
+    /// Where the sticker packs are listed; without it there are no stickers.
+    #[prop_or_default]
+    pub sticker_manifest_url: Option<String>,
+    /// Where messages are sent to be translated.
+    #[prop_or_else(TranslateConfig::from_env)]
+    pub translate: TranslateConfig,
+    /// Draws the text of each message, for embedders that want their own.
+    #[prop_or_default]
+    pub message_renderer: Renderer,
+    /// The largest file that can be shared, in bytes.
+    #[prop_or(upload::DEFAULT_MAX_FILE_BYTES)]
+    pub max_file_bytes: u64,
+    /// Sends pasted images through the upload endpoint. Without one, they
+    /// go inline, scaled down, in an `Image` frame.
+    #[prop_or(true)]
+    pub upload_images: bool,
+}
+
+/// One entry in the rendered message list.
+#[derive(Clone, PartialEq)]
+enum MessageRow {
+    Trimmed,
+    /// Divides history saved by an earlier visit from messages received since.
+    Restored,
+    /// A run of messages from a muted user, collapsed into one stub.
+    Hidden {
+        first_id: usize,
+        from: String,
+        count: usize,
+    },
+    Message {
+        message: Rc<MessageData>,
+        author: Rc<UserProfile>,
+        /// The search hit being looked at.
+        current: bool,
+        focused: bool,
+        pinned: bool,
+        own: bool,
+        moderator: bool,
+        board: Option<Rc<Board>>,
+        expanded: bool,
+        translation: Option<Translation>,
+        seen_by: Vec<Reader>,
+        reactions: Vec<ReactionChip>,
+        /// A survey we have already answered.
+        answered: bool,
+        preview: Option<Rc<LinkPreview>>,
+        replies: usize,
+        unread_replies: usize,
+    },
+    Notice(Rc<MessageData>),
+    Announcement(Rc<MessageData>),
+    /// Where messages that arrived while we were away begin.
+    Unread,
+    /// Starts the local day beginning at this midnight.
+    Day(f64),
+}
+
+impl ListItem for MessageRow {
+    fn key(&self) -> Key {
+        match self {
+            MessageRow::Trimmed => Key::from("trimmed"),
+            MessageRow::Restored => Key::from("restored"),
+            MessageRow::Hidden { first_id, .. } => Key::from(format!("hidden-{}", first_id)),
+            MessageRow::Message { message, .. }
+            | MessageRow::Notice(message)
+            | MessageRow::Announcement(message) => Key::from(format!("message-{}", message.id)),
+            MessageRow::Unread => Key::from("unread"),
+            MessageRow::Day(midnight) => Key::from(format!("day-{}", midnight)),
+        }
+    }
+}
+
+/// What the message list was last asked to scroll to.
+#[derive(Clone, Copy, PartialEq)]
+enum ScrollTarget {
+    Message(usize),
+    Unread,
+}
+
+/// An open find bar. `hits` holds the ids of the shown messages matching
+/// `query`, oldest first; it is rebuilt whenever either changes.
+#[derive(Default)]
+struct Search {
+    query: String,
+    hits: Vec<usize>,
+    current: Option<usize>,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WebSocketMessage {
-    message_type: MsgTypes,
-    data_array: Option<Vec<String>>,
-    data: Option<String>,
+impl Search {
+    fn current_id(&self) -> Option<usize> {
+        self.current.map(|i| self.hits[i])
+    }
 }
 
-#[derive(Clone)]
-struct UserProfile {
-    name: String,
-    avatar: String,
+/// A call we placed or accepted. `connection` is empty until the camera has
+/// been opened.
+struct ActiveCall {
+    peer: String,
+    connection: Option<PeerCall>,
+    remote: Option<MediaStream>,
 }
 
 pub struct Chat {
-    dark_mode: bool,
-    users: Vec<UserProfile>,
+    username: String,
+    settings: SettingsHandle,
+    _settings_listener: ContextHandle<SettingsHandle>,
+    theme: ThemeContext,
+    _theme_listener: ContextHandle<ThemeContext>,
+    locale: Locale,
+    _locale_listener: ContextHandle<Locale>,
+    export_open: bool,
+    review_open: bool,
+    /// Narrows what the message list shows; `messages` itself is untouched.
+    filter: Filter,
+    search: Option<Search>,
+    scroll_target: Option<ScrollTarget>,
+    /// The message a `#msg-…` link in the page URL points to, or that was
+    /// last opened from the room's pinned message.
+    linked: Option<usize>,
+    /// The message the list's keyboard focus is on, moved with Up and Down.
+    focused_message_id: Option<usize>,
+    /// Our message waiting for the go-ahead to delete it, by id.
+    confirm_delete: Option<usize>,
+    /// A short confirmation, such as "Copied", and the timer hiding it.
+    toast: Option<(String, Timeout)>,
+    /// Announcements taken down from the pinned area this session.
+    dismissed_announcements: HashSet<PinKey>,
+    /// Unpins the next announcement due to come down.
+    announcement_expiry: Option<Timeout>,
+    /// The latest message from someone else, read out by screen readers.
+    announcement: String,
+    /// Bumped with every scroll request, so asking twice scrolls twice.
+    scroll_generation: u32,
+    /// Timestamp of the newest message seen with the tab in view.
+    last_read: f64,
+    /// The first message that arrived while we were away, where the unread
+    /// divider goes. The title counts the open room from the same point,
+    /// and both are cleared together by `dismiss_unread`.
+    unread_from: Option<usize>,
+    /// Whether the unread divider has been on screen, so scrolling on
+    /// past it can dismiss it.
+    unread_seen: bool,
+    /// Whether the message list is scrolled to the bottom; above it, new
+    /// messages arrive out of sight.
+    at_bottom: bool,
+    /// Focus goes back to the message box after the next render, once
+    /// whatever just closed has handed it back.
+    refocus: bool,
+    _visibility: VisibilityListener,
+    _focus: Option<EventListener>,
+    /// Which rows of the message list are on screen.
+    visible_rows: Range<usize>,
+    /// How far everyone has read, for our own messages' "seen by".
+    receipts: Receipts,
+    /// The newest message timestamp we have sent a receipt for, by room.
+    sent_receipts: HashMap<Option<String>, f64>,
+    receipt_timer: Option<Timeout>,
+    commands: Commands,
+    settings_open: bool,
+    people_open: bool,
+    stats_open: bool,
+    /// Each room's topic; rooms without one have no entry.
+    topics: HashMap<Option<String>, String>,
+    /// The server's latest answer to a `StatsRequest`.
+    stats: Option<Rc<ChatStats>>,
+    /// This is the user's first visit, so they get a few tips.
+    welcome_open: bool,
+    shortcuts_open: bool,
+    /// Who last set off confetti, and when.
+    confetti: Cooldowns,
+    confetti_playing: bool,
+    connection: ConnectionState,
+    popover: Option<(String, Anchor)>,
+    /// Users whose messages are collapsed, persisted across sessions.
+    muted: HashSet<String>,
+    /// Ids of the first message of each collapsed run the user expanded.
+    revealed: HashSet<usize>,
+    /// Long messages the user expanded, by id.
+    expanded: HashSet<usize>,
+    /// The message last expanded or collapsed, held in place on screen
+    /// while its height changes.
+    anchor: Option<(usize, u32)>,
+    call: Option<ActiveCall>,
+    /// An offer waiting for Accept/Decline: caller and SDP.
+    incoming_call: Option<(String, String)>,
+    /// ICE candidates that arrived before our peer connection existed.
+    pending_ice: Vec<CallSignal>,
+    users: Rc<Users>,
+    /// Profiles of users who have left, so their old messages keep their
+    /// avatar and popover.
+    departed: HashMap<String, UserProfile>,
+    /// Built once so message items see the same callback on every render.
+    show_popover: Callback<(String, Anchor)>,
+    message_actions: Rc<MessageActions>,
+    /// Pinned messages, oldest pin first. They keep the text they were
+    /// pinned with, so a pin outlives its message being trimmed.
+    pins: Vec<Pin>,
+    /// The message a moderator pinned to each room, `None` being the lobby.
+    room_pins: HashMap<Option<String>, Rc<MessageData>>,
+    pinned: HashSet<PinKey>,
+    /// Our bookmarks, which only we see.
+    starred: HashSet<PinKey>,
+    answered_surveys: HashSet<String>,
+    /// What links lead to, by URL; `None` while the server looks.
+    link_previews: HashMap<String, Option<Rc<LinkPreview>>>,
+    /// Messages whose link preview we've closed.
+    dismissed_previews: HashSet<usize>,
+    /// The strokes on each whiteboard, by id.
+    boards: HashMap<String, Rc<Board>>,
+    draw: Callback<Draw>,
+    /// Ids our strokes go out with. Seeded from the clock so they don't
+    /// repeat those of an earlier visit.
+    next_stroke_id: u32,
     chat_input: NodeRef,
+    /// What the message box holds, kept for its counter.
+    draft: TextCount,
+    /// The Tab completion in progress, until another key is pressed.
+    completion: Option<Completion>,
+    /// What we've sent before, recalled with Ctrl+Up and Ctrl+Down.
+    input_history: InputHistory,
+    /// The message whose text is in the input to be changed, by id.
+    editing: Option<usize>,
+    /// The message being replied to, quoted above the input.
+    reply_to: Option<ReplyPreview>,
+    file_input: NodeRef,
+    /// Whether the 📎 menu is open.
+    attach_open: bool,
+    gif_open: bool,
+    stickers_open: bool,
+    emoji_open: bool,
+    /// The message the emoji picker is open to react to.
+    reacting_to: Option<usize>,
+    /// Images pasted into the message box or dropped on the chat, waiting
+    /// in turn for Send or Cancel.
+    pasted_images: VecDeque<PastedImage>,
+    /// Files are being dragged over the messages.
+    dragging: bool,
+    /// The images in the open room, and which one is shown large.
+    lightbox: Option<(Rc<Vec<LightboxImage>>, usize)>,
+    /// Translations fetched so far, by message and language.
+    translations: HashMap<(PinKey, String), String>,
+    /// The translations shown under messages, by id.
+    translated: HashMap<usize, Translation>,
+    /// The thread open in the side panel.
+    thread: Option<ThreadId>,
+    /// Replies from others to each thread since we last had it open. Kept
+    /// apart from the room's unread divider, which replies don't move.
+    thread_unread: HashMap<PinKey, usize>,
     _producer: Box<dyn Bridge<EventBus>>,
-    wss: WebsocketService,
-    messages: Vec<MessageData>,
+    /// Attached to uploads, which change things on the server.
+    csrf: CsrfToken,
+    bot: Box<dyn Bridge<ChatBot>>,
+    tx: Sender<String>,
+    messages: RingBuffer<Rc<MessageData>>,
+    next_message_id: usize,
+    /// Messages with ids below this were restored from local storage.
+    restored: usize,
+    persist: Callback<()>,
+    persist_timer: Option<Timeout>,
+    /// Recent server errors, for the "View all errors" log.
+    errors: RingBuffer<Rc<ErrorEntry>>,
+    /// Ids of the errors currently shown as toasts.
+    toasts: Vec<usize>,
+    next_error_id: usize,
+    error_log_open: bool,
+    /// Private rooms we have joined.
+    rooms: Vec<String>,
+    /// The room whose messages are shown; `None` for the lobby.
+    current_room: Option<String>,
+    /// Room invitations waiting for Accept/Decline, oldest first.
+    invites: Vec<RoomInvite>,
+    /// When a moderator's mute on us ends, in milliseconds.
+    muted_until: Option<f64>,
+    /// Ends the mute and ticks the countdown; dropping them cancels both.
+    mute_timers: Option<(Timeout, Interval)>,
+    /// Sends left before we're sending too fast.
+    send_limit: TokenBucket,
+    /// When the send that hit the limit goes out by itself, in milliseconds.
+    slow_until: Option<f64>,
+    /// Sends it and ticks the countdown; dropping them cancels both.
+    slow_timers: Option<(Timeout, Interval)>,
+    /// The operators' latest warning, shown in a banner.
+    broadcast_warning: Option<String>,
+    /// Critical broadcasts still to be acknowledged, oldest first.
+    critical_broadcasts: Vec<String>,
+    /// Why we've been disconnected, the seconds left before leaving for
+    /// the maintenance page, and what counts them down.
+    disconnecting: Option<(String, u32, Interval)>,
+    /// The last message sent, to drop an accidental repeat.
+    sent: SendGuard,
+    /// Re-enables the send button shortly after a send.
+    send_pause: Option<Timeout>,
+    /// Messages already shown, so a retransmitted one isn't shown twice.
+    seen: SeenMessages,
+    /// Unread counts of the rooms we aren't looking at.
+    room_unread: RoomUnread,
+    /// Messages that would have chimed while do not disturb was on, told
+    /// once it's turned off.
+    missed: usize,
+    /// Whether the user has left the page alone long enough to show as away.
+    idle: bool,
+    _idle_tracker: Option<IdleTracker>,
+    /// Keyboard shortcuts, listened for on the whole page.
+    _shortcuts: Option<EventListener>,
+    /// A voice message being recorded, kept until its audio arrives.
+    recorder: Option<Recorder>,
+    /// Ticks the elapsed time while recording; `None` once stopped.
+    recording_timer: Option<Interval>,
 }
 
 impl Chat {
-    fn toggle_dark_mode(&mut self) {
-        self.dark_mode = !self.dark_mode;
+    fn dark_mode(&self) -> bool {
+        self.theme.theme() == Theme::Dark
     }
-}
 
-impl Component for Chat {
-    type Message = Msg;
-    type Properties = ();
+    /// Saves state that is ours alone, under our name.
+    fn save_own<T: Serialize>(&self, name: &str, value: &T) {
+        session::save(&session::user_key(&self.username, name), value);
+    }
 
-    fn create(ctx: &Context<Self>) -> Self {
-        let (user, _) = ctx
-            .link()
-            .context::<User>(Callback::noop())
-            .expect("context to be set");
-        let wss = WebsocketService::new();
-        let username = user.username.borrow().clone();
+    /// Looks a user up whether or not they are still online.
+    fn profile(&self, name: &str) -> Option<&UserProfile> {
+        self.users.get(name).or_else(|| self.departed.get(name))
+    }
 
-        let message = WebSocketMessage {
-            message_type: MsgTypes::Register,
-            data: Some(username.to_string()),
-            data_array: None,
+    /// Replaces what the message box holds. Input events only fire for
+    /// typing, so the counter is updated here.
+    fn set_draft(&mut self, input: &HtmlInputElement, value: &str) {
+        input.set_value(value);
+        self.draft = TextCount::of(value);
+    }
+
+    /// Why `file` can't be shared, if it is over the size limit.
+    fn too_large(&self, ctx: &Context<Self>, file: &File) -> Option<String> {
+        let limit = ctx.props().max_file_bytes;
+        (file.size() as u64 > limit).then(|| {
+            self.locale.format(
+                "file.too_large",
+                &[("name", &file.name()), ("limit", &human_size(limit))],
+            )
+        })
+    }
+
+    /// Drops the reply, and the mention it put in front of the draft.
+    fn cancel_reply(&mut self) -> bool {
+        let Some(reply) = self.reply_to.take() else {
+            return false;
+        };
+        if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
+            let value = input.value();
+            if let Some(rest) = value.strip_prefix(&format!("@{} ", reply.from)) {
+                self.set_draft(&input, rest);
+            }
+        }
+        true
+    }
+
+    /// Sends a chat message to the open room.
+    fn send_text(&self, text: String, action: bool) {
+        let data = OutgoingMessage {
+            room: self.current_room.clone(),
+            message: text,
+            action,
+            thread_id: None,
+        };
+        let message = WebSocketMessage::builder(MsgTypes::Message)
+            .data(data.to_data())
+            .build()
+            .expect("chat messages carry data");
+        self.send(&message);
+    }
+
+    /// Asks the server what the message's link leads to, once per link.
+    fn request_preview(&mut self, message: &MessageData) {
+        let Some(url) = preview_link(message) else {
+            return;
         };
+        if self.link_previews.contains_key(url) {
+            return;
+        }
+        self.link_previews.insert(url.to_string(), None);
+        let request = LinkPreview {
+            url: url.to_string(),
+            ..LinkPreview::default()
+        };
+        let message = WebSocketMessage::builder(MsgTypes::LinkPreview)
+            .data(serde_json::to_string(&request).unwrap())
+            .build()
+            .unwrap();
+        self.send(&message);
+    }
+
+    fn focus_input(&self) {
+        if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
+            let _ = input.focus();
+        }
+    }
+
+    /// Sends a receipt once the view has settled.
+    fn schedule_receipt(&mut self, ctx: &Context<Self>) {
+        if !self.settings.read_receipts() {
+            return;
+        }
+        let link = ctx.link().clone();
+        self.receipt_timer = Some(Timeout::new(receipts::DEBOUNCE_MS, move || {
+            link.send_message(Msg::SendReceipt)
+        }));
+    }
+
+    /// Tells the room we have read up to the newest message on screen, if
+    /// we are looking at it and haven't said so already.
+    fn send_receipt(&mut self) {
+        if !self.settings.read_receipts() || alerts::page_hidden() || !alerts::page_focused() {
+            return;
+        }
+        let rows = self.message_rows();
+        let end = self.visible_rows.end.min(rows.len());
+        let start = self.visible_rows.start.min(end);
+        let newest = rows[start..end].iter().rev().find_map(|row| match row {
+            MessageRow::Message { message, .. } => Some(message),
+            _ => None,
+        });
+        let Some(newest) = newest else {
+            return;
+        };
+        let sent = self
+            .sent_receipts
+            .entry(self.current_room.clone())
+            .or_insert(f64::NEG_INFINITY);
+        if newest.timestamp <= *sent {
+            return;
+        }
+        *sent = newest.timestamp;
+        let receipt = ReadReceipt {
+            from: String::new(),
+            room: self.current_room.clone(),
+            sender: newest.from.clone(),
+            timestamp: newest.timestamp,
+        };
+        let message = WebSocketMessage::builder(MsgTypes::Read)
+            .data(serde_json::to_string(&receipt).unwrap())
+            .build()
+            .expect("read receipts carry data");
+        self.send(&message);
+    }
+
+    fn is_moderator(&self) -> bool {
+        self.users
+            .get(&self.username)
+            .is_some_and(|me| me.role == Role::Moderator)
+    }
+
+    fn is_muted(&self, name: &str) -> bool {
+        self.muted.contains(name)
+    }
 
-        if let Ok(_) = wss
+    fn send(&self, message: &WebSocketMessage) {
+        if let Err(e) = self
             .tx
             .clone()
-            .try_send(serde_json::to_string(&message).unwrap())
+            .try_send(serde_json::to_string(message).unwrap())
         {
-            log::debug!("message sent successfully");
+            log::debug!("error sending to channel: {:?}", e);
         }
+    }
 
-        Self {
-            users: vec![],
-            messages: vec![],
-            chat_input: NodeRef::default(),
-            wss,
-            _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
-            dark_mode: false,
+    /// What others should see: do not disturb sticks, otherwise we're away
+    /// while idle.
+    fn effective_presence(&self) -> Presence {
+        match (self.settings.do_not_disturb(), self.idle) {
+            (true, _) => Presence::DoNotDisturb,
+            (false, true) => Presence::Away,
+            (false, false) => Presence::Online,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
-        match msg {
-            Msg::ToggleDarkMode => {
-                self.toggle_dark_mode();
-                true // Signal that the component should be re-rendered
+    /// Announces our presence if it changed, and updates our own dot.
+    fn refresh_presence(&mut self) -> bool {
+        let presence = self.effective_presence();
+        let username = self.username.clone();
+        if !self.set_presence(&username, presence) {
+            return false;
+        }
+        self.send_presence(presence);
+        true
+    }
+
+    fn send_presence(&self, presence: Presence) {
+        let update = PresenceUpdate {
+            from: String::new(),
+            presence,
+        };
+        let message = WebSocketMessage::builder(MsgTypes::Presence)
+            .data(serde_json::to_string(&update).unwrap())
+            .build()
+            .expect("presence messages carry data");
+        self.send(&message);
+    }
+
+    /// Returns whether a known user's presence changed.
+    fn set_presence(&mut self, name: &str, presence: Presence) -> bool {
+        match Rc::make_mut(&mut self.users).get_mut(name) {
+            Some(user) if user.presence != presence => {
+                user.presence = presence;
+                true
             }
-            Msg::HandleMsg(s) => {
-                let msg: WebSocketMessage = serde_json::from_str(&s).unwrap();
-                match msg.message_type {
-                    MsgTypes::Users => {
-                        let users_from_message = msg.data_array.unwrap_or_default();
-                        self.users = users_from_message
-                            .iter()
-                            .map(|u| UserProfile {
-                                name: u.into(),
-                                avatar: format!(
-                                    "https://avatars.dicebear.com/api/adventurer-neutral/{}.svg",
-                                    u
-                                )
-                                .into(),
-                            })
-                            .collect();
-                        return true;
-                    }
-                    MsgTypes::Message => {
-                        let message_data: MessageData =
-                            serde_json::from_str(&msg.data.unwrap()).unwrap();
-                        self.messages.push(message_data);
-                        return true;
-                    }
-                    _ => {
-                        return false;
-                    }
+            _ => false,
+        }
+    }
+
+    /// Returns whether a known user's status changed.
+    fn set_status(&mut self, name: &str, status: String) -> bool {
+        match Rc::make_mut(&mut self.users).get_mut(name) {
+            Some(user) => {
+                user.status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn send_signal(&self, to: String, signal: CallSignal) {
+        let call = VideoCall {
+            from: String::new(),
+            to,
+            signal,
+        };
+        let message = WebSocketMessage::builder(MsgTypes::VideoCall)
+            .data(serde_json::to_string(&call).unwrap())
+            .build()
+            .expect("call messages carry data");
+        self.send(&message);
+    }
+
+    /// Opens the camera and sets up a connection to `peer`. Once it exists,
+    /// `then` runs to produce the offer or answer.
+    fn open_call<F, Fut>(&mut self, ctx: &Context<Self>, peer: String, then: F)
+    where
+        F: FnOnce(PeerCall) -> Fut + 'static,
+        Fut: std::future::Future<Output = Result<CallSignal, wasm_bindgen::JsValue>>,
+    {
+        self.call = Some(ActiveCall {
+            peer: peer.clone(),
+            connection: None,
+            remote: None,
+        });
+        let link = ctx.link().clone();
+        let on_signal = {
+            let peer = peer.clone();
+            link.callback(move |signal| Msg::SendSignal((peer.clone(), signal)))
+        };
+        let on_remote = link.callback(Msg::RemoteStream);
+        spawn_local(async move {
+            let result = match PeerCall::start(on_signal, on_remote).await {
+                Ok(call) => {
+                    link.send_message(Msg::CallConnected(call.clone()));
+                    then(call).await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(signal) => link.send_message(Msg::SendSignal((peer, signal))),
+                Err(e) => {
+                    log::error!("call failed: {:?}", e);
+                    link.send_message(Msg::HangUp);
                 }
             }
-            Msg::SubmitMessage => {
-                let input = self.chat_input.cast::<HtmlInputElement>();
-                if let Some(input) = input {
-                    let message = WebSocketMessage {
-                        message_type: MsgTypes::Message,
-                        data: Some(input.value()),
-                        data_array: None,
-                    };
-                    if let Err(e) = self
-                        .wss
-                        .tx
-                        .clone()
-                        .try_send(serde_json::to_string(&message).unwrap())
-                    {
-                        log::debug!("error sending to channel: {:?}", e);
-                    }
-                    input.set_value("");
-                };
+        });
+    }
+
+    fn handle_call_signal(&mut self, call: VideoCall) -> bool {
+        let from_peer = self.call.as_ref().filter(|c| c.peer == call.from);
+        let connection = from_peer.map(|c| c.connection.clone());
+        let from_caller = self
+            .incoming_call
+            .as_ref()
+            .is_some_and(|(from, _)| *from == call.from);
+        match call.signal {
+            CallSignal::Offer { sdp } => {
+                if self.call.is_some() || self.incoming_call.is_some() {
+                    // Busy: turn the second caller away.
+                    self.send_signal(call.from, CallSignal::Hangup);
+                    return false;
+                }
+                self.pending_ice.clear();
+                self.incoming_call = Some((call.from, sdp));
+                true
+            }
+            CallSignal::Answer { sdp } => {
+                if let Some(Some(connection)) = connection {
+                    spawn_local(async move {
+                        if let Err(e) = connection.accept_answer(&sdp).await {
+                            log::error!("call answer: {:?}", e);
+                        }
+                    });
+                }
+                false
+            }
+            signal @ CallSignal::IceCandidate { .. } => {
+                match connection {
+                    Some(Some(connection)) => add_ice_candidate(connection, signal),
+                    Some(None) => self.pending_ice.push(signal),
+                    None if from_caller => self.pending_ice.push(signal),
+                    None => {}
+                }
+                false
+            }
+            CallSignal::Hangup => {
+                if from_caller {
+                    self.incoming_call = None;
+                    return true;
+                }
+                if connection.is_some() {
+                    self.end_call();
+                    return true;
+                }
                 false
             }
         }
     }
 
+    fn end_call(&mut self) -> Option<String> {
+        self.pending_ice.clear();
+        let call = self.call.take()?;
+        if let Some(connection) = call.connection {
+            connection.hangup();
+        }
+        Some(call.peer)
+    }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        let dark_mode_class = if self.dark_mode { "dark-mode" } else { "" };
-        let text_color_class = if self.dark_mode { "text-white" } else { "text-black" };
+    /// Moves everything known about `old` over to `new`. Returns whether
+    /// anything changed.
+    fn apply_rename(&mut self, ctx: &Context<Self>, old: &str, new: &str) -> bool {
+        let users = Rc::make_mut(&mut self.users);
+        let Some(mut user) = users.shift_remove(old) else {
+            return false;
+        };
+        user.name = new.to_string();
+        user.avatar = avatar_url(new, user.avatar_style);
+        users.insert(new.to_string(), user);
+        sort_users(users, &self.username);
 
-        let submit = ctx.link().callback(|_| Msg::SubmitMessage);
-        let toggle_dark_mode = ctx.link().callback(|_| Msg::ToggleDarkMode);
+        for m in self.messages.iter_mut().filter(|m| m.from == old) {
+            let m = Rc::make_mut(m);
+            m.from = new.to_string();
+            m.formerly.get_or_insert_with(|| old.to_string());
+        }
+        if self.muted.remove(old) {
+            self.muted.insert(new.to_string());
+            self.save_own(MUTED_KEY, &self.muted);
+        }
+        for name in [
+            self.popover.as_mut().map(|(name, _)| name),
+            self.call.as_mut().map(|call| &mut call.peer),
+            self.incoming_call.as_mut().map(|(name, _)| name),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if name == old {
+                *name = new.to_string();
+            }
+        }
 
-        html! {
-            <div class={format!("flex w-screen {}", dark_mode_class)}>
-                <div class="flex-none w-56 h-screen bg-gray-100">
-                    <div class="text-xl p-3">{"Users"}</div>
-                    {
-                        self.users.clone().iter().map(|u| {
-                            html!{
-                                <div class="flex m-3 bg-white rounded-lg p-2">
-                                    <div>
-                                        <img class="w-12 h-12 rounded-full" src={u.avatar.clone()} alt="avatar"/>
-                                    </div>
-                                    <div class="flex-grow p-3">
-                                        <div class="flex text-xs justify-between">
-                                            <div>{u.name.clone()}</div>
-                                        </div>
-                                        <div class="text-xs text-gray-400">
-                                            {"Hi there!"}
-                                        </div>
-                                    </div>
-                                </div>
-                            }
-                        }).collect::<Html>()
-                    }
-                </div>
-                <div class="grow h-screen flex flex-col">
-                <div class={format!("w-full h-14 border-b-2 border-gray-300 {}", dark_mode_class)}>
-                <div class={format!("text-xl p-3 {}", text_color_class)}>{"💬 Chat!"}</div>
-            </div>
-            <div class={format!("w-full grow overflow-auto border-b-2 border-gray-300 {}", dark_mode_class)}>
-                        {
-                            self.messages.iter().map(|m| {
-                                let user = self.users.iter().find(|u| u.name == m.from).unwrap();
-                                html!{
-                                    <div class="flex items-end w-3/6 bg-gray-100 m-8 rounded-tl-lg rounded-tr-lg rounded-br-lg ">
-                                        <img class="w-8 h-8 rounded-full m-3" src={user.avatar.clone()} alt="avatar"/>
-                                        <div class="p-3">
-                                            <div class="text-sm">
-                                                {m.from.clone()}
-                                            </div>
-                                            <div class="text-xs text-gray-500">
-                                                if m.message.ends_with(".gif") {
-                                                    <img class="mt-3" src={m.message.clone()}/>
-                                                } else {
-                                                    {m.message.clone()}
-                                                }
-                                            </div>
-                                        </div>
-                                    </div>
-                                }
-                            }).collect::<Html>()
-                        }
+        if self.username == old {
+            self.username = new.to_string();
+            session::move_user(old, new);
+            let users = Rc::make_mut(&mut self.users);
+            sort_users(users, &self.username);
+            let (user, _) = ctx
+                .link()
+                .context::<User>(Callback::noop())
+                .expect("context to be set");
+            *user.username.borrow_mut() = new.to_string();
+            *user.token.borrow_mut() = Some(AuthToken(new.to_string()));
+            if session::stored_username().is_some() {
+                session::remember(new);
+            }
+        }
+        self.schedule_persist();
+        true
+    }
 
-                    </div>
-                    <div class={format!("w-full h-14 flex px-3 items-center {}", dark_mode_class)}>
-                    <input ref={self.chat_input.clone()} type="text" placeholder="Message" class={format!("block w-full py-2 pl-4 mx-3 bg-gray-100 rounded-full outline-none focus:text-gray-700 {}", text_color_class)} name="message" required=true />
-                    <button onclick={submit} class={format!("p-3 shadow-sm bg-blue-600 w-10 h-10 rounded-full flex justify-center items-center {}", text_color_class)}>
-                        <svg viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg" class="fill-white">
-                            <path d="M0 0h24v24H0z" fill="none"></path><path d="M2.01 21L23 12 2.01 3 2 10l15 2-15 2z"></path>
-                        </svg>
-                    </button>
-                    <button onclick={toggle_dark_mode} class={format!("p-3 shadow-sm w-10 h-10 rounded-full flex justify-center items-center {}", text_color_class)}>
-                        {"Dark Mode"}
-                    </button>
-                </div>
-            </div>
-        </div>
+    /// Logs a server error and toasts it, unless the same error is already
+    /// on screen.
+    fn push_error(&mut self, ctx: &Context<Self>, error: ServerError) {
+        let id = self.next_error_id;
+        self.next_error_id += 1;
+        let duplicate = self
+            .errors
+            .iter()
+            .any(|e| e.error == error && self.toasts.contains(&e.id));
+        self.errors.push(Rc::new(ErrorEntry {
+            id,
+            error,
+            timestamp: js_sys::Date::now(),
+        }));
+        if duplicate {
+            return;
+        }
+        self.toasts.push(id);
+        let link = ctx.link().clone();
+        spawn_local(async move {
+            TimeoutFuture::new(ERROR_TOAST_MS).await;
+            link.send_message(Msg::DismissError(id));
+        });
+    }
+
+    /// Chimes and, if the tab is in the background, shows a desktop
+    /// notification for someone else's message.
+    /// While do not disturb is on it only counts the message, which is why
+    /// muted users' messages don't add to the count.
+    fn alert(&mut self, message: &MessageData) {
+        if message.notice || message.from == self.username {
+            return;
+        }
+        // Announcements get through whatever we have muted.
+        if let Some(title) = &message.announcement {
+            if self.settings.sounds() {
+                alerts::chime();
+            }
+            if self.settings.notifications() {
+                let title = match title.is_empty() {
+                    true => self.locale.t("announcement.title"),
+                    false => title,
+                };
+                alerts::notify(title, &message.message);
+            }
+            return;
+        }
+        if self.is_muted(&message.from) {
+            return;
+        }
+        if self.settings.do_not_disturb() {
+            self.missed += 1;
+            return;
+        }
+        if self.settings.sounds() {
+            alerts::chime();
+        }
+        if self.settings.notifications() && alerts::page_hidden() {
+            alerts::notify(&message.from, &message.message);
+        }
+    }
+
+    fn push_message(&mut self, mut message: MessageData) {
+        if !message.notice
+            && !self
+                .seen
+                .insert(PinKey::new(&message.from, message.timestamp))
+        {
+            return;
+        }
+        self.alert(&message);
+        if message.from != self.username && !self.is_muted(&message.from) {
+            self.announcement = announcement(&self.locale, &message);
+        }
+        let viewing = message.room == self.current_room && !self.away();
+        if !self.is_muted(&message.from)
+            && self.room_unread.arrived(&message, &self.username, viewing)
+        {
+            self.refresh_title();
+        }
+        message.id = self.next_message_id;
+        self.next_message_id += 1;
+        self.request_preview(&message);
+        message.starred = self
+            .starred
+            .contains(&PinKey::new(&message.from, message.timestamp));
+        if let Some(thread) = &message.thread_id {
+            if message.from != self.username && self.thread.as_ref() != Some(thread) {
+                *self.thread_unread.entry(thread.key()).or_default() += 1;
+            }
+        } else if !self.away() {
+            self.last_read = self.last_read.max(message.timestamp);
+        } else if self.unread_from.is_none()
+            && !message.notice
+            && message.from != self.username
+            && message.room == self.current_room
+        {
+            self.unread_from = Some(message.id);
+        }
+        self.messages.push(Rc::new(message));
+        self.schedule_persist();
+        self.refresh_search();
+    }
+
+    /// Records a pin or unpin, ours or relayed. Returns whether anything
+    /// changed, as the server echoes our own back.
+    fn apply_pin(&mut self, pin: Pin, pinned: bool) -> bool {
+        let key = pin.key();
+        if !pinned {
+            self.pins.retain(|p| p.key() != key);
+            return self.pinned.remove(&key);
+        }
+        if !self.pinned.insert(key) {
+            return false;
+        }
+        self.pins.push(pin);
+        true
+    }
+
+    /// Whether we may change the message's text: only our own, and only
+    /// plain text.
+    fn editable(&self, m: &MessageData) -> bool {
+        m.from == self.username
+            && !m.notice
+            && m.file.is_none()
+            && m.diff.is_none()
+            && m.audio.is_none()
+            && m.whiteboard.is_none()
+            && m.location.is_none()
+            && m.code_review.is_none()
+            && m.survey.is_none()
+    }
+
+    /// Our latest message in the open room that can be edited.
+    fn last_editable(&self) -> Option<usize> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.room == self.current_room && m.thread_id.is_none() && self.editable(m))
+            .map(|m| m.id)
+    }
+
+    /// Puts the sender's new text in place of their message.
+    fn apply_edit(&mut self, edit: Edit) -> bool {
+        let key = edit.key();
+        let Some(m) = self
+            .messages
+            .iter_mut()
+            .find(|m| PinKey::new(&m.from, m.timestamp) == key)
+        else {
+            return false;
+        };
+        if m.message == edit.message {
+            return false;
+        }
+        let m = Rc::make_mut(m);
+        m.message = edit.message;
+        m.edited = true;
+        let id = m.id;
+        self.translated.remove(&id);
+        self.refresh_search();
+        true
+    }
+
+    /// Takes the sender's message out of the list.
+    fn apply_delete(&mut self, delete: Delete) -> bool {
+        let key = delete.key();
+        let Some(id) = self
+            .messages
+            .iter()
+            .find(|m| PinKey::new(&m.from, m.timestamp) == key)
+            .map(|m| m.id)
+        else {
+            return false;
+        };
+        self.messages.retain(|m| m.id != id);
+        if self.editing == Some(id) {
+            self.editing = None;
+        }
+        if self.focused_message_id == Some(id) {
+            self.focused_message_id = None;
+        }
+        self.refresh_search();
+        true
+    }
+
+    /// Announcements still pinned above the messages, oldest first.
+    fn pinned_announcements(&self) -> Vec<Rc<MessageData>> {
+        let pinned_for = self.settings.announcement_pin_minutes() as f64 * 60_000.0;
+        let now = js_sys::Date::now();
+        self.messages
+            .iter()
+            .filter(|m| m.announcement.is_some() && now - m.timestamp < pinned_for)
+            .filter(|m| {
+                !self
+                    .dismissed_announcements
+                    .contains(&PinKey::new(&m.from, m.timestamp))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Re-renders when the oldest pinned announcement is due to come down.
+    fn schedule_announcement_expiry(&mut self, ctx: &Context<Self>) {
+        let pinned_for = self.settings.announcement_pin_minutes() as f64 * 60_000.0;
+        self.announcement_expiry = self.pinned_announcements().first().map(|oldest| {
+            let wait = (oldest.timestamp + pinned_for - js_sys::Date::now()).max(0.0);
+            let link = ctx.link().clone();
+            Timeout::new(wait.ceil() as u32, move || {
+                link.send_message(Msg::AnnouncementExpired)
+            })
+        });
+    }
+
+    fn scroll_to(&mut self, target: ScrollTarget) {
+        self.scroll_target = Some(target);
+        self.scroll_generation = self.scroll_generation.wrapping_add(1);
+    }
+
+    /// Marks the open room read up to its newest message.
+    fn mark_room_read(&mut self) {
+        let newest = self
+            .messages
+            .iter()
+            .filter(|m| m.room == self.current_room)
+            .map(|m| m.timestamp)
+            .reduce(f64::max);
+        if let Some(newest) = newest {
+            if self.room_unread.mark_read(&self.current_room, newest) {
+                self.refresh_title();
+            }
+            self.room_unread.save(&self.username);
+        }
+    }
+
+    /// Shows the unread total in the page title, unless we're not to be
+    /// disturbed.
+    fn refresh_title(&self) {
+        if !self.settings.do_not_disturb() {
+            alerts::show_unread_in_title(self.room_unread.total());
+        }
+    }
+
+    fn show_toast(&mut self, ctx: &Context<Self>, text: String) {
+        let link = ctx.link().clone();
+        let hide = Timeout::new(TOAST_MS, move || link.send_message(Msg::HideToast));
+        self.toast = Some((text, hide));
+    }
+
+    /// Whether new messages arrive unseen: the tab is hidden, or the list
+    /// is scrolled up.
+    fn away(&self) -> bool {
+        alerts::page_hidden() || !self.at_bottom
+    }
+
+    /// Everything so far has been seen: takes down the unread divider and
+    /// clears the open room from the title's count.
+    fn dismiss_unread(&mut self) {
+        self.unread_from = None;
+        self.unread_seen = false;
+        if let Some(newest) = self.messages.iter().last() {
+            self.last_read = self.last_read.max(newest.timestamp);
+        }
+        self.save_own(LAST_READ_KEY, &self.last_read);
+        self.mark_room_read();
+    }
+
+    /// Rebuilds the search hits, staying on the current one if it's still
+    /// there and otherwise moving to the newest.
+    fn refresh_search(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let query = search.query.trim();
+        let hits: Vec<usize> = self
+            .shown_messages()
+            .filter(|m| !self.is_muted(&m.from) && message_search::contains(&m.message, query))
+            .map(|m| m.id)
+            .collect();
+        let current = search
+            .current_id()
+            .and_then(|id| hits.iter().position(|&hit| hit == id))
+            .or_else(|| hits.len().checked_sub(1));
+        let moved = current.map(|i| hits[i]);
+        if let Some(search) = &mut self.search {
+            let changed = moved != search.current_id();
+            search.hits = hits;
+            search.current = current;
+            if let Some(id) = moved.filter(|_| changed) {
+                self.scroll_to(ScrollTarget::Message(id));
+            }
+        }
+    }
+
+    /// Shows `text` to us alone, as a system line in the open room.
+    fn push_notice(&mut self, text: String) {
+        self.push_room_notice(self.current_room.clone(), text);
+    }
+
+    fn push_room_notice(&mut self, room: Option<String>, text: String) {
+        self.push_message(MessageData {
+            message: text,
+            timestamp: js_sys::Date::now(),
+            room,
+            notice: true,
+            ..Default::default()
+        });
+    }
+
+    /// Saves the history to local storage once things have been quiet for
+    /// a moment.
+    fn schedule_persist(&mut self) {
+        let persist = self.persist.clone();
+        self.persist_timer = Some(Timeout::new(PERSIST_DEBOUNCE_MS, move || persist.emit(())));
+    }
+
+    fn is_restored(&self, message: &MessageData) -> bool {
+        message.id < self.restored
+    }
+
+    /// The open room's messages that pass the filter, oldest first. Thread
+    /// replies are left to the thread panel.
+    fn shown_messages(&self) -> impl Iterator<Item = &Rc<MessageData>> {
+        self.messages.iter().filter(|m| {
+            m.room == self.current_room
+                && m.thread_id.is_none()
+                && self.filter.matches(m, export::format_date)
+        })
+    }
+
+    /// How many replies each thread in the history has.
+    fn reply_counts(&self) -> HashMap<PinKey, usize> {
+        let mut counts = HashMap::new();
+        for thread in self.messages.iter().filter_map(|m| m.thread_id.as_ref()) {
+            *counts.entry(thread.key()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Messages can predate the first `Users` frame; fall back to the
+    /// default style.
+    fn avatar(&self, name: &str) -> String {
+        self.profile(name)
+            .map(|u| u.avatar.clone())
+            .unwrap_or_else(|| avatar_url(name, AvatarStyle::default()))
+    }
+
+    /// Who has read `message`, with their avatars.
+    fn readers(&self, message: &MessageData) -> Vec<Reader> {
+        self.receipts
+            .seen_by(&self.current_room, &message.from, message.timestamp)
+            .into_iter()
+            .map(|name| Reader {
+                name: name.to_string(),
+                avatar: self.avatar(name),
+            })
+            .collect()
+    }
+
+    /// The open thread's root and replies, for the side panel.
+    fn thread_entries(&self, thread: &ThreadId) -> (Option<ThreadEntry>, Vec<ThreadEntry>) {
+        let entry = |m: &Rc<MessageData>| ThreadEntry {
+            message: Rc::clone(m),
+            avatar: self.avatar(&m.from),
+        };
+        let key = thread.key();
+        let root = self
+            .messages
+            .iter()
+            .find(|m| m.thread_id.is_none() && PinKey::new(&m.from, m.timestamp) == key)
+            .map(entry);
+        let replies = self
+            .messages
+            .iter()
+            .filter(|m| m.thread_id.as_ref() == Some(thread))
+            .map(entry)
+            .collect();
+        (root, replies)
+    }
+
+    /// Splits the open room's history into runs of consecutive messages
+    /// from the same sender, so a muted user's burst collapses into a single
+    /// stub.
+    fn message_runs(&self) -> Vec<Vec<&Rc<MessageData>>> {
+        let mut runs: Vec<Vec<&Rc<MessageData>>> = Vec::new();
+        for m in self.shown_messages() {
+            match runs.last_mut() {
+                Some(run)
+                    if run[0].from == m.from
+                        && run[0].announcement.is_none()
+                        && m.announcement.is_none()
+                        && i18n::start_of_day(run[0].timestamp)
+                            == i18n::start_of_day(m.timestamp)
+                        && self.is_restored(run[0]) == self.is_restored(m)
+                        && self.unread_from != Some(m.id) =>
+                {
+                    run.push(m)
+                }
+                _ => runs.push(vec![m]),
+            }
+        }
+        runs
+    }
+
+    fn message_rows(&self) -> Vec<MessageRow> {
+        let current_hit = self.search.as_ref().and_then(Search::current_id);
+        let moderator = self.is_moderator();
+        let replies = self.reply_counts();
+        let my_latest = self
+            .shown_messages()
+            .filter(|m| m.from == self.username && !m.notice)
+            .last()
+            .map(|m| m.id);
+        let mut rows = Vec::new();
+        if self.messages.trimmed() > 0 {
+            rows.push(MessageRow::Trimmed);
+        }
+        let mut after_restored = false;
+        let mut day = None;
+        for run in self.message_runs() {
+            let first = run[0];
+            let first_day = i18n::start_of_day(first.timestamp);
+            if day != Some(first_day) {
+                rows.push(MessageRow::Day(first_day));
+                day = Some(first_day);
+            }
+            if after_restored && !self.is_restored(first) {
+                rows.push(MessageRow::Restored);
+            }
+            after_restored = self.is_restored(first);
+            if self.unread_from == Some(first.id) {
+                rows.push(MessageRow::Unread);
+            }
+            if first.notice {
+                rows.extend(run.into_iter().map(|m| MessageRow::Notice(Rc::clone(m))));
+                continue;
+            }
+            if first.announcement.is_some() {
+                rows.push(MessageRow::Announcement(Rc::clone(first)));
+                continue;
+            }
+            if self.is_muted(&first.from) && !self.revealed.contains(&first.id) {
+                rows.push(MessageRow::Hidden {
+                    first_id: first.id,
+                    from: first.from.clone(),
+                    count: run.len(),
+                });
+                continue;
+            }
+            rows.extend(run.into_iter().map(|m| {
+                let key = PinKey::new(&m.from, m.timestamp);
+                MessageRow::Message {
+                    message: Rc::clone(m),
+                    author: Rc::new(self.profile(&m.from).cloned().unwrap_or_else(|| {
+                        UserProfile::new(&m.from, AvatarStyle::default(), m.timestamp)
+                    })),
+                    current: current_hit == Some(m.id) || self.linked == Some(m.id),
+                    focused: self.focused_message_id == Some(m.id),
+                    pinned: self.pinned.contains(&key),
+                    own: m.from == self.username,
+                    moderator,
+                    board: m
+                        .whiteboard
+                        .as_ref()
+                        .map(|id| self.boards.get(id).cloned().unwrap_or_default()),
+                    expanded: self.expanded.contains(&m.id),
+                    translation: self.translated.get(&m.id).cloned(),
+                    answered: m
+                        .survey
+                        .as_ref()
+                        .is_some_and(|s| self.answered_surveys.contains(&s.survey_id)),
+                    preview: preview_link(m)
+                        .filter(|_| !self.dismissed_previews.contains(&m.id))
+                        .and_then(|url| self.link_previews.get(url).cloned().flatten())
+                        .filter(|preview| !preview.is_empty()),
+                    seen_by: match my_latest == Some(m.id) {
+                        true => self.readers(m),
+                        false => Vec::new(),
+                    },
+                    reactions: m
+                        .reactions
+                        .iter()
+                        .map(|tally| ReactionChip {
+                            emoji: tally.emoji.clone(),
+                            names: tally.names.clone(),
+                            mine: tally.names.contains(&self.username),
+                        })
+                        .collect(),
+                    replies: replies.get(&key).copied().unwrap_or(0),
+                    unread_replies: self.thread_unread.get(&key).copied().unwrap_or(0),
+                }
+            }));
+        }
+        if after_restored {
+            rows.push(MessageRow::Restored);
+        }
+        rows
+    }
+
+    fn export_chat(&self, format: ExportFormat) {
+        let messages = self.messages.iter().filter(|m| !m.notice).map(|m| &**m);
+        let contents = export::transcript(messages, format, export::format_time);
+        let file_name = format.file_name(&export::today());
+        if let Err(e) = export::download(&file_name, format.mime_type(), &contents) {
+            log::error!("export failed: {:?}", e);
+        }
+    }
+}
+
+impl Component for Chat {
+    type Message = Msg;
+    type Properties = ChatProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let (user, _) = ctx
+            .link()
+            .context::<User>(Callback::noop())
+            .expect("context to be set");
+        let username = user.username.borrow().clone();
+
+        let registration = Registration {
+            name: username.clone(),
+            avatar_style: *user.avatar_style.borrow(),
+            role: Role::Member,
+        };
+        let message = WebSocketMessage::builder(MsgTypes::Register)
+            .data(serde_json::to_string(&registration).unwrap())
+            .build()
+            .expect("register messages carry data");
+        let (csrf, _) = ctx
+            .link()
+            .context::<CsrfToken>(Callback::noop())
+            .expect("csrf context to be set");
+        let tx = ctx.props().transport.connect(
+            serde_json::to_string(&message).unwrap(),
+            csrf.clone(),
+            ctx.link().callback(Msg::ConnectionChanged),
+        );
+
+        let (settings, settings_listener) = ctx
+            .link()
+            .context::<SettingsHandle>(ctx.link().callback(Msg::SettingsChanged))
+            .expect("settings context to be set");
+        let (theme, theme_listener) = ctx
+            .link()
+            .context::<ThemeContext>(ctx.link().callback(Msg::ThemeChanged))
+            .expect("theme context to be set");
+        let (locale, locale_listener) = ctx
+            .link()
+            .context::<Locale>(ctx.link().callback(Msg::LocaleChanged))
+            .expect("locale context to be set");
+
+        let starred: HashSet<PinKey> =
+            session::load(&session::user_key(&username, STARRED_KEY)).unwrap_or_default();
+        let restored = local_history::restore(&username);
+        let restored_count = restored.messages.len();
+        let mut messages = RingBuffer::new(settings.history_limit());
+        let mut seen = SeenMessages::default();
+        for (id, mut message) in restored.messages.into_iter().enumerate() {
+            let key = PinKey::new(&message.from, message.timestamp);
+            message.id = id;
+            message.starred = starred.contains(&key);
+            seen.insert(key);
+            messages.push(Rc::new(message));
+        }
+        // Opened from a copied message link: show that message.
+        let linked = web_sys::window()
+            .and_then(|w| w.location().hash().ok())
+            .and_then(|hash| anchor_timestamp(&hash))
+            .and_then(|timestamp| messages.iter().find(|m| m.timestamp == timestamp))
+            .map(|m| (m.id, m.room.clone().filter(|r| restored.rooms.contains(r))));
+        let current_room = linked.as_ref().and_then(|(_, room)| room.clone());
+        let mut room_unread = RoomUnread::restore(messages.iter().map(|m| &**m), &username);
+        // The room we open on is read as soon as it's seen.
+        let newest = messages
+            .iter()
+            .filter(|m| m.room == current_room)
+            .map(|m| m.timestamp)
+            .reduce(f64::max);
+        if let Some(newest) = newest.filter(|_| !alerts::page_hidden()) {
+            room_unread.mark_read(&current_room, newest);
+        }
+        // Messages saved after we last looked, e.g. while the tab was in the
+        // background, are unread.
+        let stored_last_read: Option<f64> =
+            session::load(&session::user_key(&username, LAST_READ_KEY));
+        let first_visit =
+            session::load::<bool>(&session::user_key(&username, FIRST_VISIT_KEY)).is_none();
+        let unread_from = stored_last_read.and_then(|last_read| {
+            messages
+                .iter()
+                .find(|m| m.timestamp > last_read && m.from != username)
+                .map(|m| m.id)
+        });
+        let last_read = stored_last_read.unwrap_or_default();
+        // Known for rendering the old messages, but not online until the
+        // server says so.
+        let departed = restored
+            .users
+            .into_iter()
+            .filter(|u| !u.bot)
+            .map(|u| (u.name.clone(), u))
+            .collect();
+        let answered_surveys =
+            session::load(&session::user_key(&username, SURVEYS_KEY)).unwrap_or_default();
+        let muted = session::load(&session::user_key(&username, MUTED_KEY)).unwrap_or_default();
+        let input_history = InputHistory::restore(&username);
+
+        Self {
+            username,
+            users: Rc::new(
+                [(BOT_NAME.to_string(), UserProfile::bot())]
+                    .into_iter()
+                    .collect(),
+            ),
+            departed,
+            show_popover: ctx.link().callback(Msg::ShowPopover),
+            message_actions: Rc::new(MessageActions {
+                on_react: Some(ctx.link().callback(Msg::OpenReactions)),
+                on_toggle_reaction: Some(ctx.link().callback(Msg::React)),
+                on_reply: Some(ctx.link().callback(Msg::Reply)),
+                on_edit: Some(ctx.link().callback(Msg::StartEdit)),
+                on_delete: Some(ctx.link().callback(Msg::AskDelete)),
+                on_copy: Some(ctx.link().callback(Msg::CopyMessage)),
+                on_copy_link: Some(ctx.link().callback(Msg::CopyLink)),
+                on_toggle_pin: Some(ctx.link().callback(Msg::TogglePin)),
+                on_toggle_star: Some(ctx.link().callback(Msg::ToggleStar)),
+                on_open_image: Some(ctx.link().callback(Msg::OpenImage)),
+                on_open_thread: Some(ctx.link().callback(Msg::OpenThread)),
+                on_review_comment: Some(ctx.link().callback(Msg::CommentOnReview)),
+                on_answer_survey: Some(ctx.link().callback(Msg::AnswerSurvey)),
+                on_dismiss_preview: Some(ctx.link().callback(Msg::DismissPreview)),
+                on_translate: Some(ctx.link().callback(Msg::Translate)),
+                on_show_original: Some(ctx.link().callback(Msg::ShowOriginal)),
+                on_toggle_expand: Some(ctx.link().callback(Msg::ToggleExpanded)),
+                on_pin_to_room: Some(ctx.link().callback(Msg::PinToRoom)),
+            }),
+            pins: Vec::new(),
+            room_pins: HashMap::new(),
+            pinned: HashSet::new(),
+            starred,
+            answered_surveys,
+            link_previews: HashMap::new(),
+            dismissed_previews: HashSet::new(),
+            boards: HashMap::new(),
+            draw: ctx.link().callback(Msg::Draw),
+            next_stroke_id: (js_sys::Date::now() / 1000.0) as u32,
+            messages,
+            next_message_id: restored_count,
+            restored: restored_count,
+            persist: ctx.link().callback(|_| Msg::PersistHistory),
+            persist_timer: None,
+            errors: RingBuffer::new(ERROR_LOG_LIMIT),
+            toasts: vec![],
+            next_error_id: 0,
+            error_log_open: false,
+            rooms: restored.rooms,
+            current_room,
+            invites: vec![],
+            muted_until: None,
+            mute_timers: None,
+            send_limit: TokenBucket::new(js_sys::Date::now()),
+            slow_until: None,
+            slow_timers: None,
+            broadcast_warning: None,
+            critical_broadcasts: Vec::new(),
+            disconnecting: None,
+            sent: SendGuard::default(),
+            send_pause: None,
+            seen,
+            room_unread,
+            missed: 0,
+            idle: false,
+            _idle_tracker: idle_tracker(ctx, settings.away_after_minutes()),
+            _shortcuts: None,
+            recorder: None,
+            recording_timer: None,
+            chat_input: NodeRef::default(),
+            draft: TextCount::default(),
+            completion: None,
+            input_history,
+            editing: None,
+            reply_to: None,
+            file_input: NodeRef::default(),
+            attach_open: false,
+            gif_open: false,
+            stickers_open: false,
+            emoji_open: false,
+            reacting_to: None,
+            pasted_images: VecDeque::new(),
+            dragging: false,
+            lightbox: None,
+            translations: HashMap::new(),
+            translated: HashMap::new(),
+            thread: None,
+            thread_unread: HashMap::new(),
+            tx,
+            _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
+            csrf,
+            bot: ChatBot::bridge(ctx.link().callback(|m| Msg::BotReply(Box::new(m)))),
+            settings,
+            _settings_listener: settings_listener,
+            theme,
+            _theme_listener: theme_listener,
+            locale,
+            _locale_listener: locale_listener,
+            export_open: false,
+            review_open: false,
+            filter: Filter::default(),
+            search: None,
+            scroll_target: linked.as_ref().map(|(id, _)| ScrollTarget::Message(*id)),
+            linked: linked.map(|(id, _)| id),
+            focused_message_id: None,
+            confirm_delete: None,
+            toast: None,
+            dismissed_announcements: HashSet::new(),
+            announcement_expiry: None,
+            announcement: String::new(),
+            scroll_generation: 0,
+            last_read,
+            unread_from,
+            unread_seen: false,
+            at_bottom: true,
+            refocus: false,
+            _visibility: VisibilityListener::new(ctx.link().callback(Msg::VisibilityChanged)),
+            _focus: web_sys::window().map(|window| {
+                let link = ctx.link().clone();
+                EventListener::new(&window, "focus", move |_| link.send_message(Msg::Focused))
+            }),
+            visible_rows: 0..0,
+            receipts: Receipts::default(),
+            sent_receipts: HashMap::new(),
+            receipt_timer: None,
+            commands: Commands::builtin(),
+            settings_open: false,
+            people_open: false,
+            stats_open: false,
+            topics: HashMap::new(),
+            stats: None,
+            welcome_open: first_visit,
+            shortcuts_open: false,
+            confetti: Cooldowns::default(),
+            confetti_playing: false,
+            connection: ConnectionState::default(),
+            popover: None,
+            muted,
+            revealed: HashSet::new(),
+            expanded: HashSet::new(),
+            anchor: None,
+            call: None,
+            incoming_call: None,
+            pending_ice: vec![],
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::ToggleDarkMode => {
+                // Re-renders through `ThemeChanged`.
+                self.theme.toggle();
+                false
+            }
+            Msg::ThemeChanged(theme) => {
+                self.theme = theme;
+                true
+            }
+            Msg::AcceptInvite => {
+                if self.invites.is_empty() {
+                    return false;
+                }
+                let invite = self.invites.remove(0);
+                let join = JoinRoom {
+                    room_name: invite.room_name.clone(),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::JoinRoom)
+                    .data(serde_json::to_string(&join).unwrap())
+                    .build()
+                    .expect("join messages carry data");
+                self.send(&message);
+                if !self.rooms.contains(&invite.room_name) {
+                    self.rooms.push(invite.room_name.clone());
+                }
+                self.current_room = Some(invite.room_name);
+                self.schedule_persist();
+                true
+            }
+            Msg::DeclineInvite => {
+                if self.invites.is_empty() {
+                    return false;
+                }
+                let invite = self.invites.remove(0);
+                let message = WebSocketMessage::builder(MsgTypes::DeclineInvite)
+                    .data(serde_json::to_string(&invite).unwrap())
+                    .build()
+                    .expect("decline messages carry data");
+                self.send(&message);
+                true
+            }
+            Msg::SelectRoom(room) => {
+                let changed = self.current_room != room;
+                self.current_room = room;
+                self.mark_room_read();
+                self.refresh_search();
+                changed
+            }
+            Msg::BotReply(message) => {
+                self.push_message(*message);
+                true
+            }
+            Msg::PersistHistory => {
+                self.persist_timer = None;
+                self.save_own(LAST_READ_KEY, &self.last_read);
+                self.room_unread.save(&self.username);
+                local_history::save(
+                    &self.username,
+                    self.messages.iter().filter(|m| !m.notice).map(|m| &**m),
+                    &self.rooms,
+                    self.users.values().chain(self.departed.values()),
+                );
+                false
+            }
+            Msg::SetFilter(filter) => {
+                self.filter = filter;
+                self.refresh_search();
+                true
+            }
+            Msg::ToggleSearch => {
+                self.search = match self.search {
+                    Some(_) => None,
+                    None => Some(Search::default()),
+                };
+                self.refocus = self.search.is_none();
+                true
+            }
+            Msg::SearchQuery(query) => {
+                if let Some(search) = &mut self.search {
+                    search.query = query;
+                    search.current = None;
+                }
+                self.refresh_search();
+                true
+            }
+            Msg::SearchStep(forward) => {
+                let Some(search) = self.search.as_mut().filter(|s| !s.hits.is_empty()) else {
+                    return false;
+                };
+                let len = search.hits.len();
+                let current = match (search.current, forward) {
+                    (Some(i), true) => (i + 1) % len,
+                    (Some(i), false) => (i + len - 1) % len,
+                    (None, _) => len - 1,
+                };
+                search.current = Some(current);
+                let id = search.hits[current];
+                self.scroll_to(ScrollTarget::Message(id));
+                true
+            }
+            Msg::VisibilityChanged(hidden) => {
+                if hidden {
+                    return false;
+                }
+                self.schedule_receipt(ctx);
+                // With a divider up, it and the title wait to be scrolled past.
+                if self.unread_from.is_none() && self.at_bottom {
+                    self.dismiss_unread();
+                }
+                true
+            }
+            Msg::TypeIntoInput(key) => {
+                let Some(input) = self
+                    .chat_input
+                    .cast::<HtmlInputElement>()
+                    .filter(|i| !i.disabled())
+                else {
+                    return false;
+                };
+                let value = format!("{}{}", input.value(), key);
+                self.set_draft(&input, &value);
+                let _ = input.focus();
+                let end = utf16_len(&value);
+                let _ = input.set_selection_range(end, end);
+                true
+            }
+            Msg::AtBottom(at_bottom) => {
+                self.at_bottom = at_bottom;
+                if at_bottom && self.unread_from.is_none() && !alerts::page_hidden() {
+                    self.dismiss_unread();
+                    return true;
+                }
+                false
+            }
+            Msg::Focused => {
+                self.schedule_receipt(ctx);
+                false
+            }
+            Msg::SendReceipt => {
+                self.receipt_timer = None;
+                self.send_receipt();
+                false
+            }
+            Msg::VisibleRows(in_view) => {
+                self.visible_rows = in_view.clone();
+                self.schedule_receipt(ctx);
+                if self.unread_from.is_none() {
+                    return false;
+                }
+                let rows = self.message_rows();
+                let Some(index) = rows
+                    .iter()
+                    .position(|row| matches!(row, MessageRow::Unread))
+                else {
+                    return false;
+                };
+                if in_view.contains(&index) {
+                    self.unread_seen = true;
+                    false
+                } else if self.unread_seen && index < in_view.start {
+                    self.dismiss_unread();
+                    true
+                } else {
+                    false
+                }
+            }
+            Msg::JumpToUnread => {
+                self.scroll_to(ScrollTarget::Unread);
+                true
+            }
+            Msg::DismissWelcome => {
+                self.welcome_open = false;
+                self.save_own(FIRST_VISIT_KEY, &true);
+                true
+            }
+            Msg::OpenImage(id) => {
+                let images: Vec<(usize, LightboxImage)> = self
+                    .shown_messages()
+                    .filter(|m| m.image.is_some() || (!m.action && is_image(&m.message)))
+                    .map(|m| {
+                        let image = LightboxImage {
+                            url: m.image.clone().unwrap_or_else(|| m.message.clone()),
+                            from: m.from.clone(),
+                        };
+                        (m.id, image)
+                    })
+                    .collect();
+                let Some(start) = images.iter().position(|(image_id, _)| *image_id == id) else {
+                    return false;
+                };
+                let images = images.into_iter().map(|(_, image)| image).collect();
+                self.lightbox = Some((Rc::new(images), start));
+                true
+            }
+            Msg::CloseLightbox => {
+                self.refocus = true;
+                self.lightbox.take().is_some()
+            }
+            Msg::Translate(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id && m.is_text()) else {
+                    return false;
+                };
+                let key = (
+                    PinKey::new(&m.from, m.timestamp),
+                    self.settings.language().tag().to_string(),
+                );
+                if let Some(text) = self.translations.get(&key) {
+                    self.translated.insert(id, Translation::Text(text.clone()));
+                    return true;
+                }
+                if self.translated.get(&id) == Some(&Translation::Loading) {
+                    return false;
+                }
+                self.translated.insert(id, Translation::Loading);
+                let config = ctx.props().translate.clone();
+                let text = m.message.clone();
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let result = translate::translate(&config, &text, &key.1).await;
+                    link.send_message(Msg::Translated(id, key, result));
+                });
+                true
+            }
+            Msg::Translated(id, key, result) => {
+                let shown = match result {
+                    Ok(text) => {
+                        self.translations.insert(key, text.clone());
+                        Translation::Text(text)
+                    }
+                    Err(e) => {
+                        log::warn!("translation failed: {}", e);
+                        Translation::Unavailable
+                    }
+                };
+                // Dropped if "Show original" was clicked while it loaded.
+                match self.translated.get_mut(&id) {
+                    Some(translation) => {
+                        *translation = shown;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Msg::ShowOriginal(id) => self.translated.remove(&id).is_some(),
+            Msg::OpenThread(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return false;
+                };
+                // A reply opens the thread it belongs to.
+                let thread = m.thread_id.clone().unwrap_or_else(|| ThreadId {
+                    from: m.from.clone(),
+                    timestamp: m.timestamp,
+                });
+                self.thread_unread.remove(&thread.key());
+                self.thread = Some(thread);
+                true
+            }
+            Msg::CloseThread => {
+                self.refocus = true;
+                self.thread.take().is_some()
+            }
+            Msg::SendThreadReply(text) => {
+                let Some(thread) = &self.thread else {
+                    return false;
+                };
+                let data = OutgoingMessage {
+                    room: self.current_room.clone(),
+                    message: text,
+                    action: false,
+                    thread_id: Some(thread.clone()),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::ThreadReply)
+                    .data(data.to_data())
+                    .build()
+                    .expect("thread replies carry data");
+                self.send(&message);
+                false
+            }
+            Msg::ToggleEmojiPicker => {
+                self.emoji_open = !self.emoji_open;
+                self.reacting_to = None;
+                self.gif_open = false;
+                self.stickers_open = false;
+                self.refocus = !self.emoji_open;
+                true
+            }
+            Msg::InsertEmoji(emoji) => {
+                self.emoji_open = false;
+                let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                    return true;
+                };
+                let value = input.value();
+                let caret = input
+                    .selection_start()
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| utf16_len(&value));
+                let at = byte_index(&value, caret);
+                self.set_draft(
+                    &input,
+                    &format!("{}{}{}", &value[..at], emoji, &value[at..]),
+                );
+                let caret = caret + utf16_len(&emoji);
+                let _ = input.focus();
+                let _ = input.set_selection_range(caret, caret);
+                true
+            }
+            Msg::OpenReactions(id) => {
+                self.reacting_to = Some(id);
+                self.emoji_open = false;
+                self.gif_open = false;
+                self.stickers_open = false;
+                true
+            }
+            Msg::CloseReactions => {
+                self.refocus = true;
+                self.reacting_to.take().is_some()
+            }
+            Msg::React((id, emoji)) => {
+                self.reacting_to = None;
+                let Some(m) = self.messages.iter().find(|m| m.id == id && !m.notice) else {
+                    return true;
+                };
+                let mine = m
+                    .reactions
+                    .iter()
+                    .any(|t| t.emoji == emoji && t.names.contains(&self.username));
+                let reaction = Reaction {
+                    from: String::new(),
+                    sender: m.from.clone(),
+                    timestamp: m.timestamp,
+                    emoji,
+                    remove: mine,
+                    room: m.room.clone(),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Reaction)
+                    .data(serde_json::to_string(&reaction).unwrap())
+                    .build()
+                    .expect("reactions carry data");
+                self.send(&message);
+                true
+            }
+            Msg::ToggleGifPicker => {
+                self.gif_open = !self.gif_open;
+                self.stickers_open = false;
+                self.emoji_open = false;
+                self.refocus = !self.gif_open;
+                true
+            }
+            Msg::ToggleStickers => {
+                self.stickers_open = !self.stickers_open;
+                self.gif_open = false;
+                self.emoji_open = false;
+                self.refocus = !self.stickers_open;
+                true
+            }
+            Msg::SendImage(url) => {
+                self.gif_open = false;
+                self.stickers_open = false;
+                self.refocus = true;
+                self.send_text(url, false);
+                true
+            }
+            Msg::ConfettiDone => {
+                self.confetti_playing = false;
+                true
+            }
+            Msg::DismissUnread => {
+                self.dismiss_unread();
+                true
+            }
+            Msg::ToggleStar(id) => {
+                let Some(m) = self.messages.iter_mut().find(|m| m.id == id) else {
+                    return false;
+                };
+                let m = Rc::make_mut(m);
+                let key = PinKey::new(&m.from, m.timestamp);
+                m.starred = !self.starred.remove(&key);
+                if m.starred {
+                    self.starred.insert(key);
+                }
+                self.save_own(STARRED_KEY, &self.starred);
+                self.refresh_search();
+                true
+            }
+            Msg::ToggleStarredOnly => {
+                self.filter.starred = !self.filter.starred;
+                self.refresh_search();
+                true
+            }
+            Msg::ClearStars => {
+                self.starred.clear();
+                self.save_own(STARRED_KEY, &self.starred);
+                for m in self.messages.iter_mut().filter(|m| m.starred) {
+                    Rc::make_mut(m).starred = false;
+                }
+                self.refresh_search();
+                true
+            }
+            Msg::TogglePin(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return false;
+                };
+                let pin = Pin {
+                    from: m.from.clone(),
+                    timestamp: m.timestamp,
+                    message: m.message.clone(),
+                    room: m.room.clone(),
+                };
+                let pinned = !self.pinned.contains(&pin.key());
+                let kind = if pinned {
+                    MsgTypes::Pin
+                } else {
+                    MsgTypes::Unpin
+                };
+                let message = WebSocketMessage::builder(kind)
+                    .data(serde_json::to_string(&pin).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.apply_pin(pin, pinned)
+            }
+            Msg::Reply(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return false;
+                };
+                let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                let mention = format!("@{} ", m.from);
+                let value = input.value();
+                let _ = input.focus();
+                self.reply_to = Some(ReplyPreview::of(m));
+                if !value.starts_with(&mention) {
+                    self.set_draft(&input, &format!("{}{}", mention, value));
+                }
+                true
+            }
+            Msg::CancelReply => {
+                self.refocus = true;
+                self.cancel_reply()
+            }
+            Msg::CopyMessage(id) | Msg::CopyLink(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return false;
+                };
+                let (text, done) = match msg {
+                    Msg::CopyLink(_) => {
+                        let Some(url) = message_link(m) else {
+                            return false;
+                        };
+                        (url, "toast.link_copied")
+                    }
+                    _ => (m.message.clone(), "toast.copied"),
+                };
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let key = match clipboard::write_text(&text).await {
+                        Ok(()) => done,
+                        Err(e) => {
+                            log::error!("copy failed: {:?}", e);
+                            "toast.copy_failed"
+                        }
+                    };
+                    link.send_message(Msg::ShowToast(key));
+                });
+                false
+            }
+            Msg::ShowToast(key) => {
+                self.show_toast(ctx, self.locale.t(key).to_string());
+                true
+            }
+            Msg::HideToast => self.toast.take().is_some(),
+            Msg::Unpin(pin) => {
+                let message = WebSocketMessage::builder(MsgTypes::Unpin)
+                    .data(serde_json::to_string(&pin).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.apply_pin(pin, false)
+            }
+            Msg::Draw(draw) => {
+                let stroke = match draw {
+                    Draw::Stroke { board, d } => {
+                        self.next_stroke_id = self.next_stroke_id.wrapping_add(1);
+                        WhiteboardStroke {
+                            board,
+                            from: self.username.clone(),
+                            id: self.next_stroke_id,
+                            d,
+                            erase: false,
+                        }
+                    }
+                    Draw::Undo { board } => {
+                        let last = self
+                            .boards
+                            .get(&board)
+                            .and_then(|b| b.last_by(&self.username));
+                        let Some(last) = last else {
+                            return false;
+                        };
+                        WhiteboardStroke {
+                            d: String::new(),
+                            erase: true,
+                            ..last.clone()
+                        }
+                    }
+                };
+                let message = WebSocketMessage::builder(MsgTypes::WhiteboardStroke)
+                    .data(serde_json::to_string(&stroke).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                let board = self.boards.entry(stroke.board.clone()).or_default();
+                Rc::make_mut(board).apply(stroke)
+            }
+            Msg::ShowPinned(key) => {
+                // Gone if it has been trimmed, or hidden by the filter.
+                let id = self
+                    .shown_messages()
+                    .find(|m| PinKey::new(&m.from, m.timestamp) == key)
+                    .map(|m| m.id);
+                let Some(id) = id else {
+                    return false;
+                };
+                self.scroll_to(ScrollTarget::Message(id));
+                true
+            }
+            Msg::PinToRoom(id) => {
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return false;
+                };
+                let m = Rc::clone(m);
+                let message = WebSocketMessage::builder(MsgTypes::PinnedMessage)
+                    .data(serde_json::to_string(&*m).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.room_pins.insert(m.room.clone(), m);
+                true
+            }
+            Msg::UnpinFromRoom => {
+                let unpin = UnpinMessage {
+                    room: self.current_room.clone(),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::UnpinMessage)
+                    .data(serde_json::to_string(&unpin).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.room_pins.remove(&unpin.room).is_some()
+            }
+            Msg::ShowRoomPin => {
+                let Some(pinned) = self.room_pins.get(&self.current_room) else {
+                    return false;
+                };
+                // Gone if it has been trimmed, or hidden by the filter.
+                let id = self
+                    .shown_messages()
+                    .find(|m| m.from == pinned.from && m.timestamp == pinned.timestamp)
+                    .map(|m| m.id);
+                let Some(id) = id else {
+                    return false;
+                };
+                self.linked = Some(id);
+                self.scroll_to(ScrollTarget::Message(id));
+                true
+            }
+            Msg::Shortcut(action) => match action {
+                Action::Escape => {
+                    if self.shortcuts_open {
+                        self.shortcuts_open = false;
+                        self.refocus = true;
+                        return true;
+                    }
+                    if self.gif_open
+                        || self.stickers_open
+                        || self.emoji_open
+                        || self.reacting_to.is_some()
+                    {
+                        self.gif_open = false;
+                        self.stickers_open = false;
+                        self.emoji_open = false;
+                        self.reacting_to = None;
+                        self.refocus = true;
+                        return true;
+                    }
+                    if self.pasted_images.pop_front().is_some() {
+                        self.refocus = true;
+                        return true;
+                    }
+                    if self.thread.take().is_some() {
+                        self.refocus = true;
+                        return true;
+                    }
+                    let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                        return false;
+                    };
+                    if !is_focused(&input) {
+                        return false;
+                    }
+                    self.set_draft(&input, "");
+                    let _ = input.blur();
+                    self.completion = None;
+                    self.editing = None;
+                    self.reply_to = None;
+                    true
+                }
+                Action::Search => {
+                    if self.search.is_some() {
+                        return false;
+                    }
+                    self.search = Some(Search::default());
+                    true
+                }
+                Action::FocusUserFilter => {
+                    focus_by_id("user-filter");
+                    false
+                }
+                Action::PreviousRoom | Action::NextRoom => {
+                    if self.rooms.is_empty() {
+                        return false;
+                    }
+                    // The main chat comes before the rooms, and the ends wrap.
+                    let count = self.rooms.len() + 1;
+                    let current = self
+                        .current_room
+                        .as_ref()
+                        .and_then(|room| self.rooms.iter().position(|r| r == room))
+                        .map_or(0, |i| i + 1);
+                    let next = if action == Action::NextRoom {
+                        (current + 1) % count
+                    } else {
+                        (current + count - 1) % count
+                    };
+                    let room = next.checked_sub(1).map(|i| self.rooms[i].clone());
+                    ctx.link().send_message(Msg::SelectRoom(room));
+                    false
+                }
+                Action::FocusInput => {
+                    self.focus_input();
+                    false
+                }
+                Action::ShowShortcuts => {
+                    self.shortcuts_open = true;
+                    true
+                }
+            },
+            Msg::CloseShortcuts => {
+                self.shortcuts_open = false;
+                self.refocus = true;
+                true
+            }
+            Msg::ToggleExport => {
+                self.export_open = !self.export_open;
+                self.refocus = !self.export_open;
+                true
+            }
+            Msg::ToggleSettings => {
+                self.settings_open = !self.settings_open;
+                self.refocus = !self.settings_open;
+                true
+            }
+            Msg::TogglePeople => {
+                self.people_open = !self.people_open;
+                self.refocus = !self.people_open;
+                true
+            }
+            Msg::ToggleStats => {
+                self.stats_open = !self.stats_open;
+                self.refocus = !self.stats_open;
+                true
+            }
+            Msg::RequestStats => {
+                // The request itself carries nothing.
+                let message = WebSocketMessage::builder(MsgTypes::StatsRequest)
+                    .data("")
+                    .build()
+                    .expect("stats requests carry data");
+                self.send(&message);
+                false
+            }
+            Msg::DismissError(id) => {
+                let before = self.toasts.len();
+                self.toasts.retain(|&t| t != id);
+                self.toasts.len() != before
+            }
+            Msg::ServerMute((target, duration_secs)) => {
+                let mute = Mute {
+                    from: String::new(),
+                    target,
+                    duration_secs,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Mute)
+                    .data(serde_json::to_string(&mute).unwrap())
+                    .build()
+                    .expect("mute messages carry data");
+                self.send(&message);
+                self.popover = None;
+                true
+            }
+            Msg::Kick(target) => {
+                let kick = Kick {
+                    from: String::new(),
+                    target,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Kick)
+                    .data(serde_json::to_string(&kick).unwrap())
+                    .build()
+                    .expect("kick messages carry data");
+                self.send(&message);
+                self.popover = None;
+                true
+            }
+            Msg::MuteTick => self.muted_until.is_some(),
+            Msg::MuteExpired => {
+                self.muted_until = None;
+                self.mute_timers = None;
+                true
+            }
+            Msg::SlowDownTick => self.slow_until.is_some(),
+            Msg::DismissBroadcast => self.broadcast_warning.take().is_some(),
+            Msg::AcknowledgeBroadcast => {
+                if self.critical_broadcasts.is_empty() {
+                    return false;
+                }
+                self.critical_broadcasts.remove(0);
+                self.refocus = self.critical_broadcasts.is_empty();
+                true
+            }
+            Msg::MaintenanceTick => {
+                let Some((_, seconds, _)) = &mut self.disconnecting else {
+                    return false;
+                };
+                *seconds = seconds.saturating_sub(1);
+                if *seconds == 0 {
+                    if let Some(history) = ctx.link().history() {
+                        history.push(Route::Maintenance);
+                    }
+                    return false;
+                }
+                true
+            }
+            Msg::SendPauseOver => self.send_pause.take().is_some(),
+            Msg::SlowDownOver => {
+                self.slow_until = None;
+                self.slow_timers = None;
+                ctx.link().send_message(Msg::SubmitMessage);
+                true
+            }
+            Msg::ToggleErrorLog => {
+                self.error_log_open = !self.error_log_open;
+                self.refocus = !self.error_log_open;
+                true
+            }
+            Msg::LocaleChanged(locale) => {
+                self.locale = locale;
+                true
+            }
+            Msg::SettingsChanged(settings) => {
+                if settings.history_limit() != self.messages.limit() {
+                    self.messages.set_limit(settings.history_limit());
+                }
+                let away_changed =
+                    settings.away_after_minutes() != self.settings.away_after_minutes();
+                let dnd_changed = settings.do_not_disturb() != self.settings.do_not_disturb();
+                self.settings = settings;
+                if away_changed {
+                    self._idle_tracker = idle_tracker(ctx, self.settings.away_after_minutes());
+                    self.idle = false;
+                }
+                if away_changed || dnd_changed {
+                    self.refresh_presence();
+                }
+                if dnd_changed {
+                    self.refresh_title();
+                }
+                self.schedule_announcement_expiry(ctx);
+                if dnd_changed && !self.settings.do_not_disturb() && self.missed > 0 {
+                    let missed = std::mem::take(&mut self.missed);
+                    let key = if missed == 1 {
+                        "dnd.missed_one"
+                    } else {
+                        "dnd.missed_many"
+                    };
+                    let text = self.locale.format(key, &[("count", &missed.to_string())]);
+                    self.show_toast(ctx, text);
+                }
+                true
+            }
+            Msg::ExportChat(format) => {
+                self.export_chat(format);
+                self.export_open = false;
+                true
+            }
+            Msg::ConnectionChanged(state) => {
+                // The server forgets us when the connection drops.
+                let reconnected = state.status == ConnectionStatus::Online
+                    && self.connection.status != ConnectionStatus::Online;
+                let presence = self.effective_presence();
+                if reconnected && presence != Presence::Online {
+                    self.send_presence(presence);
+                }
+                self.connection = state;
+                true
+            }
+            Msg::SetPresence(presence) => {
+                self.settings
+                    .set_do_not_disturb(presence == Presence::DoNotDisturb);
+                false
+            }
+            Msg::ToggleDoNotDisturb => {
+                self.settings
+                    .set_do_not_disturb(!self.settings.do_not_disturb());
+                false
+            }
+            Msg::Idle(idle) => {
+                self.idle = idle;
+                self.refresh_presence()
+            }
+            Msg::SetStatus(status) => {
+                let message = WebSocketMessage::builder(MsgTypes::Status)
+                    .data(status.clone())
+                    .build()
+                    .expect("status messages carry data");
+                self.send(&message);
+                let username = self.username.clone();
+                self.set_status(&username, status)
+            }
+            Msg::ShowPopover(popover) => {
+                self.popover = Some(popover);
+                true
+            }
+            Msg::ClosePopover => {
+                self.refocus = true;
+                self.popover.take().is_some()
+            }
+            Msg::MessagePrivately(name) => {
+                if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
+                    self.set_draft(&input, &format!("@{} ", name));
+                }
+                self.refocus = true;
+                self.popover = None;
+                true
+            }
+            Msg::ToggleMute(name) => {
+                if !self.muted.remove(&name) {
+                    self.muted.insert(name);
+                }
+                self.save_own(MUTED_KEY, &self.muted);
+                self.popover = None;
+                true
+            }
+            Msg::RevealMuted(start) => self.revealed.insert(start),
+            Msg::ToggleExpanded(id) => {
+                if !self.expanded.remove(&id) {
+                    self.expanded.insert(id);
+                }
+                let generation = self.anchor.map_or(0, |(_, g)| g.wrapping_add(1));
+                self.anchor = Some((id, generation));
+                true
+            }
+            Msg::StartCall(peer) => {
+                if self.call.is_some() {
+                    return false;
+                }
+                self.open_call(ctx, peer, |call| async move {
+                    Ok(CallSignal::Offer {
+                        sdp: call.offer().await?,
+                    })
+                });
+                true
+            }
+            Msg::AcceptCall => match self.incoming_call.take() {
+                Some((peer, offer)) => {
+                    self.open_call(ctx, peer, |call| async move {
+                        Ok(CallSignal::Answer {
+                            sdp: call.answer(&offer).await?,
+                        })
+                    });
+                    true
+                }
+                None => false,
+            },
+            Msg::DeclineCall => match self.incoming_call.take() {
+                Some((peer, _)) => {
+                    self.pending_ice.clear();
+                    self.send_signal(peer, CallSignal::Hangup);
+                    true
+                }
+                None => false,
+            },
+            Msg::HangUp => match self.end_call() {
+                Some(peer) => {
+                    self.send_signal(peer, CallSignal::Hangup);
+                    true
+                }
+                None => false,
+            },
+            Msg::CallConnected(connection) => match self.call.as_mut() {
+                Some(call) => {
+                    call.connection = Some(connection.clone());
+                    for signal in self.pending_ice.drain(..) {
+                        add_ice_candidate(connection.clone(), signal);
+                    }
+                    true
+                }
+                // Hung up while the camera was opening.
+                None => {
+                    connection.hangup();
+                    false
+                }
+            },
+            Msg::RemoteStream(stream) => match self.call.as_mut() {
+                Some(call) => {
+                    call.remote = Some(stream);
+                    true
+                }
+                None => false,
+            },
+            Msg::SendSignal((to, signal)) => {
+                self.send_signal(to, signal);
+                false
+            }
+            Msg::Rename(new_name) => {
+                let new_name = new_name.trim().to_string();
+                if !is_valid_username(&new_name) || self.users.contains_key(&new_name) {
+                    return false;
+                }
+                let rename = Rename {
+                    old_name: self.username.clone(),
+                    new_name,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Rename)
+                    .data(serde_json::to_string(&rename).unwrap())
+                    .build()
+                    .expect("rename messages carry data");
+                self.send(&message);
+                self.popover = None;
+                self.apply_rename(ctx, &rename.old_name, &rename.new_name)
+            }
+            Msg::SwitchUser => {
+                session::forget(&self.username);
+                let (user, _) = ctx
+                    .link()
+                    .context::<User>(Callback::noop())
+                    .expect("context to be set");
+                *user.token.borrow_mut() = None;
+                if let Some(history) = ctx.link().history() {
+                    history.push(Route::Login);
+                }
+                false
+            }
+            Msg::HandleMsg(s) => {
+                let msg: WebSocketMessage = match serde_json::from_str(&s) {
+                    Ok(msg) => msg,
+                    Err(e) => {
+                        log::warn!("bad frame: {}", e);
+                        return false;
+                    }
+                };
+                // Posted messages are named by the server's clock, never
+                // ours, so every client names them alike.
+                let timestamp = msg.timestamp.unwrap_or_default();
+                if msg.message_type.is_posted() && msg.timestamp.is_none() {
+                    log::error!("{:?} frame without a server timestamp", msg.message_type);
+                    return false;
+                }
+                if already_seen(&self.seen, &msg) {
+                    return false;
+                }
+                match msg.message_type {
+                    MsgTypes::Users => {
+                        let users_from_message = msg.data_array.unwrap_or_default();
+                        let mut previous = std::mem::take(Rc::make_mut(&mut self.users));
+                        let bot = previous.shift_remove(BOT_NAME);
+                        let mut users = merge_users(
+                            previous,
+                            &mut self.departed,
+                            &users_from_message,
+                            js_sys::Date::now(),
+                        );
+                        users.extend(bot.map(|bot| (BOT_NAME.to_string(), bot)));
+                        sort_users(&mut users, &self.username);
+                        self.users = Rc::new(users);
+                        let (me, presence) = (self.username.clone(), self.effective_presence());
+                        self.set_presence(&me, presence);
+                        self.schedule_persist();
+                        return true;
+                    }
+                    MsgTypes::Message | MsgTypes::ThreadReply => {
+                        let Some(data) = msg.payload::<MessageData>() else {
+                            return false;
+                        };
+                        let message_data = MessageData { timestamp, ..data };
+                        // The server should only send us our rooms' messages,
+                        // but don't show anything from a room we aren't in.
+                        if message_data
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        // The bot answers in the room, so it stays out of threads.
+                        if message_data.thread_id.is_none() {
+                            self.bot.send(message_data.clone());
+                        }
+                        self.push_message(message_data);
+                        self.schedule_receipt(ctx);
+                        return true;
+                    }
+                    MsgTypes::Read => {
+                        let Some(receipt) = msg.payload::<ReadReceipt>() else {
+                            return false;
+                        };
+                        if receipt
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        return self.receipts.record(&receipt);
+                    }
+                    MsgTypes::FileShare => {
+                        let Some(data) = msg.payload::<FileShareData>() else {
+                            return false;
+                        };
+                        if data.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: data.file.filename.clone(),
+                            timestamp,
+                            file: Some(data.file),
+                            room: data.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::AudioMessage => {
+                        let Some(data) = msg.payload::<AudioMessageData>() else {
+                            return false;
+                        };
+                        if data.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: data.audio.url.clone(),
+                            timestamp,
+                            audio: Some(data.audio),
+                            room: data.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::Image => {
+                        let Some(image) = msg.payload::<ImageShare>() else {
+                            return false;
+                        };
+                        if image.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: image.from,
+                            message: String::new(),
+                            timestamp,
+                            image: Some(image.data_url),
+                            room: image.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::Location => {
+                        let Some(data) = msg.payload::<LocationMessageData>() else {
+                            return false;
+                        };
+                        if data.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: format!("{}, {}", data.location.lat, data.location.lon),
+                            timestamp,
+                            location: Some(data.location),
+                            room: data.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::Diff => {
+                        let Some(data) = msg.payload::<DiffShare>() else {
+                            return false;
+                        };
+                        if data.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: diff::file_name(&data.unified_diff)
+                                .unwrap_or("diff")
+                                .to_string(),
+                            timestamp,
+                            diff: Some(data.unified_diff),
+                            room: data.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::VideoCall => {
+                        let Some(call) = msg.payload::<VideoCall>() else {
+                            return false;
+                        };
+                        if call.to != self.username {
+                            return false;
+                        }
+                        return self.handle_call_signal(call);
+                    }
+                    MsgTypes::Rename => {
+                        let Some(rename) = msg.payload::<Rename>() else {
+                            return false;
+                        };
+                        return self.apply_rename(ctx, &rename.old_name, &rename.new_name);
+                    }
+                    MsgTypes::RoomInvite => {
+                        let Some(invite) = msg.payload::<RoomInvite>() else {
+                            return false;
+                        };
+                        if invite.invitee != self.username
+                            || self.rooms.contains(&invite.room_name)
+                            || self.invites.contains(&invite)
+                        {
+                            return false;
+                        }
+                        self.invites.push(invite);
+                        return true;
+                    }
+                    MsgTypes::Mute => {
+                        let Some(mute) = msg.payload::<Mute>() else {
+                            return false;
+                        };
+                        if mute.target != self.username {
+                            let key = if mute.duration_secs > 0 {
+                                "moderation.muted"
+                            } else {
+                                "moderation.unmuted"
+                            };
+                            self.push_notice(
+                                self.locale
+                                    .format(key, &[("name", &mute.target), ("by", &mute.from)]),
+                            );
+                            return true;
+                        }
+                        self.muted_until = None;
+                        self.mute_timers = None;
+                        if mute.duration_secs > 0 {
+                            let duration_ms = mute.duration_secs.saturating_mul(1000);
+                            self.muted_until = Some(js_sys::Date::now() + duration_ms as f64);
+                            let link = ctx.link().clone();
+                            let expire =
+                                Timeout::new(duration_ms.min(u32::MAX as u64) as u32, move || {
+                                    link.send_message(Msg::MuteExpired)
+                                });
+                            let link = ctx.link().clone();
+                            let tick =
+                                Interval::new(1000, move || link.send_message(Msg::MuteTick));
+                            self.mute_timers = Some((expire, tick));
+                        }
+                        return true;
+                    }
+                    MsgTypes::Topic => {
+                        let Some(Topic { from, room, topic }) = msg.payload() else {
+                            return false;
+                        };
+                        if room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        let topic = topic.trim().to_string();
+                        if !from.is_empty() {
+                            let text = match topic.is_empty() {
+                                true => self.locale.format("topic.cleared", &[("name", &from)]),
+                                false => self
+                                    .locale
+                                    .format("topic.changed", &[("name", &from), ("topic", &topic)]),
+                            };
+                            self.push_room_notice(room.clone(), text);
+                        }
+                        if topic.is_empty() {
+                            self.topics.remove(&room);
+                        } else {
+                            self.topics.insert(room, topic);
+                        }
+                        return true;
+                    }
+                    MsgTypes::Stats => {
+                        let Some(stats) = msg.payload::<ChatStats>() else {
+                            return false;
+                        };
+                        self.stats = Some(Rc::new(stats));
+                        return self.stats_open;
+                    }
+                    MsgTypes::Kick => {
+                        let Some(kick) = msg.payload::<Kick>() else {
+                            return false;
+                        };
+                        if kick.target != self.username {
+                            self.push_notice(self.locale.format(
+                                "moderation.kicked",
+                                &[("name", &kick.target), ("by", &kick.from)],
+                            ));
+                            return true;
+                        }
+                        // Leaving the chat drops the connection.
+                        let (user, _) = ctx
+                            .link()
+                            .context::<User>(Callback::noop())
+                            .expect("context to be set");
+                        *user.token.borrow_mut() = None;
+                        *user.notice.borrow_mut() = Some(
+                            self.locale
+                                .format("moderation.you_were_kicked", &[("by", &kick.from)]),
+                        );
+                        if let Some(history) = ctx.link().history() {
+                            history.push(Route::Login);
+                        }
+                        return false;
+                    }
+                    MsgTypes::Whiteboard => {
+                        let Some(whiteboard) = msg.payload::<Whiteboard>() else {
+                            return false;
+                        };
+                        if whiteboard
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        self.boards.entry(whiteboard.id.clone()).or_default();
+                        self.push_message(MessageData {
+                            from: whiteboard.from,
+                            message: self.locale.t("whiteboard.title").to_string(),
+                            timestamp,
+                            whiteboard: Some(whiteboard.id),
+                            room: whiteboard.room,
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::WhiteboardStroke => {
+                        let Some(stroke) = msg.payload::<WhiteboardStroke>() else {
+                            return false;
+                        };
+                        let board = self.boards.entry(stroke.board.clone()).or_default();
+                        return Rc::make_mut(board).apply(stroke);
+                    }
+                    MsgTypes::Edit => {
+                        let Some(edit) = msg.payload::<Edit>() else {
+                            return false;
+                        };
+                        if edit.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        return self.apply_edit(edit);
+                    }
+                    MsgTypes::Delete => {
+                        let Some(delete) = msg.payload::<Delete>() else {
+                            return false;
+                        };
+                        if delete
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        return self.apply_delete(delete);
+                    }
+                    MsgTypes::Reaction => {
+                        let Some(reaction) = msg.payload::<Reaction>() else {
+                            return false;
+                        };
+                        if reaction
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        let key = reaction.key();
+                        let Some(m) = self
+                            .messages
+                            .iter_mut()
+                            .find(|m| !m.notice && PinKey::new(&m.from, m.timestamp) == key)
+                        else {
+                            return false;
+                        };
+                        if !reaction.apply(&mut Rc::make_mut(m).reactions) {
+                            return false;
+                        }
+                        self.schedule_persist();
+                        return true;
+                    }
+                    MsgTypes::Pin | MsgTypes::Unpin => {
+                        let Some(pin) = msg.payload::<Pin>() else {
+                            return false;
+                        };
+                        if pin.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        return self.apply_pin(pin, msg.message_type == MsgTypes::Pin);
+                    }
+                    MsgTypes::PinnedMessage => {
+                        let Some(pinned) = msg.payload::<MessageData>() else {
+                            return false;
+                        };
+                        if pinned
+                            .room
+                            .as_ref()
+                            .is_some_and(|r| !self.rooms.contains(r))
+                        {
+                            return false;
+                        }
+                        // Only one per room: a new pin replaces the last.
+                        self.room_pins.insert(pinned.room.clone(), Rc::new(pinned));
+                        return true;
+                    }
+                    MsgTypes::UnpinMessage => {
+                        let Some(unpin) = msg.payload::<UnpinMessage>() else {
+                            return false;
+                        };
+                        return self.room_pins.remove(&unpin.room).is_some();
+                    }
+                    MsgTypes::Confetti => {
+                        let Some(burst) = msg.payload::<ConfettiBurst>() else {
+                            return false;
+                        };
+                        if burst.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        // Enforced on receipt too, in case a client skips the check.
+                        if !self.confetti.fire(&burst.from, js_sys::Date::now()) {
+                            return false;
+                        }
+                        self.confetti_playing = true;
+                        return true;
+                    }
+                    MsgTypes::CodeReview => {
+                        let Some(data) = msg.payload::<CodeReviewData>() else {
+                            return false;
+                        };
+                        if data.room.as_ref().is_some_and(|r| !self.rooms.contains(r)) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: data.review.code.clone(),
+                            timestamp,
+                            room: data.room,
+                            code_review: Some(data.review),
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::CodeReviewComment => {
+                        let Some(CodeReviewComment { review, comment }) = msg.payload() else {
+                            return false;
+                        };
+                        let found = self
+                            .messages
+                            .iter_mut()
+                            .find(|m| m.code_review.as_ref().is_some_and(|r| r.id == review));
+                        let Some(m) = found else {
+                            return false;
+                        };
+                        if let Some(review) = &mut Rc::make_mut(m).code_review {
+                            review.comments.push(comment);
+                        }
+                        self.schedule_persist();
+                        return true;
+                    }
+                    MsgTypes::LinkPreview => {
+                        let Some(preview) = msg.payload::<LinkPreview>() else {
+                            return false;
+                        };
+                        self.link_previews
+                            .insert(preview.url.clone(), Some(Rc::new(preview)));
+                        return true;
+                    }
+                    MsgTypes::Survey => {
+                        let Some(survey) = msg.payload::<Survey>() else {
+                            return false;
+                        };
+                        // Sent again, e.g. on reconnecting: one card is enough.
+                        if self.messages.iter().any(|m| {
+                            m.survey
+                                .as_ref()
+                                .is_some_and(|s| s.survey_id == survey.survey_id)
+                        }) {
+                            return false;
+                        }
+                        self.push_message(MessageData {
+                            from: survey.from.clone(),
+                            message: survey
+                                .questions
+                                .iter()
+                                .map(|q| q.prompt())
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                            timestamp,
+                            survey: Some(survey),
+                            ..Default::default()
+                        });
+                        return true;
+                    }
+                    MsgTypes::Announcement => {
+                        let Some(data) = msg.payload::<Announcement>() else {
+                            return false;
+                        };
+                        self.push_message(MessageData {
+                            from: data.from,
+                            message: data.message,
+                            timestamp,
+                            announcement: Some(data.title.unwrap_or_default()),
+                            ..Default::default()
+                        });
+                        self.schedule_announcement_expiry(ctx);
+                        return true;
+                    }
+                    MsgTypes::Broadcast => {
+                        let Some(broadcast) = msg.payload::<Broadcast>() else {
+                            return false;
+                        };
+                        match broadcast.severity {
+                            BroadcastSeverity::Info => self.show_toast(ctx, broadcast.message),
+                            BroadcastSeverity::Warning => {
+                                self.broadcast_warning = Some(broadcast.message)
+                            }
+                            BroadcastSeverity::Critical => {
+                                self.critical_broadcasts.push(broadcast.message)
+                            }
+                            BroadcastSeverity::ForcedDisconnect => {
+                                if self.disconnecting.is_some() {
+                                    return false;
+                                }
+                                // Closing our end stops the socket reconnecting.
+                                self.tx.close_channel();
+                                session::forget(&self.username);
+                                let (user, _) = ctx
+                                    .link()
+                                    .context::<User>(Callback::noop())
+                                    .expect("context to be set");
+                                *user.token.borrow_mut() = None;
+                                let link = ctx.link().clone();
+                                let tick = Interval::new(1000, move || {
+                                    link.send_message(Msg::MaintenanceTick)
+                                });
+                                self.disconnecting =
+                                    Some((broadcast.message, MAINTENANCE_COUNTDOWN_SECS, tick));
+                            }
+                        }
+                        return true;
+                    }
+                    MsgTypes::ServerError => {
+                        let Some(error) = msg.payload::<ServerError>() else {
+                            return false;
+                        };
+                        self.push_error(ctx, error);
+                        return true;
+                    }
+                    MsgTypes::Presence => {
+                        let Some(update) = msg.payload::<PresenceUpdate>() else {
+                            return false;
+                        };
+                        return self.set_presence(&update.from, update.presence);
+                    }
+                    MsgTypes::Status => {
+                        let Some(status_data) = msg.payload::<StatusData>() else {
+                            return false;
+                        };
+                        return self.set_status(&status_data.from, status_data.status);
+                    }
+                    _ => {
+                        return false;
+                    }
+                }
+            }
+            Msg::ToggleAttachMenu => {
+                self.attach_open = !self.attach_open;
+                true
+            }
+            Msg::PickFile => {
+                self.attach_open = false;
+                if let Some(input) = self.file_input.cast::<HtmlInputElement>() {
+                    input.click();
+                }
+                false
+            }
+            Msg::FileChosen => {
+                let input = self.file_input.cast::<HtmlInputElement>();
+                let file = input
+                    .as_ref()
+                    .and_then(|i| i.files())
+                    .and_then(|f| f.get(0));
+                if let Some(file) = file {
+                    ctx.link().send_message(Msg::ShareFile(file));
+                }
+                if let Some(input) = input {
+                    input.set_value("");
+                }
+                false
+            }
+            Msg::ShareFile(file) => {
+                if let Some(error) = self.too_large(ctx, &file) {
+                    self.show_toast(ctx, error);
+                    return true;
+                }
+                let link = ctx.link().clone();
+                let csrf = self.csrf.clone();
+                spawn_local(async move {
+                    match upload::share(&csrf, &file).await {
+                        Ok(share) => link.send_message(Msg::FileUploaded(share)),
+                        Err(e) => {
+                            log::error!("sharing {} failed: {}", file.name(), e);
+                            link.send_message(Msg::ShowToast("chat.file_failed"));
+                        }
+                    }
+                });
+                false
+            }
+            Msg::DragFiles(e) => {
+                let files = e
+                    .data_transfer()
+                    .is_some_and(|data| data.types().includes(&"Files".into(), 0));
+                if !files {
+                    return false;
+                }
+                // Without this the browser won't let the files be dropped.
+                e.prevent_default();
+                !std::mem::replace(&mut self.dragging, true)
+            }
+            Msg::DragLeave => std::mem::take(&mut self.dragging),
+            Msg::DropFiles(e) => {
+                e.prevent_default();
+                self.dragging = false;
+                let files = e.data_transfer().and_then(|data| data.files());
+                for file in files
+                    .iter()
+                    .flat_map(|files| (0..files.length()).filter_map(|i| files.get(i)))
+                {
+                    if !file.type_().starts_with("image/") {
+                        ctx.link().send_message(Msg::ShareFile(file));
+                        continue;
+                    }
+                    // Images wait above the box in turn, as pasted ones do.
+                    if let Some(error) = self.too_large(ctx, &file) {
+                        self.show_toast(ctx, error);
+                        continue;
+                    }
+                    match PastedImage::from_file(file) {
+                        Ok(image) => self.pasted_images.push_back(image),
+                        Err(mime_type) => self.show_toast(
+                            ctx,
+                            self.locale
+                                .format("chat.image_unsupported", &[("type", &mime_type)]),
+                        ),
+                    }
+                }
+                self.refocus = true;
+                true
+            }
+            Msg::FileUploaded(file) => {
+                // Images are posted as a link and shown inline; anything else
+                // goes out as a file card.
+                if file.mime_type.starts_with("image/") && file.content.is_none() {
+                    self.send_text(file.download_url, false);
+                    return false;
+                }
+                let data = FileShareData {
+                    from: String::new(),
+                    room: self.current_room.clone(),
+                    file,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::FileShare)
+                    .data(serde_json::to_string(&data).unwrap())
+                    .build()
+                    .expect("file messages carry data");
+                self.send(&message);
+                false
+            }
+            Msg::StartRecording => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let on_done = link.callback(Msg::AudioRecorded);
+                    match Recorder::start(on_done).await {
+                        Ok(recorder) => link.send_message(Msg::RecordingStarted(recorder)),
+                        Err(e) => log::error!("recording: {:?}", e),
+                    }
+                });
+                false
+            }
+            Msg::RecordingStarted(recorder) => {
+                self.recorder = Some(recorder);
+                let link = ctx.link().clone();
+                self.recording_timer = Some(Interval::new(1000, move || {
+                    link.send_message(Msg::RecordingTick)
+                }));
+                true
+            }
+            Msg::RecordingTick => self.recording_timer.is_some(),
+            Msg::StopRecording => {
+                if let Some(recorder) = &self.recorder {
+                    recorder.stop();
+                }
+                self.recording_timer = None;
+                true
+            }
+            Msg::AudioRecorded(recording) => {
+                self.recorder = None;
+                let link = ctx.link().clone();
+                let csrf = self.csrf.clone();
+                spawn_local(async move {
+                    let filename = format!(
+                        "voice-{}.{}",
+                        js_sys::Date::now() as u64,
+                        recorder::extension(&recording.blob.type_())
+                    );
+                    match upload::upload_blob(&csrf, &recording.blob, &filename).await {
+                        Ok(url) => link.send_message(Msg::AudioUploaded(AudioClip {
+                            url,
+                            duration_secs: recording.duration_secs,
+                        })),
+                        Err(e) => {
+                            log::error!("upload failed: {}", e);
+                            link.send_message(Msg::ShowToast("chat.voice_failed"));
+                        }
+                    }
+                });
+                false
+            }
+            Msg::ShareLocation => {
+                self.attach_open = false;
+                location::current_position(ctx.link().callback(Msg::LocationFound));
+                true
+            }
+            Msg::ToggleCodeReview => {
+                self.attach_open = false;
+                self.review_open = !self.review_open;
+                self.refocus = !self.review_open;
+                true
+            }
+            Msg::SendCodeReview((language, code)) => {
+                self.review_open = false;
+                let data = CodeReviewData {
+                    from: String::new(),
+                    room: self.current_room.clone(),
+                    review: CodeReview {
+                        id: format!("{}-{}", self.username, js_sys::Date::now()),
+                        language,
+                        code,
+                        comments: Vec::new(),
+                    },
+                };
+                let message = WebSocketMessage::builder(MsgTypes::CodeReview)
+                    .data(serde_json::to_string(&data).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                true
+            }
+            Msg::DismissPreview(id) => self.dismissed_previews.insert(id),
+            Msg::AnswerSurvey((id, answers)) => {
+                let Some(survey) = self
+                    .messages
+                    .iter()
+                    .find(|m| m.id == id)
+                    .and_then(|m| m.survey.as_ref())
+                else {
+                    return false;
+                };
+                if !self.answered_surveys.insert(survey.survey_id.clone()) {
+                    return false;
+                }
+                let response = SurveyResponse {
+                    survey_id: survey.survey_id.clone(),
+                    from: String::new(),
+                    answers,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::SurveyResponse)
+                    .data(serde_json::to_string(&response).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.save_own(SURVEYS_KEY, &self.answered_surveys);
+                true
+            }
+            Msg::CommentOnReview((id, line, text)) => {
+                let Some(review) = self
+                    .messages
+                    .iter()
+                    .find(|m| m.id == id)
+                    .and_then(|m| m.code_review.as_ref())
+                else {
+                    return false;
+                };
+                // Shows up when the server relays it back.
+                let comment = CodeReviewComment {
+                    review: review.id.clone(),
+                    comment: LineComment {
+                        from: String::new(),
+                        line,
+                        text,
+                    },
+                };
+                let message = WebSocketMessage::builder(MsgTypes::CodeReviewComment)
+                    .data(serde_json::to_string(&comment).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                false
+            }
+            Msg::LocationFound(Ok(location)) => {
+                // Only the room we're in may see where we are.
+                let data = LocationMessageData {
+                    from: String::new(),
+                    room: self.current_room.clone(),
+                    location,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Location)
+                    .data(serde_json::to_string(&data).unwrap())
+                    .build()
+                    .expect("locations carry data");
+                self.send(&message);
+                false
+            }
+            Msg::LocationFound(Err(e)) => {
+                self.push_notice(self.locale.t(e.message_key()).to_string());
+                true
+            }
+            Msg::AudioUploaded(audio) => {
+                let data = AudioMessageData {
+                    from: String::new(),
+                    room: self.current_room.clone(),
+                    audio,
+                };
+                let message = WebSocketMessage::builder(MsgTypes::AudioMessage)
+                    .data(serde_json::to_string(&data).unwrap())
+                    .build()
+                    .expect("audio messages carry data");
+                self.send(&message);
+                false
+            }
+            Msg::PasteMessage(e) => {
+                // The message box is a single line, so a pasted diff would
+                // lose its newlines; send it as a Diff straight away instead.
+                // `ClipboardEvent` is behind web-sys's unstable APIs, so the
+                // clipboard data is read off the plain event.
+                let data = js_sys::Reflect::get(&e, &"clipboardData".into())
+                    .ok()
+                    .and_then(|data| data.dyn_into::<DataTransfer>().ok());
+                // A screenshot waits above the box to be sent or dropped.
+                match data.as_ref().and_then(PastedImage::from_clipboard) {
+                    Some(Ok(image)) => {
+                        e.prevent_default();
+                        self.pasted_images.push_back(image);
+                        return true;
+                    }
+                    Some(Err(mime_type)) => {
+                        e.prevent_default();
+                        self.show_toast(
+                            ctx,
+                            self.locale
+                                .format("chat.image_unsupported", &[("type", &mime_type)]),
+                        );
+                        return true;
+                    }
+                    None => {}
+                }
+                let text = data.and_then(|data| data.get_data("text/plain").ok());
+                if let Some(unified_diff) = text.filter(|t| diff::looks_like_diff(t)) {
+                    e.prevent_default();
+                    let share = DiffShare {
+                        from: String::new(),
+                        unified_diff,
+                        room: self.current_room.clone(),
+                    };
+                    let message = WebSocketMessage::builder(MsgTypes::Diff)
+                        .data(serde_json::to_string(&share).unwrap())
+                        .build()
+                        .expect("diff messages carry data");
+                    self.send(&message);
+                }
+                false
+            }
+            Msg::SendPastedImage => {
+                let Some(image) = self.pasted_images.pop_front() else {
+                    return false;
+                };
+                let link = ctx.link().clone();
+                if !ctx.props().upload_images {
+                    spawn_local(async move {
+                        link.send_message(Msg::PastedImageEncoded(image.to_data_url().await));
+                    });
+                    return true;
+                }
+                let csrf = self.csrf.clone();
+                spawn_local(async move {
+                    match upload::upload(&csrf, &image.file).await {
+                        Ok(url) => link.send_message(Msg::PastedImageUploaded(url)),
+                        Err(e) => {
+                            log::error!("upload failed: {}", e);
+                            link.send_message(Msg::ShowToast("chat.image_failed"));
+                        }
+                    }
+                });
+                true
+            }
+            Msg::PastedImageUploaded(url) => {
+                // A link to an image is shown as the image.
+                self.send_text(url, false);
+                false
+            }
+            Msg::PastedImageEncoded(Ok(data_url)) => {
+                let image = ImageShare {
+                    from: String::new(),
+                    data_url,
+                    room: self.current_room.clone(),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Image)
+                    .data(serde_json::to_string(&image).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                false
+            }
+            Msg::PastedImageEncoded(Err(e)) => {
+                log::error!("pasted image not sent: {}", e);
+                self.show_toast(ctx, self.locale.t("chat.image_failed").to_string());
+                true
+            }
+            Msg::CancelPastedImage => self.pasted_images.pop_front().is_some(),
+            Msg::DismissAnnouncement(key) => {
+                self.dismissed_announcements.insert(key);
+                self.schedule_announcement_expiry(ctx);
+                true
+            }
+            Msg::AnnouncementExpired => {
+                self.schedule_announcement_expiry(ctx);
+                true
+            }
+            Msg::InputChanged(value) => {
+                // A `:shortcode:` turns into its emoji once it is closed.
+                if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
+                    let caret = input.selection_start().ok().flatten();
+                    if let Some(edit) = caret.and_then(|caret| emoji::expand_typed(&value, caret)) {
+                        self.set_draft(&input, &edit.value);
+                        let _ = input.set_selection_range(edit.caret, edit.caret);
+                        return true;
+                    }
+                }
+                let draft = TextCount::of(&value);
+                let changed = draft != self.draft;
+                self.draft = draft;
+                changed
+            }
+            Msg::InputKeyDown(e) => {
+                if let Some(input) = self
+                    .chat_input
+                    .cast::<HtmlInputElement>()
+                    .filter(|_| !e.shift_key())
+                {
+                    let value = input.value();
+                    let press = KeyPress {
+                        key: e.key(),
+                        ctrl: e.ctrl_key() || e.meta_key(),
+                        alt: e.alt_key(),
+                        in_field: true,
+                    };
+                    let state = InputState {
+                        empty: value.is_empty(),
+                        recalling: self.input_history.is_browsing(),
+                        editing: self.editing.is_some(),
+                    };
+                    let recalled = match shortcuts::input_action(&press, state) {
+                        Some(InputAction::EditLast) => {
+                            let Some(id) = self.last_editable() else {
+                                return false;
+                            };
+                            e.prevent_default();
+                            ctx.link().send_message(Msg::StartEdit(id));
+                            return false;
+                        }
+                        Some(InputAction::Older) => {
+                            self.input_history.older(&value).map(str::to_string)
+                        }
+                        Some(InputAction::Newer) => self.input_history.newer(),
+                        Some(InputAction::Cancel) => match self.editing.take() {
+                            Some(_) => Some(String::new()),
+                            None => self.input_history.cancel(),
+                        },
+                        None => None,
+                    };
+                    if let Some(text) = recalled {
+                        e.prevent_default();
+                        // Escape would otherwise clear the box too.
+                        e.stop_propagation();
+                        self.completion = None;
+                        self.set_draft(&input, &text);
+                        let end = utf16_len(&text);
+                        let _ = input.set_selection_range(end, end);
+                        return true;
+                    }
+                }
+                if e.key() == "Escape" && self.cancel_reply() {
+                    // Not the global Escape, which would clear the box too.
+                    e.stop_propagation();
+                    return true;
+                }
+                if e.key() != "Tab" || e.alt_key() || e.ctrl_key() || e.meta_key() {
+                    if e.key() != "Shift" {
+                        self.completion = None;
+                    }
+                    return false;
+                }
+                let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                let value = input.value();
+                let caret = input.selection_start().ok().flatten().unwrap_or(0);
+                let forward = !e.shift_key();
+                let edit = match self
+                    .completion
+                    .as_mut()
+                    .and_then(|c| c.cycle(&value, caret, forward))
+                {
+                    Some(edit) => Some(edit),
+                    None => {
+                        self.completion = None;
+                        let names = self.users.keys().map(String::as_str);
+                        let names = names.filter(|name| *name != self.username);
+                        Completion::begin(&value, caret, names).map(|(completion, edit)| {
+                            self.completion = Some(completion);
+                            edit
+                        })
+                    }
+                };
+                // Nothing to complete: let Tab move focus as usual.
+                let Some(edit) = edit else {
+                    return false;
+                };
+                e.prevent_default();
+                self.set_draft(&input, &edit.value);
+                let _ = input.set_selection_range(edit.caret, edit.caret);
+                true
+            }
+            Msg::FocusMessage(id) => {
+                self.focused_message_id = Some(id);
+                let rows = self.message_rows();
+                let index = rows.iter().position(
+                    |row| matches!(row, MessageRow::Message { message, .. } if message.id == id),
+                );
+                if index.is_some_and(|index| !self.visible_rows.contains(&index)) {
+                    self.scroll_to(ScrollTarget::Message(id));
+                }
+                true
+            }
+            Msg::MessageKey((id, key)) => {
+                let msg = match key {
+                    MessageKey::Reply => Msg::Reply(id),
+                    MessageKey::React => Msg::OpenReactions(id),
+                    MessageKey::Copy => Msg::CopyMessage(id),
+                    MessageKey::Delete => Msg::AskDelete(id),
+                };
+                ctx.link().send_message(msg);
+                false
+            }
+            Msg::AskDelete(id) => {
+                if !self
+                    .messages
+                    .iter()
+                    .any(|m| m.id == id && m.from == self.username && !m.notice)
+                {
+                    return false;
+                }
+                self.confirm_delete = Some(id);
+                true
+            }
+            Msg::ConfirmDelete => {
+                let Some(id) = self.confirm_delete.take() else {
+                    return false;
+                };
+                let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                    return true;
+                };
+                let delete = Delete {
+                    from: String::new(),
+                    timestamp: m.timestamp,
+                    room: m.room.clone(),
+                };
+                let message = WebSocketMessage::builder(MsgTypes::Delete)
+                    .data(serde_json::to_string(&delete).unwrap())
+                    .build()
+                    .unwrap();
+                self.send(&message);
+                self.apply_delete(Delete {
+                    from: self.username.clone(),
+                    ..delete
+                });
+                true
+            }
+            Msg::CancelDelete => self.confirm_delete.take().is_some(),
+            Msg::StartEdit(id) => {
+                let Some(text) = self
+                    .messages
+                    .iter()
+                    .find(|m| m.id == id && self.editable(m))
+                    .map(|m| m.message.clone())
+                else {
+                    return false;
+                };
+                let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                self.input_history.cancel();
+                self.completion = None;
+                self.reply_to = None;
+                self.editing = Some(id);
+                self.set_draft(&input, &text);
+                let end = utf16_len(&text);
+                let _ = input.set_selection_range(end, end);
+                self.refocus = true;
+                true
+            }
+            Msg::CancelEdit => {
+                if self.editing.take().is_none() {
+                    return false;
+                }
+                if let Some(input) = self.chat_input.cast::<HtmlInputElement>() {
+                    self.set_draft(&input, "");
+                }
+                self.refocus = true;
+                true
+            }
+            Msg::SubmitMessage => {
+                if self.muted_until.is_some() {
+                    return false;
+                }
+                let Some(input) = self.chat_input.cast::<HtmlInputElement>() else {
+                    return false;
+                };
+                let value = input.value();
+                if TextCount::of(&value).chars > ctx.props().max_length {
+                    return false;
+                }
+                // The new text of a message we sent, rather than a new one.
+                if let Some(id) = self.editing.take() {
+                    self.set_draft(&input, "");
+                    self.refocus = true;
+                    let text = value.trim();
+                    let Some(m) = self.messages.iter().find(|m| m.id == id) else {
+                        return true;
+                    };
+                    if text.is_empty() || text == m.message {
+                        return true;
+                    }
+                    let edit = Edit {
+                        from: String::new(),
+                        timestamp: m.timestamp,
+                        message: text.to_string(),
+                        room: m.room.clone(),
+                    };
+                    let message = WebSocketMessage::builder(MsgTypes::Edit)
+                        .data(serde_json::to_string(&edit).unwrap())
+                        .build()
+                        .unwrap();
+                    self.send(&message);
+                    self.apply_edit(Edit {
+                        from: self.username.clone(),
+                        ..edit
+                    });
+                    return true;
+                }
+                // Already waiting: the send goes out by itself.
+                if self.slow_until.is_some() {
+                    return false;
+                }
+                let outcome = match commands::parse(&value) {
+                    Input::Text(text) => Outcome::Send {
+                        text,
+                        action: false,
+                    },
+                    Input::Command { name, args } => self.commands.run(&name, &args, &self.locale),
+                };
+                let now = js_sys::Date::now();
+                // What a double click or bouncing Enter would send twice.
+                let payload = match &outcome {
+                    Outcome::Send { text, action } => Some(format!(
+                        "{:?}\u{0}{}\u{0}{}",
+                        self.current_room, action, text
+                    )),
+                    _ => None,
+                };
+                if payload
+                    .as_ref()
+                    .is_some_and(|p| self.sent.is_repeat(p, now))
+                {
+                    return false;
+                }
+                let to_server = matches!(
+                    outcome,
+                    Outcome::Send { .. }
+                        | Outcome::Whiteboard
+                        | Outcome::Confetti
+                        | Outcome::Announce { .. }
+                        | Outcome::SetTopic(_)
+                );
+                if to_server {
+                    if let Err(wait) = self.send_limit.take(now) {
+                        // Keep the text and send it once allowed.
+                        self.slow_until = Some(now + wait);
+                        let link = ctx.link().clone();
+                        let over = Timeout::new(wait.ceil() as u32, move || {
+                            link.send_message(Msg::SlowDownOver)
+                        });
+                        let link = ctx.link().clone();
+                        let tick =
+                            Interval::new(1000, move || link.send_message(Msg::SlowDownTick));
+                        self.slow_timers = Some((over, tick));
+                        return true;
+                    }
+                }
+                if let Some(payload) = payload {
+                    self.sent.record(payload, now);
+                    let link = ctx.link().clone();
+                    self.send_pause = Some(Timeout::new(SEND_PAUSE_MS, move || {
+                        link.send_message(Msg::SendPauseOver)
+                    }));
+                }
+                self.set_draft(&input, "");
+                self.input_history.record(&value);
+                self.refocus = true;
+                match outcome {
+                    Outcome::Send { text, action } => {
+                        self.reply_to = None;
+                        self.dismiss_unread();
+                        self.send_text(text, action);
+                        true
+                    }
+                    Outcome::Notice(text) => {
+                        self.push_notice(text);
+                        true
+                    }
+                    Outcome::Whiteboard => {
+                        let whiteboard = Whiteboard {
+                            id: format!("{}-{}", self.username, js_sys::Date::now()),
+                            from: String::new(),
+                            room: self.current_room.clone(),
+                        };
+                        let message = WebSocketMessage::builder(MsgTypes::Whiteboard)
+                            .data(serde_json::to_string(&whiteboard).unwrap())
+                            .build()
+                            .unwrap();
+                        self.send(&message);
+                        false
+                    }
+                    Outcome::Confetti => {
+                        if let Some(left) =
+                            self.confetti.remaining(&self.username, js_sys::Date::now())
+                        {
+                            let seconds = (left / 1000.0).ceil().to_string();
+                            self.push_notice(
+                                self.locale
+                                    .format("commands.confetti_cooldown", &[("seconds", &seconds)]),
+                            );
+                            return true;
+                        }
+                        // Plays when the server echoes it back, like everyone else's.
+                        let burst = ConfettiBurst {
+                            from: String::new(),
+                            room: self.current_room.clone(),
+                        };
+                        let message = WebSocketMessage::builder(MsgTypes::Confetti)
+                            .data(serde_json::to_string(&burst).unwrap())
+                            .build()
+                            .unwrap();
+                        self.send(&message);
+                        false
+                    }
+                    Outcome::SetTopic(topic) => {
+                        if !self.is_moderator() {
+                            self.push_notice(
+                                self.locale.t("commands.topic_moderators_only").to_string(),
+                            );
+                            return true;
+                        }
+                        // Changes when the server relays it back.
+                        let topic = Topic {
+                            from: String::new(),
+                            room: self.current_room.clone(),
+                            topic,
+                        };
+                        let message = WebSocketMessage::builder(MsgTypes::Topic)
+                            .data(serde_json::to_string(&topic).unwrap())
+                            .build()
+                            .unwrap();
+                        self.send(&message);
+                        false
+                    }
+                    Outcome::Announce { title, text } => {
+                        // The server checks too; this just saves a round trip.
+                        if !self.is_moderator() {
+                            self.push_notice(
+                                self.locale.t("commands.announce_admins_only").to_string(),
+                            );
+                            return true;
+                        }
+                        let announcement = Announcement {
+                            from: String::new(),
+                            title,
+                            message: text,
+                        };
+                        let message = WebSocketMessage::builder(MsgTypes::Announcement)
+                            .data(serde_json::to_string(&announcement).unwrap())
+                            .build()
+                            .unwrap();
+                        self.send(&message);
+                        false
+                    }
+                    Outcome::Clear => {
+                        self.messages.clear();
+                        self.schedule_persist();
+                        self.refresh_search();
+                        true
+                    }
+                }
+            }
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            self.refresh_title();
+            self.schedule_announcement_expiry(ctx);
+            self._shortcuts = shortcut_listener(ctx);
+            self.focus_input();
+        }
+        if std::mem::take(&mut self.refocus) {
+            self.focus_input();
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        self._shortcuts = None;
+        self._idle_tracker = None;
+        // Release the camera when leaving the chat mid-call.
+        if let Some(peer) = self.end_call() {
+            self.send_signal(peer, CallSignal::Hangup);
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let dark_mode_class = if self.dark_mode() { "dark-mode" } else { "" };
+        let text_color_class = if self.dark_mode() {
+            "text-white"
+        } else {
+            "text-black"
+        };
+
+        let render_item = {
+            let link = ctx.link().clone();
+            let show_popover = self.show_popover.clone();
+            let actions = self.message_actions.clone();
+            let draw = self.draw.clone();
+            let renderer = ctx.props().message_renderer.clone();
+            let locale = self.locale.clone();
+            let today = i18n::start_of_day(js_sys::Date::now());
+            let highlight: Option<Rc<str>> = self
+                .search
+                .as_ref()
+                .map(|s| s.query.trim())
+                .filter(|q| !q.is_empty())
+                .map(Rc::from);
+            RenderItem::from(move |row: MessageRow| match row {
+                MessageRow::Trimmed => html! {
+                    <div class="m-3 text-center text-xs text-gray-400">{locale.t("chat.trimmed")}</div>
+                },
+                MessageRow::Day(midnight) => html! {
+                    <div role="separator" class="flex items-center m-3 text-xs text-gray-400">
+                        <div class="grow border-t border-gray-200"></div>
+                        <span class="mx-2">{locale.day_label(midnight, today)}</span>
+                        <div class="grow border-t border-gray-200"></div>
+                    </div>
+                },
+                MessageRow::Restored => html! {
+                    <div role="separator" class="flex items-center m-3 text-xs text-gray-400">
+                        <div class="grow border-t border-gray-300"></div>
+                        <span class="mx-2">{locale.t("chat.restored")}</span>
+                        <div class="grow border-t border-gray-300"></div>
+                    </div>
+                },
+                MessageRow::Hidden {
+                    first_id,
+                    from,
+                    count,
+                } => {
+                    let label = match count {
+                        1 => locale.format("chat.hidden_one", &[("name", &from)]),
+                        n => locale.format(
+                            "chat.hidden_many",
+                            &[("count", &n.to_string()), ("name", &from)],
+                        ),
+                    };
+                    html! {
+                        <div class="m-8 text-xs text-gray-400">
+                            {label}{" — "}
+                            <button class="underline" onclick={link.callback(move |_| Msg::RevealMuted(first_id))}>{locale.t("chat.show")}</button>
+                        </div>
+                    }
+                }
+                MessageRow::Unread => html! {
+                    <div role="separator" class="flex items-center m-3 text-xs text-red-500">
+                        <div class="grow border-t border-red-400"></div>
+                        <span class="mx-2">{locale.t("chat.unread")}</span>
+                        <div class="grow border-t border-red-400"></div>
+                    </div>
+                },
+                MessageRow::Notice(message) => html! {
+                    <div role="status" class="m-3 text-xs text-gray-500 whitespace-pre-line">{message.message.clone()}</div>
+                },
+                MessageRow::Announcement(message) => html! {
+                    <AnnouncementCard {message}/>
+                },
+                MessageRow::Message {
+                    message,
+                    author,
+                    current,
+                    focused,
+                    pinned,
+                    own,
+                    moderator,
+                    board,
+                    expanded,
+                    translation,
+                    seen_by,
+                    reactions,
+                    answered,
+                    preview,
+                    replies,
+                    unread_replies,
+                } => html! {
+                    <MessageItem {message} {author} renderer={renderer.clone()} highlight={highlight.clone()} {current} {focused} {pinned} {own} {moderator} {board} {expanded} {translation} {seen_by} {reactions} {answered} {preview} {replies} {unread_replies}
+                        on_avatar_click={show_popover.clone()} actions={actions.clone()} on_draw={draw.clone()}/>
+                },
+            })
+        };
+
+        let rows = self.message_rows();
+        let scroll_to = self.scroll_target.and_then(|target| {
+            let index = rows.iter().position(|row| match (target, row) {
+                (ScrollTarget::Message(id), MessageRow::Message { message, .. }) => {
+                    message.id == id
+                }
+                (ScrollTarget::Unread, MessageRow::Unread) => true,
+                _ => false,
+            })?;
+            Some(ScrollRequest {
+                index,
+                generation: self.scroll_generation,
+            })
+        });
+        let message_ids: Vec<usize> = rows
+            .iter()
+            .filter_map(|row| match row {
+                MessageRow::Message { message, .. } => Some(message.id),
+                _ => None,
+            })
+            .collect();
+        let anchor = self.anchor.and_then(|(id, generation)| {
+            let index = rows.iter().position(
+                |row| matches!(row, MessageRow::Message { message, .. } if message.id == id),
+            )?;
+            Some(ScrollRequest { index, generation })
+        });
+
+        let submit = ctx.link().callback(|_| Msg::SubmitMessage);
+        let max_length = ctx.props().max_length;
+        let too_long = self.draft.chars > max_length;
+        let word_count = match self.draft.words {
+            1 => self.locale.t("chat.word_count_one").to_string(),
+            n => self
+                .locale
+                .format("chat.word_count_many", &[("count", &n.to_string())]),
+        };
+        let toggle_dark_mode = ctx.link().callback(|_| Msg::ToggleDarkMode);
+        let switch_user = ctx.link().callback(|_| Msg::SwitchUser);
+        let t = |key| self.locale.t(key);
+
+        let am_admin = self.is_moderator();
+        let popover = self.popover.as_ref().and_then(|(name, anchor)| {
+            let user = self.profile(name)?.clone();
+            let is_me = *name == self.username;
+            Some(html! {
+                <UserPopover {user} anchor={*anchor}
+                    on_close={ctx.link().callback(|_| Msg::ClosePopover)}
+                    muted={self.is_muted(name)}
+                    on_message_privately={(!is_me).then(|| ctx.link().callback(Msg::MessagePrivately))}
+                    on_mute={(!is_me).then(|| ctx.link().callback(Msg::ToggleMute))}
+                    on_rename={is_me.then(|| ctx.link().callback(Msg::Rename))}
+                    on_server_mute={(am_admin && !is_me).then(|| ctx.link().callback(Msg::ServerMute))}
+                    on_kick={(am_admin && !is_me).then(|| ctx.link().callback(Msg::Kick))}/>
+            })
+        });
+
+        let incoming_call = self.incoming_call.as_ref().map(|(from, _)| {
+            html! {
+                <IncomingCall from={from.clone()}
+                    on_accept={ctx.link().callback(|_| Msg::AcceptCall)}
+                    on_decline={ctx.link().callback(|_| Msg::DeclineCall)}/>
+            }
+        });
+        let video_call = self.call.as_ref().and_then(|call| {
+            let local = call.connection.as_ref()?.local_stream().clone();
+            Some(html! {
+                <VideoCallView peer={call.peer.clone()} {local} remote={call.remote.clone()}
+                    on_hangup={ctx.link().callback(|_| Msg::HangUp)}/>
+            })
+        });
+
+        let toasts = self
+            .errors
+            .iter()
+            .filter(|e| self.toasts.contains(&e.id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        html! {
+                <div dir={self.locale.direction().attr()} class={format!("flex w-screen {}", dark_mode_class)}>
+                    {for popover}
+                    if self.error_log_open {
+                        <ErrorLog errors={self.errors.iter().cloned().collect::<Vec<_>>()} on_close={ctx.link().callback(|_| Msg::ToggleErrorLog)}/>
+                    } else {
+                        <ErrorToasts {toasts} on_dismiss={ctx.link().callback(Msg::DismissError)} on_view_all={ctx.link().callback(|_| Msg::ToggleErrorLog)}/>
+                    }
+                    {for incoming_call}
+                    {for video_call}
+                    if let Some((text, _)) = &self.toast {
+                        <Toast text={text.clone()}/>
+                    }
+                    if self.confetti_playing {
+                        <Confetti on_done={ctx.link().callback(|_| Msg::ConfettiDone)}/>
+                    }
+                    <UserSidebar users={Rc::clone(&self.users)} current_user={self.username.clone()} on_set_status={ctx.link().callback(Msg::SetStatus)} on_avatar_click={self.show_popover.clone()} on_call={ctx.link().callback(Msg::StartCall)}
+                        rooms={self.rooms.clone()} current_room={self.current_room.clone()} unread={self.room_unread.by_room()} mentioned={self.room_unread.mentioned()} on_select_room={ctx.link().callback(Msg::SelectRoom)}
+                        on_set_presence={ctx.link().callback(Msg::SetPresence)} on_show_people={ctx.link().callback(|_| Msg::TogglePeople)}
+                        on_show_stats={ctx.link().callback(|_| Msg::ToggleStats)}/>
+                    <div class="grow h-screen flex flex-col">
+                    <div class={format!("relative w-full h-14 border-b-2 border-gray-300 flex justify-between items-center {}", dark_mode_class)}>
+                    <div class={format!("min-w-0 p-3 {}", text_color_class)}>
+                        <div class="text-xl">{t("chat.title")}</div>
+                        if let Some(topic) = self.topics.get(&self.current_room) {
+                            <div class="max-w-md text-xs text-gray-400 truncate" title={topic.clone()} aria-label={self.locale.format("topic.label", &[("topic", topic)])}>{topic}</div>
+                        }
+                    </div>
+                    <button onclick={switch_user} class="text-xs text-gray-400 underline">
+                        {self.locale.format("chat.switch_user", &[("name", &self.username)])}
+                    </button>
+                    <div class={format!("flex items-center text-xs p-3 {}", text_color_class)}>
+                        <button onclick={ctx.link().callback(|_| Msg::ToggleStarredOnly)} aria-label={t("chat.starred")} title={t("chat.starred")} aria-pressed={self.filter.starred.to_string()}
+                            class={classes!("ms-2", "text-xl", (!self.filter.starred).then_some("grayscale"))}>
+                            <span aria-hidden="true">{"⭐"}</span>
+                        </button>
+                        <button onclick={ctx.link().callback(|_| Msg::ToggleDoNotDisturb)} aria-label={t("presence.do_not_disturb")} title={t("presence.do_not_disturb")} aria-pressed={self.settings.do_not_disturb().to_string()}
+                            class={classes!("ms-2", "text-xl", (!self.settings.do_not_disturb()).then_some("grayscale"))}>
+                            <span aria-hidden="true">{"🌙"}</span>
+                        </button>
+                        <button onclick={ctx.link().callback(|_| Msg::ToggleSearch)} aria-label={t("chat.search")} aria-pressed={self.search.is_some().to_string()} class="ms-2 text-xl">
+                            <span aria-hidden="true">{"🔍"}</span>
+                        </button>
+                        <button onclick={ctx.link().callback(|_| Msg::ToggleExport)} class="ms-2 px-3 py-1 rounded-full bg-gray-200 text-black">{t("chat.export")}</button>
+                        <button onclick={ctx.link().callback(|_| Msg::ToggleSettings)} aria-label={t("chat.settings")} aria-expanded={self.settings_open.to_string()} class="ms-2 text-xl">
+                            <span aria-hidden="true">{"⚙"}</span>
+                        </button>
+                    </div>
+                    if self.review_open {
+                        <CodeReviewDialog on_send={ctx.link().callback(Msg::SendCodeReview)}
+                            on_close={ctx.link().callback(|_| Msg::ToggleCodeReview)}/>
+                    }
+                    if self.export_open {
+                        <ExportDialog on_export={ctx.link().callback(Msg::ExportChat)}
+                            on_close={ctx.link().callback(|_| Msg::ToggleExport)}/>
+                    }
+                    if self.settings_open {
+                        <SettingsPanel on_close={ctx.link().callback(|_| Msg::ToggleSettings)}
+                            stars={self.starred.len()} on_clear_stars={ctx.link().callback(|_| Msg::ClearStars)}/>
+                    }
+                    if self.stats_open {
+                        <ChatStatistics stats={self.stats.clone()} on_refresh={ctx.link().callback(|_| Msg::RequestStats)}
+                            on_close={ctx.link().callback(|_| Msg::ToggleStats)}/>
+                    }
+                    if self.people_open {
+                        <PresenceList users={Rc::clone(&self.users)} departed={Rc::new(self.departed.values().cloned().collect::<Vec<_>>())}
+                            on_select={self.show_popover.clone()} on_close={ctx.link().callback(|_| Msg::TogglePeople)}/>
+                    }
+                    if let Some(thread) = &self.thread {
+                        {{
+                            let (root, replies) = self.thread_entries(thread);
+                            html! {
+                                <ThreadPanel {root} {replies} on_send={ctx.link().callback(Msg::SendThreadReply)}
+                                    on_close={ctx.link().callback(|_| Msg::CloseThread)}/>
+                            }
+                        }}
+                    }
+                    if let Some((images, start)) = &self.lightbox {
+                        <Lightbox images={images.clone()} start={*start} on_close={ctx.link().callback(|_| Msg::CloseLightbox)}/>
+                    }
+                    if self.shortcuts_open {
+                        <ShortcutsHelp on_close={ctx.link().callback(|_| Msg::CloseShortcuts)}/>
+                    }
+                    if self.confirm_delete.is_some() {
+                        <ConfirmDialog message={t("delete.confirm").to_string()} confirm_label={t("actions.delete").to_string()}
+                            on_confirm={ctx.link().callback(|_| Msg::ConfirmDelete)} on_cancel={ctx.link().callback(|_| Msg::CancelDelete)}/>
+                    }
+                    if let Some((message, seconds, _)) = &self.disconnecting {
+                        <BroadcastModal message={message.clone()} countdown={*seconds}/>
+                    } else if let Some(message) = self.critical_broadcasts.first() {
+                        <BroadcastModal message={message.clone()} on_acknowledge={ctx.link().callback(|_| Msg::AcknowledgeBroadcast)}/>
+                    }
+                </div>
+                <ConnectionStatusBanner state={self.connection.clone()}/>
+                if let Some(message) = &self.broadcast_warning {
+                    <div role="alert" class="flex items-center w-full px-3 py-2 text-sm bg-amber-100 text-amber-900 border-b border-amber-300">
+                        <span aria-hidden="true" class="me-2">{"⚠"}</span>
+                        <span class="grow whitespace-pre-line break-words">{message}</span>
+                        <button onclick={ctx.link().callback(|_| Msg::DismissBroadcast)} aria-label={t("broadcast.dismiss")} class="ms-3">{"✕"}</button>
+                    </div>
+                }
+                {for self.pinned_announcements().into_iter().map(|message| {
+                    let key = PinKey::new(&message.from, message.timestamp);
+                    html! {
+                        <AnnouncementCard {message} on_dismiss={ctx.link().callback(move |_| Msg::DismissAnnouncement(key.clone()))}/>
+                    }
+                })}
+                if let Some(pinned) = self.room_pins.get(&self.current_room) {
+                    <PinnedMessageBar message={Rc::clone(pinned)}
+                        avatar={self.profile(&pinned.from).map(|u| u.avatar.clone()).unwrap_or_else(|| avatar_url(&pinned.from, AvatarStyle::default()))}
+                        on_select={ctx.link().callback(|_| Msg::ShowRoomPin)}
+                        on_close={self.is_moderator().then(|| ctx.link().callback(|_| Msg::UnpinFromRoom))}/>
+                }
+                <PinnedMessages pins={self.pins.iter().filter(|p| p.room == self.current_room).cloned().collect::<Vec<_>>()}
+                    on_select={ctx.link().callback(Msg::ShowPinned)} on_unpin={ctx.link().callback(Msg::Unpin)}/>
+                if self.unread_from.is_some() {
+                    <div class="flex items-center justify-center w-full py-1 text-xs bg-blue-50 text-blue-900">
+                        <button onclick={ctx.link().callback(|_| Msg::JumpToUnread)} class="underline">{t("chat.jump_to_unread")}</button>
+                        <button onclick={ctx.link().callback(|_| Msg::DismissUnread)} aria-label={t("chat.dismiss_unread")} class="ms-3">{"✕"}</button>
+                    </div>
+                }
+                if self.welcome_open {
+                    <WelcomeBanner username={self.username.clone()} content={ctx.props().welcome_html.clone()}
+                        on_dismiss={ctx.link().callback(|_| Msg::DismissWelcome)}/>
+                }
+                if let Some(invite) = self.invites.first() {
+                    <RoomInviteBanner invite={invite.clone()}
+                        on_accept={ctx.link().callback(|_| Msg::AcceptInvite)}
+                        on_decline={ctx.link().callback(|_| Msg::DeclineInvite)}/>
+                }
+                // The list is virtual, so rows come and go as it scrolls; only the
+                // region below tells screen readers about new messages.
+                <div aria-live="polite" aria-atomic="true" class="sr-only">{self.announcement.clone()}</div>
+                <div role="region" aria-label={t("chat.messages")} class={format!("relative w-full grow flex flex-col min-h-0 border-b-2 border-gray-300 {}", dark_mode_class)}
+                    ondragenter={ctx.link().callback(Msg::DragFiles)} ondragover={ctx.link().callback(Msg::DragFiles)}>
+                    if self.dragging {
+                        <div ondragleave={ctx.link().callback(|_| Msg::DragLeave)} ondrop={ctx.link().callback(Msg::DropFiles)}
+                            class="absolute inset-0 z-40 flex items-center justify-center m-2 rounded-lg border-4 border-dashed border-blue-500 bg-blue-50 bg-opacity-90 text-xl font-semibold text-blue-700">
+                            // Nothing inside to drag over, or leaving the
+                            // overlay for it would hide the overlay.
+                            <span class="pointer-events-none"><span aria-hidden="true" class="me-2">{"📥"}</span>{t("chat.drop_to_share")}</span>
+                        </div>
+                    }
+                    if let Some(search) = &self.search {
+                        <MessageSearch query={search.query.clone()} hits={search.hits.len()} current={search.current}
+                            on_query={ctx.link().callback(Msg::SearchQuery)} on_step={ctx.link().callback(Msg::SearchStep)}
+                            on_close={ctx.link().callback(|_| Msg::ToggleSearch)}/>
+                    }
+                    <MessageFilter users={self.users.keys().cloned().collect::<Vec<_>>()} filter={self.filter.clone()}
+                        on_change={ctx.link().callback(Msg::SetFilter)}/>
+                    <KeyboardNavigableMessageList class="grow" ids={message_ids} focused={self.focused_message_id} label={t("chat.message_list").to_string()}
+                        on_focus={ctx.link().callback(Msg::FocusMessage)} on_key={ctx.link().callback(Msg::MessageKey)}>
+                        <VirtualList<MessageRow> class="grow" follow=true items={rows} item_height_estimate={MESSAGE_HEIGHT_ESTIMATE} {render_item} {scroll_to} {anchor}
+                            on_visible={ctx.link().callback(Msg::VisibleRows)} on_at_bottom={ctx.link().callback(Msg::AtBottom)}/>
+                    </KeyboardNavigableMessageList>
+                </div>
+                        if let Some(image) = self.pasted_images.front() {
+                            <div role="group" aria-label={t("chat.pasted_image")} class={format!("w-full flex items-end gap-3 px-6 pt-2 {}", dark_mode_class)}>
+                                <img src={image.preview_url.clone()} alt={t("chat.pasted_image")} class="max-h-32 max-w-xs rounded border border-gray-300"/>
+                                <button onclick={ctx.link().callback(|_| Msg::SendPastedImage)} disabled={self.muted_until.is_some()} class="px-3 py-1 rounded-full bg-blue-600 text-white text-sm">{t("chat.send_image")}</button>
+                                <button onclick={ctx.link().callback(|_| Msg::CancelPastedImage)} class="px-3 py-1 rounded-full bg-gray-200 text-black text-sm">{t("chat.cancel")}</button>
+                                if self.pasted_images.len() > 1 {
+                                    <span class="text-sm text-gray-500">{self.locale.format("chat.images_waiting", &[("count", &(self.pasted_images.len() - 1).to_string())])}</span>
+                                }
+                            </div>
+                        }
+                        if let Some(reply_to) = &self.reply_to {
+                            <QuotedReply reply_to={reply_to.clone()} on_cancel={ctx.link().callback(|_| Msg::CancelReply)}/>
+                        }
+                        if self.editing.is_some() {
+                            <div role="status" class={format!("w-full flex items-center gap-3 px-6 pt-2 text-sm {}", dark_mode_class)}>
+                                <span aria-hidden="true">{"✎"}</span>
+                                <span class="grow">{t("edit.banner")}</span>
+                                <button onclick={ctx.link().callback(|_| Msg::CancelEdit)} class="px-3 py-1 rounded-full bg-gray-200 text-black text-sm">{t("chat.cancel")}</button>
+                            </div>
+                        }
+                        <div class={format!("w-full h-14 flex px-3 items-center {}", dark_mode_class)}>
+                        <label for="message-input" class="sr-only">{t("chat.message_placeholder")}</label>
+                        if let Some(until) = self.muted_until {
+                            <span role="status" class="shrink-0 ms-3 text-xs text-red-600">
+                                {self.locale.format("chat.muted", &[("time", &format_duration(((until - js_sys::Date::now()) / 1000.0).ceil().max(0.0) as u64))])}
+                            </span>
+                        }
+                        if let Some(until) = self.slow_until {
+                            <span role="status" class="shrink-0 ms-3 text-xs text-amber-600">
+                                {self.locale.format("chat.slow_down", &[("seconds", &((until - js_sys::Date::now()) / 1000.0).ceil().max(1.0).to_string())])}
+                            </span>
+                        }
+                        <input ref={self.chat_input.clone()} id="message-input" type="text" dir="auto" disabled={self.muted_until.is_some()} placeholder={t("chat.message_placeholder")} onpaste={ctx.link().callback(Msg::PasteMessage)} onkeydown={ctx.link().callback(Msg::InputKeyDown)} class={format!("block w-full py-2 ps-4 mx-3 bg-gray-100 rounded-full outline-none focus:text-gray-700 {}", text_color_class)} name="message" required=true
+                            oninput={ctx.link().callback(|e: InputEvent| Msg::InputChanged(e.target_unchecked_into::<HtmlInputElement>().value()))} aria-describedby="message-count" />
+                        <span id="message-count" class={classes!("shrink-0", "me-2", "text-xs", "whitespace-nowrap", if too_long { "text-red-600" } else { "text-gray-400" })}>
+                            if self.draft.words > 0 {
+                                {word_count}{" · "}
+                            }
+                            {format!("{}/{}", self.draft.chars, max_length)}
+                        </span>
+                        <input ref={self.file_input.clone()} type="file" class="hidden" onchange={ctx.link().callback(|_| Msg::FileChosen)}/>
+                        <div class="relative">
+                            <button onclick={ctx.link().callback(|_| Msg::ToggleAttachMenu)} disabled={self.muted_until.is_some()} aria-label={t("chat.attach")} aria-haspopup="menu" aria-expanded={self.attach_open.to_string()} class="p-2 me-2 text-xl">
+                                <span aria-hidden="true">{"📎"}</span>
+                            </button>
+                            if self.attach_open {
+                                <div role="menu" class="absolute bottom-full start-0 mb-2 flex flex-col w-44 py-1 rounded shadow bg-white text-sm text-black">
+                                    <button role="menuitem" onclick={ctx.link().callback(|_| Msg::PickFile)} class="px-3 py-1 text-start hover:bg-gray-100">{"📄 "}{t("chat.share_file")}</button>
+                                    <button role="menuitem" onclick={ctx.link().callback(|_| Msg::ShareLocation)} class="px-3 py-1 text-start hover:bg-gray-100">{"📍 "}{t("chat.share_location")}</button>
+                                    <button role="menuitem" onclick={ctx.link().callback(|_| Msg::ToggleCodeReview)} class="px-3 py-1 text-start hover:bg-gray-100">{"🧑‍💻 "}{t("review.share")}</button>
+                                </div>
+                            }
+                        </div>
+                        <div class="relative">
+                            <button onclick={ctx.link().callback(|_| Msg::ToggleEmojiPicker)} disabled={self.muted_until.is_some()} aria-label={t("chat.emoji")} aria-haspopup="dialog" aria-expanded={self.emoji_open.to_string()} class="p-2 me-2 text-xl">
+                                <span aria-hidden="true">{"😊"}</span>
+                            </button>
+                            if self.emoji_open {
+                                <ReactionPicker on_select={ctx.link().callback(Msg::InsertEmoji)} on_close={ctx.link().callback(|_| Msg::ToggleEmojiPicker)}/>
+                            } else if let Some(m) = self.reacting_to.and_then(|id| self.messages.iter().find(|m| m.id == id)) {
+                                <ReactionPicker title={self.locale.format("reactions.react_to", &[("name", &m.from)])}
+                                    on_select={ctx.link().callback({ let id = m.id; move |emoji| Msg::React((id, emoji)) })}
+                                    on_close={ctx.link().callback(|_| Msg::CloseReactions)}/>
+                            }
+                        </div>
+                        if let Some(config) = &ctx.props().gif {
+                            <div class="relative">
+                                <button onclick={ctx.link().callback(|_| Msg::ToggleGifPicker)} disabled={self.muted_until.is_some()} aria-label={t("chat.gif")} aria-haspopup="dialog" aria-expanded={self.gif_open.to_string()} class="p-2 me-2 text-xs font-bold">
+                                    {"GIF"}
+                                </button>
+                                if self.gif_open {
+                                    <GifPicker config={config.clone()} on_select={ctx.link().callback(Msg::SendImage)} on_close={ctx.link().callback(|_| Msg::ToggleGifPicker)}/>
+                                }
+                            </div>
+                        }
+                        if let Some(manifest_url) = &ctx.props().sticker_manifest_url {
+                            <div class="relative">
+                                <button onclick={ctx.link().callback(|_| Msg::ToggleStickers)} disabled={self.muted_until.is_some()} aria-label={t("chat.stickers")} aria-haspopup="dialog" aria-expanded={self.stickers_open.to_string()} class="p-2 me-2 text-xl">
+                                    <span aria-hidden="true">{"🏷"}</span>
+                                </button>
+                                if self.stickers_open {
+                                    <StickerPicker manifest_url={manifest_url.clone()} on_select={ctx.link().callback(Msg::SendImage)} on_close={ctx.link().callback(|_| Msg::ToggleStickers)}/>
+                                }
+                            </div>
+                        }
+                        if let (Some(recorder), Some(_)) = (&self.recorder, &self.recording_timer) {
+                            <button onclick={ctx.link().callback(|_| Msg::StopRecording)} aria-label={t("chat.stop_recording")} class="shrink-0 flex items-center h-10 px-3 rounded-full bg-gray-200 text-black">
+                                <span class="w-3 h-3 me-2 rounded-full bg-red-600 animate-pulse" aria-hidden="true"></span>
+                                <span role="timer">{format_duration(recorder.elapsed_secs() as u64)}</span>
+                                <span class="ms-2" aria-hidden="true">{"⏹"}</span>
+                            </button>
+                        } else {
+                            <button onclick={ctx.link().callback(|_| Msg::StartRecording)} disabled={self.muted_until.is_some() || self.recorder.is_some()} aria-label={t("chat.record")} class="p-2 me-2 text-xl">
+                                <span aria-hidden="true">{"🎤"}</span>
+                            </button>
+                            <button onclick={submit} disabled={self.muted_until.is_some() || too_long || self.send_pause.is_some()} aria-label={t("chat.send")} class={format!("p-3 shadow-sm bg-blue-600 w-10 h-10 rounded-full flex justify-center items-center {}", text_color_class)}>
+                                <svg viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg" class="fill-white" aria-hidden="true">
+                                    <path d="M0 0h24v24H0z" fill="none"></path><path d="M2.01 21L23 12 2.01 3 2 10l15 2-15 2z"></path>
+                                </svg>
+                            </button>
+                        }
+                        <button onclick={toggle_dark_mode} aria-label={t("chat.toggle_dark_mode")} aria-pressed={self.dark_mode().to_string()} class={format!("p-3 shadow-sm w-10 h-10 rounded-full flex justify-center items-center {}", text_color_class)}>
+                            {t("chat.dark_mode")}
+                        </button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+/// The size of a piece of text. Characters are Unicode scalar values; words
+/// are separated by whitespace.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct TextCount {
+    chars: usize,
+    words: usize,
+}
+
+impl TextCount {
+    fn of(text: &str) -> Self {
+        Self {
+            chars: text.chars().count(),
+            words: text.split_whitespace().count(),
+        }
+    }
+}
+
+/// Whether `msg` is a posted message we already have, such as one the
+/// server retransmits after a reconnect. Messages are noted as seen once
+/// they are shown, by `push_message`.
+fn already_seen(seen: &SeenMessages, msg: &WebSocketMessage) -> bool {
+    if !msg.message_type.is_posted() {
+        return false;
+    }
+    let (Some(Sender { from }), Some(timestamp)) = (msg.payload(), msg.timestamp) else {
+        return false;
+    };
+    seen.contains(&PinKey::new(&from, timestamp))
+}
+
+/// The URL fragment naming a message. Ids differ between clients and
+/// visits, so it goes by the server's timestamp, the same for everyone.
+fn message_anchor(message: &MessageData) -> String {
+    format!("msg-{}", message.timestamp)
+}
+
+/// The timestamp a `#msg-…` fragment names.
+fn anchor_timestamp(hash: &str) -> Option<f64> {
+    hash.trim_start_matches('#')
+        .strip_prefix("msg-")?
+        .parse()
+        .ok()
+}
+
+/// What screen readers say when `message` arrives.
+fn announcement(locale: &Locale, message: &MessageData) -> String {
+    let text = message.message.trim();
+    if message.notice {
+        text.to_string()
+    } else if text.is_empty() {
+        locale.format("chat.announce_attachment", &[("name", &message.from)])
+    } else if message.action {
+        format!("{} {}", message.from, text)
+    } else {
+        locale.format(
+            "chat.announce",
+            &[("name", &message.from), ("message", text)],
+        )
+    }
+}
+
+/// This page's URL, pointing at `message`.
+fn message_link(message: &MessageData) -> Option<String> {
+    let href = web_sys::window()?.location().href().ok()?;
+    let page = href.split('#').next().unwrap_or_default();
+    Some(format!("{}#{}", page, message_anchor(message)))
+}
+
+/// Set once `username` has seen the welcome banner.
+/// Watches for the user going idle, unless going away is switched off.
+fn idle_tracker(ctx: &Context<Chat>, away_after_minutes: u32) -> Option<IdleTracker> {
+    if away_after_minutes == 0 {
+        return None;
+    }
+    IdleTracker::new(away_after_minutes * 60_000, ctx.link().callback(Msg::Idle))
+}
+
+/// Listens for the keyboard shortcuts on the whole page.
+fn shortcut_listener(ctx: &Context<Chat>) -> Option<EventListener> {
+    let document = web_sys::window()?.document()?;
+    let link = ctx.link().clone();
+    let options = EventListenerOptions::enable_prevent_default();
+    Some(EventListener::new_with_options(
+        &document,
+        "keydown",
+        options,
+        move |e| {
+            let Some(e) = e.dyn_ref::<KeyboardEvent>() else {
+                return;
+            };
+            let press = KeyPress {
+                key: e.key(),
+                ctrl: e.ctrl_key() || e.meta_key(),
+                alt: e.alt_key(),
+                in_field: e
+                    .target()
+                    .and_then(|t| t.dyn_into::<Element>().ok())
+                    .is_some_and(|t| is_text_field(&t)),
+            };
+            if let Some(action) = shortcuts::find(&press) {
+                if action != Action::Escape {
+                    e.prevent_default();
+                }
+                link.send_message(Msg::Shortcut(action));
+            } else if is_typing_elsewhere(e) {
+                e.prevent_default();
+                link.send_message(Msg::TypeIntoInput(e.key()));
+            }
+        },
+    ))
+}
+
+/// The link a text message gets a preview card for: its first, unless
+/// that's an image, which shows inline anyway.
+fn preview_link(message: &MessageData) -> Option<&str> {
+    let plain = !message.notice
+        && message.file.is_none()
+        && message.diff.is_none()
+        && message.code_review.is_none()
+        && message.survey.is_none();
+    bidi::first_link(&message.message)
+        .filter(|link| plain && link.starts_with("http") && !is_image(link))
+}
+
+/// Whether `e` types a character with nothing to type it into, so it can
+/// go to the message box. Not inside a dialog, which keeps its own keys,
+/// and not a space, which presses buttons and scrolls.
+fn is_typing_elsewhere(e: &KeyboardEvent) -> bool {
+    let target = e.target().and_then(|t| t.dyn_into::<Element>().ok());
+    let key = e.key();
+    let mut chars = key.chars();
+    let printable =
+        chars.next().is_some_and(|c| !c.is_control() && c != ' ') && chars.next().is_none();
+    printable
+        && !(e.ctrl_key() || e.meta_key() || e.alt_key())
+        && !e.is_composing()
+        && target.is_some_and(|t| {
+            !is_text_field(&t) && t.closest("[role=dialog]").ok().flatten().is_none()
+        })
+}
+
+/// Whether typing into `element` would enter text.
+fn is_text_field(element: &Element) -> bool {
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element
+            .dyn_ref::<HtmlElement>()
+            .is_some_and(|e| e.is_content_editable())
+}
+
+fn is_focused(element: &Element) -> bool {
+    let active = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.active_element());
+    active.as_ref() == Some(element)
+}
+
+fn focus_by_id(id: &str) {
+    let element = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id));
+    if let Some(element) = element.and_then(|e| e.dyn_into::<HtmlElement>().ok()) {
+        let _ = element.focus();
+    }
+}
+
+/// A duration as `m:ss`, or `h:mm:ss` past an hour.
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+fn add_ice_candidate(connection: PeerCall, signal: CallSignal) {
+    if let CallSignal::IceCandidate {
+        candidate,
+        sdp_mid,
+        sdp_m_line_index,
+    } = signal
+    {
+        spawn_local(async move {
+            if let Err(e) = connection
+                .add_ice_candidate(&candidate, sdp_mid.as_deref(), sdp_m_line_index)
+                .await
+            {
+                log::error!("ice candidate: {:?}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Language;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn message(file: Option<FileShare>) -> MessageData {
+        MessageData {
+            from: "alice".into(),
+            message: "hi".into(),
+            timestamp: 1_700_000_000_000.0,
+            file,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn announces_new_messages() {
+        let locale = Locale::new(Language::En);
+        let mut m = message(None);
+        assert_eq!(announcement(&locale, &m), "alice: hi");
+        m.action = true;
+        m.message = "waves".into();
+        assert_eq!(announcement(&locale, &m), "alice waves");
+        m.action = false;
+        m.message = String::new();
+        assert_eq!(announcement(&locale, &m), "alice shared an attachment");
+    }
+
+    #[test]
+    fn only_plain_text_is_text() {
+        assert!(message(None).is_text());
+        let file = FileShare {
+            filename: "notes.pdf".into(),
+            size_bytes: 2048,
+            mime_type: "application/pdf".into(),
+            download_url: "http://127.0.0.1:8080/files/1".into(),
+            content: None,
+        };
+        assert!(!message(Some(file)).is_text());
+        let mut m = message(None);
+        m.message = "https://example.com/cat.png".into();
+        assert!(!m.is_text());
+    }
+
+    #[test]
+    fn inline_images_are_announced_as_attachments() {
+        let locale = Locale::new(Language::En);
+        let mut m = message(None);
+        m.message = String::new();
+        m.image = Some("data:image/jpeg;base64,/9j/4AAQ".into());
+        assert!(!m.is_text());
+        assert_eq!(announcement(&locale, &m), "alice shared an attachment");
+    }
+
+    #[test]
+    fn counts_characters_and_words() {
+        assert_eq!(TextCount::of(""), TextCount { chars: 0, words: 0 });
+        assert_eq!(
+            TextCount::of("  hello   world "),
+            TextCount {
+                chars: 16,
+                words: 2
+            }
+        );
+        assert_eq!(
+            TextCount::of("¡olé!\tñandú"),
+            TextCount {
+                chars: 11,
+                words: 2
+            }
+        );
+        assert_eq!(TextCount::of("👋"), TextCount { chars: 1, words: 1 });
+    }
+
+    #[test]
+    fn message_anchors_round_trip() {
+        let m = message(None);
+        assert_eq!(message_anchor(&m), "msg-1700000000000");
+        assert_eq!(anchor_timestamp("#msg-1700000000000"), Some(m.timestamp));
+        assert_eq!(anchor_timestamp("#msg-"), None);
+        assert_eq!(anchor_timestamp("#top"), None);
+    }
+
+    #[test]
+    fn message_data_round_trips() {
+        let json = serde_json::to_string(&message(None)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"from":"alice","message":"hi","timestamp":1700000000000.0}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<MessageData>(&json).unwrap(),
+            message(None)
+        );
+    }
+
+    #[test]
+    fn message_data_with_file_round_trips() {
+        let original = message(Some(FileShare {
+            filename: "notes.pdf".into(),
+            size_bytes: 2048,
+            mime_type: "application/pdf".into(),
+            download_url: "http://127.0.0.1:8080/files/1".into(),
+            content: None,
+        }));
+        let json = serde_json::to_string(&original).unwrap();
+        assert!(json.contains(r#""file":{"filename":"notes.pdf","sizeBytes":2048"#));
+        assert_eq!(
+            serde_json::from_str::<MessageData>(&json).unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn file_share_frame_flattens_file_fields() {
+        let data: FileShareData = serde_json::from_str(
+            r#"{"from":"bob","filename":"a.zip","sizeBytes":1,"mimeType":"application/zip","downloadUrl":"u"}"#,
+        )
+        .unwrap();
+        assert_eq!(data.from, "bob");
+        assert_eq!(data.file.filename, "a.zip");
+        assert_eq!(data.room, None);
+    }
+
+    #[test]
+    fn audio_frame_carries_its_room() {
+        let data: AudioMessageData =
+            serde_json::from_str(r#"{"from":"bob","room":"ops","url":"u","durationSecs":2.5}"#)
+                .unwrap();
+        assert_eq!(data.room.as_deref(), Some("ops"));
+        assert_eq!(data.audio.duration_secs, 2.5);
+        let lobby: AudioMessageData =
+            serde_json::from_str(r#"{"from":"bob","url":"u","durationSecs":1.0}"#).unwrap();
+        assert_eq!(lobby.room, None);
+    }
+
+    #[test]
+    fn location_frame_carries_its_room() {
+        let data = LocationMessageData {
+            from: String::new(),
+            room: Some("ops".into()),
+            location: Location {
+                lat: 51.5,
+                lon: -0.1,
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            r#"{"room":"ops","lat":51.5,"lon":-0.1}"#
+        );
+    }
+
+    #[test]
+    fn file_share_frame_carries_its_room() {
+        let data = FileShareData {
+            from: String::new(),
+            room: Some("ops".into()),
+            file: FileShare {
+                filename: "a.zip".into(),
+                size_bytes: 1,
+                mime_type: "application/zip".into(),
+                download_url: "u".into(),
+                content: None,
+            },
+        };
+        let json = serde_json::to_string(&data).unwrap();
+        assert!(json.starts_with(r#"{"room":"ops","filename":"a.zip""#));
+        let read: FileShareData = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.room.as_deref(), Some("ops"));
+    }
+
+    #[test]
+    fn malformed_message_frames_are_skipped() {
+        let frame = |s: &str| serde_json::from_str::<WebSocketMessage>(s).unwrap();
+        let missing = frame(r#"{"messageType":"message","timestamp":1.0}"#);
+        assert_eq!(missing.payload::<MessageData>(), None);
+        let bad = frame(r#"{"messageType":"message","data":"{\"from\":1}","timestamp":1.0}"#);
+        assert_eq!(bad.payload::<MessageData>(), None);
+        let good = frame(
+            r#"{"messageType":"threadreply","data":"{\"from\":\"alice\",\"message\":\"hi\"}","timestamp":1.0}"#,
+        );
+        assert_eq!(
+            good.payload::<MessageData>().map(|m| m.from),
+            Some("alice".to_string())
+        );
+    }
+
+    fn posted(message_type: MsgTypes, data: &str) -> WebSocketMessage {
+        WebSocketMessage {
+            message_type,
+            data_array: None,
+            data: Some(data.into()),
+            timestamp: Some(1_700_000_000_000.0),
+        }
+    }
+
+    /// What `push_message` notes once a frame is shown.
+    fn show(seen: &mut SeenMessages, msg: &WebSocketMessage) {
+        let Sender { from } = msg.payload().unwrap();
+        seen.insert(PinKey::new(&from, msg.timestamp.unwrap()));
+    }
+
+    #[test]
+    fn retransmitted_frames_of_any_posted_type_are_dropped() {
+        let mut seen = SeenMessages::default();
+        let location = posted(MsgTypes::Location, r#"{"from":"bob","lat":1.0,"lon":2.0}"#);
+        assert!(!already_seen(&seen, &location));
+        show(&mut seen, &location);
+        assert!(already_seen(&seen, &location));
+        let file = posted(
+            MsgTypes::FileShare,
+            r#"{"from":"alice","filename":"a.zip","sizeBytes":1,"mimeType":"application/zip","downloadUrl":"u"}"#,
+        );
+        assert!(!already_seen(&seen, &file));
+        show(&mut seen, &file);
+        assert!(already_seen(&seen, &file));
+        // Not posted, so never taken for a repeat.
+        let read = posted(MsgTypes::Read, r#"{"from":"bob","timestamp":1.0}"#);
+        assert!(!already_seen(&seen, &read));
+    }
+
+    #[test]
+    fn a_frame_dropped_before_joining_its_room_shows_once_after() {
+        let mut seen = SeenMessages::default();
+        let mut rooms: Vec<String> = Vec::new();
+        let frame = posted(
+            MsgTypes::Message,
+            r#"{"from":"bob","message":"hi","room":"ops"}"#,
+        );
+        let mut bubbles = 0;
+        let mut receive = |seen: &mut SeenMessages, rooms: &[String]| {
+            if already_seen(seen, &frame) {
+                return;
+            }
+            let data = frame.payload::<MessageData>().unwrap();
+            if data.room.as_ref().is_some_and(|r| !rooms.contains(r)) {
+                return;
+            }
+            show(seen, &frame);
+            bubbles += 1;
+        };
+        receive(&mut seen, &rooms);
+        rooms.push("ops".into());
+        receive(&mut seen, &rooms);
+        receive(&mut seen, &rooms);
+        assert_eq!(bubbles, 1);
+    }
+
+    fn names(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("user{}", i)).collect()
+    }
+
+    #[test]
+    fn departed_users_keep_their_profiles() {
+        let mut departed = HashMap::new();
+        let users = merge_users(Users::new(), &mut departed, &names(0..200), 1.0);
+        let users = merge_users(users, &mut departed, &names(100..200), 2.0);
+        assert_eq!(users.len(), 100);
+        assert_eq!(departed.len(), 100);
+
+        // 1,000 messages against 200 senders, half of them gone.
+        for i in 0..1000 {
+            let from = format!("user{}", i % 200);
+            let profile = users.get(&from).or_else(|| departed.get(&from));
+            assert_eq!(profile.map(|p| p.name.as_str()), Some(from.as_str()));
+        }
+
+        let users = merge_users(users, &mut departed, &names(0..200), 3.0);
+        assert!(departed.is_empty());
+        assert_eq!(users["user0"].joined_at, 1.0);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(59), "0:59");
+        assert_eq!(format_duration(600), "10:00");
+        assert_eq!(format_duration(3600), "1:00:00");
     }
 }
-}
\ No newline at end of file
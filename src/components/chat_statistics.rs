@@ -0,0 +1,147 @@
+use std::rc::Rc;
+
+use gloo_timers::callback::Interval;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+use crate::services::message::ChatStats;
+
+/// How often the open panel asks for fresh numbers.
+const REFRESH_MS: u32 = 60_000;
+
+#[derive(Properties, PartialEq)]
+pub struct ChatStatisticsProps {
+    /// `None` until the server first answers.
+    pub stats: Option<Rc<ChatStats>>,
+    /// Asks the server for the latest numbers.
+    pub on_refresh: Callback<()>,
+    pub on_close: Callback<()>,
+}
+
+/// Each hour's bar height as a percentage of the busiest hour's.
+fn bar_heights(hourly: &[u64]) -> Vec<f64> {
+    let busiest = hourly.iter().copied().max().unwrap_or(0).max(1) as f64;
+    (0..24)
+        .map(|hour| hourly.get(hour).copied().unwrap_or(0) as f64 / busiest * 100.0)
+        .collect()
+}
+
+/// Activity across the chat: how much is said, when, and by whom. Asks for
+/// new numbers when opened and every minute while open.
+#[function_component(ChatStatistics)]
+pub fn chat_statistics(props: &ChatStatisticsProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    {
+        let on_refresh = props.on_refresh.clone();
+        use_effect_with_deps(
+            move |_| {
+                on_refresh.emit(());
+                let refresh = Interval::new(REFRESH_MS, move || on_refresh.emit(()));
+                move || drop(refresh)
+            },
+            (),
+        );
+    }
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_close.emit(());
+            }
+        })
+    };
+    let body = match &props.stats {
+        None => html! {
+            <p role="status" class="text-gray-500">{locale.t("stats.loading")}</p>
+        },
+        Some(stats) => {
+            let bars = bar_heights(&stats.hourly).into_iter().enumerate().map(|(hour, height)| {
+                let count = stats.hourly.get(hour).copied().unwrap_or(0);
+                let label = locale.format(
+                    "stats.hour",
+                    &[("hour", &format!("{:02}:00", hour)), ("count", &count.to_string())],
+                );
+                let style = format!(
+                    "left: calc({:.4}% + 1px); width: calc({:.4}% - 2px); height: {:.1}%;",
+                    hour as f64 * 100.0 / 24.0,
+                    100.0 / 24.0,
+                    height,
+                );
+                html! {
+                    <div key={hour} role="img" aria-label={label.clone()} title={label} {style}
+                        class="absolute bottom-0 rounded-t bg-violet-500 hover:bg-violet-700"></div>
+                }
+            });
+            html! {
+                <>
+                    <dl class="grid grid-cols-2 gap-4 mb-6">
+                        <div class="p-3 rounded-lg bg-gray-100">
+                            <dt class="text-xs text-gray-500">{locale.t("stats.total")}</dt>
+                            <dd class="text-2xl font-semibold">{stats.total}</dd>
+                        </div>
+                        <div class="p-3 rounded-lg bg-gray-100">
+                            <dt class="text-xs text-gray-500">{locale.t("stats.busiest_day")}</dt>
+                            <dd class="text-2xl font-semibold">{stats.busiest_day.clone().unwrap_or_else(|| "—".into())}</dd>
+                        </div>
+                    </dl>
+                    <h3 class="mb-2 text-sm font-semibold">{locale.t("stats.by_hour")}</h3>
+                    <div class="relative h-32 mb-1 border-b border-gray-300">{for bars}</div>
+                    <div class="relative h-4 mb-6 text-xs text-gray-400">
+                        {for [0, 6, 12, 18].into_iter().map(|hour| html! {
+                            <span class="absolute" style={format!("left: {:.4}%;", hour as f64 * 100.0 / 24.0)}>{format!("{:02}", hour)}</span>
+                        })}
+                    </div>
+                    <h3 class="mb-2 text-sm font-semibold">{locale.t("stats.top_senders")}</h3>
+                    <ol class="list-decimal ps-6">
+                        {for stats.top_senders.iter().map(|sender| html! {
+                            <li class="py-1">
+                                <span class="font-semibold">{&sender.name}</span>
+                                <span class="ms-2 text-gray-500">{locale.format("stats.messages", &[("count", &sender.count.to_string())])}</span>
+                            </li>
+                        })}
+                    </ol>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <aside ref={dialog} tabindex="-1" role="dialog" aria-label={locale.t("stats.title")} {onkeydown}
+            class="fixed inset-0 z-40 flex flex-col bg-white text-black">
+            <div class="flex items-center justify-between h-14 px-4 border-b border-gray-300">
+                <span class="text-lg font-semibold">{locale.t("stats.title")}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")}>{"✕"}</button>
+            </div>
+            <div class="grow overflow-y-auto">
+                <div class="max-w-2xl mx-auto p-4">{body}</div>
+            </div>
+        </aside>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_scale_to_the_busiest_hour() {
+        let mut hourly = vec![0; 24];
+        hourly[9] = 20;
+        hourly[14] = 5;
+        let heights = bar_heights(&hourly);
+        assert_eq!(heights.len(), 24);
+        assert_eq!(heights[9], 100.0);
+        assert_eq!(heights[14], 25.0);
+        assert_eq!(heights[0], 0.0);
+    }
+
+    #[test]
+    fn short_or_empty_data_still_has_every_hour() {
+        assert_eq!(bar_heights(&[]), vec![0.0; 24]);
+        assert_eq!(bar_heights(&[4])[0], 100.0);
+    }
+}
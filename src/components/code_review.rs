@@ -0,0 +1,141 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::CodeReview;
+use crate::syntax::{self, Language};
+
+#[derive(Properties, PartialEq)]
+pub struct CodeReviewViewProps {
+    pub review: CodeReview,
+    /// Comments on a line, by number. Without it lines can't be commented.
+    #[prop_or_default]
+    pub on_comment: Option<Callback<(u32, String)>>,
+}
+
+/// A shared snippet with line numbers. Pointing at a commented line shows
+/// what was said about it, and the `+` in the gutter adds to that.
+#[function_component(CodeReviewView)]
+pub fn code_review_view(props: &CodeReviewViewProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    // The line a comment is being written for.
+    let replying = use_state(|| None::<u32>);
+    let draft = use_state(String::new);
+    let review = &props.review;
+    let language = Language::from_tag(&review.language.to_lowercase());
+
+    let code = |text: &str| match language {
+        Some(language) => syntax::tokenize(language, text)
+            .map(|token| match token.kind.class() {
+                Some(class) => html! { <span {class}>{token.text}</span> },
+                None => html! { {token.text} },
+            })
+            .collect::<Html>(),
+        None => html! { {text} },
+    };
+    let reply_form = |line: u32| {
+        let on_comment = props.on_comment.clone();
+        let oninput = {
+            let draft = draft.clone();
+            Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                draft.set(input.value());
+            })
+        };
+        let onsubmit = {
+            let (draft, replying) = (draft.clone(), replying.clone());
+            Callback::from(move |e: FocusEvent| {
+                e.prevent_default();
+                let text = draft.trim();
+                if let (false, Some(on_comment)) = (text.is_empty(), &on_comment) {
+                    on_comment.emit((line, text.to_string()));
+                }
+                draft.set(String::new());
+                replying.set(None);
+            })
+        };
+        let cancel = {
+            let (draft, replying) = (draft.clone(), replying.clone());
+            move || {
+                draft.set(String::new());
+                replying.set(None);
+            }
+        };
+        let onkeydown = {
+            let cancel = cancel.clone();
+            Callback::from(move |e: KeyboardEvent| {
+                if e.key() == "Escape" {
+                    e.stop_propagation();
+                    cancel();
+                }
+            })
+        };
+        let label = locale.format("review.comment_on_line", &[("line", &line.to_string())]);
+        html! {
+            <tr>
+                <td></td>
+                <td class="p-1">
+                    <form {onsubmit} class="flex gap-1 font-sans whitespace-normal">
+                        <input {oninput} {onkeydown} value={(*draft).clone()} autofocus=true aria-label={label.clone()} placeholder={label}
+                            class="grow min-w-0 px-2 py-1 rounded bg-white text-black outline-none"/>
+                        <button type="submit" class="px-2 rounded bg-blue-600 text-white">{locale.t("review.comment")}</button>
+                        <button type="button" onclick={Callback::from(move |_| cancel())} class="px-2 rounded bg-gray-600">{locale.t("export.cancel")}</button>
+                    </form>
+                </td>
+            </tr>
+        }
+    };
+
+    let rows = review.code.lines().zip(1u32..).map(|(text, line)| {
+        let comments: Vec<_> = review.comments.iter().filter(|c| c.line == line).collect();
+        let add = props.on_comment.as_ref().map(|_| {
+            let replying = replying.clone();
+            Callback::from(move |_: MouseEvent| replying.set(Some(line)))
+        });
+        let commented = !comments.is_empty();
+        html! {
+            <>
+                <tr tabindex={commented.then_some("0")} class={classes!("group", commented.then_some("code-commented"))}>
+                    <td class="diff-line-number">
+                        if let Some(onclick) = add {
+                            <button {onclick} aria-label={locale.format("review.comment_on_line", &[("line", &line.to_string())])}
+                                class="invisible group-hover:visible focus:visible me-1 px-1 rounded bg-blue-600 text-white">{"+"}</button>
+                        }
+                        {line}
+                    </td>
+                    <td class="diff-code relative">
+                        {code(text)}
+                        if commented {
+                            <span class="ms-2" aria-label={locale.format("review.comments", &[("count", &comments.len().to_string())])}>{"💬"}</span>
+                            <div role="tooltip" class="hidden group-hover:block group-focus:block absolute start-8 top-full z-10 w-64 p-2 rounded shadow bg-white text-black font-sans whitespace-normal">
+                                {for comments.iter().map(|c| html! {
+                                    <p class="mb-1 last:mb-0">
+                                        if !c.from.is_empty() {
+                                            <span class="font-semibold">{&c.from}{": "}</span>
+                                        }
+                                        {&c.text}
+                                    </p>
+                                })}
+                            </div>
+                        }
+                    </td>
+                </tr>
+                if *replying == Some(line) {
+                    {reply_form(line)}
+                }
+            </>
+        }
+    });
+
+    html! {
+        <div class="diff-block mt-1">
+            <div class="diff-summary">
+                {locale.t("review.title")}
+                if !review.language.is_empty() {
+                    {format!(" · {}", review.language)}
+                }
+            </div>
+            <table class="diff-table">{for rows}</table>
+        </div>
+    }
+}
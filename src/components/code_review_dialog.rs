@@ -0,0 +1,77 @@
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+
+#[derive(Properties, PartialEq)]
+pub struct CodeReviewDialogProps {
+    /// Called with the language and the code.
+    pub on_send: Callback<(String, String)>,
+    pub on_close: Callback<()>,
+}
+
+/// Takes a snippet to share for review. The message box is a single line,
+/// so code gets its own.
+#[function_component(CodeReviewDialog)]
+pub fn code_review_dialog(props: &CodeReviewDialogProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let language = use_state(String::new);
+    let code = use_state(String::new);
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                on_close.emit(());
+            }
+        })
+    };
+    let on_language = {
+        let language = language.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            language.set(input.value());
+        })
+    };
+    let on_code = {
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlTextAreaElement = e.target_unchecked_into();
+            code.set(input.value());
+        })
+    };
+    let empty = code.trim().is_empty();
+    let send = {
+        let (language, code) = (language.clone(), code.clone());
+        props
+            .on_send
+            .reform(move |_: MouseEvent| (language.trim().to_string(), (*code).clone()))
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30" onclick={props.on_close.reform(|_| ())}>
+            <div {onkeydown} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                ref={dialog} tabindex="-1" role="dialog" aria-modal="true" aria-label={locale.t("review.share").to_string()}
+                class="w-[36rem] max-w-full p-4 bg-white text-black rounded-lg shadow-lg text-sm flex flex-col gap-3">
+                <span class="text-lg font-semibold">{locale.t("review.share")}</span>
+                <input oninput={on_language} value={(*language).clone()} list="review-languages"
+                    aria-label={locale.t("review.language")} placeholder={locale.t("review.language")}
+                    class="px-2 py-1 rounded bg-gray-100 outline-none"/>
+                <datalist id="review-languages">
+                    <option value="rust"/>
+                    <option value="javascript"/>
+                    <option value="python"/>
+                </datalist>
+                <textarea oninput={on_code} value={(*code).clone()} rows="12" spellcheck="false"
+                    aria-label={locale.t("review.code")} placeholder={locale.t("review.code")}
+                    class="px-2 py-1 rounded bg-gray-100 font-mono whitespace-pre outline-none"></textarea>
+                <div class="flex justify-end gap-2">
+                    <button onclick={props.on_close.reform(|_| ())} class="px-3 py-1 rounded-full bg-gray-200">{locale.t("export.cancel")}</button>
+                    <button onclick={send} disabled={empty} class="px-3 py-1 rounded-full bg-blue-600 text-white disabled:opacity-50">{locale.t("review.send")}</button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,125 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::prelude::*;
+
+use crate::services::confetti::{burst, DURATION_MS};
+
+#[derive(Properties, PartialEq)]
+pub struct ConfettiProps {
+    /// Called once the burst has fallen, to take the canvas away.
+    pub on_done: Callback<()>,
+}
+
+type Tick = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+/// A burst of confetti over the whole window. The canvas is portalled into
+/// `<body>` so no scrolling container clips it, and lets clicks through.
+#[function_component(Confetti)]
+pub fn confetti(props: &ConfettiProps) -> Html {
+    let canvas = use_node_ref();
+    {
+        let canvas = canvas.clone();
+        let on_done = props.on_done.clone();
+        use_effect_with_deps(
+            move |_| {
+                let tick: Tick = Rc::new(RefCell::new(None));
+                let frame = Rc::new(Cell::new(None));
+                if let Some(canvas) = canvas.cast::<HtmlCanvasElement>() {
+                    animate(&canvas, &tick, &frame, on_done);
+                }
+                move || {
+                    if let (Some(window), Some(id)) = (web_sys::window(), frame.get()) {
+                        let _ = window.cancel_animation_frame(id);
+                    }
+                    // The closure holds on to itself to schedule the next
+                    // frame; dropping it here breaks the cycle.
+                    tick.borrow_mut().take();
+                }
+            },
+            (),
+        );
+    }
+
+    let body = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.body());
+    match body {
+        Some(body) => create_portal(
+            html! {
+                <canvas ref={canvas} aria-hidden="true" class="fixed inset-0 z-[9999] pointer-events-none"></canvas>
+            },
+            body.into(),
+        ),
+        None => html! {},
+    }
+}
+
+/// Sizes the canvas to the window and draws a frame per animation frame
+/// until the burst is over.
+fn animate(
+    canvas: &HtmlCanvasElement,
+    tick: &Tick,
+    frame: &Rc<Cell<Option<i32>>>,
+    on_done: Callback<()>,
+) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let size = |value: Result<JsValue, JsValue>| value.ok().and_then(|v| v.as_f64());
+    let width = size(window.inner_width()).unwrap_or(800.0);
+    let height = size(window.inner_height()).unwrap_or(600.0);
+    canvas.set_width(width as u32);
+    canvas.set_height(height as u32);
+    let Some(context) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+    else {
+        on_done.emit(());
+        return;
+    };
+
+    let mut particles = burst(width, js_sys::Math::random);
+    let mut start = None;
+    let mut last = None;
+    let next = tick.clone();
+    let scheduled = frame.clone();
+    *tick.borrow_mut() = Some(Closure::wrap(Box::new(move |now: f64| {
+        let start = *start.get_or_insert(now);
+        let dt = (now - last.replace(now).unwrap_or(now)) / 1000.0;
+        context.clear_rect(0.0, 0.0, width, height);
+        if now - start >= DURATION_MS {
+            scheduled.set(None);
+            on_done.emit(());
+            return;
+        }
+        for particle in &mut particles {
+            particle.step(dt);
+            context.save();
+            let _ = context.translate(particle.x, particle.y);
+            let _ = context.rotate(particle.angle);
+            context.set_fill_style(&JsValue::from_str(particle.color));
+            context.fill_rect(
+                -particle.width / 2.0,
+                -particle.height / 2.0,
+                particle.width,
+                particle.height,
+            );
+            context.restore();
+        }
+        scheduled.set(request_frame(&next));
+    }) as Box<dyn FnMut(f64)>));
+    frame.set(request_frame(tick));
+}
+
+fn request_frame(tick: &Tick) -> Option<i32> {
+    let tick = tick.borrow();
+    web_sys::window()?
+        .request_animation_frame(tick.as_ref()?.as_ref().unchecked_ref())
+        .ok()
+}
@@ -0,0 +1,44 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+
+#[derive(Properties, PartialEq)]
+pub struct ConfirmDialogProps {
+    pub message: String,
+    /// The button that goes ahead, such as "Delete".
+    pub confirm_label: String,
+    pub on_confirm: Callback<()>,
+    pub on_cancel: Callback<()>,
+}
+
+/// Asks before something that can't be taken back. Escape or clicking
+/// outside cancels.
+#[function_component(ConfirmDialog)]
+pub fn confirm_dialog(props: &ConfirmDialogProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let onkeydown = {
+        let on_cancel = props.on_cancel.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_cancel.emit(());
+            }
+        })
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30" onclick={props.on_cancel.reform(|_| ())}>
+            <div onclick={Callback::from(|e: MouseEvent| e.stop_propagation())} {onkeydown}
+                ref={dialog} tabindex="-1" role="alertdialog" aria-modal="true" aria-label={props.message.clone()}
+                class="w-80 p-4 bg-white text-black rounded-lg shadow-lg text-sm flex flex-col gap-3">
+                <p>{&props.message}</p>
+                <div class="flex justify-end gap-2">
+                    <button onclick={props.on_cancel.reform(|_| ())} class="px-3 py-1 rounded-full bg-gray-200">{locale.t("chat.cancel")}</button>
+                    <button onclick={props.on_confirm.reform(|_| ())} class="px-3 py-1 rounded-full bg-red-600 text-white">{&props.confirm_label}</button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,46 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::websocket::{ConnectionState, ConnectionStatus};
+
+#[derive(Properties, PartialEq)]
+pub struct ConnectionStatusBannerProps {
+    pub state: ConnectionState,
+}
+
+#[function_component(ConnectionStatusBanner)]
+pub fn connection_status_banner(props: &ConnectionStatusBannerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let state = &props.state;
+    let status = match state.status {
+        ConnectionStatus::Online => None,
+        ConnectionStatus::Connecting => Some(locale.t("connection.connecting").to_string()),
+        ConnectionStatus::Flushing => Some(locale.t("connection.flushing").to_string()),
+        ConnectionStatus::Offline if state.queued > 0 => Some(locale.format(
+            "connection.offline_queued",
+            &[("count", &state.queued.to_string())],
+        )),
+        ConnectionStatus::Offline => Some(locale.t("connection.offline").to_string()),
+    };
+    let dropped = (state.dropped > 0).then(|| {
+        locale.format(
+            "connection.dropped",
+            &[("count", &state.dropped.to_string())],
+        )
+    });
+
+    if status.is_none() && dropped.is_none() {
+        return html! {};
+    }
+
+    html! {
+        <div class="w-full px-3 py-1 text-xs bg-yellow-100 text-yellow-800">
+            if let Some(status) = status {
+                <div>{status}</div>
+            }
+            if let Some(dropped) = dropped {
+                <div class="font-bold">{dropped}</div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,72 @@
+use yew::prelude::*;
+
+use crate::diff::{self, LineKind};
+use crate::syntax;
+
+/// Diffs longer than this start collapsed.
+const COLLAPSE_AFTER_LINES: usize = 12;
+
+#[derive(Properties, PartialEq)]
+pub struct DiffViewProps {
+    pub diff: String,
+}
+
+#[function_component(DiffView)]
+pub fn diff_view(props: &DiffViewProps) -> Html {
+    let lines = diff::parse(&props.diff);
+    let collapsed = use_state(|| lines.len() > COLLAPSE_AFTER_LINES);
+    let language = diff::language(&props.diff);
+
+    let added = lines.iter().filter(|l| l.kind == LineKind::Added).count();
+    let removed = lines.iter().filter(|l| l.kind == LineKind::Removed).count();
+    let toggle = {
+        let collapsed = collapsed.clone();
+        Callback::from(move |_| collapsed.set(!*collapsed))
+    };
+
+    let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+    let code = |text: &str| match language {
+        Some(language) => syntax::tokenize(language, text)
+            .map(|token| match token.kind.class() {
+                Some(class) => html! { <span {class}>{token.text}</span> },
+                None => html! { {token.text} },
+            })
+            .collect::<Html>(),
+        None => html! { {text} },
+    };
+
+    html! {
+        <div class="diff-block mt-1">
+            <button onclick={toggle} aria-expanded={(!*collapsed).to_string()} class="diff-summary">
+                <span aria-hidden="true">{if *collapsed { "▸ " } else { "▾ " }}</span>
+                {diff::file_name(&props.diff).unwrap_or("diff")}
+                <span class="diff-added-count">{format!(" +{}", added)}</span>
+                <span class="diff-removed-count">{format!(" −{}", removed)}</span>
+            </button>
+            if !*collapsed {
+                <table class="diff-table">
+                    {for lines.iter().filter(|l| l.kind != LineKind::Header).map(|line| {
+                        let (class, marker) = match line.kind {
+                            LineKind::Added => ("diff-added", "+"),
+                            LineKind::Removed => ("diff-removed", "-"),
+                            LineKind::Hunk => ("diff-hunk", ""),
+                            LineKind::Meta => ("diff-meta", ""),
+                            LineKind::Context | LineKind::Header => ("", " "),
+                        };
+                        let text = match line.kind {
+                            LineKind::Hunk | LineKind::Meta => html! { {line.text} },
+                            _ => code(line.text),
+                        };
+                        html! {
+                            <tr {class}>
+                                <td class="diff-line-number">{number(line.old_line)}</td>
+                                <td class="diff-line-number">{number(line.new_line)}</td>
+                                <td class="diff-code">{marker}{text}</td>
+                            </tr>
+                        }
+                    })}
+                </table>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,112 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::export::format_time;
+use crate::services::message::{ServerError, Severity};
+
+/// A server error as the client keeps it. `id` is assigned on arrival and
+/// keys both the toast and its log entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorEntry {
+    pub id: usize,
+    pub error: ServerError,
+    pub timestamp: f64,
+}
+
+fn icon(error: &ServerError) -> &'static str {
+    match error.severity() {
+        Severity::Info => "ℹ️",
+        Severity::Alert => "🚨",
+    }
+}
+
+fn toast_class(error: &ServerError) -> &'static str {
+    match error.severity() {
+        Severity::Info => "bg-blue-50 text-blue-900 border-blue-300",
+        Severity::Alert => "bg-red-100 text-red-900 border-red-400",
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorToastsProps {
+    pub toasts: Vec<Rc<ErrorEntry>>,
+    pub on_dismiss: Callback<usize>,
+    pub on_view_all: Callback<()>,
+}
+
+/// Server errors stacked at the top right of the viewport.
+#[function_component(ErrorToasts)]
+pub fn error_toasts(props: &ErrorToastsProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    if props.toasts.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div class="fixed top-3 end-3 z-50 w-80 flex flex-col gap-2">
+            {for props.toasts.iter().map(|entry| {
+                let id = entry.id;
+                let role = match entry.error.severity() {
+                    Severity::Info => "status",
+                    Severity::Alert => "alert",
+                };
+                html! {
+                    <div key={id} {role} class={classes!("flex", "items-start", "p-3", "rounded-lg", "border", "shadow", "text-sm", toast_class(&entry.error))}>
+                        <span aria-hidden="true" class="me-2">{icon(&entry.error)}</span>
+                        <div class="grow">
+                            <div class="font-semibold">{locale.format("errors.code", &[("code", &entry.error.code.to_string())])}</div>
+                            <div>{entry.error.detail.clone()}</div>
+                        </div>
+                        <button onclick={props.on_dismiss.reform(move |_| id)} aria-label={locale.t("errors.dismiss")} class="ms-2">{"✕"}</button>
+                    </div>
+                }
+            })}
+            <button onclick={props.on_view_all.reform(|_| ())} class="self-end text-xs underline text-gray-500">
+                {locale.t("errors.view_all")}
+            </button>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorLogProps {
+    pub errors: Vec<Rc<ErrorEntry>>,
+    pub on_close: Callback<()>,
+}
+
+/// Recent server errors, newest first.
+#[function_component(ErrorLog)]
+pub fn error_log(props: &ErrorLogProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                on_close.emit(());
+            }
+        })
+    };
+    html! {
+        <div {onkeydown} role="dialog" aria-label={locale.t("errors.title")} class="fixed top-3 end-3 z-50 w-96 max-h-96 flex flex-col bg-white text-black rounded-lg shadow-lg text-sm">
+            <div class="flex justify-between items-center p-3 border-b">
+                <span class="font-semibold">{locale.t("errors.title")}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")}>{"✕"}</button>
+            </div>
+            <ul class="overflow-auto">
+                if props.errors.is_empty() {
+                    <li class="p-3 text-gray-400">{locale.t("errors.none")}</li>
+                }
+                {for props.errors.iter().rev().map(|entry| html! {
+                    <li key={entry.id} class="flex items-start p-3 border-b">
+                        <span aria-hidden="true" class="me-2">{icon(&entry.error)}</span>
+                        <div class="grow">
+                            <span class="font-semibold">{entry.error.code}</span>{" "}{entry.error.detail.clone()}
+                        </div>
+                        <span class="ms-2 text-xs text-gray-400">{format_time(entry.timestamp)}</span>
+                    </li>
+                })}
+            </ul>
+        </div>
+    }
+}
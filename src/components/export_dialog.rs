@@ -0,0 +1,65 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::export::ExportFormat;
+use crate::services::focus::use_modal_focus;
+
+#[derive(Properties, PartialEq)]
+pub struct ExportDialogProps {
+    pub on_export: Callback<ExportFormat>,
+    pub on_close: Callback<()>,
+}
+
+/// Asks for a format before downloading the transcript.
+#[function_component(ExportDialog)]
+pub fn export_dialog(props: &ExportDialogProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let format = use_state(|| ExportFormat::Json);
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                on_close.emit(());
+            }
+        })
+    };
+    let download = {
+        let format = format.clone();
+        props.on_export.reform(move |_: MouseEvent| *format)
+    };
+    let option = |option: ExportFormat| {
+        let label = match option {
+            ExportFormat::Json => locale.t("chat.export_json"),
+            ExportFormat::Text => locale.t("chat.export_text"),
+        };
+        let onchange = {
+            let format = format.clone();
+            Callback::from(move |_: Event| format.set(option))
+        };
+        html! {
+            <label class="me-3">
+                <input type="radio" name="export-format" checked={*format == option} {onchange}/>
+                {" "}{label}
+            </label>
+        }
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30" onclick={props.on_close.reform(|_| ())}>
+            <div {onkeydown} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                ref={dialog} tabindex="-1" role="dialog" aria-modal="true" aria-label={locale.t("export.title").to_string()}
+                class="w-72 p-4 bg-white text-black rounded-lg shadow-lg text-sm flex flex-col gap-3">
+                <span class="text-lg font-semibold">{locale.t("export.title")}</span>
+                <fieldset>
+                    {for ExportFormat::ALL.into_iter().map(option)}
+                </fieldset>
+                <div class="flex justify-end gap-2">
+                    <button onclick={props.on_close.reform(|_| ())} class="px-3 py-1 rounded-full bg-gray-200">{locale.t("export.cancel")}</button>
+                    <button onclick={download} class="px-3 py-1 rounded-full bg-blue-600 text-white">{locale.t("export.download")}</button>
+                </div>
+            </div>
+        </div>
+    }
+}
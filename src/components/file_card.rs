@@ -0,0 +1,81 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::FileShare;
+use crate::services::upload::ObjectUrl;
+
+#[derive(Properties, PartialEq)]
+pub struct FileCardProps {
+    pub file: FileShare,
+}
+
+#[function_component(FileCard)]
+pub fn file_card(props: &FileCardProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let file = &props.file;
+    // A file sent inline downloads from a blob of its content.
+    let inline = use_state(|| {
+        file.content
+            .as_deref()
+            .and_then(|content| ObjectUrl::from_base64(content, &file.mime_type))
+    });
+    let href = match &*inline {
+        Some(url) => url.as_str().to_string(),
+        None => file.download_url.clone(),
+    };
+    html! {
+        <div class="flex items-center mt-2 p-2 rounded bg-white border border-gray-200 text-black">
+            <span class="text-2xl me-2" aria-hidden="true">{file_icon(file)}</span>
+            <div class="flex-grow min-w-0">
+                <div class="text-sm truncate" title={file.filename.clone()}>{file.filename.clone()}</div>
+                <div class="text-xs text-gray-400">{human_size(file.size_bytes)}</div>
+            </div>
+            <a {href} download={file.filename.clone()} target="_blank" rel="noopener"
+                class="ms-2 px-3 py-1 text-xs rounded-full bg-blue-600 text-white">{locale.t("file.download")}</a>
+        </div>
+    }
+}
+
+fn file_icon(file: &FileShare) -> &'static str {
+    let extension = file
+        .filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match (file.mime_type.as_str(), extension.as_str()) {
+        ("application/pdf", _) | (_, "pdf") => "📕",
+        (_, "zip" | "gz" | "tar" | "7z" | "rar") => "🗜️",
+        (mime, _) if mime.starts_with("audio/") => "🎵",
+        (mime, _) if mime.starts_with("video/") => "🎬",
+        (mime, _) if mime.starts_with("image/") => "🖼️",
+        (mime, _) if mime.starts_with("text/") => "📝",
+        _ => "📄",
+    }
+}
+
+/// Formats a byte count with binary units, e.g. `1.5 MB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::human_size;
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -0,0 +1,45 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::components::chat::{MessageData, UserProfile};
+use crate::components::message_actions::MessageActions;
+use crate::components::message_item::MessageItem;
+use crate::services::avatars::AvatarStyle;
+
+fn fixture(id: usize, message: String) -> Rc<MessageData> {
+    Rc::new(MessageData {
+        id,
+        from: "fixture".into(),
+        message,
+        ..Default::default()
+    })
+}
+
+/// Messages that have broken the bubble layout before, rendered at the width
+/// of the chat list. None of them may make the page scroll sideways; check
+/// here after changing how messages render.
+#[function_component(OverflowFixture)]
+pub fn overflow_fixture() -> Html {
+    let messages = use_state(|| {
+        let url = format!("https://example.com/{}", "a1".repeat(990));
+        vec![
+            fixture(0, "a".repeat(500)),
+            fixture(1, url.clone()),
+            fixture(2, format!("see {} and {}", "b".repeat(500), url)),
+            fixture(3, format!("```\n{}\n```", "c".repeat(500))),
+            fixture(4, "https://picsum.photos/4000/300.jpg".into()),
+        ]
+    });
+    let actions = use_state(|| Rc::new(MessageActions::default()));
+    let author = use_state(|| Rc::new(UserProfile::new("fixture", AvatarStyle::default(), 0.0)));
+
+    html! {
+        <div class="flex flex-col w-full h-full overflow-y-auto">
+            {for messages.iter().map(|message| html! {
+                <MessageItem message={message.clone()} author={(*author).clone()} actions={(*actions).clone()}
+                    on_avatar_click={Callback::noop()} on_draw={Callback::noop()}/>
+            })}
+        </div>
+    }
+}
@@ -0,0 +1,174 @@
+use std::rc::Rc;
+
+use gloo_timers::callback::Timeout;
+use web_sys::{Element, HtmlInputElement};
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::gifs::{self, GifConfig, Results, ResultsAction};
+
+/// How long typing has to pause before a search goes out.
+const DEBOUNCE_MS: u32 = 300;
+/// How close to the bottom of the grid, in pixels, the next page loads.
+const LOAD_MORE_MARGIN: i32 = 200;
+
+#[derive(Properties, PartialEq)]
+pub struct GifPickerProps {
+    pub config: GifConfig,
+    /// Called with the chosen GIF's URL.
+    pub on_select: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+impl Reducible for Results {
+    type Action = ResultsAction;
+
+    fn reduce(self: Rc<Self>, action: ResultsAction) -> Rc<Self> {
+        let mut results = (*self).clone();
+        results.apply(action);
+        results.into()
+    }
+}
+
+/// Searches for a GIF to send. Opens on what is trending, and loads more
+/// results as the grid is scrolled to the bottom.
+#[function_component(GifPicker)]
+pub fn gif_picker(props: &GifPickerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let query = use_state(String::new);
+    let debounced = use_state(String::new);
+    let timer = use_mut_ref(|| None::<Timeout>);
+    let generation = use_mut_ref(|| 0u32);
+    // The page last asked for, so a burst of scroll events asks only once.
+    let requested = use_mut_ref(|| None::<String>);
+    let results = use_reducer(Results::default);
+    let input = use_node_ref();
+
+    {
+        let input = input.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(input) = input.cast::<HtmlInputElement>() {
+                    let _ = input.focus();
+                }
+                || ()
+            },
+            (),
+        );
+    }
+
+    // Each new query starts over from the first page.
+    {
+        let config = props.config.clone();
+        let results = results.clone();
+        let generation = generation.clone();
+        use_effect_with_deps(
+            move |query: &String| {
+                *generation.borrow_mut() += 1;
+                let current = *generation.borrow();
+                results.dispatch(ResultsAction::Reset(current));
+                fetch(config, query.clone(), None, current, results);
+                || ()
+            },
+            (*debounced).clone(),
+        );
+    }
+
+    let oninput = {
+        let query = query.clone();
+        let debounced = debounced.clone();
+        Callback::from(move |e: InputEvent| {
+            let value = e.target_unchecked_into::<HtmlInputElement>().value();
+            query.set(value.clone());
+            let debounced = debounced.clone();
+            *timer.borrow_mut() = Some(Timeout::new(DEBOUNCE_MS, move || debounced.set(value)));
+        })
+    };
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_close.emit(());
+            }
+        })
+    };
+    let load_more = {
+        let config = props.config.clone();
+        let results = results.clone();
+        let query = (*debounced).clone();
+        Callback::from(move |_: ()| {
+            if !results.has_more() || *requested.borrow() == results.next {
+                return;
+            }
+            *requested.borrow_mut() = results.next.clone();
+            results.dispatch(ResultsAction::LoadMore);
+            fetch(
+                config.clone(),
+                query.clone(),
+                results.next.clone(),
+                results.generation,
+                results.clone(),
+            );
+        })
+    };
+    let onscroll = {
+        let load_more = load_more.clone();
+        Callback::from(move |e: Event| {
+            let grid = e.target_unchecked_into::<Element>();
+            let bottom = grid.scroll_top() + grid.client_height();
+            if bottom >= grid.scroll_height() - LOAD_MORE_MARGIN {
+                load_more.emit(());
+            }
+        })
+    };
+
+    html! {
+        <div role="dialog" aria-label={locale.t("gifs.title")} {onkeydown}
+            class="absolute bottom-full end-0 mb-2 w-80 h-96 flex flex-col p-2 gap-2 rounded-lg shadow-lg bg-white text-sm text-black">
+            <div class="flex items-center">
+                <input ref={input} type="search" value={(*query).clone()} {oninput} placeholder={locale.t("gifs.search")}
+                    aria-label={locale.t("gifs.search")} class="grow px-3 py-1 rounded-full bg-gray-100 outline-none"/>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")} class="ms-2 px-1">{"✕"}</button>
+            </div>
+            <div {onscroll} aria-busy={results.loading.to_string()} class="grow overflow-y-auto">
+                <div class="grid grid-cols-2 gap-1">
+                    {for results.gifs.iter().map(|gif| {
+                        let url = gif.url.clone();
+                        html! {
+                            <button key={gif.id.clone()} onclick={props.on_select.reform(move |_| url.clone())}
+                                title={gif.description.clone()} class="block overflow-hidden rounded bg-gray-100">
+                                <img src={gif.preview_url.clone()} alt={gif.description.clone()} loading="lazy" class="w-full h-24 object-cover"/>
+                            </button>
+                        }
+                    })}
+                </div>
+                if let Some(error) = &results.error {
+                    <p role="alert" class="p-2 text-red-600">{locale.format("gifs.error", &[("error", error)])}</p>
+                } else if results.loading {
+                    <p role="status" class="p-2 text-gray-500">{locale.t("gifs.loading")}</p>
+                } else if results.gifs.is_empty() {
+                    <p class="p-2 text-gray-500">{locale.t("gifs.empty")}</p>
+                } else if results.has_more() {
+                    <button onclick={load_more.reform(|_| ())} class="w-full p-2 text-blue-600 underline">{locale.t("gifs.more")}</button>
+                }
+            </div>
+        </div>
+    }
+}
+
+fn fetch(
+    config: GifConfig,
+    query: String,
+    pos: Option<String>,
+    generation: u32,
+    results: UseReducerHandle<Results>,
+) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let action = match gifs::search(&config, &query, pos.as_deref()).await {
+            Ok(page) => ResultsAction::Loaded(generation, page),
+            Err(error) => ResultsAction::Failed(generation, error),
+        };
+        results.dispatch(action);
+    });
+}
@@ -0,0 +1,120 @@
+use yew::prelude::*;
+
+/// What a key does to the focused message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKey {
+    Reply,
+    React,
+    Copy,
+    /// Asks first, and only for our own messages.
+    Delete,
+}
+
+impl MessageKey {
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "r" | "R" => Some(Self::Reply),
+            "e" | "E" => Some(Self::React),
+            "c" | "C" => Some(Self::Copy),
+            "Delete" => Some(Self::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// The element id of a message's bubble, for `aria-activedescendant`.
+pub fn message_element_id(id: usize) -> String {
+    format!("message-{}", id)
+}
+
+/// The message after or before `current` among `ids`, staying put at either
+/// end. With none focused yet, or one no longer listed, the newest.
+pub fn step(ids: &[usize], current: Option<usize>, down: bool) -> Option<usize> {
+    let Some(index) = current.and_then(|current| ids.iter().position(|&id| id == current)) else {
+        return ids.last().copied();
+    };
+    let next = match down {
+        true => (index + 1).min(ids.len() - 1),
+        false => index.saturating_sub(1),
+    };
+    Some(ids[next])
+}
+
+#[derive(Properties, PartialEq)]
+pub struct KeyboardNavigableMessageListProps {
+    /// The messages in the list, by id, oldest first.
+    pub ids: Vec<usize>,
+    pub focused: Option<usize>,
+    /// Moves the focus to another message.
+    pub on_focus: Callback<usize>,
+    /// A key pressed on the focused message.
+    pub on_key: Callback<(usize, MessageKey)>,
+    pub label: String,
+    pub children: Children,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+/// The message list as one stop in the tab order. Up and Down move a focus
+/// ring between messages, announced through `aria-activedescendant`, and
+/// keys act on the message it's on. Keys pressed inside a message, such as
+/// in a survey, are left alone.
+#[function_component(KeyboardNavigableMessageList)]
+pub fn keyboard_navigable_message_list(props: &KeyboardNavigableMessageListProps) -> Html {
+    let onkeydown = {
+        let ids = props.ids.clone();
+        let focused = props.focused;
+        let on_focus = props.on_focus.clone();
+        let on_key = props.on_key.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            let on_list = e.target().is_some() && e.target() == e.current_target();
+            if !on_list || e.ctrl_key() || e.alt_key() || e.meta_key() {
+                return;
+            }
+            let key = e.key();
+            if let "ArrowUp" | "ArrowDown" = key.as_str() {
+                e.prevent_default();
+                if let Some(next) = step(&ids, focused, key == "ArrowDown") {
+                    on_focus.emit(next);
+                }
+            } else if let (Some(id), Some(action)) = (focused, MessageKey::from_key(&key)) {
+                // Not typed into the message box, either.
+                e.prevent_default();
+                e.stop_propagation();
+                on_key.emit((id, action));
+            }
+        })
+    };
+
+    html! {
+        <div tabindex="0" role="group" aria-label={props.label.clone()} aria-activedescendant={props.focused.map(message_element_id)} {onkeydown}
+            class={classes!("flex", "flex-col", "min-h-0", "outline-none", props.class.clone())}>
+            {for props.children.iter()}
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_between_messages_and_stops_at_the_ends() {
+        let ids = [3, 5, 8];
+        assert_eq!(step(&ids, None, false), Some(8));
+        assert_eq!(step(&ids, Some(5), false), Some(3));
+        assert_eq!(step(&ids, Some(5), true), Some(8));
+        assert_eq!(step(&ids, Some(3), false), Some(3));
+        assert_eq!(step(&ids, Some(8), true), Some(8));
+        assert_eq!(step(&ids, Some(4), true), Some(8));
+        assert_eq!(step(&[], None, true), None);
+    }
+
+    #[test]
+    fn keys_name_their_actions() {
+        assert_eq!(MessageKey::from_key("r"), Some(MessageKey::Reply));
+        assert_eq!(MessageKey::from_key("E"), Some(MessageKey::React));
+        assert_eq!(MessageKey::from_key("Delete"), Some(MessageKey::Delete));
+        assert_eq!(MessageKey::from_key("Backspace"), None);
+    }
+}
@@ -0,0 +1,173 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Element, IntersectionObserver, IntersectionObserverEntry, IntersectionObserverInit};
+use yew::prelude::*;
+use yew::virtual_dom::AttrValue;
+
+/// Placeholders carry their registration under this attribute, so the
+/// observer can tell whose turn it is.
+const ID_ATTRIBUTE: &str = "data-avatar-id";
+/// Start loading a little before the avatar scrolls into view.
+const ROOT_MARGIN: &str = "200px";
+
+type Waiting = Rc<RefCell<HashMap<u32, Callback<()>>>>;
+
+/// One `IntersectionObserver` shared through context by every
+/// [`LazyAvatar`], so a long member list or history doesn't set up one per
+/// image. Empty where the browser has none, in which case avatars load
+/// straight away.
+#[derive(Clone)]
+pub struct AvatarObserver(Option<Rc<Shared>>);
+
+struct Shared {
+    observer: IntersectionObserver,
+    waiting: Waiting,
+    next_id: Cell<u32>,
+    _on_intersect: Closure<dyn FnMut(js_sys::Array, IntersectionObserver)>,
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+impl PartialEq for AvatarObserver {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        }
+    }
+}
+
+impl Default for AvatarObserver {
+    fn default() -> Self {
+        Self(Self::observe().map(Rc::new))
+    }
+}
+
+impl AvatarObserver {
+    fn observe() -> Option<Shared> {
+        let waiting = Waiting::default();
+        let on_intersect = {
+            let waiting = waiting.clone();
+            Closure::wrap(Box::new(
+                move |entries: js_sys::Array, observer: IntersectionObserver| {
+                    for entry in entries.iter() {
+                        let entry: IntersectionObserverEntry = entry.unchecked_into();
+                        if !entry.is_intersecting() {
+                            continue;
+                        }
+                        let target = entry.target();
+                        observer.unobserve(&target);
+                        let id = target
+                            .get_attribute(ID_ATTRIBUTE)
+                            .and_then(|id| id.parse().ok());
+                        let shown = id.and_then(|id| waiting.borrow_mut().remove(&id));
+                        if let Some(shown) = shown {
+                            shown.emit(());
+                        }
+                    }
+                },
+            )
+                as Box<dyn FnMut(js_sys::Array, IntersectionObserver)>)
+        };
+        let mut options = IntersectionObserverInit::new();
+        options.root_margin(ROOT_MARGIN);
+        let observer =
+            IntersectionObserver::new_with_options(on_intersect.as_ref().unchecked_ref(), &options)
+                .ok()?;
+        Some(Shared {
+            observer,
+            waiting,
+            next_id: Cell::new(0),
+            _on_intersect: on_intersect,
+        })
+    }
+
+    fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Calls `on_visible` once `element` nears the viewport. Returns the
+    /// registration, to give to [`AvatarObserver::unwatch`].
+    fn watch(&self, element: &Element, on_visible: Callback<()>) -> Option<u32> {
+        let shared = self.0.as_ref()?;
+        let id = shared.next_id.get();
+        shared.next_id.set(id.wrapping_add(1));
+        let _ = element.set_attribute(ID_ATTRIBUTE, &id.to_string());
+        shared.waiting.borrow_mut().insert(id, on_visible);
+        shared.observer.observe(element);
+        Some(id)
+    }
+
+    fn unwatch(&self, element: &Element, id: u32) {
+        if let Some(shared) = &self.0 {
+            shared.observer.unobserve(element);
+            shared.waiting.borrow_mut().remove(&id);
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LazyAvatarProps {
+    pub src: AttrValue,
+    pub alt: AttrValue,
+    #[prop_or_default]
+    pub class: Classes,
+    #[prop_or_default]
+    pub style: Option<AttrValue>,
+    #[prop_or_default]
+    pub onclick: Option<Callback<MouseEvent>>,
+    #[prop_or_default]
+    pub onerror: Option<Callback<Event>>,
+}
+
+/// An avatar image that is only requested once it nears the viewport. Until
+/// then a grey placeholder of the same size holds its place.
+#[function_component(LazyAvatar)]
+pub fn lazy_avatar(props: &LazyAvatarProps) -> Html {
+    let observer = use_context::<AvatarObserver>();
+    let visible = use_state(|| !observer.as_ref().is_some_and(AvatarObserver::is_active));
+    let placeholder = use_node_ref();
+
+    {
+        let placeholder = placeholder.clone();
+        let visible = visible.clone();
+        use_effect_with_deps(
+            move |observer| {
+                let watched = match (observer, placeholder.cast::<Element>()) {
+                    (Some(observer), Some(element)) if !*visible => {
+                        let on_visible = Callback::from(move |_| visible.set(true));
+                        let id = observer.watch(&element, on_visible);
+                        id.map(|id| (observer.clone(), element, id))
+                    }
+                    _ => None,
+                };
+                move || {
+                    if let Some((observer, element, id)) = watched {
+                        observer.unwatch(&element, id);
+                    }
+                }
+            },
+            observer,
+        );
+    }
+
+    let class = props.class.clone();
+    let style = props.style.clone();
+    let onclick = props.onclick.clone();
+    if !*visible {
+        return html! {
+            <div ref={placeholder} class={classes!(class, "bg-gray-300")} {style} {onclick} role="img" aria-label={props.alt.clone()}></div>
+        };
+    }
+    let onerror = props.onerror.clone();
+    html! {
+        <img {class} {style} {onclick} {onerror} src={props.src.clone()} alt={props.alt.clone()}/>
+    }
+}
@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+
+/// An image from the message history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightboxImage {
+    pub url: String,
+    /// Who posted it.
+    pub from: String,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LightboxProps {
+    pub images: Rc<Vec<LightboxImage>>,
+    /// The image that was clicked.
+    pub start: usize,
+    pub on_close: Callback<()>,
+}
+
+/// An image shown large over a dimmed backdrop. The arrow keys or buttons
+/// step through the other images in the history.
+#[function_component(Lightbox)]
+pub fn lightbox(props: &LightboxProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let index = use_state(|| props.start);
+    let count = props.images.len();
+    let Some(image) = props.images.get(*index) else {
+        return html! {};
+    };
+
+    let step = |delta: isize| {
+        let index = index.clone();
+        let target = index.checked_add_signed(delta).filter(|i| *i < count);
+        Callback::from(move |_: ()| {
+            if let Some(target) = target {
+                index.set(target);
+            }
+        })
+    };
+    let (previous, next) = (step(-1), step(1));
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        let (previous, next) = (previous.clone(), next.clone());
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "Escape" => on_close.emit(()),
+            "ArrowLeft" => previous.emit(()),
+            "ArrowRight" => next.emit(()),
+            _ => {}
+        })
+    };
+    let nav_button = |label: &str, icon: &str, onclick: Callback<()>, enabled: bool| {
+        html! {
+            <button onclick={onclick.reform(|e: MouseEvent| e.stop_propagation())} disabled={!enabled}
+                aria-label={locale.t(label).to_string()} class="px-3 py-2 text-3xl text-white disabled:opacity-30">
+                <span aria-hidden="true">{icon}</span>
+            </button>
+        }
+    };
+
+    html! {
+        <div ref={dialog} tabindex="-1" role="dialog" aria-modal="true" aria-label={locale.t("lightbox.title").to_string()} {onkeydown}
+            onclick={props.on_close.reform(|_| ())}
+            class="fixed inset-0 z-50 flex flex-col items-center justify-center gap-2 bg-black bg-opacity-80">
+            <div class="flex items-center">
+                {nav_button("lightbox.previous", "‹", previous, *index > 0)}
+                <img src={image.url.clone()} alt={locale.format("lightbox.alt", &[("name", &image.from)])}
+                    onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                    class="max-w-[80vw] max-h-[80vh] object-contain shadow-lg"/>
+                {nav_button("lightbox.next", "›", next, *index + 1 < count)}
+            </div>
+            <div onclick={Callback::from(|e: MouseEvent| e.stop_propagation())} class="flex items-center gap-4 text-sm text-white">
+                <span>{locale.format("lightbox.position", &[("index", &(*index + 1).to_string()), ("count", &count.to_string())])}</span>
+                <a href={image.url.clone()} target="_blank" rel="noopener noreferrer" download="" class="underline">{locale.t("lightbox.original")}</a>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close").to_string()}>{"✕"}</button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,51 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::LinkPreview;
+use crate::text::truncate;
+
+/// How much of the description is shown, in characters.
+const DESCRIPTION_CHARS: usize = 160;
+
+#[derive(Properties, PartialEq)]
+pub struct LinkPreviewCardProps {
+    pub preview: Rc<LinkPreview>,
+    /// Hides the card, for us alone.
+    #[prop_or_default]
+    pub on_dismiss: Option<Callback<()>>,
+}
+
+/// What a link in a message leads to: thumbnail, site, title and the start
+/// of the description. The whole card opens the link in a new tab.
+#[function_component(LinkPreviewCard)]
+pub fn link_preview_card(props: &LinkPreviewCardProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let preview = &props.preview;
+
+    html! {
+        <div class="relative mt-2 max-w-sm rounded-lg border border-gray-300 bg-white text-black overflow-hidden">
+            <a href={preview.url.clone()} target="_blank" rel="noopener noreferrer" class="flex hover:bg-gray-50">
+                if let Some(image) = &preview.image_url {
+                    <img src={image.clone()} alt="" loading="lazy" class="w-20 h-20 shrink-0 object-cover"/>
+                }
+                <div class="min-w-0 p-2 pe-6 text-xs">
+                    if let Some(site) = &preview.site_name {
+                        <div class="text-gray-500 truncate">{site}</div>
+                    }
+                    if let Some(title) = &preview.title {
+                        <div class="font-semibold text-sm truncate">{title}</div>
+                    }
+                    if let Some(description) = &preview.description {
+                        <p class="text-gray-600 break-words">{truncate(description, DESCRIPTION_CHARS)}</p>
+                    }
+                </div>
+            </a>
+            if let Some(on_dismiss) = &props.on_dismiss {
+                <button onclick={on_dismiss.reform(|_| ())} aria-label={locale.t("preview.dismiss")} title={locale.t("preview.dismiss")}
+                    class="absolute top-1 end-1 px-1 text-gray-500 hover:text-black">{"✕"}</button>
+            }
+        </div>
+    }
+}
@@ -3,13 +3,36 @@ use yew::functional::*;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+use crate::i18n::Locale;
+use crate::services::avatars::{avatar_url, AvatarStyle};
+use crate::services::names::{generate_name, is_valid_username};
+use crate::services::session;
+use crate::services::settings::{use_theme, Theme};
 use crate::Route;
-use crate::User;
+use crate::{AuthToken, User, AVATAR_STYLE_KEY};
 
 #[function_component(Login)]
 pub fn login() -> Html {
-    let username = use_state(|| String::new());
+    let stored_username = session::stored_username();
+    let username = use_state(|| stored_username.clone().unwrap_or_default());
+    let remember_me = use_state(|| stored_username.is_some());
     let user = use_context::<User>().expect("No context found.");
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let avatar_style = use_state(|| *user.avatar_style.borrow());
+    let notice = use_state(|| user.notice.borrow_mut().take());
+    let background = match use_theme().theme() {
+        Theme::Light => "bg-gray-800",
+        Theme::Dark => "bg-gray-900",
+    };
+
+    // Only auto-rejoin on a fresh load; coming back here from the chat should
+    // show the pre-filled form instead of bouncing straight back.
+    // Nor after being sent away, so the reason can be read.
+    if let (Some(name), false, None) = (stored_username, user.is_authenticated(), &*notice) {
+        *user.token.borrow_mut() = Some(AuthToken(name.clone()));
+        *user.username.borrow_mut() = name;
+        return html! { <Redirect<Route> to={Route::Chat}/> };
+    }
 
     let oninput = {
         let current_username = username.clone();
@@ -20,19 +43,74 @@ pub fn login() -> Html {
         })
     };
 
+    let randomize = {
+        let username = username.clone();
+        Callback::from(move |_| {
+            let seed = (js_sys::Math::random() * u64::MAX as f64) as u64;
+            username.set(generate_name(seed));
+        })
+    };
+
+    let onchange = {
+        let remember_me = remember_me.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            remember_me.set(input.checked());
+        })
+    };
+
+    let style_option = |style: AvatarStyle| {
+        let selected = *avatar_style == style;
+        let onchange = {
+            let avatar_style = avatar_style.clone();
+            Callback::from(move |_| avatar_style.set(style))
+        };
+        // Preview with the name being typed, so the choice is what others see.
+        let seed = if username.is_empty() {
+            "yewchat"
+        } else {
+            username.as_str()
+        };
+        html! {
+            <label title={style.label()} class={classes!("m-1", "p-1", "rounded-full", "cursor-pointer", selected.then_some("bg-violet-600"))}>
+                <input type="radio" name="avatar-style" class="sr-only" checked={selected} {onchange}/>
+                <img class="w-10 h-10 rounded-full bg-white" src={avatar_url(seed, style)} alt={style.label()}/>
+            </label>
+        }
+    };
+
     let onclick = {
         let username = username.clone();
+        let remember_me = remember_me.clone();
+        let avatar_style = avatar_style.clone();
         let user = user.clone();
-        Callback::from(move |_| *user.username.borrow_mut() = (*username).clone())
+        Callback::from(move |_| {
+            session::log_in(&username, *remember_me);
+            session::save(AVATAR_STYLE_KEY, &*avatar_style);
+            *user.avatar_style.borrow_mut() = *avatar_style;
+            *user.token.borrow_mut() = Some(AuthToken((*username).clone()));
+            *user.username.borrow_mut() = (*username).clone();
+        })
     };
 
     html! {
-       <div class="bg-gray-800 flex w-screen">
+       <div class={classes!(background, "flex", "w-screen")}>
             <div class="container mx-auto flex flex-col justify-center items-center">
+                if let Some(notice) = &*notice {
+                    <div role="alert" class="m-4 px-4 py-2 rounded bg-red-100 text-red-900">{notice}</div>
+                }
                 <form class="m-4 flex">
-                    <input {oninput} class="rounded-l-lg p-4 border-t mr-0 border-b border-l text-gray-800 border-gray-200 bg-white" placeholder="Username" />
-                    <Link<Route> to={Route::Chat}> <button {onclick} disabled={username.len()<1} class="px-8 rounded-r-lg bg-violet-600	  text-white font-bold p-4 uppercase border-violet-600 border-t border-b border-r" >{"Go Chatting!"}</button></Link<Route>>
+                    <input {oninput} value={(*username).clone()} class="rounded-s-lg p-4 border-t me-0 border-b border-s text-gray-800 border-gray-200 bg-white" placeholder={locale.t("login.username")} />
+                    <button type="button" onclick={randomize} class="px-4 border-t border-b border-gray-200 bg-white" title={locale.t("login.random_name")}>{"🎲 "}{locale.t("login.random_name")}</button>
+                    <Link<Route> to={Route::Chat}> <button {onclick} disabled={!is_valid_username(&username)} class="px-8 rounded-e-lg bg-violet-600	  text-white font-bold p-4 uppercase border-violet-600 border-t border-b border-e" >{locale.t("login.submit")}</button></Link<Route>>
                 </form>
+                <div role="radiogroup" aria-label={locale.t("login.avatar_style")} class="flex flex-wrap justify-center mb-4">
+                    {for AvatarStyle::ALL.into_iter().map(style_option)}
+                </div>
+                <label class="text-white text-sm">
+                    <input type="checkbox" class="me-2" checked={*remember_me} {onchange}/>
+                    {locale.t("login.remember_me")}
+                </label>
             </div>
         </div>
     }
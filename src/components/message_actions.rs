@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::Answer;
+
+/// What can be done to a message from its action bar, each by message id.
+/// An action without a callback gets no button. Built once by the list that
+/// renders the messages, so items can compare it cheaply.
+#[derive(Clone, Default, PartialEq)]
+pub struct MessageActions {
+    /// Opens the emoji picker to react to the message.
+    pub on_react: Option<Callback<usize>>,
+    pub on_reply: Option<Callback<usize>>,
+    pub on_copy: Option<Callback<usize>>,
+    pub on_copy_link: Option<Callback<usize>>,
+    pub on_toggle_pin: Option<Callback<usize>>,
+    /// Stars or unstars the message, for us alone.
+    pub on_toggle_star: Option<Callback<usize>>,
+    /// Pins the message to the top of its room; moderators only.
+    pub on_pin_to_room: Option<Callback<usize>>,
+    pub on_edit: Option<Callback<usize>>,
+    pub on_delete: Option<Callback<usize>>,
+    /// Opens the message's image in the lightbox. Not a toolbar button: the
+    /// image itself is clicked.
+    pub on_open_image: Option<Callback<usize>>,
+    /// Translates the message into our language.
+    pub on_translate: Option<Callback<usize>>,
+    /// Hides a translation again. Not a toolbar button: it sits under the
+    /// translation.
+    pub on_show_original: Option<Callback<usize>>,
+    /// Expands or collapses a long message in place. Not a toolbar button:
+    /// the message has its own "Show more".
+    pub on_toggle_expand: Option<Callback<usize>>,
+    /// Opens the thread the message roots, to read or reply to it.
+    pub on_open_thread: Option<Callback<usize>>,
+    /// Comments on a line of the message's code review. Not a toolbar
+    /// button: each line has its own `+`.
+    pub on_review_comment: Option<Callback<(usize, u32, String)>>,
+    /// Answers the message's survey. Not a toolbar button: the survey is a
+    /// form.
+    pub on_answer_survey: Option<Callback<(usize, Vec<Answer>)>>,
+    /// Hides the message's link preview. Not a toolbar button: the card
+    /// has its own.
+    pub on_dismiss_preview: Option<Callback<usize>>,
+    /// Adds our reaction with an emoji already on the message, or takes it
+    /// back. Not a toolbar button: each reaction under the message is one.
+    pub on_toggle_reaction: Option<Callback<(usize, String)>>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MessageActionBarProps {
+    pub id: usize,
+    pub actions: Rc<MessageActions>,
+    /// Whether the message is ours, for edit and delete.
+    #[prop_or_default]
+    pub own: bool,
+    /// Whether the message is plain text, for translate.
+    #[prop_or_default]
+    pub text: bool,
+    #[prop_or_default]
+    pub pinned: bool,
+    #[prop_or_default]
+    pub starred: bool,
+    #[prop_or_default]
+    pub moderator: bool,
+}
+
+/// Who a button is offered to.
+#[derive(Clone, Copy, PartialEq)]
+enum Offer {
+    Anyone,
+    /// The sender of the message.
+    Own,
+    /// Plain text messages, not files, images or cards.
+    Text,
+    Moderator,
+}
+
+/// A row of buttons floating over the corner of a message bubble. It shows
+/// while the bubble is hovered or has focus inside it; see `styles.css`.
+#[function_component(MessageActionBar)]
+pub fn message_action_bar(props: &MessageActionBarProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let actions = &props.actions;
+    let pin_label = if props.pinned {
+        "actions.unpin"
+    } else {
+        "actions.pin"
+    };
+    let star_label = if props.starred {
+        "actions.unstar"
+    } else {
+        "actions.star"
+    };
+    use Offer::{Anyone, Moderator, Own, Text};
+    let buttons = [
+        (&actions.on_react, "actions.react", "😊", Anyone),
+        (&actions.on_reply, "actions.reply", "↩", Anyone),
+        (&actions.on_open_thread, "actions.thread", "🧵", Anyone),
+        (&actions.on_copy, "actions.copy", "📋", Anyone),
+        (&actions.on_translate, "actions.translate", "🌐", Text),
+        (&actions.on_copy_link, "actions.copy_link", "🔗", Anyone),
+        (&actions.on_toggle_pin, pin_label, "📌", Anyone),
+        (&actions.on_toggle_star, star_label, "⭐", Anyone),
+        (
+            &actions.on_pin_to_room,
+            "actions.pin_to_room",
+            "📢",
+            Moderator,
+        ),
+        (&actions.on_edit, "actions.edit", "✎", Own),
+        (&actions.on_delete, "actions.delete", "🗑", Own),
+    ];
+    let buttons = buttons
+        .into_iter()
+        .filter(|(.., offer)| match offer {
+            Anyone => true,
+            Own => props.own,
+            Text => props.text,
+            Moderator => props.moderator,
+        })
+        .filter_map(|(callback, label, icon, _)| {
+            let id = props.id;
+            let onclick = callback.as_ref()?.reform(move |_: MouseEvent| id);
+            let label = locale.t(label);
+            Some(html! {
+                <button {onclick} aria-label={label} title={label} class="px-1 rounded hover:bg-gray-200">
+                    <span aria-hidden="true">{icon}</span>
+                </button>
+            })
+        })
+        .collect::<Html>();
+
+    html! {
+        <div role="toolbar" aria-label={locale.t("actions.label")} class="message-actions absolute -top-3 end-2 flex px-1 rounded shadow bg-white text-sm text-black">
+            {buttons}
+        </div>
+    }
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::bidi;
+use crate::components::message_search;
+use crate::emoji;
+use crate::i18n::Locale;
+use crate::profanity::{self, WordList};
+use crate::services::settings::use_settings;
+use crate::syntax::{self, Segment};
+
+#[derive(Properties, PartialEq)]
+pub struct MessageContentProps {
+    pub text: String,
+    /// A search query to mark in the text.
+    #[prop_or_default]
+    pub highlight: Option<Rc<str>>,
+    /// Makes an image clickable, to open it in the lightbox.
+    #[prop_or_default]
+    pub on_image_click: Option<Callback<()>>,
+}
+
+const IMAGE_EXTENSIONS: &[&str] = &[".gif", ".png", ".jpg", ".jpeg", ".webp"];
+
+/// Whether a message is a link to an image, or an inline `data:` image,
+/// shown as the image itself.
+pub fn is_image(text: &str) -> bool {
+    if text.starts_with("data:image/") {
+        return true;
+    }
+    let lower = text.to_ascii_lowercase();
+    IMAGE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Renders a message body: an image link becomes an image, fenced code blocks
+/// are highlighted, everything else is shown as text, with swear words
+/// masked if the user asked for that.
+#[function_component(MessageContent)]
+pub fn message_content(props: &MessageContentProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let settings = use_settings();
+    if is_image(&props.text) {
+        let image = html! { <img class="mt-3 max-w-full h-auto" src={props.text.clone()}/> };
+        return match &props.on_image_click {
+            Some(onclick) => html! {
+                <button onclick={onclick.reform(|_| ())} aria-label={locale.t("lightbox.open")} class="block cursor-zoom-in">
+                    {image}
+                </button>
+            },
+            None => image,
+        };
+    }
+
+    let mark = |text: &str| match &props.highlight {
+        Some(query) => message_search::highlight(text, query),
+        None => html! { {text} },
+    };
+    let text = match settings.profanity_filter() {
+        true => profanity::censor(&props.text, WordList::bundled()),
+        false => Cow::Borrowed(props.text.as_str()),
+    };
+    syntax::segments(&text)
+        .map(|segment| match segment {
+            Segment::Text(text) => bidi::split_links(&emoji::expand(text))
+                .into_iter()
+                .map(|(run, link)| match link {
+                    true => html! { <bdi dir="ltr">{mark(run)}</bdi> },
+                    false => mark(run),
+                })
+                .collect::<Html>(),
+            Segment::Code { language, code } => html! {
+                <pre class="code-block" dir="ltr"><code>{
+                    match language {
+                        Some(language) => syntax::tokenize(language, code)
+                            .map(|token| match token.kind.class() {
+                                Some(class) => html! { <span {class}>{token.text}</span> },
+                                None => html! { {token.text} },
+                            })
+                            .collect::<Html>(),
+                        None => mark(code),
+                    }
+                }</code></pre>
+            },
+        })
+        .collect::<Html>()
+}
@@ -0,0 +1,267 @@
+use std::collections::BTreeSet;
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::chat::MessageData;
+use crate::i18n::Locale;
+
+/// Which messages to show. Every part that is set must match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    pub users: BTreeSet<String>,
+    /// Inclusive bounds as `YYYY-MM-DD`.
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub keyword: String,
+    /// Only messages we starred.
+    pub starred: bool,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        !self.users.is_empty()
+            || self.starred
+            || self.from_date.is_some()
+            || self.to_date.is_some()
+            || !self.keyword.trim().is_empty()
+    }
+
+    /// `date_of` turns a timestamp into the message's local `YYYY-MM-DD`
+    /// date; it is only called when a date bound is set.
+    pub fn matches(&self, message: &MessageData, date_of: impl Fn(f64) -> String) -> bool {
+        if self.starred && !message.starred {
+            return false;
+        }
+        if !self.users.is_empty() && !self.users.contains(&message.from) {
+            return false;
+        }
+        if self.from_date.is_some() || self.to_date.is_some() {
+            let date = date_of(message.timestamp);
+            if self.from_date.as_ref().is_some_and(|from| date < *from)
+                || self.to_date.as_ref().is_some_and(|to| date > *to)
+            {
+                return false;
+            }
+        }
+        let keyword = self.keyword.trim();
+        keyword.is_empty()
+            || message
+                .message
+                .to_lowercase()
+                .contains(&keyword.to_lowercase())
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MessageFilterProps {
+    /// Names offered in the user picker.
+    pub users: Vec<String>,
+    pub filter: Filter,
+    pub on_change: Callback<Filter>,
+}
+
+/// A toolbar above the message list that narrows what it shows. Collapsed
+/// until the filter button is pressed; active filters stay visible as chips.
+#[function_component(MessageFilter)]
+pub fn message_filter(props: &MessageFilterProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let expanded = use_state(|| false);
+    let filter = &props.filter;
+
+    // Builds a callback that applies `change` to a copy of the filter.
+    let update = |change: fn(&mut Filter, String)| {
+        let filter = filter.clone();
+        let on_change = props.on_change.clone();
+        move |value: String| {
+            let mut filter = filter.clone();
+            change(&mut filter, value);
+            on_change.emit(filter);
+        }
+    };
+    let input_value = |e: Event| e.target_unchecked_into::<HtmlInputElement>().value();
+
+    let toggle = {
+        let expanded = expanded.clone();
+        Callback::from(move |_| expanded.set(!*expanded))
+    };
+    let on_keyword = {
+        let update = update(|f, value| f.keyword = value);
+        Callback::from(move |e: InputEvent| {
+            update(e.target_unchecked_into::<HtmlInputElement>().value())
+        })
+    };
+    let on_from = {
+        let update = update(|f, value| f.from_date = non_empty(value));
+        Callback::from(move |e: Event| update(input_value(e)))
+    };
+    let on_to = {
+        let update = update(|f, value| f.to_date = non_empty(value));
+        Callback::from(move |e: Event| update(input_value(e)))
+    };
+    let toggle_user = |name: &str| {
+        let name = name.to_string();
+        let update = update(|f, name| {
+            if !f.users.remove(&name) {
+                f.users.insert(name);
+            }
+        });
+        Callback::from(move |_: Event| update(name.clone()))
+    };
+
+    let chip = |label: String, clear: Callback<MouseEvent>| {
+        html! {
+            <span class="inline-flex items-center me-2 mb-1 px-2 py-0.5 rounded-full bg-blue-100 text-blue-900 text-xs">
+                {label}
+                <button onclick={clear} aria-label={locale.t("filter.remove").to_string()} class="ms-1">{"✕"}</button>
+            </span>
+        }
+    };
+    let clear = |change: fn(&mut Filter)| {
+        let filter = filter.clone();
+        props.on_change.reform(move |_: MouseEvent| {
+            let mut filter = filter.clone();
+            change(&mut filter);
+            filter
+        })
+    };
+    let mut chips: Vec<Html> = filter
+        .users
+        .iter()
+        .map(|name| {
+            let filter = filter.clone();
+            let name = name.clone();
+            let label = locale.format("filter.from_user", &[("name", &name)]);
+            let clear = props.on_change.reform(move |_: MouseEvent| {
+                let mut filter = filter.clone();
+                filter.users.remove(&name);
+                filter
+            });
+            chip(label, clear)
+        })
+        .collect();
+    if filter.starred {
+        chips.push(chip(
+            locale.t("filter.starred").to_string(),
+            clear(|f| f.starred = false),
+        ));
+    }
+    if let Some(from) = &filter.from_date {
+        chips.push(chip(
+            locale.format("filter.after", &[("date", from)]),
+            clear(|f| f.from_date = None),
+        ));
+    }
+    if let Some(to) = &filter.to_date {
+        chips.push(chip(
+            locale.format("filter.before", &[("date", to)]),
+            clear(|f| f.to_date = None),
+        ));
+    }
+    if !filter.keyword.trim().is_empty() {
+        chips.push(chip(
+            format!("“{}”", filter.keyword.trim()),
+            clear(|f| f.keyword.clear()),
+        ));
+    }
+
+    html! {
+        <div class="w-full px-3 py-1 text-sm border-b border-gray-300">
+            <div class="flex items-center flex-wrap">
+                <button onclick={toggle} aria-expanded={expanded.to_string()} aria-controls="message-filter"
+                    aria-label={locale.t("filter.toggle").to_string()} class={classes!("me-2", "text-lg", filter.is_active().then_some("text-blue-600"))}>
+                    <span aria-hidden="true">{"⏷"}</span>
+                </button>
+                {for chips}
+            </div>
+            if *expanded {
+                <div id="message-filter" class="flex flex-wrap items-center gap-3 py-2">
+                    <details class="relative">
+                        <summary class="cursor-pointer">{locale.t("filter.users")}</summary>
+                        <div class="absolute z-30 mt-1 p-2 max-h-60 overflow-auto bg-white text-black rounded shadow">
+                            {for props.users.iter().map(|name| html! {
+                                <label class="block whitespace-nowrap">
+                                    <input type="checkbox" checked={filter.users.contains(name)} onchange={toggle_user(name)}/>
+                                    {" "}{name}
+                                </label>
+                            })}
+                        </div>
+                    </details>
+                    <label>
+                        {locale.t("filter.from_date")}{" "}
+                        <input type="date" value={filter.from_date.clone().unwrap_or_default()} onchange={on_from} class="px-1 rounded bg-gray-100 text-black"/>
+                    </label>
+                    <label>
+                        {locale.t("filter.to_date")}{" "}
+                        <input type="date" value={filter.to_date.clone().unwrap_or_default()} onchange={on_to} class="px-1 rounded bg-gray-100 text-black"/>
+                    </label>
+                    <input type="search" value={filter.keyword.clone()} oninput={on_keyword}
+                        placeholder={locale.t("filter.keyword").to_string()} aria-label={locale.t("filter.keyword").to_string()}
+                        class="px-2 py-0.5 rounded-full bg-gray-100 text-black outline-none"/>
+                </div>
+            }
+        </div>
+    }
+}
+
+/// An `<input type="date">` reports a cleared field as an empty string.
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(from: &str, text: &str, timestamp: f64) -> MessageData {
+        MessageData {
+            from: from.into(),
+            message: text.into(),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    // Day numbers stand in for timestamps.
+    fn date_of(timestamp: f64) -> String {
+        format!("2024-01-{:02}", timestamp as u32)
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = Filter::default();
+        assert!(!filter.is_active());
+        assert!(filter.matches(&message("a", "hi", 1.0), |_| unreachable!()));
+    }
+
+    #[test]
+    fn all_parts_must_match() {
+        let filter = Filter {
+            users: ["alice".to_string()].into_iter().collect(),
+            from_date: Some("2024-01-05".into()),
+            to_date: Some("2024-01-10".into()),
+            keyword: " Deploy ".into(),
+            starred: false,
+        };
+        assert!(filter.is_active());
+        assert!(filter.matches(&message("alice", "deploy done", 5.0), date_of));
+        assert!(filter.matches(&message("alice", "DEPLOYING", 10.0), date_of));
+        assert!(!filter.matches(&message("bob", "deploy done", 5.0), date_of));
+        assert!(!filter.matches(&message("alice", "deploy done", 4.0), date_of));
+        assert!(!filter.matches(&message("alice", "deploy done", 11.0), date_of));
+        assert!(!filter.matches(&message("alice", "lunch?", 6.0), date_of));
+    }
+
+    #[test]
+    fn starred_only() {
+        let filter = Filter {
+            starred: true,
+            ..Filter::default()
+        };
+        assert!(filter.is_active());
+        let mut starred = message("a", "hi", 1.0);
+        starred.starred = true;
+        assert!(filter.matches(&starred, |_| unreachable!()));
+        assert!(!filter.matches(&message("a", "hi", 1.0), |_| unreachable!()));
+    }
+}
@@ -0,0 +1,314 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::bidi;
+use crate::colors::name_color;
+use crate::components::avatar::Avatar;
+use crate::components::chat::{format_duration, MessageData, UserProfile};
+use crate::components::code_review::CodeReviewView;
+use crate::components::diff_view::DiffView;
+use crate::components::file_card::FileCard;
+use crate::components::keyboard_navigable_message_list::message_element_id;
+use crate::components::link_preview_card::LinkPreviewCard;
+use crate::components::message_actions::{MessageActionBar, MessageActions};
+use crate::components::message_content::{self, MessageContent};
+use crate::components::message_render::{RenderContext, Renderer};
+use crate::components::message_search::match_ranges;
+use crate::components::survey_card::SurveyCard;
+use crate::components::truncated_message::{self, TruncatedMessage};
+use crate::components::user_popover::Anchor;
+use crate::components::whiteboard::{Draw, Whiteboard};
+use crate::i18n::Locale;
+use crate::services::location::{self, PREVIEW_ZOOM, TILE_SIZE};
+use crate::services::message::{LinkPreview, Role};
+use crate::services::settings::{use_settings, use_theme};
+use crate::services::whiteboard::Board;
+
+/// Someone who has read the message.
+#[derive(Clone, PartialEq)]
+pub struct Reader {
+    pub name: String,
+    pub avatar: String,
+}
+
+/// An emoji the message was reacted to with, and who did.
+#[derive(Clone, PartialEq)]
+pub struct ReactionChip {
+    pub emoji: String,
+    pub names: Vec<String>,
+    /// We are among them.
+    pub mine: bool,
+}
+
+/// A translation shown under the message.
+#[derive(Clone, PartialEq)]
+pub enum Translation {
+    Loading,
+    Text(String),
+    Unavailable,
+}
+
+/// How many readers' avatars are stacked before only the count is shown.
+const READER_AVATARS: usize = 3;
+
+/// Every prop is cheap to compare, so a new message only renders itself and
+/// leaves the rest of the history alone.
+#[derive(Properties, PartialEq)]
+pub struct MessageItemProps {
+    pub message: Rc<MessageData>,
+    /// Who sent it.
+    pub author: Rc<UserProfile>,
+    /// Draws the text of the message.
+    #[prop_or_default]
+    pub renderer: Renderer,
+    /// The search query, to mark where the text matches it.
+    #[prop_or_default]
+    pub highlight: Option<Rc<str>>,
+    /// The search hit being looked at.
+    #[prop_or_default]
+    pub current: bool,
+    /// Where the list's keyboard focus is.
+    #[prop_or_default]
+    pub focused: bool,
+    #[prop_or_default]
+    pub pinned: bool,
+    /// Sent by us, so it can be edited and deleted.
+    #[prop_or_default]
+    pub own: bool,
+    /// We are a moderator, so it can be pinned to the room.
+    #[prop_or_default]
+    pub moderator: bool,
+    /// The strokes, if the message is a whiteboard.
+    #[prop_or_default]
+    pub board: Option<Rc<Board>>,
+    /// A long message shown in full rather than collapsed.
+    #[prop_or_default]
+    pub expanded: bool,
+    #[prop_or_default]
+    pub translation: Option<Translation>,
+    /// Who has read it, for our own latest message.
+    #[prop_or_default]
+    pub seen_by: Vec<Reader>,
+    #[prop_or_default]
+    pub reactions: Vec<ReactionChip>,
+    /// A survey we have already answered.
+    #[prop_or_default]
+    pub answered: bool,
+    /// What the message's link leads to.
+    #[prop_or_default]
+    pub preview: Option<Rc<LinkPreview>>,
+    /// How many replies the message's thread has.
+    #[prop_or_default]
+    pub replies: usize,
+    /// Of those, how many came in since we last had the thread open.
+    #[prop_or_default]
+    pub unread_replies: usize,
+    pub on_avatar_click: Callback<(String, Anchor)>,
+    pub actions: Rc<MessageActions>,
+    pub on_draw: Callback<Draw>,
+}
+
+#[function_component(MessageItem)]
+pub fn message_item(props: &MessageItemProps) -> Html {
+    let m = &props.message;
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let settings = use_settings();
+    let theme = use_theme().theme();
+    let palette = theme.palette();
+    let time = settings.timestamp_format().format(m.timestamp);
+    let onclick = {
+        let name = m.from.clone();
+        props
+            .on_avatar_click
+            .reform(move |e: MouseEvent| (name.clone(), Anchor::from_event(&e)))
+    };
+    let map_tile = m
+        .location
+        .as_ref()
+        .map(|spot| location::tile(spot, PREVIEW_ZOOM));
+    let on_image_click = props.actions.on_open_image.as_ref().map(|open| {
+        let id = m.id;
+        open.reform(move |_| id)
+    });
+    let on_review_comment = props.actions.on_review_comment.as_ref().map(|comment| {
+        let id = m.id;
+        comment.reform(move |(line, text)| (id, line, text))
+    });
+    let on_answer_survey = props.actions.on_answer_survey.as_ref().map(|answer| {
+        let id = m.id;
+        answer.reform(move |answers| (id, answers))
+    });
+    let on_dismiss_preview = props.actions.on_dismiss_preview.as_ref().map(|dismiss| {
+        let id = m.id;
+        dismiss.reform(move |_| id)
+    });
+    let open_thread = props.actions.on_open_thread.as_ref().map(|open| {
+        let id = m.id;
+        open.reform(move |_: MouseEvent| id)
+    });
+    let reactions = props.reactions.iter().map(|chip| {
+        let names = chip.names.join(", ");
+        let label = locale.format("reactions.chip", &[("emoji", &chip.emoji), ("names", &names)]);
+        let onclick = props.actions.on_toggle_reaction.as_ref().map(|toggle| {
+            let (id, emoji) = (m.id, chip.emoji.clone());
+            toggle.reform(move |_: MouseEvent| (id, emoji.clone()))
+        });
+        html! {
+            <button {onclick} aria-label={label} title={names} aria-pressed={chip.mine.to_string()}
+                class={classes!("px-2", "rounded-full", "border", if chip.mine { "border-blue-400 bg-blue-50" } else { "border-gray-300 bg-white" })}>
+                {&chip.emoji}{" "}{chip.names.len()}
+            </button>
+        }
+    });
+    let replies_key = if props.replies == 1 {
+        "thread.replies_one"
+    } else {
+        "thread.replies_many"
+    };
+    // The start of a long message, shown until it's expanded.
+    let collapsed =
+        truncated_message::collapse(&m.message, settings.long_message_chars()).map(|start| {
+            MessageData {
+                message: format!("{}…", start),
+                ..(**m).clone()
+            }
+        });
+    let render = |message: &MessageData| {
+        let highlights = props
+            .highlight
+            .as_deref()
+            .map(|query| match_ranges(&message.message, query))
+            .unwrap_or_default();
+        let ctx = RenderContext {
+            is_own: props.own,
+            theme: &theme,
+            highlights: &highlights,
+        };
+        props.renderer.0.render(message, &props.author, &ctx)
+    };
+    let toggle_expand = props.actions.on_toggle_expand.as_ref().map(|toggle| {
+        let id = m.id;
+        toggle.reform(move |_| id)
+    });
+    let translation = props.translation.as_ref().map(|translation| {
+        let show_original = props.actions.on_show_original.as_ref().map(|show| {
+            let id = m.id;
+            show.reform(move |_: MouseEvent| id)
+        });
+        let body = match translation {
+            Translation::Loading => html! {
+                <span role="status" class="text-gray-400">{locale.t("translate.loading")}</span>
+            },
+            Translation::Text(text) => html! { <p class="message-text italic" dir="auto">{text}</p> },
+            Translation::Unavailable => html! {
+                <span role="alert" class="text-red-600">{locale.t("translate.unavailable")}</span>
+            },
+        };
+        html! {
+            <div class="mt-1 pt-1 border-t border-gray-200 text-xs">
+                {body}
+                if let Some(onclick) = show_original {
+                    <button {onclick} class="block mt-1 font-semibold text-blue-600 hover:underline">{locale.t("translate.show_original")}</button>
+                }
+            </div>
+        }
+    });
+    let dir = bidi::first_strong(&m.message).map_or("auto", |dir| dir.attr());
+    html! {
+        <div id={message_element_id(m.id)} tabindex="0" class={classes!("message-bubble", "relative", "flex", "items-end", "w-3/6", "bg-gray-100", "m-8", "rounded-ss-lg", "rounded-se-lg", "rounded-ee-lg", props.current.then_some("search-current"), props.focused.then_some("message-focused"))}>
+            <Avatar name={m.from.clone()} url={props.author.avatar.clone()} size={32} class="m-3 cursor-pointer" {onclick}/>
+            <div class="p-3 min-w-0">
+                <div class="text-sm font-semibold break-words" style={format!("color: {};", name_color(&m.from, palette))} title={m.formerly.as_ref().map(|old| locale.format("chat.formerly", &[("name", old)]))}>
+                    {m.from.clone()}
+                    if props.author.bot {
+                        <span class="ms-1 px-1 rounded bg-gray-300 text-gray-700 text-xs font-normal">{"[BOT]"}</span>
+                    }
+                    if props.author.role == Role::Moderator {
+                        <span class="ms-1 px-1 rounded bg-violet-100 text-violet-700 text-xs font-normal" title={locale.t("moderation.badge")}>{locale.t("moderation.badge_short")}</span>
+                    }
+                    if let Some(time) = time {
+                        <span class="ms-2 text-xs font-normal text-gray-400">{time}</span>
+                    }
+                    if m.edited {
+                        <span class="ms-1 text-xs font-normal text-gray-400">{locale.t("edit.edited")}</span>
+                    }
+                    if props.pinned {
+                        <span class="ms-1 text-xs" title={locale.t("actions.pinned")}>{"📌"}</span>
+                    }
+                    if m.starred {
+                        <span class="ms-1 text-xs text-yellow-500" title={locale.t("actions.starred")}>{"★"}</span>
+                    }
+                </div>
+                <div class="text-xs text-gray-500">
+                    if let Some(file) = &m.file {
+                        <FileCard file={file.clone()}/>
+                    } else if let Some(diff) = &m.diff {
+                        <DiffView diff={diff.clone()}/>
+                    } else if let Some(review) = &m.code_review {
+                        <CodeReviewView review={review.clone()} on_comment={on_review_comment}/>
+                    } else if let Some(survey) = &m.survey {
+                        <SurveyCard survey={survey.clone()} answered={props.answered} on_submit={on_answer_survey}/>
+                    } else if let Some(audio) = &m.audio {
+                        <div class="flex items-center mt-2">
+                            <audio controls=true preload="metadata" src={audio.url.clone()}></audio>
+                            <span class="ms-2 text-xs text-gray-400">{format_duration(audio.duration_secs.round() as u64)}</span>
+                        </div>
+                    } else if let (Some(id), Some(board)) = (&m.whiteboard, &props.board) {
+                        <div class="text-xs text-gray-400">{locale.t("whiteboard.hint")}</div>
+                        <Whiteboard id={id.clone()} board={board.clone()} on_draw={props.on_draw.clone()}/>
+                    } else if let (Some(spot), Some(tile)) = (&m.location, &map_tile) {
+                        <a href={location::maps_url(spot)} target="_blank" rel="noopener noreferrer" title={locale.t("location.open")}
+                            class="relative block mt-2 overflow-hidden rounded" style={format!("width: {0}px; height: {0}px;", TILE_SIZE)}>
+                            <img src={tile.url.clone()} alt={locale.t("location.map")} width={TILE_SIZE.to_string()} height={TILE_SIZE.to_string()} loading="lazy"/>
+                            <span class="absolute text-2xl" aria-hidden="true"
+                                style={format!("left: {:.0}px; top: {:.0}px; transform: translate(-50%, -100%);", tile.x, tile.y)}>{"📍"}</span>
+                        </a>
+                        <div class="mt-1 text-xs text-gray-400">{"© OpenStreetMap contributors"}</div>
+                    } else if let Some(image) = &m.image {
+                        <MessageContent text={image.clone()} on_image_click={on_image_click.clone()}/>
+                    } else if m.action {
+                        <em {dir} class="message-text">{"* "}{m.from.clone()}{" "}{render(m)}</em>
+                    } else {
+                        <TruncatedMessage {dir} preview={collapsed.as_ref().map(render)} clamp={!truncated_message::has_code(&m.message)} expanded={props.expanded} on_toggle={toggle_expand}>
+                            if message_content::is_image(&m.message) {
+                                <MessageContent text={m.message.clone()} {on_image_click}/>
+                            } else {
+                                {render(m)}
+                            }
+                        </TruncatedMessage>
+                    }
+                </div>
+                if let Some(preview) = &props.preview {
+                    <LinkPreviewCard preview={preview.clone()} on_dismiss={on_dismiss_preview}/>
+                }
+                {translation.unwrap_or_default()}
+                if !props.reactions.is_empty() {
+                    <div class="flex flex-wrap gap-1 mt-1 text-xs text-black">{for reactions}</div>
+                }
+                if !props.seen_by.is_empty() {
+                    <div class="flex items-center justify-end gap-1 mt-1 text-xs text-gray-400"
+                        title={props.seen_by.iter().map(|r| r.name.as_str()).collect::<Vec<_>>().join(", ")}>
+                        <span class="flex -space-x-1" aria-hidden="true">
+                            {for props.seen_by.iter().take(READER_AVATARS).map(|reader| html! {
+                                <Avatar name={reader.name.clone()} url={reader.avatar.clone()} size={16} class="ring-1 ring-white"/>
+                            })}
+                        </span>
+                        <span>{locale.format("receipts.seen_by", &[("count", &props.seen_by.len().to_string())])}</span>
+                    </div>
+                }
+                if let (true, Some(onclick)) = (props.replies > 0, open_thread) {
+                    <button {onclick} class="mt-1 text-xs font-semibold text-blue-600 hover:underline">
+                        {locale.format(replies_key, &[("count", &props.replies.to_string())])}
+                        if props.unread_replies > 0 {
+                            <span class="ms-1 px-1 rounded-full bg-red-500 text-white font-normal">
+                                {locale.format("thread.new", &[("count", &props.unread_replies.to_string())])}
+                            </span>
+                        }
+                    </button>
+                }
+            </div>
+            <MessageActionBar id={m.id} actions={props.actions.clone()} own={props.own} text={m.is_text()} pinned={props.pinned} starred={m.starred} moderator={props.moderator}/>
+        </div>
+    }
+}
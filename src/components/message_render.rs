@@ -0,0 +1,93 @@
+use std::ops::Range;
+use std::rc::Rc;
+
+use yew::html::IntoPropValue;
+use yew::prelude::*;
+
+use crate::components::chat::{MessageData, UserProfile};
+use crate::components::message_content::MessageContent;
+use crate::services::settings::Theme;
+
+/// What a renderer knows about where the message is shown.
+pub struct RenderContext<'a> {
+    /// Sent by us.
+    pub is_own: bool,
+    pub theme: &'a Theme,
+    /// Byte ranges of the text matching the search, if one is open.
+    pub highlights: &'a [Range<usize>],
+}
+
+/// Draws the body of a text message; the bubble around it, with the avatar,
+/// name and actions, stays the same whatever the renderer. Pass one to
+/// `Chat` as `message_renderer` to change how messages look.
+pub trait MessageRender {
+    fn render(&self, msg: &MessageData, user: &UserProfile, ctx: &RenderContext) -> Html;
+}
+
+/// A shared renderer. Props compare it by identity, so swapping in a new
+/// one re-renders; any `Rc<dyn MessageRender>` can be passed as one.
+#[derive(Clone)]
+pub struct Renderer(pub Rc<dyn MessageRender>);
+
+impl PartialEq for Renderer {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(
+            Rc::as_ptr(&self.0) as *const (),
+            Rc::as_ptr(&other.0) as *const (),
+        )
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self(Rc::new(DefaultMessageRender))
+    }
+}
+
+impl IntoPropValue<Renderer> for Rc<dyn MessageRender> {
+    fn into_prop_value(self) -> Renderer {
+        Renderer(self)
+    }
+}
+
+/// Links, code blocks, emoji and search marks, as `MessageContent` shows
+/// them.
+pub struct DefaultMessageRender;
+
+impl MessageRender for DefaultMessageRender {
+    fn render(&self, msg: &MessageData, _user: &UserProfile, ctx: &RenderContext) -> Html {
+        let highlight = highlighted_query(&msg.message, ctx.highlights);
+        html! { <MessageContent text={msg.message.clone()} {highlight}/> }
+    }
+}
+
+/// The search query back from its matches: they all spell it, ignoring
+/// case, which is how `MessageContent` looks for it.
+fn highlighted_query(text: &str, highlights: &[Range<usize>]) -> Option<Rc<str>> {
+    highlights
+        .first()
+        .and_then(|range| text.get(range.clone()))
+        .map(Rc::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::message_search::match_ranges;
+
+    #[test]
+    fn the_query_comes_back_from_its_matches() {
+        let text = "Deploy, then deploy again";
+        let ranges = match_ranges(text, "DEPLOY");
+        let query = highlighted_query(text, &ranges).unwrap();
+        assert_eq!(match_ranges(text, &query), ranges);
+        assert_eq!(highlighted_query(text, &[]), None);
+    }
+
+    #[test]
+    fn renderers_are_equal_only_to_themselves() {
+        let renderer = Renderer::default();
+        assert!(renderer == renderer.clone());
+        assert!(renderer != Renderer::default());
+    }
+}
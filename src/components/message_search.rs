@@ -0,0 +1,152 @@
+use std::ops::Range;
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+
+#[derive(Properties, PartialEq)]
+pub struct MessageSearchProps {
+    pub query: String,
+    pub hits: usize,
+    /// Position of the selected hit, counting from zero.
+    pub current: Option<usize>,
+    pub on_query: Callback<String>,
+    /// `true` for the next hit, `false` for the previous one.
+    pub on_step: Callback<bool>,
+    pub on_close: Callback<()>,
+}
+
+/// The find bar: a query box, a "3/17" count and buttons to step through
+/// the hits. Enter and Shift+Enter step too, Escape closes it.
+#[function_component(MessageSearch)]
+pub fn message_search(props: &MessageSearchProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let input = use_node_ref();
+    {
+        let input = input.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(input) = input.cast::<HtmlInputElement>() {
+                    let _ = input.focus();
+                }
+                || ()
+            },
+            (),
+        );
+    }
+
+    let oninput = props
+        .on_query
+        .reform(|e: InputEvent| e.target_unchecked_into::<HtmlInputElement>().value());
+    let onkeydown = {
+        let on_step = props.on_step.clone();
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "Enter" => on_step.emit(!e.shift_key()),
+            "Escape" => on_close.emit(()),
+            _ => {}
+        })
+    };
+    let count = match props.current {
+        Some(current) => format!("{}/{}", current + 1, props.hits),
+        None if props.query.is_empty() => String::new(),
+        None => "0/0".to_string(),
+    };
+    let stepper = |forward: bool| props.on_step.reform(move |_: MouseEvent| forward);
+
+    html! {
+        <div role="search" class="flex items-center w-full px-3 py-1 text-sm border-b border-gray-300">
+            <input ref={input} type="search" value={props.query.clone()} {oninput} {onkeydown}
+                placeholder={locale.t("search.placeholder").to_string()} aria-label={locale.t("search.placeholder").to_string()}
+                class="grow px-3 py-0.5 rounded-full bg-gray-100 text-black outline-none"/>
+            <span role="status" class="w-14 mx-2 text-center text-xs text-gray-400">{count}</span>
+            <button onclick={stepper(false)} disabled={props.hits == 0} aria-label={locale.t("search.previous").to_string()} class="px-1">{"▲"}</button>
+            <button onclick={stepper(true)} disabled={props.hits == 0} aria-label={locale.t("search.next").to_string()} class="px-1">{"▼"}</button>
+            <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("search.close").to_string()} class="ms-2 px-1">{"✕"}</button>
+        </div>
+    }
+}
+
+/// Byte ranges of `text` matching `query`, ignoring case, without overlaps.
+pub fn match_ranges(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut from = 0;
+    while from < text.len() {
+        match match_at(&text[from..], query) {
+            Some(len) => {
+                ranges.push(from..from + len);
+                from += len;
+            }
+            None => from += text[from..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+    ranges
+}
+
+pub fn contains(text: &str, query: &str) -> bool {
+    !query.is_empty()
+        && text
+            .char_indices()
+            .any(|(i, _)| match_at(&text[i..], query).is_some())
+}
+
+/// Length in bytes of the match of `query` at the start of `text`, if any.
+fn match_at(text: &str, query: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for q in query.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(q.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+/// `text` with each match of `query` wrapped in a `<mark>`.
+pub fn highlight(text: &str, query: &str) -> Html {
+    let mut parts = Vec::new();
+    let mut last = 0;
+    for range in match_ranges(text, query) {
+        parts.push(html! { {&text[last..range.start]} });
+        parts.push(html! { <mark>{&text[range.clone()]}</mark> });
+        last = range.end;
+    }
+    parts.push(html! { {&text[last..]} });
+    parts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ignoring_case() {
+        assert_eq!(
+            match_ranges("Deploy, deploy, DEPLOY", "deploy"),
+            [0..6, 8..14, 16..22]
+        );
+        assert_eq!(match_ranges("aaaa", "aa"), [0..2, 2..4]);
+        assert!(match_ranges("anything", "").is_empty());
+    }
+
+    #[test]
+    fn ranges_fall_on_char_boundaries() {
+        let text = "Ünïcödé ünï";
+        let ranges = match_ranges(text, "ÜNÏ");
+        assert_eq!(ranges.len(), 2);
+        for range in ranges {
+            assert_eq!(text[range].to_lowercase(), "ünï");
+        }
+    }
+
+    #[test]
+    fn contains_agrees_with_ranges() {
+        assert!(contains("Hello World", "o w"));
+        assert!(!contains("Hello", "hello!"));
+        assert!(!contains("Hello", ""));
+    }
+}
@@ -1,2 +1,48 @@
+pub mod announcement_card;
+pub mod avatar;
+pub mod broadcast_modal;
 pub mod chat;
+pub mod chat_statistics;
+pub mod code_review;
+pub mod code_review_dialog;
+pub mod confetti;
+pub mod confirm_dialog;
+pub mod connection_status_banner;
+pub mod diff_view;
+pub mod error_toasts;
+pub mod export_dialog;
+pub mod file_card;
+pub mod fixtures;
+pub mod gif_picker;
+pub mod keyboard_navigable_message_list;
+pub mod lazy_avatar;
+pub mod lightbox;
+pub mod link_preview_card;
 pub mod login;
+pub mod message_actions;
+pub mod message_content;
+pub mod message_filter;
+pub mod message_item;
+pub mod message_render;
+pub mod message_search;
+pub mod pinned_message_bar;
+pub mod pinned_messages;
+pub mod presence_list;
+pub mod profile;
+pub mod quoted_reply;
+pub mod reaction_picker;
+pub mod room_invite;
+pub mod settings_panel;
+pub mod shortcuts_help;
+pub mod sidebar;
+pub mod sticker_picker;
+pub mod survey_card;
+pub mod thread_panel;
+pub mod toast;
+pub mod truncated_message;
+pub mod user_popover;
+pub mod user_sidebar;
+pub mod video_call;
+pub mod virtual_list;
+pub mod welcome;
+pub mod whiteboard;
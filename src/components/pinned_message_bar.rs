@@ -0,0 +1,60 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::components::avatar::Avatar;
+use crate::components::chat::MessageData;
+use crate::components::pinned_messages::preview;
+use crate::i18n::Locale;
+
+/// How much of the pinned message the bar shows.
+const PREVIEW_CHARS: usize = 100;
+
+#[derive(Properties, PartialEq)]
+pub struct PinnedMessageBarProps {
+    pub message: Rc<MessageData>,
+    pub avatar: String,
+    /// Jumps to the message in the list.
+    pub on_select: Callback<()>,
+    /// Unpins it for everyone; moderators only.
+    #[prop_or_default]
+    pub on_close: Option<Callback<()>>,
+}
+
+/// The message a moderator pinned to the open room, in a bar under the
+/// header. It can be folded away to a single button.
+#[function_component(PinnedMessageBar)]
+pub fn pinned_message_bar(props: &PinnedMessageBarProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let expanded = use_state(|| true);
+    let toggle = {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| expanded.set(!*expanded))
+    };
+    let m = &props.message;
+    let toggle_label = if *expanded {
+        "pinned_message.hide"
+    } else {
+        "pinned_message.show"
+    };
+
+    html! {
+        <div role="region" aria-label={locale.t("pinned_message.title")} class="flex items-center w-full px-3 py-1 text-sm border-b border-gray-300 bg-blue-50 text-blue-900">
+            <button onclick={toggle} aria-expanded={expanded.to_string()} aria-label={locale.t(toggle_label)} title={locale.t(toggle_label)} class="me-2 shrink-0">
+                <span aria-hidden="true">{"📢"}</span>
+            </button>
+            if *expanded {
+                <button onclick={props.on_select.reform(|_| ())} class="flex items-center grow min-w-0 text-start">
+                    <Avatar name={m.from.clone()} url={props.avatar.clone()} size={20} class="me-2"/>
+                    <span class="font-semibold shrink-0">{&m.from}</span>
+                    <span class="ms-2 truncate">{preview(&m.message, PREVIEW_CHARS)}</span>
+                </button>
+                if let Some(on_close) = &props.on_close {
+                    <button onclick={on_close.reform(|_| ())} aria-label={locale.t("pinned_message.unpin")} title={locale.t("pinned_message.unpin")} class="ms-2 px-1 shrink-0">{"✕"}</button>
+                }
+            } else {
+                <span class="grow text-xs">{locale.t("pinned_message.title")}</span>
+            }
+        </div>
+    }
+}
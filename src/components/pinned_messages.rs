@@ -0,0 +1,95 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::{Pin, PinKey};
+
+/// How much of a pinned message the strip shows.
+const PREVIEW_CHARS: usize = 80;
+
+#[derive(Properties, PartialEq)]
+pub struct PinnedMessagesProps {
+    /// Oldest pin first.
+    pub pins: Vec<Pin>,
+    pub on_select: Callback<PinKey>,
+    pub on_unpin: Callback<Pin>,
+}
+
+/// The pinned messages of the open room, in a strip under the header.
+/// Collapsed it shows only the newest pin.
+#[function_component(PinnedMessages)]
+pub fn pinned_messages(props: &PinnedMessagesProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let expanded = use_state(|| false);
+    if props.pins.is_empty() {
+        return html! {};
+    }
+
+    let toggle = {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| expanded.set(!*expanded))
+    };
+    let shown = if *expanded {
+        &props.pins[..]
+    } else {
+        &props.pins[props.pins.len() - 1..]
+    };
+    let rows = shown.iter().rev().map(|pin| {
+        let onclick = {
+            let key = pin.key();
+            props.on_select.reform(move |_: MouseEvent| key.clone())
+        };
+        let unpin = {
+            let pin = pin.clone();
+            props.on_unpin.reform(move |_: MouseEvent| pin.clone())
+        };
+        html! {
+            <li class="flex items-center">
+                <button {onclick} class="grow min-w-0 text-start truncate">
+                    <span class="font-semibold">{&pin.from}</span>{": "}{preview(&pin.message, PREVIEW_CHARS)}
+                </button>
+                <button onclick={unpin} aria-label={locale.t("pins.unpin")} class="ms-2 px-1">{"✕"}</button>
+            </li>
+        }
+    });
+
+    html! {
+        <div role="region" aria-label={locale.t("pins.title")} class="flex items-start w-full px-3 py-1 text-xs border-b border-gray-300 bg-amber-50 text-amber-900">
+            <span class="me-2" aria-hidden="true">{"📌"}</span>
+            <ul class="grow min-w-0">{for rows}</ul>
+            if props.pins.len() > 1 {
+                <button onclick={toggle} aria-expanded={expanded.to_string()} class="ms-2 underline shrink-0">
+                    if *expanded {
+                        {locale.t("pins.collapse")}
+                    } else {
+                        {locale.format("pins.show_all", &[("count", &props.pins.len().to_string())])}
+                    }
+                </button>
+            }
+        </div>
+    }
+}
+
+/// The first line of `text`, cut to `max` characters with an ellipsis.
+pub fn preview(text: &str, max: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    let cut = text.len() > line.len();
+    match line.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None if cut => format!("{}…", line),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preview;
+
+    #[test]
+    fn previews_are_cut_short() {
+        assert_eq!(preview("short", 10), "short");
+        assert_eq!(preview("exactly10!", 10), "exactly10!");
+        assert_eq!(preview("a little too long", 10), "a little t…");
+        assert_eq!(preview("first line\nsecond", 80), "first line…");
+        assert_eq!(preview("ünïcödé", 3), "ünï…");
+    }
+}
@@ -0,0 +1,198 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::components::avatar::Avatar;
+use crate::components::chat::{UserProfile, Users};
+use crate::components::user_popover::Anchor;
+use crate::components::user_sidebar::compare_usernames;
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+use crate::services::message::Presence;
+
+/// Offline users last seen longer ago than this are folded away.
+const LONG_OFFLINE_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+
+#[derive(Properties, PartialEq)]
+pub struct PresenceListProps {
+    pub users: Rc<Users>,
+    /// People seen before who aren't online now.
+    pub departed: Rc<Vec<UserProfile>>,
+    /// Opens a user's profile card.
+    pub on_select: Callback<(String, Anchor)>,
+    pub on_close: Callback<()>,
+}
+
+/// Everyone sorted into the groups the panel shows, each by name.
+#[derive(Default)]
+struct Groups<'a> {
+    online: Vec<&'a UserProfile>,
+    away: Vec<&'a UserProfile>,
+    do_not_disturb: Vec<&'a UserProfile>,
+    offline: Vec<&'a UserProfile>,
+    /// Offline for over a day, or since before we kept track.
+    long_offline: Vec<&'a UserProfile>,
+}
+
+fn group<'a>(
+    users: impl IntoIterator<Item = &'a UserProfile>,
+    departed: impl IntoIterator<Item = &'a UserProfile>,
+    now: f64,
+) -> Groups<'a> {
+    let mut groups = Groups::default();
+    for user in users.into_iter().filter(|u| !u.bot) {
+        match user.presence {
+            Presence::Online => groups.online.push(user),
+            Presence::Away => groups.away.push(user),
+            Presence::DoNotDisturb => groups.do_not_disturb.push(user),
+        }
+    }
+    for user in departed.into_iter().filter(|u| !u.bot) {
+        match user.last_seen {
+            Some(seen) if now - seen <= LONG_OFFLINE_MS => groups.offline.push(user),
+            _ => groups.long_offline.push(user),
+        }
+    }
+    for list in [
+        &mut groups.online,
+        &mut groups.away,
+        &mut groups.do_not_disturb,
+        &mut groups.offline,
+        &mut groups.long_offline,
+    ] {
+        list.sort_by(|a, b| compare_usernames(&a.name, &b.name));
+    }
+    groups
+}
+
+/// The "People" view: everyone we know of, grouped by whether they're
+/// around. It follows presence changes as they come in.
+#[function_component(PresenceList)]
+pub fn presence_list(props: &PresenceListProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+    let show_long_offline = use_state(|| false);
+    let groups = group(
+        props.users.values(),
+        props.departed.iter(),
+        js_sys::Date::now(),
+    );
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_close.emit(());
+            }
+        })
+    };
+    let entry = |user: &UserProfile, presence: &'static str| {
+        let name = user.name.clone();
+        let onclick = props
+            .on_select
+            .reform(move |e: MouseEvent| (name.clone(), Anchor::from_event(&e)));
+        html! {
+            <li key={user.name.clone()}>
+                <button {onclick} class="flex items-center gap-3 w-full p-2 rounded-lg text-start hover:bg-gray-100">
+                    <Avatar name={user.name.clone()} url={user.avatar.clone()} size={40}/>
+                    <span class="min-w-0">
+                        <span class="block font-semibold truncate">{&user.name}</span>
+                        <span class="block text-xs text-gray-500">{locale.t(presence)}</span>
+                        if !user.status.is_empty() {
+                            <span class="block text-xs text-gray-400 truncate" title={user.status.clone()}>{&user.status}</span>
+                        }
+                    </span>
+                </button>
+            </li>
+        }
+    };
+    let section = |title: &'static str, users: &[&UserProfile], presence: &'static str| {
+        if users.is_empty() {
+            return Html::default();
+        }
+        html! {
+            <section class="mb-4" aria-label={locale.t(title)}>
+                <h3 class="px-2 mb-1 text-xs font-semibold uppercase tracking-wide text-gray-500">
+                    {locale.t(title)}{format!(" · {}", users.len())}
+                </h3>
+                <ul>{for users.iter().map(|user| entry(user, presence))}</ul>
+            </section>
+        }
+    };
+    let toggle_long_offline = {
+        let show_long_offline = show_long_offline.clone();
+        Callback::from(move |_| show_long_offline.set(!*show_long_offline))
+    };
+
+    html! {
+        <aside ref={dialog} tabindex="-1" role="dialog" aria-label={locale.t("people.title")} {onkeydown}
+            class="fixed inset-0 z-40 flex flex-col bg-white text-black">
+            <div class="flex items-center justify-between h-14 px-4 border-b border-gray-300">
+                <span class="text-lg font-semibold">{locale.t("people.title")}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")}>{"✕"}</button>
+            </div>
+            <div class="grow overflow-y-auto">
+                <div class="max-w-2xl mx-auto p-4">
+                    {section("presence.online", &groups.online, "presence.online")}
+                    {section("presence.away", &groups.away, "presence.away")}
+                    {section("presence.do_not_disturb", &groups.do_not_disturb, "presence.do_not_disturb")}
+                    {section("presence.offline", &groups.offline, "presence.offline")}
+                    if !groups.long_offline.is_empty() {
+                        <section aria-label={locale.t("people.long_offline")}>
+                            <button onclick={toggle_long_offline} aria-expanded={show_long_offline.to_string()} aria-controls="people-long-offline"
+                                class="flex items-center px-2 mb-1 text-xs font-semibold uppercase tracking-wide text-gray-500">
+                                <span aria-hidden="true" class="me-1">{if *show_long_offline { "▾" } else { "▸" }}</span>
+                                {locale.t("people.long_offline")}{format!(" · {}", groups.long_offline.len())}
+                            </button>
+                            if *show_long_offline {
+                                <ul id="people-long-offline">
+                                    {for groups.long_offline.iter().map(|user| entry(user, "presence.offline"))}
+                                </ul>
+                            }
+                        </section>
+                    }
+                </div>
+            </div>
+        </aside>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str, presence: Presence, last_seen: Option<f64>) -> UserProfile {
+        UserProfile {
+            presence,
+            last_seen,
+            ..UserProfile::new(name, Default::default(), 0.0)
+        }
+    }
+
+    fn names(users: &[&UserProfile]) -> Vec<String> {
+        users.iter().map(|u| u.name.clone()).collect()
+    }
+
+    #[test]
+    fn groups_by_presence_and_name() {
+        let now = 2.0 * LONG_OFFLINE_MS;
+        let online = [
+            user("zoe", Presence::Online, None),
+            user("Alice", Presence::Online, None),
+            user("bob", Presence::Away, None),
+            user("carol", Presence::DoNotDisturb, None),
+        ];
+        let departed = [
+            user("dave", Presence::Online, Some(now - 1_000.0)),
+            user("erin", Presence::Online, Some(now - LONG_OFFLINE_MS - 1.0)),
+            user("frank", Presence::Online, None),
+        ];
+        let groups = group(&online, &departed, now);
+        assert_eq!(names(&groups.online), ["Alice", "zoe"]);
+        assert_eq!(names(&groups.away), ["bob"]);
+        assert_eq!(names(&groups.do_not_disturb), ["carol"]);
+        assert_eq!(names(&groups.offline), ["dave"]);
+        assert_eq!(names(&groups.long_offline), ["erin", "frank"]);
+    }
+}
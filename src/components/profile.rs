@@ -0,0 +1,36 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::components::avatar::Avatar;
+use crate::i18n::Locale;
+use crate::services::avatars::avatar_url;
+use crate::{Route, User};
+
+#[derive(Properties, PartialEq)]
+pub struct ProfileProps {
+    pub username: String,
+}
+
+#[function_component(Profile)]
+pub fn profile(props: &ProfileProps) -> Html {
+    let user = use_context::<User>().expect("No context found.");
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    // Other users' styles are only known inside the chat.
+    let style = if *user.username.borrow() == props.username {
+        *user.avatar_style.borrow()
+    } else {
+        Default::default()
+    };
+    html! {
+        <div class="bg-gray-800 flex w-screen">
+            <div class="container mx-auto flex flex-col justify-center items-center">
+                <div class="flex flex-col items-center bg-white rounded-lg p-8">
+                    <Avatar name={props.username.clone()} url={avatar_url(&props.username, style)} size={128}/>
+                    <div class="text-2xl mt-4">{props.username.clone()}</div>
+                    <div class="text-sm text-gray-400">{locale.t("profile.greeting")}</div>
+                    <Link<Route> to={Route::Chat} classes="mt-6 text-violet-600 underline">{locale.t("profile.back")}</Link<Route>>
+                </div>
+            </div>
+        </div>
+    }
+}
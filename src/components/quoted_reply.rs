@@ -0,0 +1,85 @@
+use yew::prelude::*;
+
+use crate::colors::name_color;
+use crate::components::chat::MessageData;
+use crate::i18n::Locale;
+use crate::services::settings::use_theme;
+use crate::text::truncate;
+
+/// How much of the replied-to message is quoted, in characters.
+const EXCERPT_CHARS: usize = 80;
+
+/// The message being replied to, as the strip above the input shows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyPreview {
+    /// Its id in the message list.
+    pub id: usize,
+    pub from: String,
+    /// The start of its text, on one line.
+    pub excerpt: String,
+}
+
+impl ReplyPreview {
+    pub fn of(message: &MessageData) -> Self {
+        let text = message
+            .message
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            id: message.id,
+            from: message.from.clone(),
+            excerpt: truncate(&text, EXCERPT_CHARS),
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct QuotedReplyProps {
+    pub reply_to: ReplyPreview,
+    /// Drops the reply, keeping whatever has been typed.
+    pub on_cancel: Callback<()>,
+}
+
+/// A strip above the message box quoting the message being replied to,
+/// with its sender's color down the side.
+#[function_component(QuotedReply)]
+pub fn quoted_reply(props: &QuotedReplyProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let palette = use_theme().theme().palette();
+    let reply = &props.reply_to;
+    let color = name_color(&reply.from, palette);
+
+    html! {
+        <div role="group" aria-label={locale.format("reply.to", &[("name", &reply.from)])}
+            class="flex items-center gap-2 mx-6 mt-2 ps-2 py-1 text-sm border-s-4 rounded bg-gray-100 text-black"
+            style={format!("border-color: {};", color)}>
+            <div class="grow min-w-0">
+                <div class="font-semibold" style={format!("color: {};", color)}>{&reply.from}</div>
+                <div class="truncate text-gray-600">{&reply.excerpt}</div>
+            </div>
+            <button onclick={props.on_cancel.reform(|_| ())} aria-label={locale.t("reply.cancel")} title={locale.t("reply.cancel")}
+                class="shrink-0 px-2 text-gray-500 hover:text-black">{"×"}</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_the_start_on_one_line() {
+        let message: MessageData = serde_json::from_value(serde_json::json!({
+            "from": "alice",
+            "message": format!("first line\nsecond {}", "word ".repeat(30)),
+            "timestamp": 0.0,
+        }))
+        .unwrap();
+        let preview = ReplyPreview::of(&message);
+        assert_eq!(preview.from, "alice");
+        assert!(preview.excerpt.starts_with("first line second word"));
+        assert!(preview.excerpt.ends_with('…'));
+        assert!(preview.excerpt.chars().count() <= EXCERPT_CHARS + 1);
+    }
+}
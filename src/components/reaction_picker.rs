@@ -0,0 +1,137 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::emoji::{self, SkinTone};
+use crate::i18n::Locale;
+use crate::services::session;
+
+const SKIN_TONE_KEY: &str = "skin_tone";
+
+#[derive(Properties, PartialEq)]
+pub struct ReactionPickerProps {
+    /// The heading; "Emoji" if not set.
+    #[prop_or_default]
+    pub title: Option<String>,
+    /// Called with the chosen emoji, in the chosen skin tone.
+    pub on_select: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+/// A searchable grid of the emoji we know by name, for typing one or
+/// reacting to a message with it. Pointing at a person or hand shows its
+/// skin tones; the one picked becomes the default for next time.
+#[function_component(ReactionPicker)]
+pub fn reaction_picker(props: &ReactionPickerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let query = use_state(String::new);
+    let tone = use_state(|| session::load::<SkinTone>(SKIN_TONE_KEY).unwrap_or_default());
+    // The emoji whose skin tones are showing.
+    let toning = use_state(|| None::<&'static str>);
+    let search = use_node_ref();
+
+    {
+        let search = search.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(input) = search.cast::<HtmlInputElement>() {
+                    let _ = input.focus();
+                }
+                || ()
+            },
+            (),
+        );
+    }
+
+    let oninput = {
+        let query = query.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            query.set(input.value());
+        })
+    };
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_close.emit(());
+            }
+        })
+    };
+    let pick_tone = |base: &'static str, choice: SkinTone| {
+        let tone = tone.clone();
+        let on_select = props.on_select.clone();
+        Callback::from(move |_: MouseEvent| {
+            session::save(SKIN_TONE_KEY, &choice);
+            tone.set(choice);
+            on_select.emit(emoji::with_skin_tone(base, choice).into_owned());
+        })
+    };
+
+    let title = props
+        .title
+        .clone()
+        .unwrap_or_else(|| locale.t("reactions.title").to_string());
+    let found = emoji::search(&query);
+    let grid = found.iter().map(|&(name, base)| {
+        let shown = emoji::with_skin_tone(base, *tone).into_owned();
+        let onclick = props.on_select.reform({
+            let shown = shown.clone();
+            move |_| shown.clone()
+        });
+        let label = name.replace('_', " ");
+        if !emoji::takes_skin_tone(base) {
+            return html! {
+                <button key={name} {onclick} title={label.clone()} aria-label={label.clone()} class="p-1 rounded text-2xl hover:bg-gray-100">{shown}</button>
+            };
+        }
+        let show_tones = {
+            let toning = toning.clone();
+            Callback::from(move |_: MouseEvent| toning.set(Some(base)))
+        };
+        let focus_tones = {
+            let toning = toning.clone();
+            Callback::from(move |_: FocusEvent| toning.set(Some(base)))
+        };
+        let hide_tones = {
+            let toning = toning.clone();
+            Callback::from(move |_| toning.set(None))
+        };
+        html! {
+            <div key={name} class="relative" onmouseenter={show_tones} onmouseleave={hide_tones} onfocusin={focus_tones}>
+                <button {onclick} title={label.clone()} aria-label={label.clone()} class="w-full p-1 rounded text-2xl hover:bg-gray-100">{shown}</button>
+                if *toning == Some(base) {
+                    <div role="group" aria-label={locale.t("reactions.skin_tone")}
+                        class="absolute bottom-full start-1/2 -translate-x-1/2 z-10 flex p-1 rounded-full shadow bg-white">
+                        {for SkinTone::ALL.iter().map(|&choice| html! {
+                            <button onclick={pick_tone(base, choice)} aria-label={locale.t(choice.key())} title={locale.t(choice.key())}
+                                aria-pressed={(choice == *tone).to_string()}
+                                class={classes!("px-1", "rounded-full", "text-xl", (choice == *tone).then_some("bg-gray-200"))}>
+                                {emoji::with_skin_tone(base, choice).into_owned()}
+                            </button>
+                        })}
+                    </div>
+                }
+            </div>
+        }
+    });
+
+    html! {
+        <div role="dialog" aria-label={title.clone()} {onkeydown} tabindex="-1"
+            class="absolute bottom-full end-0 mb-2 w-80 h-96 flex flex-col p-2 gap-2 rounded-lg shadow-lg bg-white text-sm text-black">
+            <div class="flex items-center justify-between">
+                <span class="font-semibold">{title}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")} class="px-1">{"✕"}</button>
+            </div>
+            <input ref={search} {oninput} value={(*query).clone()} type="search" placeholder={locale.t("reactions.search")} aria-label={locale.t("reactions.search")}
+                class="px-3 py-1 rounded-full bg-gray-100 outline-none"/>
+            if found.is_empty() {
+                <p class="p-2 text-gray-500">{locale.t("reactions.no_match")}</p>
+            }
+            // Room at the top so the first row's skin tones aren't cut off.
+            <div class="grow overflow-y-auto grid grid-cols-7 gap-1 content-start pt-10">
+                {for grid}
+            </div>
+        </div>
+    }
+}
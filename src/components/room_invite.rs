@@ -0,0 +1,34 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::RoomInvite;
+
+#[derive(Properties, PartialEq)]
+pub struct RoomInviteBannerProps {
+    pub invite: RoomInvite,
+    pub on_accept: Callback<()>,
+    pub on_decline: Callback<()>,
+}
+
+#[function_component(RoomInviteBanner)]
+pub fn room_invite_banner(props: &RoomInviteBannerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let text = locale.format(
+        "rooms.invited",
+        &[
+            ("name", &props.invite.inviter),
+            ("room", &props.invite.room_name),
+        ],
+    );
+    html! {
+        <div role="status" class="w-full px-3 py-2 flex items-center text-sm bg-violet-100 text-violet-900">
+            <span class="grow">{text}</span>
+            <button onclick={props.on_accept.reform(|_| ())} class="ms-2 px-3 py-1 rounded-full bg-violet-600 text-white">
+                {locale.t("rooms.accept")}
+            </button>
+            <button onclick={props.on_decline.reform(|_| ())} class="ms-2 px-3 py-1 rounded-full bg-gray-200">
+                {locale.t("rooms.decline")}
+            </button>
+        </div>
+    }
+}
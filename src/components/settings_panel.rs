@@ -0,0 +1,274 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::i18n::{Language, Locale};
+use crate::services::alerts;
+use crate::services::focus::use_modal_focus;
+use crate::services::history::MIN_HISTORY_LIMIT;
+use crate::services::local_history;
+use crate::services::settings::{
+    use_settings, use_theme, Theme, TimestampFormat, ANNOUNCEMENT_PIN_CHOICES, AWAY_AFTER_CHOICES,
+    LONG_MESSAGE_CHOICES,
+};
+use crate::User;
+
+const MAX_HISTORY_LIMIT: usize = 5000;
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsPanelProps {
+    pub on_close: Callback<()>,
+    /// How many messages we starred.
+    #[prop_or_default]
+    pub stars: usize,
+    pub on_clear_stars: Callback<()>,
+}
+
+/// Client-side preferences, opened from the gear in the chat header. Changes
+/// apply and persist as soon as they are made.
+#[function_component(SettingsPanel)]
+pub fn settings_panel(props: &SettingsPanelProps) -> Html {
+    let settings = use_settings();
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+
+    let current_theme = use_theme();
+    let on_theme = |theme: Theme| {
+        let current_theme = current_theme.clone();
+        Callback::from(move |_: Event| current_theme.set(theme))
+    };
+    let on_notifications = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if input.checked() {
+                alerts::request_notification_permission();
+            }
+            settings.set_notifications(input.checked());
+        })
+    };
+    let on_sounds = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            settings.set_sounds(input.checked());
+        })
+    };
+    let on_read_receipts = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            settings.set_read_receipts(input.checked());
+        })
+    };
+    let on_do_not_disturb = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            settings.set_do_not_disturb(input.checked());
+        })
+    };
+    let on_profanity_filter = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            settings.set_profanity_filter(input.checked());
+        })
+    };
+    let on_language = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(language) = Language::ALL.get(select.selected_index() as usize) {
+                settings.set_language(*language);
+            }
+        })
+    };
+    let on_timestamp_format = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(format) = TimestampFormat::ALL.get(select.selected_index() as usize) {
+                settings.set_timestamp_format(*format);
+            }
+        })
+    };
+    let on_away_after = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(minutes) = AWAY_AFTER_CHOICES.get(select.selected_index() as usize) {
+                settings.set_away_after_minutes(*minutes);
+            }
+        })
+    };
+    let on_long_message = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(chars) = LONG_MESSAGE_CHOICES.get(select.selected_index() as usize) {
+                settings.set_long_message_chars(*chars);
+            }
+        })
+    };
+    let on_announcement_pin = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(minutes) = ANNOUNCEMENT_PIN_CHOICES.get(select.selected_index() as usize) {
+                settings.set_announcement_pin_minutes(*minutes);
+            }
+        })
+    };
+    let on_history_limit = {
+        let settings = settings.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let limit = input
+                .value()
+                .parse::<usize>()
+                .unwrap_or(MIN_HISTORY_LIMIT)
+                .clamp(MIN_HISTORY_LIMIT, MAX_HISTORY_LIMIT);
+            settings.set_history_limit(limit);
+        })
+    };
+    let user = use_context::<User>().expect("No context found.");
+    let history_cleared = use_state(|| false);
+    let clear_history = {
+        let history_cleared = history_cleared.clone();
+        Callback::from(move |_| {
+            local_history::clear(&user.username.borrow());
+            history_cleared.set(true);
+        })
+    };
+    let reset = {
+        let settings = settings.clone();
+        Callback::from(move |_| settings.reset())
+    };
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                on_close.emit(());
+            }
+        })
+    };
+
+    let theme_radio = |theme: Theme, label: &str| {
+        html! {
+            <label class="me-3">
+                <input type="radio" name="theme" checked={current_theme.theme() == theme} onchange={on_theme(theme)}/>
+                {" "}{label}
+            </label>
+        }
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30" onclick={props.on_close.reform(|_| ())}>
+            <div {onkeydown} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                ref={dialog} tabindex="-1" role="dialog" aria-modal="true" aria-label={locale.t("settings.title").to_string()}
+                class="w-80 p-4 bg-white text-black rounded-lg shadow-lg text-sm flex flex-col gap-3">
+                <div class="flex justify-between items-center">
+                    <span class="text-lg font-semibold">{locale.t("settings.title")}</span>
+                    <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close").to_string()}>{"✕"}</button>
+                </div>
+                <fieldset>
+                    <legend class="mb-1">{locale.t("settings.theme")}</legend>
+                    {theme_radio(Theme::Light, locale.t("settings.theme_light"))}
+                    {theme_radio(Theme::Dark, locale.t("settings.theme_dark"))}
+                </fieldset>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.language")}
+                    <select onchange={on_language} class="px-2 py-1 rounded bg-gray-100">
+                        {for Language::ALL.iter().map(|language| html! {
+                            <option selected={settings.language() == *language}>{language.label()}</option>
+                        })}
+                    </select>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.notifications")}
+                    <input type="checkbox" checked={settings.notifications()} onchange={on_notifications}/>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.sounds")}
+                    <input type="checkbox" checked={settings.sounds()} onchange={on_sounds}/>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.read_receipts")}
+                    <input type="checkbox" checked={settings.read_receipts()} onchange={on_read_receipts}/>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("presence.do_not_disturb")}
+                    <input type="checkbox" checked={settings.do_not_disturb()} onchange={on_do_not_disturb}/>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.profanity_filter")}
+                    <input type="checkbox" checked={settings.profanity_filter()} onchange={on_profanity_filter}/>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.timestamps")}
+                    <select onchange={on_timestamp_format} class="px-2 py-1 rounded bg-gray-100">
+                        {for TimestampFormat::ALL.iter().map(|format| html! {
+                            <option selected={settings.timestamp_format() == *format}>{locale.t(format.label_key())}</option>
+                        })}
+                    </select>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.away_after")}
+                    <select onchange={on_away_after} class="px-2 py-1 rounded bg-gray-100">
+                        {for AWAY_AFTER_CHOICES.iter().map(|minutes| {
+                            let label = match minutes {
+                                0 => locale.t("settings.away_never").to_string(),
+                                n => locale.format("settings.away_minutes", &[("count", &n.to_string())]),
+                            };
+                            html! {
+                                <option selected={settings.away_after_minutes() == *minutes}>{label}</option>
+                            }
+                        })}
+                    </select>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.long_messages")}
+                    <select onchange={on_long_message} class="px-2 py-1 rounded bg-gray-100">
+                        {for LONG_MESSAGE_CHOICES.iter().map(|chars| html! {
+                            <option selected={settings.long_message_chars() == *chars}>
+                                {locale.format("settings.long_message_chars", &[("count", &chars.to_string())])}
+                            </option>
+                        })}
+                    </select>
+                </label>
+                <label class="flex items-center justify-between">
+                    {locale.t("settings.announcement_pin")}
+                    <select onchange={on_announcement_pin} class="px-2 py-1 rounded bg-gray-100">
+                        {for ANNOUNCEMENT_PIN_CHOICES.iter().map(|minutes| {
+                            let label = match minutes % 60 {
+                                0 => locale.format("settings.pin_hours", &[("count", &(minutes / 60).to_string())]),
+                                _ => locale.format("settings.away_minutes", &[("count", &minutes.to_string())]),
+                            };
+                            html! {
+                                <option selected={settings.announcement_pin_minutes() == *minutes}>{label}</option>
+                            }
+                        })}
+                    </select>
+                </label>
+                <div>
+                    <label class="flex items-center justify-between">
+                        {locale.t("settings.history_limit")}
+                        <input type="number" onchange={on_history_limit} value={settings.history_limit().to_string()}
+                            min={MIN_HISTORY_LIMIT.to_string()} max={MAX_HISTORY_LIMIT.to_string()} step="50"
+                            class="w-20 px-2 py-1 rounded bg-gray-100 outline-none"/>
+                    </label>
+                    <div class="mt-1 text-xs text-gray-400">{locale.t("settings.history_limit_hint")}</div>
+                </div>
+                if *history_cleared {
+                    <span role="status" class="text-xs text-gray-500">{locale.t("settings.history_cleared")}</span>
+                } else {
+                    <button onclick={clear_history} class="self-start text-xs underline text-gray-500">{locale.t("settings.clear_history")}</button>
+                }
+                <button onclick={props.on_clear_stars.reform(|_| ())} disabled={props.stars == 0} class="self-start text-xs underline text-gray-500 disabled:no-underline disabled:opacity-50">
+                    {locale.format("settings.clear_stars", &[("count", &props.stars.to_string())])}
+                </button>
+                <button onclick={reset} class="self-start text-xs underline text-gray-500">{locale.t("settings.reset")}</button>
+            </div>
+        </div>
+    }
+}
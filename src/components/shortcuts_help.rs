@@ -0,0 +1,47 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+use crate::services::shortcuts::{INPUT_SHORTCUTS, SHORTCUTS};
+
+#[derive(Properties, PartialEq)]
+pub struct ShortcutsHelpProps {
+    pub on_close: Callback<()>,
+}
+
+/// The cheat sheet opened with `?`, listing every keyboard shortcut.
+#[function_component(ShortcutsHelp)]
+pub fn shortcuts_help(props: &ShortcutsHelpProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let dialog = use_modal_focus();
+
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-30" onclick={props.on_close.reform(|_| ())}>
+            <div onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                ref={dialog} tabindex="-1" role="dialog" aria-modal="true" aria-label={locale.t("shortcuts.title").to_string()}
+                class="w-80 p-4 bg-white text-black rounded-lg shadow-lg text-sm flex flex-col gap-3">
+                <div class="flex justify-between items-center">
+                    <span class="text-lg font-semibold">{locale.t("shortcuts.title")}</span>
+                    <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close").to_string()}>{"✕"}</button>
+                </div>
+                <dl class="grid grid-cols-2 gap-2">
+                    {for SHORTCUTS.iter().map(|shortcut| html! {
+                        <>
+                            <dt><kbd class="px-1 rounded border border-gray-300 bg-gray-100 font-mono">{shortcut.label}</kbd></dt>
+                            <dd>{locale.t(shortcut.description)}</dd>
+                        </>
+                    })}
+                </dl>
+                <span class="font-semibold">{locale.t("shortcuts.in_input")}</span>
+                <dl class="grid grid-cols-2 gap-2">
+                    {for INPUT_SHORTCUTS.iter().map(|(label, description)| html! {
+                        <>
+                            <dt><kbd class="px-1 rounded border border-gray-300 bg-gray-100 font-mono">{*label}</kbd></dt>
+                            <dd>{locale.t(description)}</dd>
+                        </>
+                    })}
+                </dl>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,226 @@
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use gloo_events::EventListener;
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+use yew::prelude::*;
+use yew::virtual_dom::AttrValue;
+
+use crate::bidi::Direction;
+use crate::i18n::Locale;
+use crate::services::session;
+
+const COLLAPSED_KEY: &str = "sidebar_collapsed";
+const WIDTH_KEY: &str = "sidebar_width";
+const CLOSED_SECTIONS_KEY: &str = "sidebar_closed_sections";
+const NARROW_SCREEN_QUERY: &str = "(max-width: 768px)";
+
+/// Widths in CSS pixels. The default matches the old fixed `w-56`.
+const DEFAULT_WIDTH: f64 = 224.0;
+const MIN_WIDTH: f64 = 160.0;
+const MAX_WIDTH: f64 = 480.0;
+/// Room for a 48px avatar and its margins.
+const COLLAPSED_WIDTH: f64 = 72.0;
+/// How far an arrow key moves the resize handle.
+const KEYBOARD_STEP: f64 = 16.0;
+
+/// A titled part of the sidebar that can be folded away on its own.
+#[derive(Clone, PartialEq)]
+pub struct SidebarSection {
+    /// Remembers whether the section is folded, so it must be unique.
+    pub id: AttrValue,
+    pub title: AttrValue,
+    pub content: Html,
+    /// Shown instead of the content while the whole sidebar is collapsed to
+    /// icons. Sections without any are left out then.
+    pub icons: Option<Html>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SidebarProps {
+    pub sections: Vec<SidebarSection>,
+    /// Beside the collapse button, while expanded.
+    #[prop_or_default]
+    pub header: Html,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+/// The side panel: resizable by dragging its inner edge, collapsible to a
+/// strip of icons, with sections that fold individually. The width, the
+/// collapse and folded sections are kept in local storage.
+#[function_component(Sidebar)]
+pub fn sidebar(props: &SidebarProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let collapsed = use_state(|| session::load(COLLAPSED_KEY).unwrap_or_else(is_narrow_screen));
+    let width = use_state(|| session::load(WIDTH_KEY).map_or(DEFAULT_WIDTH, clamp_width));
+    let closed =
+        use_state(|| session::load::<HashSet<String>>(CLOSED_SECTIONS_KEY).unwrap_or_default());
+    let dragging = use_state(|| false);
+    let panel = use_node_ref();
+    let rtl = locale.direction() == Direction::Rtl;
+
+    {
+        let panel = panel.clone();
+        let width = width.clone();
+        let stop = dragging.clone();
+        use_effect_with_deps(
+            move |&is_dragging| {
+                let listeners = is_dragging.then(|| {
+                    let window = web_sys::window().expect("no window");
+                    let latest = Rc::new(Cell::new(*width));
+                    let on_move = {
+                        let latest = latest.clone();
+                        EventListener::new(&window, "mousemove", move |e| {
+                            let e: &MouseEvent = e.unchecked_ref();
+                            let Some(panel) = panel.cast::<Element>() else {
+                                return;
+                            };
+                            let rect = panel.get_bounding_client_rect();
+                            let x = f64::from(e.client_x());
+                            let next = clamp_width(if rtl {
+                                rect.right() - x
+                            } else {
+                                x - rect.left()
+                            });
+                            latest.set(next);
+                            width.set(next);
+                        })
+                    };
+                    let on_up = EventListener::new(&window, "mouseup", move |_| {
+                        session::save(WIDTH_KEY, &latest.get());
+                        stop.set(false);
+                    });
+                    (on_move, on_up)
+                });
+                move || drop(listeners)
+            },
+            *dragging,
+        );
+    }
+
+    let toggle_collapsed = {
+        let collapsed = collapsed.clone();
+        Callback::from(move |_| {
+            let next = !*collapsed;
+            session::save(COLLAPSED_KEY, &next);
+            collapsed.set(next);
+        })
+    };
+    let start_drag = {
+        let dragging = dragging.clone();
+        Callback::from(move |e: MouseEvent| {
+            // Keep the drag from selecting text on the way.
+            e.prevent_default();
+            dragging.set(true);
+        })
+    };
+    let resize_by_key = {
+        let width = width.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            let step = match (e.key().as_str(), rtl) {
+                ("ArrowRight", false) | ("ArrowLeft", true) => KEYBOARD_STEP,
+                ("ArrowLeft", false) | ("ArrowRight", true) => -KEYBOARD_STEP,
+                _ => return,
+            };
+            e.prevent_default();
+            let next = clamp_width(*width + step);
+            session::save(WIDTH_KEY, &next);
+            width.set(next);
+        })
+    };
+    let toggle_section = |id: &AttrValue| {
+        let closed = closed.clone();
+        let id = id.to_string();
+        Callback::from(move |_| {
+            let mut next = (*closed).clone();
+            if !next.remove(&id) {
+                next.insert(id.clone());
+            }
+            session::save(CLOSED_SECTIONS_KEY, &next);
+            closed.set(next);
+        })
+    };
+
+    let shown_width = if *collapsed { COLLAPSED_WIDTH } else { *width };
+    let sections = if *collapsed {
+        props
+            .sections
+            .iter()
+            .filter_map(|section| section.icons.clone())
+            .collect::<Html>()
+    } else {
+        props
+            .sections
+            .iter()
+            .map(|section| {
+                let open = !closed.contains(&*section.id);
+                let content_id = format!("sidebar-{}", section.id);
+                html! {
+                    <section key={section.id.to_string()} aria-label={section.title.clone()}>
+                        <button onclick={toggle_section(&section.id)} aria-expanded={open.to_string()} aria-controls={content_id.clone()}
+                            class="flex items-center w-full px-3 py-1 text-xs font-semibold uppercase tracking-wide text-gray-500">
+                            <span aria-hidden="true" class="me-1">{if open { "▾" } else { "▸" }}</span>
+                            {section.title.clone()}
+                        </button>
+                        if open {
+                            <div id={content_id}>{section.content.clone()}</div>
+                        }
+                    </section>
+                }
+            })
+            .collect::<Html>()
+    };
+
+    html! {
+        <div ref={panel} style={format!("width: {}px", shown_width)}
+            class={classes!("relative", "flex-none", "h-screen", "flex", "flex-col", props.class.clone(), (!*dragging).then_some("transition-[width] duration-300"))}>
+            <div class="flex items-center p-3">
+                <button onclick={toggle_collapsed} class="me-2 text-xl" aria-label={locale.t(if *collapsed { "sidebar.show" } else { "sidebar.hide" })} aria-expanded={(!*collapsed).to_string()}>
+                    <span aria-hidden="true">{match (*collapsed, rtl) {
+                        (true, false) | (false, true) => "»",
+                        (false, false) | (true, true) => "«",
+                    }}</span>
+                </button>
+                if !*collapsed {
+                    <div class="flex grow justify-between items-center min-w-0 whitespace-nowrap">{props.header.clone()}</div>
+                }
+            </div>
+            <div class="grow overflow-y-auto overflow-x-hidden">{sections}</div>
+            if !*collapsed {
+                <div role="separator" aria-orientation="vertical" aria-label={locale.t("sidebar.resize")} tabindex="0"
+                    aria-valuenow={(*width as u32).to_string()} aria-valuemin={(MIN_WIDTH as u32).to_string()} aria-valuemax={(MAX_WIDTH as u32).to_string()}
+                    onmousedown={start_drag} onkeydown={resize_by_key}
+                    class={classes!("absolute", "inset-y-0", "end-0", "w-1", "cursor-col-resize", "hover:bg-blue-400", "focus:bg-blue-400", "outline-none", dragging.then_some("bg-blue-400"))}></div>
+            }
+        </div>
+    }
+}
+
+fn clamp_width(width: f64) -> f64 {
+    if width.is_nan() {
+        return DEFAULT_WIDTH;
+    }
+    width.clamp(MIN_WIDTH, MAX_WIDTH)
+}
+
+fn is_narrow_screen() -> bool {
+    web_sys::window()
+        .and_then(|w| w.match_media(NARROW_SCREEN_QUERY).ok().flatten())
+        .is_some_and(|query| query.matches())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_stay_in_bounds() {
+        assert_eq!(clamp_width(10.0), MIN_WIDTH);
+        assert_eq!(clamp_width(300.0), 300.0);
+        assert_eq!(clamp_width(5_000.0), MAX_WIDTH);
+        assert_eq!(clamp_width(f64::NAN), DEFAULT_WIDTH);
+    }
+}
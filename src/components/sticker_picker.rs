@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::stickers::{self, Manifest};
+
+#[derive(Properties, PartialEq)]
+pub struct StickerPickerProps {
+    pub manifest_url: String,
+    /// Called with the chosen sticker's URL.
+    pub on_select: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+/// The sticker packs from the manifest, one tab per pack.
+#[function_component(StickerPicker)]
+pub fn sticker_picker(props: &StickerPickerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let manifest = use_state(|| None::<Result<Rc<Manifest>, String>>);
+    let tab = use_state(|| 0usize);
+
+    {
+        let manifest = manifest.clone();
+        use_effect_with_deps(
+            move |url: &String| {
+                let url = url.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    manifest.set(Some(stickers::load(&url).await.map(Rc::new)));
+                });
+                || ()
+            },
+            props.manifest_url.clone(),
+        );
+    }
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                e.stop_propagation();
+                on_close.emit(());
+            }
+        })
+    };
+    let body = match &*manifest {
+        None => {
+            html! { <p role="status" class="p-2 text-gray-500">{locale.t("stickers.loading")}</p> }
+        }
+        Some(Err(error)) => html! {
+            <p role="alert" class="p-2 text-red-600">{locale.format("stickers.error", &[("error", error)])}</p>
+        },
+        Some(Ok(manifest)) if manifest.packs.is_empty() => html! {
+            <p class="p-2 text-gray-500">{locale.t("stickers.empty")}</p>
+        },
+        Some(Ok(manifest)) => {
+            let current = (*tab).min(manifest.packs.len() - 1);
+            let pack = &manifest.packs[current];
+            html! {
+                <>
+                    <div role="tablist" class="flex gap-1 overflow-x-auto shrink-0">
+                        {for manifest.packs.iter().enumerate().map(|(i, pack)| {
+                            let tab = tab.clone();
+                            html! {
+                                <button role="tab" aria-selected={(i == current).to_string()} onclick={Callback::from(move |_| tab.set(i))}
+                                    class={classes!("px-2", "py-1", "rounded", "whitespace-nowrap", (i == current).then_some("bg-gray-200"))}>
+                                    {&pack.name}
+                                </button>
+                            }
+                        })}
+                    </div>
+                    <div role="tabpanel" aria-label={pack.name.clone()} class="grow overflow-y-auto grid grid-cols-4 gap-1 content-start">
+                        {for pack.stickers.iter().map(|sticker| {
+                            let url = pack.url(sticker);
+                            html! {
+                                <button key={sticker.id.clone()} onclick={props.on_select.reform({
+                                    let url = url.clone();
+                                    move |_| url.clone()
+                                })} title={sticker.id.clone()} class="p-1 rounded hover:bg-gray-100">
+                                    <img src={url} alt={sticker.id.clone()} loading="lazy" class="w-full h-16 object-contain"/>
+                                </button>
+                            }
+                        })}
+                    </div>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <div role="dialog" aria-label={locale.t("stickers.title")} {onkeydown} tabindex="-1"
+            class="absolute bottom-full end-0 mb-2 w-80 h-96 flex flex-col p-2 gap-2 rounded-lg shadow-lg bg-white text-sm text-black">
+            <div class="flex items-center justify-between">
+                <span class="font-semibold">{locale.t("stickers.title")}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close")} class="px-1">{"✕"}</button>
+            </div>
+            {body}
+        </div>
+    }
+}
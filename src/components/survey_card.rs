@@ -0,0 +1,162 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::message::{Answer, Question, Survey};
+
+#[derive(Properties, PartialEq)]
+pub struct SurveyCardProps {
+    pub survey: Survey,
+    /// Already answered, here or in an earlier visit.
+    #[prop_or_default]
+    pub answered: bool,
+    /// Sends the answers, one per question.
+    #[prop_or_default]
+    pub on_submit: Option<Callback<Vec<Answer>>>,
+}
+
+/// The answers to send, once every rating and choice has been made. Text
+/// questions may be left blank.
+fn complete(questions: &[Question], answers: &[Option<Answer>]) -> Option<Vec<Answer>> {
+    questions
+        .iter()
+        .enumerate()
+        .map(
+            |(i, question)| match (question, answers.get(i).cloned().flatten()) {
+                (_, Some(answer)) => Some(answer),
+                (Question::Text { .. }, None) => Some(Answer::Text(String::new())),
+                (_, None) => None,
+            },
+        )
+        .collect()
+}
+
+/// A survey as a form in the message list. Once it's been answered only
+/// the thanks are left.
+#[function_component(SurveyCard)]
+pub fn survey_card(props: &SurveyCardProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let answers = use_state(|| vec![None::<Answer>; props.survey.questions.len()]);
+    let survey = &props.survey;
+
+    if props.answered {
+        return html! {
+            <div role="status" class="mt-2 p-3 rounded-lg border border-blue-300 bg-blue-50 text-blue-900 text-sm">
+                <span aria-hidden="true" class="me-1">{"📋"}</span>{locale.t("survey.thanks")}
+            </div>
+        };
+    }
+
+    let set = |index: usize| {
+        let answers = answers.clone();
+        Callback::from(move |answer: Answer| {
+            let mut next = (*answers).clone();
+            next.resize(index + 1, None);
+            next[index] = Some(answer);
+            answers.set(next);
+        })
+    };
+    let questions = survey.questions.iter().enumerate().map(|(index, question)| {
+        let set = set(index);
+        let current = answers.get(index).cloned().flatten();
+        let field = match question {
+            Question::Text { .. } => {
+                let oninput = set.reform(|e: InputEvent| {
+                    Answer::Text(e.target_unchecked_into::<HtmlInputElement>().value())
+                });
+                let value = match current {
+                    Some(Answer::Text(text)) => text,
+                    _ => String::new(),
+                };
+                html! {
+                    <input {oninput} {value} aria-label={question.prompt().to_string()} placeholder={locale.t("survey.answer")}
+                        class="w-full px-2 py-1 rounded bg-white text-black outline-none"/>
+                }
+            }
+            Question::Rating { max, .. } => html! {
+                <div role="radiogroup" aria-label={question.prompt().to_string()} class="flex gap-1">
+                    {for (1..=*max).map(|value| {
+                        let chosen = current == Some(Answer::Rating(value));
+                        let label = locale.format("survey.rating", &[("value", &value.to_string()), ("max", &max.to_string())]);
+                        html! {
+                            <button type="button" role="radio" aria-checked={chosen.to_string()} aria-label={label.clone()} title={label}
+                                onclick={set.reform(move |_| Answer::Rating(value))}
+                                class={classes!("w-8", "h-8", "rounded-full", if chosen { "bg-blue-600 text-white" } else { "bg-white text-black" })}>
+                                {value}
+                            </button>
+                        }
+                    })}
+                </div>
+            },
+            Question::MultipleChoice { options, .. } => {
+                let name = format!("survey-{}-{}", survey.survey_id, index);
+                html! {
+                    <div role="radiogroup" aria-label={question.prompt().to_string()} class="flex flex-col gap-1">
+                        {for options.iter().map(|option| {
+                            let checked = current.as_ref() == Some(&Answer::Choice(option.clone()));
+                            let choice = option.clone();
+                            html! {
+                                <label class="flex items-center gap-2 cursor-pointer">
+                                    <input type="radio" name={name.clone()} {checked} onchange={set.reform(move |_| Answer::Choice(choice.clone()))}/>
+                                    {option}
+                                </label>
+                            }
+                        })}
+                    </div>
+                }
+            }
+        };
+        html! {
+            <fieldset class="mb-3">
+                <legend class="mb-1 font-semibold">{question.prompt()}</legend>
+                {field}
+            </fieldset>
+        }
+    });
+    let ready = complete(&survey.questions, &answers);
+    let onsubmit = {
+        let on_submit = props.on_submit.clone();
+        let ready = ready.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            if let (Some(on_submit), Some(answers)) = (&on_submit, &ready) {
+                on_submit.emit(answers.clone());
+            }
+        })
+    };
+
+    html! {
+        <form {onsubmit} aria-label={locale.t("survey.title")}
+            class="mt-2 p-3 rounded-lg border border-blue-300 bg-blue-50 text-blue-950 text-sm">
+            <div class="mb-2 text-xs font-semibold uppercase tracking-wide text-blue-700">
+                <span aria-hidden="true" class="me-1">{"📋"}</span>{locale.t("survey.title")}
+            </div>
+            {for questions}
+            <button type="submit" disabled={ready.is_none() || props.on_submit.is_none()}
+                class="px-3 py-1 rounded-full bg-blue-600 text-white disabled:opacity-50">{locale.t("survey.submit")}</button>
+        </form>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_text_is_fine_but_ratings_and_choices_are_needed() {
+        let questions = vec![
+            Question::Text {
+                prompt: "Anything else?".into(),
+            },
+            Question::Rating {
+                prompt: "How was it?".into(),
+                max: 5,
+            },
+        ];
+        assert_eq!(complete(&questions, &[None, None]), None);
+        assert_eq!(
+            complete(&questions, &[None, Some(Answer::Rating(3))]),
+            Some(vec![Answer::Text(String::new()), Answer::Rating(3)])
+        );
+    }
+}
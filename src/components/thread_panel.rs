@@ -0,0 +1,117 @@
+use std::rc::Rc;
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::avatar::Avatar;
+use crate::components::chat::MessageData;
+use crate::components::message_content::MessageContent;
+use crate::i18n::Locale;
+use crate::services::focus::use_modal_focus;
+use crate::services::settings::use_settings;
+
+/// A message in the thread, with its sender's avatar.
+#[derive(Clone, PartialEq)]
+pub struct ThreadEntry {
+    pub message: Rc<MessageData>,
+    pub avatar: String,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ThreadPanelProps {
+    /// `None` once the root has been trimmed from the history.
+    pub root: Option<ThreadEntry>,
+    pub replies: Vec<ThreadEntry>,
+    /// Sends a reply to the thread.
+    pub on_send: Callback<String>,
+    pub on_close: Callback<()>,
+}
+
+/// A thread opened from its root message, sliding in from the end of the
+/// window, with its own box for replying.
+#[function_component(ThreadPanel)]
+pub fn thread_panel(props: &ThreadPanelProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let settings = use_settings();
+    let dialog = use_modal_focus();
+    let input = use_node_ref();
+
+    let send = {
+        let input = input.clone();
+        let on_send = props.on_send.clone();
+        Callback::from(move |_: ()| {
+            let Some(input) = input.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let text = input.value();
+            if !text.trim().is_empty() {
+                on_send.emit(text);
+                input.set_value("");
+            }
+        })
+    };
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        let send = send.clone();
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "Escape" => on_close.emit(()),
+            "Enter" if !e.shift_key() => {
+                e.prevent_default();
+                send.emit(());
+            }
+            _ => {}
+        })
+    };
+    let entry = |entry: &ThreadEntry| {
+        let m = &entry.message;
+        let time = settings.timestamp_format().format(m.timestamp);
+        html! {
+            <li key={m.id} class="flex items-start gap-2">
+                <Avatar name={m.from.clone()} url={entry.avatar.clone()} size={24}/>
+                <div class="min-w-0">
+                    <div class="text-xs font-semibold">
+                        {&m.from}
+                        if let Some(time) = time {
+                            <span class="ms-2 font-normal text-gray-400">{time}</span>
+                        }
+                    </div>
+                    <div class="message-text text-sm" dir="auto"><MessageContent text={m.message.clone()}/></div>
+                </div>
+            </li>
+        }
+    };
+    let count = props.replies.len();
+    let count_key = if count == 1 {
+        "thread.replies_one"
+    } else {
+        "thread.replies_many"
+    };
+
+    html! {
+        <aside ref={dialog} tabindex="-1" role="dialog" aria-label={locale.t("thread.title").to_string()} {onkeydown}
+            class="thread-panel fixed inset-y-0 end-0 z-40 w-96 max-w-full flex flex-col bg-white text-black shadow-lg">
+            <div class="flex items-center justify-between h-14 px-3 border-b border-gray-300">
+                <span class="text-lg font-semibold">{locale.t("thread.title")}</span>
+                <button onclick={props.on_close.reform(|_| ())} aria-label={locale.t("settings.close").to_string()}>{"✕"}</button>
+            </div>
+            <div class="grow overflow-y-auto p-3">
+                if let Some(root) = &props.root {
+                    <ul class="pb-3 mb-3 border-b border-gray-200">{entry(root)}</ul>
+                } else {
+                    <p class="pb-3 mb-3 text-xs text-gray-400 border-b border-gray-200">{locale.t("thread.root_trimmed")}</p>
+                }
+                <div class="mb-2 text-xs text-gray-500">{locale.format(count_key, &[("count", &count.to_string())])}</div>
+                <ul class="flex flex-col gap-3">
+                    {for props.replies.iter().map(entry)}
+                </ul>
+            </div>
+            <div class="flex items-center p-3 border-t border-gray-300">
+                <input ref={input} type="text" dir="auto" placeholder={locale.t("thread.reply_placeholder")} aria-label={locale.t("thread.reply_placeholder")}
+                    class="grow py-2 ps-4 me-2 bg-gray-100 rounded-full outline-none"/>
+                <button onclick={send.reform(|_| ())} aria-label={locale.t("chat.send")} class="px-3 py-2 rounded-full bg-blue-600 text-white">
+                    {locale.t("thread.send")}
+                </button>
+            </div>
+        </aside>
+    }
+}
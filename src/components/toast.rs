@@ -0,0 +1,16 @@
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct ToastProps {
+    pub text: String,
+}
+
+/// A short confirmation, such as "Copied", over the bottom of the screen.
+#[function_component(Toast)]
+pub fn toast(props: &ToastProps) -> Html {
+    html! {
+        <div role="status" class="fixed bottom-20 left-1/2 z-50 px-4 py-2 rounded-full shadow bg-gray-800 text-white text-sm" style="transform: translateX(-50%);">
+            {props.text.clone()}
+        </div>
+    }
+}
@@ -0,0 +1,114 @@
+use web_sys::Element;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::syntax::{self, Segment};
+
+/// How much of a long message is shown while collapsed, in characters.
+pub const SHOWN_CHARS: usize = 300;
+
+/// The start of `message` to show collapsed, if it is over `limit`
+/// characters: at most `SHOWN_CHARS` of them, cut back to a word break.
+/// Code blocks don't count and are never cut.
+pub fn collapse(message: &str, limit: usize) -> Option<&str> {
+    syntax::truncate_prose(message, limit)?;
+    syntax::truncate_prose(message, SHOWN_CHARS.min(limit))
+}
+
+/// Whether the message holds a fenced code block, which is never clamped.
+pub fn has_code(message: &str) -> bool {
+    syntax::segments(message).any(|segment| matches!(segment, Segment::Code { .. }))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TruncatedMessageProps {
+    /// The whole message.
+    pub children: Children,
+    /// The start of it, shown until expanded, if it's too long.
+    #[prop_or_default]
+    pub preview: Option<Html>,
+    /// Clamp it to the height `styles.css` allows, too. Off for code.
+    #[prop_or(true)]
+    pub clamp: bool,
+    #[prop_or("auto")]
+    pub dir: &'static str,
+    /// Shown in full. Kept by the owner, so it outlasts this component when
+    /// the message scrolls out of a virtual list and back.
+    #[prop_or_default]
+    pub expanded: bool,
+    /// Asked on each Show more or Show less to flip `expanded`. Without it
+    /// there is no button.
+    #[prop_or_default]
+    pub on_toggle: Option<Callback<()>>,
+}
+
+/// A message collapsed to its start, or to the height it's clamped to, with
+/// a button to show it all in place and back again.
+#[function_component(TruncatedMessage)]
+pub fn truncated_message(props: &TruncatedMessageProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let expanded = props.expanded;
+    // Past the clamped height, which takes a render to find out.
+    let body = use_node_ref();
+    let too_tall = use_state(|| false);
+    {
+        let body = body.clone();
+        let too_tall = too_tall.clone();
+        let open = expanded || !props.clamp;
+        use_effect(move || {
+            if let Some(body) = body.cast::<Element>().filter(|_| !open) {
+                let overflows = body.scroll_height() > body.client_height() + 1;
+                if overflows != *too_tall {
+                    too_tall.set(overflows);
+                }
+            }
+            || ()
+        });
+    }
+    let long = props.preview.is_some() || (props.clamp && *too_tall);
+    let collapsed = long && !expanded;
+    let toggle = props
+        .on_toggle
+        .as_ref()
+        .map(|toggle| toggle.reform(|_: MouseEvent| ()));
+    let content = match &props.preview {
+        Some(preview) if !expanded => preview.clone(),
+        _ => html! { for props.children.iter() },
+    };
+
+    html! {
+        <>
+            <div ref={body} dir={props.dir} class={classes!("message-text", (props.clamp && !expanded).then_some("message-clamp"), collapsed.then_some("message-faded"))}>
+                {content}
+            </div>
+            if let (true, Some(onclick)) = (long, toggle) {
+                <button {onclick} aria-expanded={expanded.to_string()} class="mt-1 text-xs font-semibold text-blue-600 hover:underline">
+                    {locale.t(if expanded { "message.show_less" } else { "message.show_more" })}
+                </button>
+            }
+        </>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_long_messages_collapse_to_their_start() {
+        let short = "word ".repeat(100);
+        assert_eq!(collapse(&short, 500), None);
+        let long = "word ".repeat(120);
+        let start = collapse(&long, 500).unwrap();
+        assert!(start.chars().count() <= SHOWN_CHARS);
+        assert!(start.ends_with("word"));
+    }
+
+    #[test]
+    fn code_blocks_are_left_whole() {
+        let code = format!("```\n{}\n```", "x".repeat(600));
+        assert_eq!(collapse(&code, 500), None);
+        assert!(has_code(&code));
+        assert!(!has_code("plain `inline` text"));
+    }
+}
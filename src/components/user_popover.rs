@@ -0,0 +1,189 @@
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, HtmlInputElement};
+use yew::prelude::*;
+
+use crate::components::avatar::Avatar;
+use crate::components::chat::UserProfile;
+use crate::i18n::Locale;
+use crate::services::export::format_time;
+use crate::services::message::Role;
+use crate::services::names::is_valid_username;
+
+/// Screen position a popover is attached to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Anchor {
+    /// Anchors to the right-hand edge of the clicked element.
+    pub fn from_event(e: &MouseEvent) -> Self {
+        let rect = e
+            .target()
+            .map(|t| t.unchecked_into::<Element>().get_bounding_client_rect());
+        match rect {
+            Some(rect) => Self {
+                x: rect.right() + 8.0,
+                y: rect.top(),
+            },
+            None => Self {
+                x: e.client_x() as f64,
+                y: e.client_y() as f64,
+            },
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct UserPopoverProps {
+    pub user: UserProfile,
+    pub anchor: Anchor,
+    pub on_close: Callback<()>,
+    /// Whether the user is currently muted; flips the mute action's label.
+    #[prop_or_default]
+    pub muted: bool,
+    /// Actions are only shown when the parent handles them.
+    #[prop_or_default]
+    pub on_message_privately: Option<Callback<String>>,
+    #[prop_or_default]
+    pub on_mute: Option<Callback<String>>,
+    /// Offered on the current user's own card.
+    #[prop_or_default]
+    pub on_rename: Option<Callback<String>>,
+    /// Offered to moderators: mutes the user server-side for a number of
+    /// seconds.
+    #[prop_or_default]
+    pub on_server_mute: Option<Callback<(String, u64)>>,
+    /// Offered to moderators: removes the user from the chat.
+    #[prop_or_default]
+    pub on_kick: Option<Callback<String>>,
+}
+
+/// Server mute lengths in seconds, with the locale key of each label.
+const MUTE_PRESETS: [(u64, &str); 3] = [
+    (60, "popover.mute_1m"),
+    (600, "popover.mute_10m"),
+    (3600, "popover.mute_1h"),
+];
+
+#[function_component(UserPopover)]
+pub fn user_popover(props: &UserPopoverProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let card = use_node_ref();
+    let new_name = use_state(|| props.user.name.clone());
+
+    // Take focus so Escape reaches us without a document-wide listener.
+    {
+        let card = card.clone();
+        use_effect_with_deps(
+            move |_| {
+                if let Some(card) = card.cast::<HtmlElement>() {
+                    let _ = card.focus();
+                }
+                || ()
+            },
+            props.user.name.clone(),
+        );
+    }
+
+    let onkeydown = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                on_close.emit(());
+            }
+        })
+    };
+    let close = props.on_close.reform(|_: MouseEvent| ());
+    let action = |label: &'static str, callback: &Option<Callback<String>>| {
+        callback.as_ref().map(|callback| {
+            let name = props.user.name.clone();
+            let onclick = callback.reform(move |_: MouseEvent| name.clone());
+            html! {
+                <button {onclick} class="flex-1 px-2 py-1 text-xs rounded bg-gray-100 hover:bg-gray-200">{label}</button>
+            }
+        })
+    };
+    let rename = props.on_rename.as_ref().map(|on_rename| {
+        let valid = is_valid_username(new_name.trim()) && new_name.trim() != props.user.name;
+        let oninput = {
+            let new_name = new_name.clone();
+            Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                new_name.set(input.value());
+            })
+        };
+        let onsubmit = {
+            let new_name = new_name.clone();
+            let on_rename = on_rename.clone();
+            Callback::from(move |e: FocusEvent| {
+                e.prevent_default();
+                if valid {
+                    on_rename.emit((*new_name).clone());
+                }
+            })
+        };
+        html! {
+            <form {onsubmit} class="flex gap-2 mt-3">
+                <input {oninput} value={(*new_name).clone()} maxlength="32" aria-label={locale.t("popover.new_name")}
+                    class="flex-1 min-w-0 px-2 py-1 text-xs rounded bg-gray-100 outline-none"/>
+                <button type="submit" disabled={!valid} class="px-2 py-1 text-xs rounded bg-gray-100 hover:bg-gray-200 disabled:opacity-50">{locale.t("popover.rename")}</button>
+            </form>
+        }
+    });
+    let server_mute = props.on_server_mute.as_ref().map(|on_server_mute| {
+        html! {
+            <details class="mt-3 text-xs">
+                <summary class="cursor-pointer">{locale.t("popover.mute_for")}</summary>
+                <div class="flex gap-2 mt-2">
+                    {for MUTE_PRESETS.iter().map(|&(secs, label)| {
+                        let name = props.user.name.clone();
+                        let onclick = on_server_mute.reform(move |_: MouseEvent| (name.clone(), secs));
+                        html! {
+                            <button {onclick} class="flex-1 px-2 py-1 rounded bg-gray-100 hover:bg-gray-200">{locale.t(label)}</button>
+                        }
+                    })}
+                </div>
+            </details>
+        }
+    });
+    let style = format!("left: {}px; top: {}px;", props.anchor.x, props.anchor.y);
+
+    html! {
+        <>
+            // Clicks anywhere outside the card land here and close it.
+            <div class="fixed inset-0 z-40" onclick={close}></div>
+            <div ref={card} {onkeydown} tabindex="-1" role="dialog" aria-label={locale.format("popover.label", &[("name", &props.user.name)])}
+                class="fixed z-50 w-60 p-4 bg-white text-black rounded-lg shadow-lg outline-none" {style}>
+                <Avatar name={props.user.name.clone()} url={props.user.avatar.clone()} size={96} class="mx-auto"/>
+                <div class="mt-2 text-center text-lg">
+                    {props.user.name.clone()}
+                    if props.user.role == Role::Moderator {
+                        <span class="ms-1 px-1 align-middle rounded bg-violet-100 text-violet-700 text-xs" title={locale.t("moderation.badge")}>{locale.t("moderation.badge_short")}</span>
+                    }
+                </div>
+                if !props.user.status.is_empty() {
+                    <div class="text-center text-xs text-gray-500 break-words">{props.user.status.clone()}</div>
+                }
+                <div class="mt-1 text-center text-xs text-gray-400">
+                    {locale.format("popover.online_since", &[
+                        ("time", &format_time(props.user.joined_at)),
+                        ("ago", &locale.relative_time(js_sys::Date::now() - props.user.joined_at)),
+                    ])}
+                </div>
+                <div class="flex gap-2 mt-3">
+                    {for action(locale.t("popover.message_privately"), &props.on_message_privately)}
+                    {for action(locale.t(if props.muted { "popover.unmute" } else { "popover.mute" }), &props.on_mute)}
+                </div>
+                if props.on_kick.is_some() {
+                    <div class="flex gap-2 mt-3">
+                        {for action(locale.t("popover.kick"), &props.on_kick)}
+                    </div>
+                }
+                {for server_mute}
+                {for rename}
+            </div>
+        </>
+    }
+}
@@ -0,0 +1,449 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::colors::name_color;
+use crate::components::avatar::Avatar;
+use crate::components::chat::{UserProfile, Users};
+use crate::components::sidebar::{Sidebar, SidebarSection};
+use crate::components::user_popover::Anchor;
+use crate::i18n::Locale;
+use crate::services::message::{Presence, Role};
+use crate::services::settings::{use_theme, Theme};
+use crate::text::truncate;
+use crate::Route;
+
+const STATUS_DISPLAY_LEN: usize = 40;
+
+#[derive(Properties, PartialEq)]
+pub struct UserSidebarProps {
+    pub users: Rc<Users>,
+    pub current_user: String,
+    pub on_set_status: Callback<String>,
+    pub on_avatar_click: Callback<(String, Anchor)>,
+    pub on_call: Callback<String>,
+    /// Private rooms the user has joined. The lobby is always listed first.
+    #[prop_or_default]
+    pub rooms: Vec<String>,
+    /// `None` for the lobby.
+    #[prop_or_default]
+    pub current_room: Option<String>,
+    #[prop_or_default]
+    pub on_select_room: Callback<Option<String>>,
+    /// Unread messages in rooms other than the current one.
+    #[prop_or_default]
+    pub unread: HashMap<Option<String>, usize>,
+    /// Of those rooms, the ones where someone mentioned me.
+    #[prop_or_default]
+    pub mentioned: HashSet<Option<String>>,
+    /// Sets my status by hand: online or do not disturb.
+    #[prop_or_default]
+    pub on_set_presence: Callback<Presence>,
+    /// Opens the full list of everyone, online or not.
+    #[prop_or_default]
+    pub on_show_people: Callback<()>,
+    /// Opens the chat's activity statistics.
+    #[prop_or_default]
+    pub on_show_stats: Callback<()>,
+}
+
+#[function_component(UserSidebar)]
+pub fn user_sidebar(props: &UserSidebarProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let filter = use_state(String::new);
+    let editing_status = use_state(|| false);
+    let status_input = use_node_ref();
+
+    let oninput = {
+        let filter = filter.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            filter.set(input.value());
+        })
+    };
+
+    let onkeydown = {
+        let filter = filter.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                filter.set(String::new());
+            }
+        })
+    };
+
+    {
+        let status_input = status_input.clone();
+        use_effect_with_deps(
+            move |editing| {
+                if *editing {
+                    if let Some(input) = status_input.cast::<HtmlInputElement>() {
+                        let _ = input.focus();
+                    }
+                }
+                || ()
+            },
+            *editing_status,
+        );
+    }
+
+    let start_editing = {
+        let editing_status = editing_status.clone();
+        Callback::from(move |_| editing_status.set(true))
+    };
+
+    let status_keydown = {
+        let editing_status = editing_status.clone();
+        let on_set_status = props.on_set_status.clone();
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "Enter" => {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                on_set_status.emit(input.value().trim().to_string());
+                editing_status.set(false);
+            }
+            "Escape" => editing_status.set(false),
+            _ => {}
+        })
+    };
+
+    let stop_editing = {
+        let editing_status = editing_status.clone();
+        Callback::from(move |_| editing_status.set(false))
+    };
+
+    let avatar_click = |name: &str| {
+        let name = name.to_string();
+        props.on_avatar_click.reform(move |e: MouseEvent| {
+            // Don't let the click also start editing my own status.
+            e.stop_propagation();
+            (name.clone(), Anchor::from_event(&e))
+        })
+    };
+
+    let call_click = |name: &str| {
+        let name = name.to_string();
+        props.on_call.reform(move |e: MouseEvent| {
+            e.stop_propagation();
+            name.clone()
+        })
+    };
+
+    let presence_change = props.on_set_presence.reform(|e: Event| {
+        let select: HtmlSelectElement = e.target_unchecked_into();
+        match select.value().as_str() {
+            "dnd" => Presence::DoNotDisturb,
+            _ => Presence::Online,
+        }
+    });
+
+    let matching: Vec<(&UserProfile, Option<(usize, usize)>)> = props
+        .users
+        .values()
+        .filter_map(|u| {
+            if filter.is_empty() {
+                Some((u, None))
+            } else {
+                find_ignore_case(&u.name, &filter).map(|range| (u, Some(range)))
+            }
+        })
+        .collect();
+
+    let theme = use_theme().theme();
+    let (panel, card) = match theme {
+        Theme::Light => ("bg-gray-100", "bg-white"),
+        Theme::Dark => ("bg-gray-800 text-white", "bg-gray-700"),
+    };
+
+    let presence_dot = |u: &UserProfile| {
+        if u.bot {
+            return Html::default();
+        }
+        let label = locale.t(presence_key(u.presence));
+        html! {
+            <span title={label} aria-label={label}
+                class={classes!("absolute", "bottom-0", "end-0", "w-3", "h-3", "rounded-full", "border-2", "border-white", presence_color(u.presence))}></span>
+        }
+    };
+
+    let header = html! {
+        <>
+            <div class="text-xl truncate">{locale.format("sidebar.title", &[("count", &props.users.len().to_string())])}</div>
+            <Link<Route> to={Route::Profile { username: props.current_user.clone() }} classes="ms-2 text-xs text-violet-600 underline">
+                {locale.t("sidebar.my_profile")}
+            </Link<Route>>
+            <button onclick={props.on_show_people.reform(|_| ())} aria-label={locale.t("sidebar.people")} title={locale.t("sidebar.people")} class="ms-2 text-lg">
+                <span aria-hidden="true">{"👥"}</span>
+            </button>
+            <button onclick={props.on_show_stats.reform(|_| ())} aria-label={locale.t("stats.title")} title={locale.t("stats.title")} class="ms-2 text-lg">
+                <span aria-hidden="true">{"📊"}</span>
+            </button>
+        </>
+    };
+    let rooms = html! {
+        <div role="list" aria-label={locale.t("sidebar.rooms")} class="mx-3 mb-3 text-sm">
+            {for std::iter::once(None).chain(props.rooms.iter().cloned().map(Some)).map(|room| {
+                let current = room == props.current_room;
+                let unread = props.unread.get(&room).copied().unwrap_or(0);
+                let mentioned = props.mentioned.contains(&room);
+                let label = match &room {
+                    Some(name) => html! { <><span aria-label={locale.t("sidebar.private")}>{"🔒 "}</span>{format!("#{}", name)}</> },
+                    None => html! { {locale.t("sidebar.lobby")} },
+                };
+                let onclick = props.on_select_room.reform({
+                    let room = room.clone();
+                    move |_| room.clone()
+                });
+                html! {
+                    <div key={room.clone().unwrap_or_default()} role="listitem">
+                        <button {onclick} aria-current={current.then_some("true")}
+                            class={classes!("flex", "items-center", "w-full", "text-start", "px-2", "py-1", "rounded", (current || unread > 0).then_some("font-semibold"))}>
+                            <span class="grow truncate">{label}</span>
+                            if unread > 0 {
+                                <span aria-label={locale.format(if mentioned { "sidebar.unread_mentioned" } else { "sidebar.unread" }, &[("count", &unread.to_string())])}
+                                    class={classes!("ms-2", "min-w-[1.25rem]", "px-1.5", "rounded-full", "text-white", "text-xs", "text-center", if mentioned { "bg-red-600" } else { "bg-gray-500" })}>
+                                    {if unread > 99 { "99+".to_string() } else { unread.to_string() }}
+                                </span>
+                            }
+                        </button>
+                    </div>
+                }
+            })}
+        </div>
+    };
+    let users = html! {
+        <>
+            <div class="px-3">
+                <input {oninput} {onkeydown} id="user-filter" value={(*filter).clone()} type="search" placeholder={locale.t("sidebar.filter")} aria-label={locale.t("sidebar.filter")} class="block w-full py-1 px-3 text-sm rounded-full outline-none"/>
+            </div>
+            if matching.is_empty() && !props.users.is_empty() {
+                <div class="m-3 text-sm text-gray-400">{locale.t("sidebar.no_match")}</div>
+            }
+            <div role="list" aria-label={locale.t("sidebar.users")}>
+                {
+                    matching.into_iter().map(|(u, range)| {
+                        let is_me = u.name == props.current_user;
+                        let status = if is_me && *editing_status {
+                            html! {
+                                <input ref={status_input.clone()} value={u.status.clone()} onkeydown={status_keydown.clone()} onblur={stop_editing.clone()}
+                                    aria-label={locale.t("sidebar.your_status")} placeholder={locale.t("sidebar.status_placeholder")} class="w-full text-xs rounded outline-none bg-gray-100 px-1"/>
+                            }
+                        } else if u.status.is_empty() {
+                            html! { <span class="italic">{if is_me { locale.t("sidebar.set_status") } else { "" }}</span> }
+                        } else {
+                            html! { <span title={u.status.clone()}>{truncate(&u.status, STATUS_DISPLAY_LEN)}</span> }
+                        };
+                        html!{
+                            <div key={u.name.clone()} role="listitem" onclick={is_me.then(|| start_editing.clone())} class={classes!("flex", "m-3", card, "rounded-lg", "p-2", is_me.then_some("cursor-pointer"))}>
+                                <div class="relative self-start">
+                                    <Avatar name={u.name.clone()} url={u.avatar.clone()} size={48} class="cursor-pointer" onclick={avatar_click(&u.name)}/>
+                                    {presence_dot(u)}
+                                </div>
+                                <div class="flex-grow p-3">
+                                    <div class="flex text-xs justify-between">
+                                        <div class="font-semibold" style={format!("color: {};", name_color(&u.name, theme.palette()))}>
+                                            {highlight(&u.name, range)}
+                                            if is_me {
+                                                <span class="text-gray-400">{" "}{locale.t("sidebar.you")}</span>
+                                            }
+                                            if u.bot {
+                                                <span class="text-gray-400">{" [BOT]"}</span>
+                                            }
+                                            if u.role == Role::Moderator {
+                                                <span class="ms-1 px-1 rounded bg-violet-100 text-violet-700" title={locale.t("moderation.badge")}>{locale.t("moderation.badge_short")}</span>
+                                            }
+                                        </div>
+                                        if !is_me && !u.bot {
+                                            <button onclick={call_click(&u.name)} aria-label={locale.format("sidebar.video_call_user", &[("name", &u.name)])} title={locale.t("sidebar.video_call")}>
+                                                <span aria-hidden="true">{"📹"}</span>
+                                            </button>
+                                        }
+                                    </div>
+                                    <div class="text-xs text-gray-400 break-words">
+                                        {status}
+                                    </div>
+                                    if is_me {
+                                        <select onchange={presence_change.clone()} onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+                                            aria-label={locale.t("sidebar.availability")} class="mt-1 text-xs rounded bg-transparent">
+                                            <option value="online" selected={u.presence != Presence::DoNotDisturb}>{locale.t("presence.available")}</option>
+                                            <option value="dnd" selected={u.presence == Presence::DoNotDisturb}>{locale.t("presence.do_not_disturb")}</option>
+                                        </select>
+                                    }
+                                </div>
+                            </div>
+                        }
+                    }).collect::<Html>()
+                }
+            </div>
+        </>
+    };
+    // Collapsed, only the avatars are left, named in their tooltips.
+    let avatars = html! {
+        <div role="list" aria-label={locale.t("sidebar.users")}>
+            {for props.users.values().map(|u| html! {
+                <div key={u.name.clone()} role="listitem" title={u.name.clone()} class="relative m-3 w-12">
+                    <Avatar name={u.name.clone()} url={u.avatar.clone()} size={48} class="cursor-pointer" onclick={avatar_click(&u.name)}/>
+                    {presence_dot(u)}
+                </div>
+            })}
+        </div>
+    };
+    let sections = vec![
+        SidebarSection {
+            id: "rooms".into(),
+            title: locale.t("sidebar.rooms").to_string().into(),
+            content: rooms,
+            icons: None,
+        },
+        SidebarSection {
+            id: "users".into(),
+            title: locale.t("sidebar.users").to_string().into(),
+            content: users,
+            icons: Some(avatars),
+        },
+    ];
+
+    html! {
+        <Sidebar {sections} {header} class={classes!(panel)}/>
+    }
+}
+
+/// Locale key for the name of `presence`.
+fn presence_key(presence: Presence) -> &'static str {
+    match presence {
+        Presence::Online => "presence.online",
+        Presence::Away => "presence.away",
+        Presence::DoNotDisturb => "presence.do_not_disturb",
+    }
+}
+
+fn presence_color(presence: Presence) -> &'static str {
+    match presence {
+        Presence::Online => "bg-green-500",
+        Presence::Away => "bg-yellow-400",
+        Presence::DoNotDisturb => "bg-red-500",
+    }
+}
+
+/// Orders users alphabetically with `me` pinned first.
+pub fn sort_users(users: &mut Users, me: &str) {
+    users.sort_by(|_, a, _, b| match (a.name == me, b.name == me) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => compare_usernames(&a.name, &b.name),
+    });
+}
+
+/// Case- and accent-insensitive ordering, so "Émile" sorts next to "emma"
+/// rather than after "zoe". Falls back to a plain comparison to keep the
+/// order total and stable between refreshes.
+pub fn compare_usernames(a: &str, b: &str) -> Ordering {
+    let key = |s: &str| -> Vec<char> {
+        s.chars()
+            .flat_map(char::to_lowercase)
+            .map(fold_accent)
+            .collect()
+    };
+    key(a).cmp(&key(b)).then_with(|| a.cmp(b))
+}
+
+fn fold_accent(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è'..='ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ì'..='ï' | 'ī' | 'į' => 'i',
+        'ł' => 'l',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ř' => 'r',
+        'ś' | 'š' => 's',
+        'ť' => 't',
+        'ù'..='ü' | 'ū' | 'ů' | 'ű' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => c,
+    }
+}
+
+fn highlight(name: &str, range: Option<(usize, usize)>) -> Html {
+    match range {
+        Some((start, end)) => html! {
+            <>
+                {&name[..start]}
+                <mark>{&name[start..end]}</mark>
+                {&name[end..]}
+            </>
+        },
+        None => html! { {name} },
+    }
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle` in
+/// `haystack`. Compares char by char so the range always lands on char
+/// boundaries of the original string, even when lowercasing changes lengths.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut pending = needle.as_slice();
+        for (offset, c) in haystack[start..].char_indices() {
+            for lower in c.to_lowercase() {
+                match pending.split_first() {
+                    Some((n, rest)) if *n == lower => pending = rest,
+                    _ => return None,
+                }
+            }
+            if pending.is_empty() {
+                return Some((start, start + offset + c.len_utf8()));
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(users: &Users) -> Vec<&str> {
+        users.keys().map(String::as_str).collect()
+    }
+
+    fn user(name: &str) -> UserProfile {
+        UserProfile::new(name, Default::default(), 0.0)
+    }
+
+    #[test]
+    fn compares_ignoring_case_and_accents() {
+        assert_eq!(compare_usernames("bob", "Alice"), Ordering::Greater);
+        assert_eq!(compare_usernames("Émile", "emma"), Ordering::Less);
+        assert_eq!(compare_usernames("zoe", "Émile"), Ordering::Greater);
+        assert_ne!(compare_usernames("bob", "Bob"), Ordering::Equal);
+    }
+
+    #[test]
+    fn pins_current_user_first() {
+        let mut users: Users = ["carol", "zed", "Alice", "bob"]
+            .into_iter()
+            .map(|name| (name.to_string(), user(name)))
+            .collect();
+        sort_users(&mut users, "zed");
+        assert_eq!(names(&users), ["zed", "Alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn finds_case_insensitive_match() {
+        assert_eq!(find_ignore_case("AliceBob", "cEb"), Some((3, 6)));
+        assert_eq!(find_ignore_case("alice", "bob"), None);
+    }
+
+    #[test]
+    fn keeps_char_boundaries() {
+        let name = "Zoë-Élan";
+        let (start, end) = find_ignore_case(name, "éla").unwrap();
+        assert_eq!(&name[start..end], "Éla");
+    }
+}
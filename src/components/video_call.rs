@@ -0,0 +1,85 @@
+use web_sys::{HtmlMediaElement, MediaStream};
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+
+#[derive(Properties, PartialEq)]
+pub struct IncomingCallProps {
+    pub from: String,
+    pub on_accept: Callback<()>,
+    pub on_decline: Callback<()>,
+}
+
+#[function_component(IncomingCall)]
+pub fn incoming_call(props: &IncomingCallProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    html! {
+        <div class="fixed inset-0 z-50 flex items-center justify-center bg-black bg-opacity-50">
+            <div role="alertdialog" aria-label={locale.t("call.incoming")} class="w-72 p-6 bg-white text-black rounded-lg shadow-lg text-center">
+                <div class="text-lg">{"📹 "}{locale.format("call.calling_you", &[("name", &props.from)])}</div>
+                <div class="flex gap-2 mt-4">
+                    <button onclick={props.on_accept.reform(|_: MouseEvent| ())} class="flex-1 px-3 py-1 rounded-full bg-green-600 text-white">{locale.t("call.accept")}</button>
+                    <button onclick={props.on_decline.reform(|_: MouseEvent| ())} class="flex-1 px-3 py-1 rounded-full bg-red-600 text-white">{locale.t("call.decline")}</button>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VideoCallProps {
+    pub peer: String,
+    pub local: MediaStream,
+    /// Not known until the peer's first track arrives.
+    #[prop_or_default]
+    pub remote: Option<MediaStream>,
+    pub on_hangup: Callback<()>,
+}
+
+#[function_component(VideoCall)]
+pub fn video_call(props: &VideoCallProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let local_video = use_node_ref();
+    let remote_video = use_node_ref();
+
+    // `srcObject` has no attribute form, so streams are attached by hand.
+    {
+        let local_video = local_video.clone();
+        use_effect_with_deps(
+            move |local| {
+                if let Some(video) = local_video.cast::<HtmlMediaElement>() {
+                    video.set_src_object(Some(local));
+                }
+                || ()
+            },
+            props.local.clone(),
+        );
+    }
+    {
+        let remote_video = remote_video.clone();
+        use_effect_with_deps(
+            move |remote| {
+                if let Some(video) = remote_video.cast::<HtmlMediaElement>() {
+                    video.set_src_object(remote.as_ref());
+                }
+                || ()
+            },
+            props.remote.clone(),
+        );
+    }
+
+    html! {
+        <div role="dialog" aria-label={locale.format("call.with", &[("name", &props.peer)])} class="fixed bottom-4 end-4 z-50 w-80 p-2 bg-gray-900 rounded-lg shadow-lg">
+            <div class="relative">
+                <video ref={remote_video} autoplay=true playsinline=true class="w-full rounded bg-black"></video>
+                <video ref={local_video} autoplay=true playsinline=true muted=true class="absolute bottom-2 end-2 w-20 rounded border border-white"></video>
+                if props.remote.is_none() {
+                    <div class="absolute inset-0 flex items-center justify-center text-sm text-white">
+                        {locale.format("call.calling", &[("name", &props.peer)])}
+                    </div>
+                }
+            </div>
+            <button onclick={props.on_hangup.reform(|_: MouseEvent| ())} class="w-full mt-2 px-3 py-1 rounded-full bg-red-600 text-white">{locale.t("call.hang_up")}</button>
+        </div>
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Element, HtmlElement};
+use yew::prelude::*;
+use yew::virtual_dom::Key;
+
+#[derive(Properties, PartialEq)]
+pub struct VirtualListProps<T: PartialEq> {
+    pub items: Vec<T>,
+    /// Height used for rows that haven't been measured yet, in pixels.
+    pub item_height_estimate: f32,
+    /// Rows rendered beyond each edge of the viewport.
+    #[prop_or(5)]
+    pub overscan: usize,
+    pub render_item: RenderItem<T>,
+    /// Stick to the newest row, as a chat log does.
+    #[prop_or_default]
+    pub follow: bool,
+    /// Brings a row to the middle of the viewport.
+    #[prop_or_default]
+    pub scroll_to: Option<ScrollRequest>,
+    /// Keeps a row's top where it is on screen while its height changes,
+    /// as when a long message is expanded.
+    #[prop_or_default]
+    pub anchor: Option<ScrollRequest>,
+    /// Told which rows are in the viewport whenever that changes.
+    #[prop_or_default]
+    pub on_visible: Callback<Range<usize>>,
+    /// Told when the reader scrolls to or away from the bottom.
+    #[prop_or_default]
+    pub on_at_bottom: Callback<bool>,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+/// A row to scroll to. Bump `generation` to go back to the same row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollRequest {
+    pub index: usize,
+    pub generation: u32,
+}
+
+/// What a row is known by. It must stay with the item as rows are added and
+/// dropped around it: the row's state and measured height follow the key.
+pub trait ListItem {
+    fn key(&self) -> Key;
+}
+
+/// Turns a list item into its row. Yew 0.19 callbacks can't return a value,
+/// so this plays that role; like a callback it compares by identity.
+pub struct RenderItem<T>(Rc<dyn Fn(T) -> Html>);
+
+impl<T> RenderItem<T> {
+    fn emit(&self, item: T) -> Html {
+        (self.0)(item)
+    }
+}
+
+impl<T, F: Fn(T) -> Html + 'static> From<F> for RenderItem<T> {
+    fn from(render: F) -> Self {
+        Self(Rc::new(render))
+    }
+}
+
+impl<T> PartialEq for RenderItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// How close to the end counts as "at the bottom", in pixels.
+const BOTTOM_SLACK: f32 = 40.0;
+
+/// Renders only the rows in and around the viewport. Spacers above and below
+/// stand in for the rest, sized from measured heights where known and the
+/// estimate elsewhere, so the scrollbar stays honest.
+///
+/// With `follow` set the list sticks to the bottom as rows are added, and
+/// shows a "jump to latest" pill when the reader has scrolled away.
+#[function_component(VirtualList)]
+pub fn virtual_list<T>(props: &VirtualListProps<T>) -> Html
+where
+    T: ListItem + PartialEq + Clone + 'static,
+{
+    let viewport = use_node_ref();
+    let scroll_top = use_state(|| 0.0_f32);
+    let viewport_height = use_state(|| props.item_height_estimate * 10.0);
+    // Measured heights by row key, so they stay with their rows when older
+    // ones are dropped from the front.
+    let measured = use_mut_ref(HashMap::<Key, f32>::new);
+    let rows = use_mut_ref(Vec::<(Key, NodeRef)>::new);
+    let at_bottom = use_mut_ref(|| true);
+    // The newest row when the reader last saw the list, to spot new ones.
+    let seen_last = use_mut_ref(|| None::<Key>);
+    let unseen = use_state(|| false);
+    // The row asked for by `scroll_to`, until it has been laid out in place.
+    let target = use_mut_ref(|| None::<Key>);
+    // The row held in place by `anchor`, and its top within the viewport.
+    let anchored = use_mut_ref(|| None::<(Key, f32)>);
+    // Bumped when a measurement changes the layout.
+    let layout_version = use_state(|| 0_u32);
+
+    let keys: Rc<Vec<Key>> = Rc::new(props.items.iter().map(ListItem::key).collect());
+    let heights = {
+        let mut measured = measured.borrow_mut();
+        if measured.len() > keys.len() {
+            let live: HashSet<&Key> = keys.iter().collect();
+            measured.retain(|key, _| live.contains(key));
+        }
+        heights(&keys, &measured, props.item_height_estimate)
+    };
+    let offsets = offsets(&heights);
+    let range = visible_range(&offsets, *scroll_top, *viewport_height, props.overscan);
+    let in_view = visible_range(&offsets, *scroll_top, *viewport_height, 0);
+
+    {
+        let on_visible = props.on_visible.clone();
+        use_effect_with_deps(
+            move |in_view| {
+                on_visible.emit(in_view.clone());
+                || ()
+            },
+            in_view,
+        );
+    }
+
+    // Images don't know their height until they load, and `load` doesn't
+    // bubble, so catch it on the way down and measure again.
+    {
+        let viewport = viewport.clone();
+        let layout_version = layout_version.clone();
+        use_effect_with_deps(
+            move |_| {
+                let on_load = Closure::wrap(Box::new(move || {
+                    layout_version.set(layout_version.wrapping_add(1));
+                }) as Box<dyn FnMut()>);
+                let target = viewport.cast::<Element>();
+                if let Some(target) = &target {
+                    let _ = target.add_event_listener_with_callback_and_bool(
+                        "load",
+                        on_load.as_ref().unchecked_ref(),
+                        true,
+                    );
+                }
+                move || {
+                    if let Some(target) = target {
+                        let _ = target.remove_event_listener_with_callback_and_bool(
+                            "load",
+                            on_load.as_ref().unchecked_ref(),
+                            true,
+                        );
+                    }
+                }
+            },
+            (),
+        );
+    }
+
+    {
+        let target = target.clone();
+        let at_bottom = at_bottom.clone();
+        let keys = keys.clone();
+        use_effect_with_deps(
+            move |scroll_to| {
+                if let Some(key) = scroll_to.and_then(|r| keys.get(r.index)) {
+                    *target.borrow_mut() = Some(key.clone());
+                    *at_bottom.borrow_mut() = false;
+                }
+                || ()
+            },
+            props.scroll_to,
+        );
+    }
+
+    // Note where the anchor row sits before it is measured again.
+    {
+        let anchored = anchored.clone();
+        let at_bottom = at_bottom.clone();
+        let top = offsets.clone();
+        let keys = keys.clone();
+        let scroll_top = *scroll_top;
+        use_effect_with_deps(
+            move |anchor| {
+                if let Some(index) = anchor.map(|r| r.index).filter(|&i| i < keys.len()) {
+                    *anchored.borrow_mut() = Some((keys[index].clone(), top[index] - scroll_top));
+                    *at_bottom.borrow_mut() = false;
+                }
+                || ()
+            },
+            props.anchor,
+        );
+    }
+
+    // Measure what was just rendered and lay out again if anything moved.
+    {
+        let measured = measured.clone();
+        let keys = keys.clone();
+        let estimate = props.item_height_estimate;
+        let rows = rows.clone();
+        let viewport = viewport.clone();
+        let viewport_height = viewport_height.clone();
+        let layout_version = layout_version.clone();
+        let at_bottom = at_bottom.clone();
+        let target = target.clone();
+        let anchored = anchored.clone();
+        let scroll_top = scroll_top.clone();
+        let seen_last = seen_last.clone();
+        let unseen = unseen.clone();
+        let follow = props.follow;
+        use_effect(move || {
+            let mut changed = false;
+            let mut measured = measured.borrow_mut();
+            for (key, row) in rows.borrow().iter() {
+                if let Some(row) = row.cast::<HtmlElement>() {
+                    let height = row.offset_height() as f32;
+                    let known = measured.insert(key.clone(), height);
+                    if (height - known.unwrap_or(estimate)).abs() > 0.5 {
+                        changed = true;
+                    }
+                }
+            }
+            let heights = self::heights(&keys, &measured, estimate);
+            let index_of = |key: &Key| keys.iter().position(|k| k == key);
+            if let Some(viewport) = viewport.cast::<Element>() {
+                let measured = viewport.client_height() as f32;
+                if (measured - *viewport_height).abs() > 0.5 {
+                    viewport_height.set(measured);
+                }
+                // Rows above the target may change height as they come into
+                // view, so keep steering until it is rendered and nothing moved.
+                let steer = target.borrow().as_ref().and_then(index_of);
+                if let Some(index) = steer {
+                    let top: f32 = heights[..index].iter().sum();
+                    let centered = (top - (measured - heights[index]) / 2.0).max(0.0);
+                    viewport.set_scroll_top(centered as i32);
+                    if (centered - *scroll_top).abs() > 0.5 {
+                        scroll_top.set(centered);
+                    }
+                    let in_view = rows.borrow().iter().any(|(key, _)| *key == keys[index]);
+                    if in_view && !changed {
+                        *target.borrow_mut() = None;
+                    }
+                } else if let Some((index, within)) = anchored
+                    .borrow()
+                    .as_ref()
+                    .and_then(|(key, within)| Some((index_of(key)?, *within)))
+                {
+                    let top: f32 = heights[..index].iter().sum();
+                    let kept = (top - within).max(0.0);
+                    viewport.set_scroll_top(kept as i32);
+                    if (kept - *scroll_top).abs() > 0.5 {
+                        scroll_top.set(kept);
+                    }
+                } else if follow {
+                    if *at_bottom.borrow() {
+                        viewport.set_scroll_top(viewport.scroll_height());
+                    } else if rows_added(seen_last.borrow().as_ref(), &keys) && !*unseen {
+                        unseen.set(true);
+                    }
+                }
+            }
+            *seen_last.borrow_mut() = keys.last().cloned();
+            if changed {
+                layout_version.set(layout_version.wrapping_add(1));
+            } else {
+                anchored.borrow_mut().take();
+            }
+            || ()
+        });
+    }
+
+    let onscroll = {
+        let scroll_top = scroll_top.clone();
+        let unseen = unseen.clone();
+        let on_at_bottom = props.on_at_bottom.clone();
+        Callback::from(move |e: Event| {
+            let viewport: Element = e.target_unchecked_into();
+            let top = viewport.scroll_top() as f32;
+            let bottom = (viewport.scroll_height() - viewport.client_height()) as f32;
+            let is_at_bottom = top >= bottom - BOTTOM_SLACK;
+            if is_at_bottom != *at_bottom.borrow() {
+                on_at_bottom.emit(is_at_bottom);
+            }
+            *at_bottom.borrow_mut() = is_at_bottom;
+            if is_at_bottom && *unseen {
+                unseen.set(false);
+            }
+            scroll_top.set(top);
+        })
+    };
+
+    let jump_to_latest = {
+        let viewport = viewport.clone();
+        Callback::from(move |_| {
+            if let Some(viewport) = viewport.cast::<Element>() {
+                viewport.set_scroll_top(viewport.scroll_height());
+            }
+        })
+    };
+
+    let mut rendered = Vec::with_capacity(range.len());
+    let items = range.clone().map(|index| {
+        let row = NodeRef::default();
+        let key = keys[index].clone();
+        rendered.push((key.clone(), row.clone()));
+        // `flow-root` keeps the row's margins inside its measured height.
+        html! {
+            <div {key} ref={row} style="display: flow-root;">
+                {props.render_item.emit(props.items[index].clone())}
+            </div>
+        }
+    });
+    let items: Html = items.collect();
+    *rows.borrow_mut() = rendered;
+
+    let total = offsets[offsets.len() - 1];
+    let above = offsets[range.start];
+    let below = total - offsets[range.end];
+
+    html! {
+        <div class={classes!("relative", "min-h-0", props.class.clone())}>
+            <div ref={viewport} {onscroll} class="h-full overflow-auto">
+                <div style={format!("height: {}px;", above)}></div>
+                {items}
+                <div style={format!("height: {}px;", below)}></div>
+            </div>
+            if *unseen {
+                <button onclick={jump_to_latest} class="absolute bottom-3 left-1/2 -translate-x-1/2 transform px-3 py-1 rounded-full bg-blue-600 text-white text-xs shadow">
+                    {"↓ Jump to latest"}
+                </button>
+            }
+        </div>
+    }
+}
+
+/// Each row's height: as last measured, or the estimate if it hasn't been.
+fn heights(keys: &[Key], measured: &HashMap<Key, f32>, estimate: f32) -> Vec<f32> {
+    keys.iter()
+        .map(|key| measured.get(key).copied().unwrap_or(estimate))
+        .collect()
+}
+
+/// Whether rows were added after `last`, the newest row before. Rows
+/// dropped from the front, as when history is capped, or taken from the
+/// end don't count.
+fn rows_added(last: Option<&Key>, keys: &[Key]) -> bool {
+    match (last, keys.last()) {
+        (Some(last), Some(newest)) => last != newest && keys.iter().rev().any(|key| key == last),
+        (None, newest) => newest.is_some(),
+        (Some(_), None) => false,
+    }
+}
+
+/// Top of each row, followed by the total height.
+fn offsets(heights: &[f32]) -> Vec<f32> {
+    let mut offsets = Vec::with_capacity(heights.len() + 1);
+    let mut top = 0.0;
+    offsets.push(top);
+    for height in heights {
+        top += height;
+        offsets.push(top);
+    }
+    offsets
+}
+
+/// The rows overlapping `scroll_top..scroll_top + viewport`, widened by
+/// `overscan` on both sides.
+fn visible_range(offsets: &[f32], scroll_top: f32, viewport: f32, overscan: usize) -> Range<usize> {
+    let count = offsets.len() - 1;
+    let first = offsets[1..].partition_point(|&bottom| bottom <= scroll_top);
+    let end = offsets[..count].partition_point(|&top| top < scroll_top + viewport);
+    first.saturating_sub(overscan)..(end + overscan).min(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_accumulate_heights() {
+        assert_eq!(offsets(&[10.0, 20.0, 5.0]), [0.0, 10.0, 30.0, 35.0]);
+        assert_eq!(offsets(&[]), [0.0]);
+    }
+
+    #[test]
+    fn renders_only_visible_rows() {
+        let offsets = offsets(&[50.0; 100]);
+        assert_eq!(visible_range(&offsets, 0.0, 200.0, 0), 0..4);
+        assert_eq!(visible_range(&offsets, 1000.0, 200.0, 0), 20..24);
+        assert_eq!(visible_range(&offsets, 1010.0, 200.0, 0), 20..25);
+    }
+
+    #[test]
+    fn overscan_is_clamped_to_the_list() {
+        let offsets = offsets(&[50.0; 100]);
+        assert_eq!(visible_range(&offsets, 1000.0, 200.0, 5), 15..29);
+        assert_eq!(visible_range(&offsets, 0.0, 200.0, 5), 0..9);
+        assert_eq!(visible_range(&offsets, 4900.0, 200.0, 5), 93..100);
+    }
+
+    #[test]
+    fn empty_list_renders_nothing() {
+        assert_eq!(visible_range(&offsets(&[]), 0.0, 200.0, 5), 0..0);
+    }
+
+    #[test]
+    fn large_history_mounts_a_screenful() {
+        let offsets = offsets(&[120.0; 5000]);
+        let bottom = offsets[5000] - 600.0;
+        for scroll_top in [0.0, 300_000.0, bottom] {
+            let range = visible_range(&offsets, scroll_top, 600.0, 5);
+            assert!(range.len() <= 600 / 120 + 1 + 2 * 5, "{:?}", range);
+        }
+        assert_eq!(visible_range(&offsets, bottom, 600.0, 5).end, 5000);
+    }
+
+    fn keys(names: &[&str]) -> Vec<Key> {
+        names.iter().map(|&name| Key::from(name)).collect()
+    }
+
+    #[test]
+    fn heights_follow_their_rows_when_the_front_is_dropped() {
+        let measured = HashMap::from([(Key::from("b"), 80.0), (Key::from("c"), 30.0)]);
+        assert_eq!(
+            heights(&keys(&["a", "b", "c"]), &measured, 50.0),
+            [50.0, 80.0, 30.0]
+        );
+        assert_eq!(
+            heights(&keys(&["b", "c", "d"]), &measured, 50.0),
+            [80.0, 30.0, 50.0]
+        );
+    }
+
+    #[test]
+    fn new_rows_are_spotted_by_the_newest_key() {
+        let a = Key::from("a");
+        let c = Key::from("c");
+        // At the cap the length stays put as the oldest row goes.
+        assert!(rows_added(Some(&c), &keys(&["b", "c", "d"])));
+        assert!(!rows_added(Some(&c), &keys(&["a", "b", "c"])));
+        // The newest row was deleted.
+        assert!(!rows_added(Some(&c), &keys(&["a", "b"])));
+        assert!(!rows_added(Some(&a), &[]));
+        assert!(rows_added(None, &keys(&["a"])));
+    }
+
+    #[test]
+    fn uneven_heights() {
+        let offsets = offsets(&[100.0, 10.0, 10.0, 300.0, 10.0]);
+        assert_eq!(visible_range(&offsets, 115.0, 20.0, 0), 2..4);
+    }
+}
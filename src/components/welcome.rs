@@ -0,0 +1,39 @@
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+
+#[derive(Properties, PartialEq)]
+pub struct WelcomeBannerProps {
+    pub username: String,
+    /// Replaces the built-in orientation.
+    #[prop_or_default]
+    pub content: Option<Html>,
+    pub on_dismiss: Callback<()>,
+}
+
+/// Greets a user on their first visit with a few pointers to get started.
+#[function_component(WelcomeBanner)]
+pub fn welcome_banner(props: &WelcomeBannerProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let content = props.content.clone().unwrap_or_else(|| {
+        html! {
+            <ul class="list-disc ms-5">
+                <li>{locale.t("welcome.send")}</li>
+                <li>{locale.t("welcome.settings")}</li>
+                <li>{locale.t("welcome.avatar")}</li>
+                <li>{locale.t("welcome.shortcuts")}</li>
+            </ul>
+        }
+    });
+    html! {
+        <div role="region" aria-label={locale.t("welcome.label")} class="w-full px-3 py-2 flex items-start text-sm bg-green-50 text-green-900">
+            <div class="grow">
+                <div class="font-semibold">{locale.format("welcome.title", &[("name", &props.username)])}</div>
+                {content}
+            </div>
+            <button onclick={props.on_dismiss.reform(|_| ())} class="ms-2 px-3 py-1 rounded-full bg-green-600 text-white shrink-0">
+                {locale.t("welcome.dismiss")}
+            </button>
+        </div>
+    }
+}
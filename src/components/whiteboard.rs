@@ -0,0 +1,111 @@
+use std::rc::Rc;
+
+use web_sys::Element;
+use yew::prelude::*;
+
+use crate::i18n::Locale;
+use crate::services::whiteboard::{path_data, Board, HEIGHT, WIDTH};
+
+/// Something done on a whiteboard, for the chat to apply and broadcast.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Draw {
+    Stroke { board: String, d: String },
+    Undo { board: String },
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WhiteboardProps {
+    pub id: String,
+    pub board: Rc<Board>,
+    pub on_draw: Callback<Draw>,
+}
+
+/// A shared canvas in a message bubble. A stroke is sent once the pointer
+/// is lifted; Ctrl+Z takes back our newest one.
+#[function_component(Whiteboard)]
+pub fn whiteboard(props: &WhiteboardProps) -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    let canvas = use_node_ref();
+    // The stroke being drawn, in canvas units.
+    let points = use_state(Vec::<(f64, f64)>::new);
+
+    let to_canvas = {
+        let canvas = canvas.clone();
+        move |e: &MouseEvent| {
+            let rect = canvas.cast::<Element>()?.get_bounding_client_rect();
+            if rect.width() == 0.0 || rect.height() == 0.0 {
+                return None;
+            }
+            Some((
+                (e.client_x() as f64 - rect.left()) * WIDTH / rect.width(),
+                (e.client_y() as f64 - rect.top()) * HEIGHT / rect.height(),
+            ))
+        }
+    };
+    let onpointerdown = {
+        let points = points.clone();
+        let canvas = canvas.clone();
+        let to_canvas = to_canvas.clone();
+        Callback::from(move |e: PointerEvent| {
+            if let Some(canvas) = canvas.cast::<Element>() {
+                // Keep the stroke going if the pointer strays off the canvas.
+                let _ = canvas.set_pointer_capture(e.pointer_id());
+            }
+            points.set(to_canvas(&e).into_iter().collect());
+        })
+    };
+    let onpointermove = {
+        let points = points.clone();
+        Callback::from(move |e: PointerEvent| {
+            if points.is_empty() {
+                return;
+            }
+            if let Some(point) = to_canvas(&e) {
+                let mut next = (*points).clone();
+                next.push(point);
+                points.set(next);
+            }
+        })
+    };
+    let onpointerup = {
+        let points = points.clone();
+        let on_draw = props.on_draw.clone();
+        let board = props.id.clone();
+        Callback::from(move |_: PointerEvent| {
+            if points.len() > 1 {
+                on_draw.emit(Draw::Stroke {
+                    board: board.clone(),
+                    d: path_data(&points),
+                });
+            }
+            points.set(Vec::new());
+        })
+    };
+    let onpointercancel = onpointerup.clone();
+    let onkeydown = {
+        let on_draw = props.on_draw.clone();
+        let board = props.id.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if (e.ctrl_key() || e.meta_key()) && e.key().eq_ignore_ascii_case("z") {
+                e.prevent_default();
+                on_draw.emit(Draw::Undo {
+                    board: board.clone(),
+                });
+            }
+        })
+    };
+
+    html! {
+        <svg ref={canvas} viewBox={format!("0 0 {} {}", WIDTH, HEIGHT)} tabindex="0" role="img"
+            aria-label={locale.t("whiteboard.canvas")}
+            {onpointerdown} {onpointermove} {onpointerup} {onpointercancel} {onkeydown}
+            class="whiteboard mt-2 w-full rounded bg-white">
+            { for props.board.strokes().map(|stroke| html! {
+                <path key={format!("{}/{}", stroke.from, stroke.id)} d={stroke.d.clone()}/>
+            }) }
+            if points.len() > 1 {
+                <path d={path_data(&points)}/>
+            }
+        </svg>
+    }
+}
@@ -0,0 +1,188 @@
+//! Parser for unified diffs shared in chat.
+//!
+//! Like [`syntax`](crate::syntax) this only uses `core` and borrows from the
+//! input, so it can be unit-tested natively.
+
+use crate::syntax::Language;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    /// `diff --git`, `index`, `---` and `+++` lines before the first hunk.
+    Header,
+    /// An `@@ -a,b +c,d @@` hunk header.
+    Hunk,
+    Context,
+    Added,
+    Removed,
+    /// `\ No newline at end of file` and anything else we don't recognise.
+    Meta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffLine<'a> {
+    pub kind: LineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// The line without its `+`, `-` or ` ` marker.
+    pub text: &'a str,
+}
+
+/// Splits a unified diff into lines and numbers them from the hunk headers.
+pub fn parse(diff: &str) -> Vec<DiffLine<'_>> {
+    let mut old_line = 0;
+    let mut new_line = 0;
+    let mut in_hunk = false;
+    diff.lines()
+        .map(|line| {
+            let (kind, text) = if line.starts_with("@@") {
+                match hunk_start(line) {
+                    Some((old, new)) => {
+                        old_line = old;
+                        new_line = new;
+                        in_hunk = true;
+                        (LineKind::Hunk, line)
+                    }
+                    None => (LineKind::Meta, line),
+                }
+            } else if !in_hunk || line.starts_with("diff ") {
+                in_hunk = false;
+                (LineKind::Header, line)
+            } else if let Some(text) = line.strip_prefix('+') {
+                (LineKind::Added, text)
+            } else if let Some(text) = line.strip_prefix('-') {
+                (LineKind::Removed, text)
+            } else if let Some(text) = line.strip_prefix(' ') {
+                (LineKind::Context, text)
+            } else if line.is_empty() {
+                // Some editors strip the trailing space of blank context lines.
+                (LineKind::Context, line)
+            } else {
+                (LineKind::Meta, line)
+            };
+
+            let (old, new) = match kind {
+                LineKind::Context => (Some(old_line), Some(new_line)),
+                LineKind::Removed => (Some(old_line), None),
+                LineKind::Added => (None, Some(new_line)),
+                _ => (None, None),
+            };
+            old_line += old.is_some() as u32;
+            new_line += new.is_some() as u32;
+            DiffLine {
+                kind,
+                old_line: old,
+                new_line: new,
+                text,
+            }
+        })
+        .collect()
+}
+
+/// Reads the starting line numbers out of `@@ -12,5 +12,7 @@`.
+fn hunk_start(line: &str) -> Option<(u32, u32)> {
+    let mut parts = line.strip_prefix("@@ ")?.split(' ');
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    let start = |range: &str| range.split(',').next()?.parse().ok();
+    Some((start(old)?, start(new)?))
+}
+
+/// Whether pasted text is a unified diff rather than an ordinary message.
+pub fn looks_like_diff(text: &str) -> bool {
+    let starts_like_diff = text.starts_with("diff ") || text.starts_with("--- ");
+    starts_like_diff && text.lines().any(|line| hunk_start(line).is_some())
+}
+
+/// The file the diff changes, from its `+++ b/...` header.
+pub fn file_name(diff: &str) -> Option<&str> {
+    diff.lines()
+        .find_map(|line| line.strip_prefix("+++ "))
+        .map(|path| path.strip_prefix("b/").unwrap_or(path))
+        .map(|path| path.split('\t').next().unwrap_or(path))
+}
+
+/// Picks a highlighter from the changed file's extension.
+pub fn language(diff: &str) -> Option<Language> {
+    let (_, extension) = file_name(diff)?.rsplit_once('.')?;
+    Language::from_tag(extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,4 +10,5 @@ mod services;
+ fn main() {
+-    old();
++    new();
++    more();
+ }
+\\ No newline at end of file";
+
+    #[test]
+    fn classifies_lines() {
+        let kinds: Vec<LineKind> = parse(DIFF).iter().map(|l| l.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                LineKind::Header,
+                LineKind::Header,
+                LineKind::Header,
+                LineKind::Header,
+                LineKind::Hunk,
+                LineKind::Context,
+                LineKind::Removed,
+                LineKind::Added,
+                LineKind::Added,
+                LineKind::Context,
+                LineKind::Meta,
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_lines_from_hunk_header() {
+        let lines = parse(DIFF);
+        let numbers: Vec<(Option<u32>, Option<u32>)> = lines[5..10]
+            .iter()
+            .map(|l| (l.old_line, l.new_line))
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                (Some(10), Some(10)),
+                (Some(11), None),
+                (None, Some(11)),
+                (None, Some(12)),
+                (Some(12), Some(13)),
+            ]
+        );
+        assert_eq!(lines[6].text, "    old();");
+    }
+
+    #[test]
+    fn restarts_numbering_per_hunk() {
+        let lines = parse("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n@@ -40,2 +40,2 @@\n c\n");
+        assert_eq!(lines[6].old_line, Some(40));
+        assert_eq!(lines[6].new_line, Some(40));
+    }
+
+    #[test]
+    fn detects_diffs() {
+        assert!(looks_like_diff(DIFF));
+        assert!(looks_like_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b"));
+        assert!(!looks_like_diff("--- not a diff"));
+        assert!(!looks_like_diff("hello @@ -1 +1 @@"));
+    }
+
+    #[test]
+    fn finds_file_and_language() {
+        assert_eq!(file_name(DIFF), Some("src/lib.rs"));
+        assert_eq!(language(DIFF), Some(Language::Rust));
+        assert_eq!(language("--- a/x\n+++ b/x.txt\n"), None);
+    }
+}
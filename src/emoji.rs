@@ -0,0 +1,423 @@
+//! `:shortcode:` emoji, as on GitHub and Slack. The same table expands
+//! them as they are typed and when a message is shown, so older clients'
+//! messages get them too.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::completion::{byte_index, utf16_len, Edit};
+
+/// A trimmed set of the gemoji names, sorted by name for binary search.
+static SHORTCODES: [(&str, &str); 139] = [
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("angry", "😠"),
+    ("ant", "🐜"),
+    ("apple", "🍎"),
+    ("astonished", "😲"),
+    ("baby", "👶"),
+    ("balloon", "🎈"),
+    ("bear", "🐻"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("birthday", "🎂"),
+    ("blush", "😊"),
+    ("boom", "💥"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("car", "🚗"),
+    ("cat", "🐱"),
+    ("check", "✔️"),
+    ("clap", "👏"),
+    ("clock1", "🕐"),
+    ("cloud", "☁️"),
+    ("coffee", "☕"),
+    ("cold_sweat", "😰"),
+    ("confetti_ball", "🎊"),
+    ("confused", "😕"),
+    ("cool", "🆒"),
+    ("cry", "😢"),
+    ("crying_cat_face", "😿"),
+    ("dancer", "💃"),
+    ("disappointed", "😞"),
+    ("dizzy_face", "😵"),
+    ("dog", "🐶"),
+    ("eyes", "👀"),
+    ("facepalm", "🤦"),
+    ("fire", "🔥"),
+    ("fish", "🐟"),
+    ("flushed", "😳"),
+    ("frog", "🐸"),
+    ("frowning", "😦"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("grimacing", "😬"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("hand", "✋"),
+    ("heart", "❤️"),
+    ("heart_eyes", "😍"),
+    ("hearts", "♥️"),
+    ("heavy_check_mark", "✔️"),
+    ("hourglass", "⌛"),
+    ("hugs", "🤗"),
+    ("hushed", "😯"),
+    ("innocent", "😇"),
+    ("joy", "😂"),
+    ("kiss", "😘"),
+    ("kissing_heart", "😘"),
+    ("laughing", "😆"),
+    ("lock", "🔒"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("money_mouth_face", "🤑"),
+    ("monkey", "🐒"),
+    ("moon", "🌙"),
+    ("muscle", "💪"),
+    ("neutral_face", "😐"),
+    ("no_entry", "🚫"),
+    ("no_mouth", "😶"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("open_mouth", "😮"),
+    ("partying_face", "🥳"),
+    ("pensive", "😔"),
+    ("pizza", "🍕"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "👆"),
+    ("poop", "💩"),
+    ("pray", "🙏"),
+    ("question", "❓"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("relaxed", "☺️"),
+    ("relieved", "😌"),
+    ("rocket", "🚀"),
+    ("rofl", "🤣"),
+    ("rose", "🌹"),
+    ("scream", "😱"),
+    ("see_no_evil", "🙈"),
+    ("shrug", "🤷"),
+    ("sleeping", "😴"),
+    ("sleepy", "😪"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("smirk", "😏"),
+    ("snowflake", "❄️"),
+    ("sob", "😭"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("star_struck", "🤩"),
+    ("stuck_out_tongue", "😛"),
+    ("stuck_out_tongue_winking_eye", "😜"),
+    ("sun_with_face", "🌞"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀️"),
+    ("sweat", "😓"),
+    ("sweat_smile", "😅"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("tired_face", "😫"),
+    ("trophy", "🏆"),
+    ("turtle", "🐢"),
+    ("umbrella", "☂️"),
+    ("unamused", "😒"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌️"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("weary", "😩"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("worried", "😟"),
+    ("x", "❌"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zipper_mouth_face", "🤐"),
+    ("zzz", "💤"),
+];
+
+/// The people and hands in the table, which come in skin tones.
+static MODIFIER_BASES: [char; 18] = [
+    '👆', '👇', '👈', '👉', '👋', '👌', '👍', '👎', '👏', '👶', '💃', '💪', '🙌', '🙏', '🤦', '🤷',
+    '✋', '✌',
+];
+
+/// The default yellow and the five Fitzpatrick modifiers, lightest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkinTone {
+    #[default]
+    Default,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+impl SkinTone {
+    pub const ALL: [SkinTone; 6] = [
+        SkinTone::Default,
+        SkinTone::Light,
+        SkinTone::MediumLight,
+        SkinTone::Medium,
+        SkinTone::MediumDark,
+        SkinTone::Dark,
+    ];
+
+    fn modifier(self) -> Option<char> {
+        match self {
+            SkinTone::Default => None,
+            SkinTone::Light => Some('\u{1F3FB}'),
+            SkinTone::MediumLight => Some('\u{1F3FC}'),
+            SkinTone::Medium => Some('\u{1F3FD}'),
+            SkinTone::MediumDark => Some('\u{1F3FE}'),
+            SkinTone::Dark => Some('\u{1F3FF}'),
+        }
+    }
+
+    /// Locale key for its name.
+    pub fn key(self) -> &'static str {
+        match self {
+            SkinTone::Default => "skin_tone.default",
+            SkinTone::Light => "skin_tone.light",
+            SkinTone::MediumLight => "skin_tone.medium_light",
+            SkinTone::Medium => "skin_tone.medium",
+            SkinTone::MediumDark => "skin_tone.medium_dark",
+            SkinTone::Dark => "skin_tone.dark",
+        }
+    }
+}
+
+pub fn lookup(name: &str) -> Option<&'static str> {
+    SHORTCODES
+        .binary_search_by(|(code, _)| (*code).cmp(name))
+        .ok()
+        .map(|i| SHORTCODES[i].1)
+}
+
+/// The emoji with `query` in their name, in name order. Names that are
+/// aliases of one emoji, like `+1` and `thumbsup`, give it only once.
+pub fn search(query: &str) -> Vec<(&'static str, &'static str)> {
+    let query = query.trim().to_lowercase().replace(' ', "_");
+    let mut seen = HashSet::new();
+    SHORTCODES
+        .iter()
+        .filter(|(name, emoji)| name.contains(query.as_str()) && seen.insert(*emoji))
+        .copied()
+        .collect()
+}
+
+/// Whether `emoji` is a person or hand that comes in skin tones.
+pub fn takes_skin_tone(emoji: &str) -> bool {
+    emoji
+        .chars()
+        .next()
+        .is_some_and(|c| MODIFIER_BASES.contains(&c))
+}
+
+/// `emoji` in skin tone `tone`, if it takes one.
+pub fn with_skin_tone(emoji: &str, tone: SkinTone) -> Cow<'_, str> {
+    let (Some(modifier), Some(base)) = (tone.modifier(), emoji.chars().next()) else {
+        return Cow::Borrowed(emoji);
+    };
+    if !MODIFIER_BASES.contains(&base) {
+        return Cow::Borrowed(emoji);
+    }
+    // The modifier takes the place of a presentation selector, as in ✌️.
+    let rest = emoji[base.len_utf8()..].trim_start_matches('\u{FE0F}');
+    Cow::Owned(format!("{}{}{}", base, modifier, rest))
+}
+
+fn is_name_char(c: u8) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, b'_' | b'+' | b'-')
+}
+
+fn is_alphanumeric_at(text: &str, i: usize) -> bool {
+    text[i..].chars().next().is_some_and(char::is_alphanumeric)
+}
+
+fn is_alphanumeric_before(text: &str, i: usize) -> bool {
+    text[..i]
+        .chars()
+        .next_back()
+        .is_some_and(char::is_alphanumeric)
+}
+
+/// Finds the known shortcodes in `text`. A shortcode can't touch a letter
+/// or digit on either side, so times like `10:30:45` are left alone, and
+/// nothing between backticks is touched.
+fn shortcodes(text: &str) -> Vec<(Range<usize>, &'static str)> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut in_code = false;
+    // The closing colon of an unknown shortcode may open the next one, as in
+    // `:nope:smile:`.
+    let mut reopen = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => in_code = !in_code,
+            b':' if !in_code && (reopen == Some(i) || !is_alphanumeric_before(text, i)) => {
+                let len = bytes[i + 1..]
+                    .iter()
+                    .take_while(|&&c| is_name_char(c))
+                    .count();
+                let close = i + 1 + len;
+                if len > 0 && bytes.get(close) == Some(&b':') {
+                    let end = close + 1;
+                    match lookup(&text[i + 1..close]).filter(|_| !is_alphanumeric_at(text, end)) {
+                        Some(emoji) => {
+                            found.push((i..end, emoji));
+                            i = end;
+                        }
+                        None => {
+                            reopen = Some(close);
+                            i = close;
+                        }
+                    }
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Replaces every known shortcode in `text`; unknown ones stay as typed.
+pub fn expand(text: &str) -> Cow<'_, str> {
+    let found = shortcodes(text);
+    if found.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut expanded = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (range, emoji) in found {
+        expanded.push_str(&text[copied..range.start]);
+        expanded.push_str(emoji);
+        copied = range.end;
+    }
+    expanded.push_str(&text[copied..]);
+    Cow::Owned(expanded)
+}
+
+/// Expands the shortcode just closed before `caret`, if there is one, for
+/// replacing it as it is typed. `caret` is in UTF-16 code units, as the DOM
+/// reports it.
+pub fn expand_typed(value: &str, caret: u32) -> Option<Edit> {
+    let caret = byte_index(value, caret);
+    let (range, emoji) = shortcodes(&value[..caret]).pop()?;
+    if range.end != caret || is_alphanumeric_at(value, caret) {
+        return None;
+    }
+    let before = &value[..range.start];
+    Some(Edit {
+        value: format!("{}{}{}", before, emoji, &value[caret..]),
+        caret: utf16_len(before) + utf16_len(emoji),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(SHORTCODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(lookup("+1"), Some("👍"));
+        assert_eq!(lookup("smile"), Some("😄"));
+        assert_eq!(lookup("nope"), None);
+    }
+
+    #[test]
+    fn searches_names_once_per_emoji() {
+        let thumbs: Vec<_> = search("thumbs").into_iter().map(|(_, e)| e).collect();
+        assert_eq!(thumbs, ["👎", "👍"]);
+        assert!(search("+1").iter().any(|(_, e)| *e == "👍"));
+        assert_eq!(search("Heart Eyes"), [("heart_eyes", "😍")]);
+        assert_eq!(search("").len(), {
+            let unique: HashSet<_> = SHORTCODES.iter().map(|(_, e)| e).collect();
+            unique.len()
+        });
+    }
+
+    #[test]
+    fn applies_skin_tones_to_people_only() {
+        assert!(takes_skin_tone("👍"));
+        assert!(!takes_skin_tone("🐶"));
+        assert_eq!(with_skin_tone("👍", SkinTone::Medium), "👍\u{1F3FD}");
+        assert_eq!(with_skin_tone("✌️", SkinTone::Dark), "✌\u{1F3FF}");
+        assert_eq!(with_skin_tone("👍", SkinTone::Default), "👍");
+        assert_eq!(with_skin_tone("🐶", SkinTone::Light), "🐶");
+    }
+
+    #[test]
+    fn expands_known_shortcodes() {
+        assert_eq!(expand("hi :wave: there"), "hi 👋 there");
+        assert_eq!(expand(":smile::wave:"), "😄👋");
+        assert_eq!(expand("(:tada:)"), "(🎉)");
+        assert_eq!(expand("no colons"), "no colons");
+    }
+
+    #[test]
+    fn leaves_lookalikes_alone() {
+        assert_eq!(expand("10:30:45"), "10:30:45");
+        assert_eq!(expand("12:100:00"), "12:100:00");
+        assert_eq!(expand("a:smile:b"), "a:smile:b");
+        assert_eq!(expand(":nope: :Smile: ::"), ":nope: :Smile: ::");
+        assert_eq!(expand("https://example.com"), "https://example.com");
+    }
+
+    #[test]
+    fn unknown_shortcodes_can_close_into_known_ones() {
+        assert_eq!(expand(":nope::smile:"), ":nope:😄");
+        assert_eq!(expand(":nope:smile:"), ":nope😄");
+    }
+
+    #[test]
+    fn skips_code_spans() {
+        assert_eq!(expand("`:smile:` :smile:"), "`:smile:` 😄");
+        assert_eq!(expand("`unclosed :smile:"), "`unclosed :smile:");
+    }
+
+    #[test]
+    fn expands_as_you_type() {
+        assert_eq!(
+            expand_typed("hi :wave:", 9),
+            Some(Edit {
+                value: "hi 👋".into(),
+                caret: 5,
+            })
+        );
+        assert_eq!(
+            expand_typed("😄 :+1: ok", 7),
+            Some(Edit {
+                value: "😄 👍 ok".into(),
+                caret: 5,
+            })
+        );
+        // Not just closed at the caret.
+        assert_eq!(expand_typed("hi :wave: ", 10), None);
+        assert_eq!(expand_typed("hi :wave", 8), None);
+        assert_eq!(expand_typed("at 10:30:", 9), None);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::bidi::Direction;
+
+const EN: &str = include_str!("../locales/en.json");
+const ES: &str = include_str!("../locales/es.json");
+
+const MINUTE_MS: f64 = 60_000.0;
+const HOUR_MS: f64 = 60.0 * MINUTE_MS;
+const DAY_MS: f64 = 24.0 * HOUR_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Es,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::En, Language::Es];
+
+    /// The language's name in itself, for the switcher.
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::En => "English",
+            Language::Es => "Español",
+        }
+    }
+
+    /// The BCP 47 tag, for the browser's date formatting.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Es => "es",
+        }
+    }
+
+    /// Which way the interface reads. Neither language is written right to
+    /// left yet, but the layout follows this rather than assuming.
+    pub fn direction(self) -> Direction {
+        match self {
+            Language::En | Language::Es => Direction::Ltr,
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::En => EN,
+            Language::Es => ES,
+        }
+    }
+}
+
+type Strings = HashMap<&'static str, &'static str>;
+
+fn parse(source: &'static str) -> Strings {
+    serde_json::from_str(source).expect("locale files are valid JSON without escapes")
+}
+
+/// UI strings for one language, shared through context. Keys missing from a
+/// translation fall back to English, then to the key itself.
+#[derive(Clone)]
+pub struct Locale {
+    language: Language,
+    strings: Rc<Strings>,
+    fallback: Rc<Strings>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            strings: Rc::new(parse(language.source())),
+            fallback: Rc::new(parse(EN)),
+        }
+    }
+
+    /// Which way the layout flows.
+    pub fn direction(&self) -> Direction {
+        self.language.direction()
+    }
+
+    pub fn t<'a>(&self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .copied()
+            .unwrap_or(key)
+    }
+
+    /// Like [`t`](Self::t), filling in `{name}` style placeholders.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.t(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+
+    /// How long ago something happened, as in "5 minutes ago".
+    pub fn relative_time(&self, elapsed_ms: f64) -> String {
+        if elapsed_ms < MINUTE_MS {
+            return self.t("time.just_now").to_string();
+        }
+        let (unit, one, many) = if elapsed_ms < HOUR_MS {
+            (MINUTE_MS, "time.minutes_one", "time.minutes_many")
+        } else if elapsed_ms < DAY_MS {
+            (HOUR_MS, "time.hours_one", "time.hours_many")
+        } else {
+            (DAY_MS, "time.days_one", "time.days_many")
+        };
+        match (elapsed_ms / unit).floor() as u64 {
+            1 => self.t(one).to_string(),
+            n => self.format(many, &[("count", &n.to_string())]),
+        }
+    }
+
+    /// Names the local day starting at `midnight`, for the separators in the
+    /// message list: "Today", "Yesterday", or the date.
+    pub fn day_label(&self, midnight: f64, today: f64) -> String {
+        match day_name((today - midnight) / DAY_MS) {
+            Some(key) => self.t(key).to_string(),
+            None => {
+                let options = js_sys::Object::new();
+                for (name, value) in [("weekday", "long"), ("month", "long"), ("day", "numeric")] {
+                    let _ = js_sys::Reflect::set(&options, &name.into(), &value.into());
+                }
+                js_sys::Date::new(&JsValue::from_f64(midnight))
+                    .to_locale_date_string(self.language.tag(), &options)
+                    .into()
+            }
+        }
+    }
+}
+
+/// Locale key naming a day this many days back, if it has a name. Days are
+/// rounded, as a day with a daylight saving change is an hour longer or
+/// shorter.
+fn day_name(days_ago: f64) -> Option<&'static str> {
+    match days_ago.round() as i64 {
+        0 => Some("time.today"),
+        1 => Some("time.yesterday"),
+        _ => None,
+    }
+}
+
+/// The local midnight starting the day `timestamp` falls on.
+pub fn start_of_day(timestamp: f64) -> f64 {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp));
+    date.set_hours(0);
+    date.set_minutes(0);
+    date.set_seconds(0);
+    date.set_milliseconds(0);
+    date.get_time()
+}
+
+impl PartialEq for Locale {
+    fn eq(&self, other: &Self) -> bool {
+        self.language == other.language
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_has_every_key() {
+        let english = parse(EN);
+        for language in Language::ALL {
+            let strings = parse(language.source());
+            for key in english.keys() {
+                assert!(
+                    strings.contains_key(key),
+                    "{:?} is missing {}",
+                    language,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn looks_up_and_falls_back() {
+        let locale = Locale::new(Language::Es);
+        assert_eq!(locale.t("chat.export"), "Exportar");
+        assert_eq!(locale.t("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let locale = Locale::new(Language::En);
+        assert_eq!(
+            locale.format("chat.hidden_many", &[("count", "3"), ("name", "bob")]),
+            "3 hidden messages from bob"
+        );
+    }
+
+    #[test]
+    fn says_how_long_ago() {
+        let locale = Locale::new(Language::En);
+        assert_eq!(locale.relative_time(30_000.0), "just now");
+        assert_eq!(locale.relative_time(MINUTE_MS * 1.5), "1 minute ago");
+        assert_eq!(locale.relative_time(HOUR_MS * 3.0), "3 hours ago");
+        assert_eq!(locale.relative_time(DAY_MS * 2.0), "2 days ago");
+        let locale = Locale::new(Language::Es);
+        assert_eq!(locale.relative_time(MINUTE_MS * 5.0), "hace 5 minutos");
+    }
+
+    #[test]
+    fn names_nearby_days() {
+        assert_eq!(day_name(0.0), Some("time.today"));
+        assert_eq!(day_name(1.04), Some("time.yesterday"));
+        assert_eq!(day_name(0.96), Some("time.yesterday"));
+        assert_eq!(day_name(3.0), None);
+    }
+}
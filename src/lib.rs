@@ -1,7 +1,15 @@
 #![recursion_limit = "512"]
 
+mod bidi;
+mod colors;
 mod components;
+mod diff;
+mod emoji;
+mod i18n;
+mod profanity;
 mod services;
+mod syntax;
+mod text;
 
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -12,7 +20,16 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 use components::chat::Chat;
+use components::fixtures::OverflowFixture;
+use components::lazy_avatar::AvatarObserver;
 use components::login::Login;
+use components::profile::Profile;
+use i18n::Locale;
+use services::avatars::AvatarStyle;
+use services::csrf::CsrfToken;
+use services::session;
+use services::settings::{self, SettingsHandle, ThemeContext};
+use services::transport::Transport;
 
 // When the `wee_alloc` feature is enabled, this uses `wee_alloc` as the global
 // allocator.
@@ -22,12 +39,22 @@ use components::login::Login;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-#[derive(Debug, Clone, Copy, PartialEq, Routable)]
+#[derive(Debug, Clone, PartialEq, Routable)]
 pub enum Route {
     #[at("/")]
+    Home,
+    #[at("/login")]
     Login,
     #[at("/chat")]
     Chat,
+    #[at("/profile/:username")]
+    Profile { username: String },
+    /// Awkward messages for checking layout by eye.
+    #[at("/fixtures/overflow")]
+    OverflowFixture,
+    /// Where everyone is sent when the server goes down for maintenance.
+    #[at("/maintenance")]
+    Maintenance,
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -35,9 +62,27 @@ pub enum Route {
 
 pub type User = Rc<UserInner>;
 
+/// Storage key for the avatar style picked on the login screen.
+pub const AVATAR_STYLE_KEY: &str = "avatar_style";
+
+/// Marks a session as logged in. The chat server has no real authentication,
+/// so for now this just carries the name the user registered with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthToken(pub String);
+
 #[derive(Debug, PartialEq)]
 pub struct UserInner {
     pub username: RefCell<String>,
+    pub token: RefCell<Option<AuthToken>>,
+    pub avatar_style: RefCell<AvatarStyle>,
+    /// Why the chat sent us back to the login page, shown there once.
+    pub notice: RefCell<Option<String>>,
+}
+
+impl UserInner {
+    pub fn is_authenticated(&self) -> bool {
+        self.token.borrow().is_some()
+    }
 }
 
 #[function_component(Main)]
@@ -45,25 +90,114 @@ fn main() -> Html {
     let ctx = use_state(|| {
         Rc::new(UserInner {
             username: RefCell::new("initial".into()),
+            token: RefCell::new(None),
+            avatar_style: RefCell::new(session::load(AVATAR_STYLE_KEY).unwrap_or_default()),
+            notice: RefCell::new(None),
         })
     });
+    let csrf = use_state(CsrfToken::default);
+    let avatar_observer = use_state(AvatarObserver::default);
+    {
+        let csrf = (*csrf).clone();
+        use_effect_with_deps(
+            move |_| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Err(e) = csrf.refresh().await {
+                        log::warn!("csrf token: {}", e);
+                    }
+                });
+                || ()
+            },
+            (),
+        );
+    }
+    let stored_settings = use_state(|| Rc::new(settings::load()));
+    let locale = Locale::new(stored_settings.language);
+    let settings = SettingsHandle::new((*stored_settings).clone(), {
+        let stored_settings = stored_settings.clone();
+        Callback::from(move |updated| {
+            settings::save(&updated);
+            stored_settings.set(Rc::new(updated));
+        })
+    });
+    let theme = ThemeContext::new(stored_settings.theme, {
+        let settings = settings.clone();
+        Callback::from(move |theme| settings.set_theme(theme))
+    });
+    let root_class = classes!(
+        "flex",
+        "w-screen",
+        "h-screen",
+        stored_settings.theme.class()
+    );
 
     html! {
         <ContextProvider<User> context={(*ctx).clone()}>
-            <BrowserRouter>
-                <div class="flex w-screen h-screen">
-                    <Switch<Route> render={Switch::render(switch)}/>
-                </div>
-            </BrowserRouter>
+            <ContextProvider<CsrfToken> context={(*csrf).clone()}>
+                <ContextProvider<AvatarObserver> context={(*avatar_observer).clone()}>
+                    <ContextProvider<SettingsHandle> context={settings}>
+                        <ContextProvider<ThemeContext> context={theme}>
+                            <ContextProvider<Locale> context={locale}>
+                                <BrowserRouter>
+                                    <div class={root_class} dir={stored_settings.language.direction().attr()} lang={stored_settings.language.tag()}>
+                                        <Switch<Route> render={Switch::render(switch)}/>
+                                    </div>
+                                </BrowserRouter>
+                            </ContextProvider<Locale>>
+                        </ContextProvider<ThemeContext>>
+                    </ContextProvider<SettingsHandle>>
+                </ContextProvider<AvatarObserver>>
+            </ContextProvider<CsrfToken>>
         </ContextProvider<User>>
     }
 }
 
+#[derive(Properties, PartialEq)]
+struct RequireAuthProps {
+    children: Children,
+}
+
+/// Renders its children only for a logged-in user, sending everyone else to
+/// the login page.
+#[function_component(RequireAuth)]
+fn require_auth(props: &RequireAuthProps) -> Html {
+    let user = use_context::<User>().expect("No context found.");
+    if user.is_authenticated() {
+        html! { <>{ for props.children.iter() }</> }
+    } else {
+        html! { <Redirect<Route> to={Route::Login}/> }
+    }
+}
+
+#[function_component(Maintenance)]
+fn maintenance() -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    html! {
+        <div class="flex flex-col items-center justify-center w-screen h-screen gap-2 text-center">
+            <h1 class="text-2xl font-semibold">{locale.t("maintenance.title")}</h1>
+            <p class="text-gray-600">{locale.t("maintenance.body")}</p>
+            <Link<Route> to={Route::Login} classes="mt-2 underline">{locale.t("maintenance.back")}</Link<Route>>
+        </div>
+    }
+}
+
+#[function_component(NotFound)]
+fn not_found() -> Html {
+    let locale = use_context::<Locale>().expect("locale context to be set");
+    html! { <h1>{locale.t("not_found")}</h1> }
+}
+
 fn switch(selected_route: &Route) -> Html {
     match selected_route {
+        Route::Home => html! {<Redirect<Route> to={Route::Chat}/>},
         Route::Login => html! {<Login />},
-        Route::Chat => html! {<Chat/>},
-        Route::NotFound => html! {<h1>{"404 baby"}</h1>},
+        Route::Chat => {
+            html! {<RequireAuth><Chat transport={Transport::from_query()}/></RequireAuth>}
+        }
+        Route::Profile { username } => html! {<Profile username={username.clone()}/>},
+        Route::OverflowFixture => html! {<OverflowFixture />},
+        Route::Maintenance => html! {<Maintenance />},
+        Route::NotFound => html! {<NotFound />},
     }
 }
 
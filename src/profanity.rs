@@ -0,0 +1,182 @@
+//! Masks swear words in messages as they are shown, for those who'd rather
+//! not see them. Messages themselves are never changed.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// A short list of common English swear words, in their plain spelling.
+static BUNDLED: [&str; 26] = [
+    "arse",
+    "arsehole",
+    "ass",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "bullshit",
+    "cock",
+    "crap",
+    "cunt",
+    "damn",
+    "dick",
+    "douche",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fucking",
+    "jackass",
+    "motherfucker",
+    "piss",
+    "prick",
+    "shit",
+    "shitty",
+    "twat",
+    "wanker",
+];
+
+/// Words to mask, matched whole and regardless of case.
+#[derive(Debug)]
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            words: words.into_iter().map(str::to_lowercase).collect(),
+        }
+    }
+
+    /// The list that ships with the app.
+    pub fn bundled() -> &'static WordList {
+        static LIST: OnceLock<WordList> = OnceLock::new();
+        LIST.get_or_init(|| WordList::new(BUNDLED))
+    }
+
+    /// Where in `token` a listed word is, if the token is one. Exclamation
+    /// marks count as an `i` only inside a word, so "shit!" still matches.
+    fn find(&self, token: &str) -> Option<Range<usize>> {
+        let start = token.len() - token.trim_start_matches('!').len();
+        let end = token.trim_end_matches('!').len();
+        if start >= end {
+            return None;
+        }
+        let plain: String = token[start..end].chars().map(unleet).collect();
+        self.words.contains(&plain).then_some(start..end)
+    }
+}
+
+/// Reads a leetspeak character as the letter it stands for.
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        c => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '@' | '$' | '!')
+}
+
+fn is_url(chunk: &str) -> bool {
+    chunk.contains("://") || chunk.to_ascii_lowercase().starts_with("www.")
+}
+
+/// `text` with every listed word replaced by as many asterisks as it has
+/// letters. Links and anything between backticks, code blocks included, are
+/// left alone.
+pub fn censor<'a>(text: &'a str, words: &WordList) -> Cow<'a, str> {
+    let mut masked = Vec::new();
+    let mut in_code = false;
+    let chunks = text
+        .split_whitespace()
+        .map(|chunk| (chunk.as_ptr() as usize - text.as_ptr() as usize, chunk));
+    for (offset, chunk) in chunks {
+        if !in_code && is_url(chunk) {
+            continue;
+        }
+        let mut word = None;
+        let ends = chunk
+            .char_indices()
+            .chain(std::iter::once((chunk.len(), ' ')));
+        for (i, c) in ends {
+            if !in_code && is_word_char(c) {
+                word.get_or_insert(i);
+                continue;
+            }
+            if let Some(from) = word.take() {
+                if let Some(found) = words.find(&chunk[from..i]) {
+                    masked.push(offset + from + found.start..offset + from + found.end);
+                }
+            }
+            if c == '`' {
+                in_code = !in_code;
+            }
+        }
+    }
+    if masked.is_empty() {
+        return Cow::Borrowed(text);
+    }
+    let mut censored = String::with_capacity(text.len());
+    let mut copied = 0;
+    for range in masked {
+        censored.push_str(&text[copied..range.start]);
+        censored.extend(text[range.clone()].chars().map(|_| '*'));
+        copied = range.end;
+    }
+    censored.push_str(&text[copied..]);
+    Cow::Owned(censored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn censored(text: &str) -> String {
+        censor(text, WordList::bundled()).into_owned()
+    }
+
+    #[test]
+    fn masks_whole_words_keeping_their_length() {
+        assert_eq!(censored("well shit, that's crap"), "well ****, that's ****");
+        assert_eq!(censored("SHIT!"), "****!");
+        assert!(matches!(
+            censor("all good here", WordList::bundled()),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn leaves_words_that_only_contain_one() {
+        assert_eq!(censored("class assessment"), "class assessment");
+        assert_eq!(censored("Scunthorpe"), "Scunthorpe");
+    }
+
+    #[test]
+    fn reads_leetspeak() {
+        assert_eq!(censored("sh1t"), "****");
+        assert_eq!(censored("$h!t happens"), "**** happens");
+        assert_eq!(censored("a$$"), "***");
+        assert_eq!(censored("b1tch3s"), "b1tch3s");
+    }
+
+    #[test]
+    fn skips_code_and_links() {
+        assert_eq!(censored("`shit` shit"), "`shit` ****");
+        assert_eq!(
+            censored("```\nlet shit = 1;\n``` crap"),
+            "```\nlet shit = 1;\n``` ****"
+        );
+        assert_eq!(
+            censored("see https://example.com/shit and www.crap.com"),
+            "see https://example.com/shit and www.crap.com"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    AudioContext, Notification, NotificationOptions, NotificationPermission, OscillatorType,
+};
+use yew::Callback;
+
+/// Whether the user is looking at another tab or window.
+pub fn page_hidden() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .map(|d| d.hidden())
+        .unwrap_or(false)
+}
+
+/// Whether the page has keyboard focus, so the user is looking at it rather
+/// than at another window beside it.
+pub fn page_focused() -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.has_focus().ok())
+        .unwrap_or(false)
+}
+
+/// Puts the unread count in front of the page title, as in "(3) Yewchat!",
+/// or takes it off again at zero.
+pub fn show_unread_in_title(count: usize) {
+    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+        document.set_title(&badged_title(&document.title(), count));
+    }
+}
+
+fn badged_title(title: &str, count: usize) -> String {
+    let plain = title
+        .strip_prefix('(')
+        .and_then(|rest| rest.split_once(") "))
+        .filter(|(count, _)| count.trim_end_matches('+').parse::<usize>().is_ok())
+        .map_or(title, |(_, plain)| plain);
+    match count {
+        0 => plain.to_string(),
+        1..=99 => format!("({}) {}", count, plain),
+        _ => format!("(99+) {}", plain),
+    }
+}
+
+/// Calls back with `page_hidden()` whenever it changes, until dropped.
+pub struct VisibilityListener {
+    callback: Closure<dyn FnMut()>,
+}
+
+impl VisibilityListener {
+    pub fn new(on_change: Callback<bool>) -> Self {
+        let callback =
+            Closure::wrap(Box::new(move || on_change.emit(page_hidden())) as Box<dyn FnMut()>);
+        if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+            let _ = document.add_event_listener_with_callback(
+                "visibilitychange",
+                callback.as_ref().unchecked_ref(),
+            );
+        }
+        Self { callback }
+    }
+}
+
+impl Drop for VisibilityListener {
+    fn drop(&mut self) {
+        if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+            let _ = document.remove_event_listener_with_callback(
+                "visibilitychange",
+                self.callback.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+/// Asks for permission to show desktop notifications, if not yet decided.
+pub fn request_notification_permission() {
+    if Notification::permission() == NotificationPermission::Default {
+        if let Err(e) = Notification::request_permission() {
+            log::error!("notification permission: {:?}", e);
+        }
+    }
+}
+
+/// Shows a desktop notification, provided the user allowed them.
+pub fn notify(title: &str, body: &str) {
+    if Notification::permission() != NotificationPermission::Granted {
+        return;
+    }
+    let mut options = NotificationOptions::new();
+    options.body(body);
+    if let Err(e) = Notification::new_with_options(title, &options) {
+        log::error!("notification: {:?}", e);
+    }
+}
+
+/// Plays a short two-tone chime.
+pub fn chime() {
+    if let Err(e) = try_chime() {
+        log::error!("chime: {:?}", e);
+    }
+}
+
+fn try_chime() -> Result<(), JsValue> {
+    let audio = AudioContext::new()?;
+    let oscillator = audio.create_oscillator()?;
+    let gain = audio.create_gain()?;
+    oscillator.set_type(OscillatorType::Sine);
+    let now = audio.current_time();
+    oscillator.frequency().set_value_at_time(880.0, now)?;
+    oscillator
+        .frequency()
+        .set_value_at_time(1320.0, now + 0.08)?;
+    gain.gain().set_value_at_time(0.1, now)?;
+    gain.gain()
+        .exponential_ramp_to_value_at_time(0.001, now + 0.25)?;
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&audio.destination())?;
+    oscillator.start()?;
+    oscillator.stop_with_when(now + 0.25)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::badged_title;
+
+    #[test]
+    fn replaces_the_count_in_the_title() {
+        assert_eq!(badged_title("Yewchat!", 3), "(3) Yewchat!");
+        assert_eq!(badged_title("(3) Yewchat!", 4), "(4) Yewchat!");
+        assert_eq!(badged_title("(99+) Yewchat!", 0), "Yewchat!");
+        assert_eq!(
+            badged_title("(beta) Yewchat!", 120),
+            "(99+) (beta) Yewchat!"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+/// The public DiceBear API. Set `YEWCHAT_AVATAR_URL` at build time to point
+/// at a self-hosted instance instead; it must serve the same
+/// `<base>/<style>/svg?seed=<name>` paths.
+const DEFAULT_BASE_URL: &str = "https://api.dicebear.com/7.x";
+
+/// The DiceBear styles users can pick from on the login screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AvatarStyle {
+    #[default]
+    AdventurerNeutral,
+    Adventurer,
+    Avataaars,
+    Bottts,
+    FunEmoji,
+    Initials,
+    PixelArt,
+    Thumbs,
+}
+
+impl AvatarStyle {
+    pub const ALL: [AvatarStyle; 8] = [
+        AvatarStyle::AdventurerNeutral,
+        AvatarStyle::Adventurer,
+        AvatarStyle::Avataaars,
+        AvatarStyle::Bottts,
+        AvatarStyle::FunEmoji,
+        AvatarStyle::Initials,
+        AvatarStyle::PixelArt,
+        AvatarStyle::Thumbs,
+    ];
+
+    /// The style's path segment in the DiceBear API.
+    pub fn slug(self) -> &'static str {
+        match self {
+            AvatarStyle::AdventurerNeutral => "adventurer-neutral",
+            AvatarStyle::Adventurer => "adventurer",
+            AvatarStyle::Avataaars => "avataaars",
+            AvatarStyle::Bottts => "bottts",
+            AvatarStyle::FunEmoji => "fun-emoji",
+            AvatarStyle::Initials => "initials",
+            AvatarStyle::PixelArt => "pixel-art",
+            AvatarStyle::Thumbs => "thumbs",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AvatarStyle::AdventurerNeutral => "Adventurer (neutral)",
+            AvatarStyle::Adventurer => "Adventurer",
+            AvatarStyle::Avataaars => "Avataaars",
+            AvatarStyle::Bottts => "Bottts",
+            AvatarStyle::FunEmoji => "Fun emoji",
+            AvatarStyle::Initials => "Initials",
+            AvatarStyle::PixelArt => "Pixel art",
+            AvatarStyle::Thumbs => "Thumbs",
+        }
+    }
+}
+
+fn base_url() -> &'static str {
+    option_env!("YEWCHAT_AVATAR_URL").unwrap_or(DEFAULT_BASE_URL)
+}
+
+/// The avatar image for `name` drawn in `style`.
+pub fn avatar_url(name: &str, style: AvatarStyle) -> String {
+    format!(
+        "{}/{}/svg?seed={}",
+        base_url().trim_end_matches('/'),
+        style.slug(),
+        encode_query_value(name)
+    )
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+pub(crate) fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_url_for_style() {
+        assert_eq!(
+            avatar_url("alice", AvatarStyle::PixelArt),
+            format!("{}/pixel-art/svg?seed=alice", base_url())
+        );
+    }
+
+    #[test]
+    fn encodes_spaces_and_unicode() {
+        assert!(
+            avatar_url("ada lovelace", AvatarStyle::default()).ends_with("?seed=ada%20lovelace")
+        );
+        assert!(avatar_url("zoë", AvatarStyle::default()).ends_with("?seed=zo%C3%AB"));
+        assert!(avatar_url("a&b=c", AvatarStyle::default()).ends_with("?seed=a%26b%3Dc"));
+    }
+
+    #[test]
+    fn style_wire_name_matches_slug() {
+        for style in AvatarStyle::ALL {
+            assert_eq!(
+                serde_json::to_string(&style).unwrap(),
+                format!("\"{}\"", style.slug())
+            );
+        }
+    }
+}
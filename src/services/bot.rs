@@ -0,0 +1,221 @@
+use yew_agent::{Agent, AgentLink, Context, HandlerId};
+
+use crate::components::chat::MessageData;
+
+/// Not a valid username, so nobody can join under the bot's name.
+pub const BOT_NAME: &str = "Yew Bot";
+
+/// A small regular expression subset that needs no extra crate: literal
+/// characters, `.`, `*` on the preceding atom, `^` and `$` anchors, `\` to
+/// escape, and a leading `(?i)` for case-insensitive matching.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    atoms: Vec<(Atom, bool)>,
+    anchored_start: bool,
+    anchored_end: bool,
+    ignore_case: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Atom {
+    Any,
+    Char(char),
+}
+
+impl Atom {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Atom::Any => true,
+            Atom::Char(a) => a == c,
+        }
+    }
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Self {
+        let (ignore_case, source) = match source.strip_prefix("(?i)") {
+            Some(rest) => (true, rest),
+            None => (false, source),
+        };
+        let (anchored_start, source) = match source.strip_prefix('^') {
+            Some(rest) => (true, rest),
+            None => (false, source),
+        };
+        let mut atoms: Vec<(Atom, bool)> = Vec::new();
+        let mut anchored_end = false;
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            let atom = match c {
+                '.' => Atom::Any,
+                '$' if chars.peek().is_none() => {
+                    anchored_end = true;
+                    break;
+                }
+                '*' => match atoms.last_mut() {
+                    Some((_, star)) => {
+                        *star = true;
+                        continue;
+                    }
+                    None => Atom::Char('*'),
+                },
+                '\\' => Atom::Char(chars.next().unwrap_or('\\')),
+                c => Atom::Char(c),
+            };
+            let atom = match atom {
+                Atom::Char(c) if ignore_case => Atom::Char(fold(c)),
+                atom => atom,
+            };
+            atoms.push((atom, false));
+        }
+        Self {
+            atoms,
+            anchored_start,
+            anchored_end,
+            ignore_case,
+        }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = if self.ignore_case {
+            text.chars().map(fold).collect()
+        } else {
+            text.chars().collect()
+        };
+        if self.anchored_start {
+            return self.match_here(&self.atoms, &text);
+        }
+        (0..=text.len()).any(|start| self.match_here(&self.atoms, &text[start..]))
+    }
+
+    fn match_here(&self, atoms: &[(Atom, bool)], text: &[char]) -> bool {
+        match atoms.split_first() {
+            None => !self.anchored_end || text.is_empty(),
+            Some((&(atom, true), rest)) => {
+                // Try the shortest run first; any run of matching characters
+                // may be followed by the rest of the pattern.
+                let run = text.iter().take_while(|&&c| atom.matches(c)).count();
+                (0..=run).any(|n| self.match_here(rest, &text[n..]))
+            }
+            Some((&(atom, false), rest)) => match text.split_first() {
+                Some((&c, text)) if atom.matches(c) => self.match_here(rest, text),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotRule {
+    pub pattern: Pattern,
+    pub response: String,
+}
+
+impl BotRule {
+    pub fn new(pattern: &str, response: &str) -> Self {
+        Self {
+            pattern: Pattern::new(pattern),
+            response: response.to_string(),
+        }
+    }
+}
+
+fn default_rules() -> Vec<BotRule> {
+    vec![
+        BotRule::new("(?i)^!ping$", "pong"),
+        BotRule::new("(?i)^!help", "I answer !ping and !help. Say hi to me, too."),
+        BotRule::new("(?i)^h.*i.* bot", "Hello! 👋"),
+    ]
+}
+
+/// The response of the first rule matching `text`, if any.
+fn respond<'a>(rules: &'a [BotRule], text: &str) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| rule.pattern.is_match(text))
+        .map(|rule| rule.response.as_str())
+}
+
+/// Answers chat messages matching its rules. Every component bridged to it
+/// forwards the messages it receives, and gets the bot's replies back to show
+/// locally; nothing the bot says goes through the server.
+pub struct ChatBot {
+    link: AgentLink<ChatBot>,
+    rules: Vec<BotRule>,
+}
+
+impl Agent for ChatBot {
+    type Reach = Context<Self>;
+    type Message = ();
+    type Input = MessageData;
+    type Output = MessageData;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        Self {
+            link,
+            rules: default_rules(),
+        }
+    }
+
+    fn update(&mut self, _msg: Self::Message) {}
+
+    fn handle_input(&mut self, message: Self::Input, id: HandlerId) {
+        if message.from == BOT_NAME {
+            return;
+        }
+        if let Some(response) = respond(&self.rules, &message.message) {
+            self.link.respond(
+                id,
+                MessageData {
+                    from: BOT_NAME.to_string(),
+                    message: response.to_string(),
+                    timestamp: js_sys::Date::now(),
+                    room: message.room,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_and_anchors() {
+        assert!(Pattern::new("ping").is_match("a ping b"));
+        assert!(Pattern::new("^!ping$").is_match("!ping"));
+        assert!(!Pattern::new("^!ping$").is_match("!ping now"));
+        assert!(!Pattern::new("^!ping").is_match("say !ping"));
+    }
+
+    #[test]
+    fn wildcards_and_stars() {
+        let pattern = Pattern::new("^h.*i.* bot");
+        assert!(pattern.is_match("hi bot"));
+        assert!(pattern.is_match("hello there, fine bot"));
+        assert!(!pattern.is_match("bot, hi"));
+        assert!(Pattern::new("^a*$").is_match(""));
+        assert!(Pattern::new(r"^1\.5$").is_match("1.5"));
+        assert!(!Pattern::new(r"^1\.5$").is_match("125"));
+    }
+
+    #[test]
+    fn default_rules_answer_commands() {
+        let rules = default_rules();
+        assert_eq!(respond(&rules, "!PING"), Some("pong"));
+        assert_eq!(respond(&rules, "hi bot"), Some("Hello! 👋"));
+        assert_eq!(respond(&rules, "just chatting"), None);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        assert!(Pattern::new("(?i)^!PING$").is_match("!Ping"));
+        assert!(!Pattern::new("^!PING$").is_match("!Ping"));
+        assert!(Pattern::new("(?i)straße").is_match("STRASSE straße"));
+    }
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MediaStream, MediaStreamConstraints, MediaStreamTrack, RtcIceCandidateInit, RtcPeerConnection,
+    RtcPeerConnectionIceEvent, RtcSdpType, RtcSessionDescription, RtcSessionDescriptionInit,
+    RtcTrackEvent,
+};
+use yew::Callback;
+
+use crate::services::message::CallSignal;
+
+/// One side of a video call: the local camera stream plus the peer
+/// connection. Cheap to clone so async steps can hold on to it.
+#[derive(Clone)]
+pub struct PeerCall {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    connection: RtcPeerConnection,
+    local: MediaStream,
+    /// Candidates that arrived before the remote description was set.
+    pending: RefCell<Vec<RtcIceCandidateInit>>,
+    _on_ice: Closure<dyn FnMut(RtcPeerConnectionIceEvent)>,
+    _on_track: Closure<dyn FnMut(RtcTrackEvent)>,
+}
+
+impl PartialEq for PeerCall {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl PeerCall {
+    /// Opens the camera and microphone and sets up a peer connection.
+    /// Local ICE candidates are handed to `on_signal` for the caller to
+    /// forward; the remote stream is handed to `on_remote` once it arrives.
+    pub async fn start(
+        on_signal: Callback<CallSignal>,
+        on_remote: Callback<MediaStream>,
+    ) -> Result<Self, JsValue> {
+        let local = user_media().await?;
+        let connection = RtcPeerConnection::new()?;
+        for track in local.get_tracks().iter() {
+            let track: MediaStreamTrack = track.unchecked_into();
+            connection.add_track(&track, &local, &js_sys::Array::new());
+        }
+
+        let on_ice = Closure::wrap(Box::new(move |e: RtcPeerConnectionIceEvent| {
+            if let Some(candidate) = e.candidate() {
+                on_signal.emit(CallSignal::IceCandidate {
+                    candidate: candidate.candidate(),
+                    sdp_mid: candidate.sdp_mid(),
+                    sdp_m_line_index: candidate.sdp_m_line_index(),
+                });
+            }
+        }) as Box<dyn FnMut(RtcPeerConnectionIceEvent)>);
+        let on_track = Closure::wrap(Box::new(move |e: RtcTrackEvent| {
+            if let Ok(stream) = e.streams().get(0).dyn_into::<MediaStream>() {
+                on_remote.emit(stream);
+            }
+        }) as Box<dyn FnMut(RtcTrackEvent)>);
+        connection.set_onicecandidate(Some(on_ice.as_ref().unchecked_ref()));
+        connection.set_ontrack(Some(on_track.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            inner: Rc::new(Inner {
+                connection,
+                local,
+                pending: RefCell::new(Vec::new()),
+                _on_ice: on_ice,
+                _on_track: on_track,
+            }),
+        })
+    }
+
+    pub fn local_stream(&self) -> &MediaStream {
+        &self.inner.local
+    }
+
+    /// Creates the SDP offer for the caller's side.
+    pub async fn offer(&self) -> Result<String, JsValue> {
+        let offer = JsFuture::from(self.inner.connection.create_offer()).await?;
+        self.set_local(RtcSdpType::Offer, offer).await
+    }
+
+    /// Takes the caller's offer and creates the callee's answer.
+    pub async fn answer(&self, offer_sdp: &str) -> Result<String, JsValue> {
+        self.set_remote(RtcSdpType::Offer, offer_sdp).await?;
+        let answer = JsFuture::from(self.inner.connection.create_answer()).await?;
+        self.set_local(RtcSdpType::Answer, answer).await
+    }
+
+    /// Completes the caller's side of the handshake.
+    pub async fn accept_answer(&self, answer_sdp: &str) -> Result<(), JsValue> {
+        self.set_remote(RtcSdpType::Answer, answer_sdp).await
+    }
+
+    pub async fn add_ice_candidate(
+        &self,
+        candidate: &str,
+        sdp_mid: Option<&str>,
+        sdp_m_line_index: Option<u16>,
+    ) -> Result<(), JsValue> {
+        let mut init = RtcIceCandidateInit::new(candidate);
+        init.sdp_mid(sdp_mid).sdp_m_line_index(sdp_m_line_index);
+        if self.inner.connection.remote_description().is_none() {
+            self.inner.pending.borrow_mut().push(init);
+            return Ok(());
+        }
+        self.add_candidate(&init).await
+    }
+
+    /// Stops the camera and closes the connection.
+    pub fn hangup(&self) {
+        for track in self.inner.local.get_tracks().iter() {
+            track.unchecked_into::<MediaStreamTrack>().stop();
+        }
+        self.inner.connection.close();
+    }
+
+    async fn set_local(&self, kind: RtcSdpType, description: JsValue) -> Result<String, JsValue> {
+        let sdp = description.unchecked_into::<RtcSessionDescription>().sdp();
+        let mut init = RtcSessionDescriptionInit::new(kind);
+        init.sdp(&sdp);
+        JsFuture::from(self.inner.connection.set_local_description(&init)).await?;
+        Ok(sdp)
+    }
+
+    async fn set_remote(&self, kind: RtcSdpType, sdp: &str) -> Result<(), JsValue> {
+        let mut init = RtcSessionDescriptionInit::new(kind);
+        init.sdp(sdp);
+        JsFuture::from(self.inner.connection.set_remote_description(&init)).await?;
+        let pending = self.inner.pending.take();
+        for candidate in pending {
+            self.add_candidate(&candidate).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_candidate(&self, init: &RtcIceCandidateInit) -> Result<(), JsValue> {
+        let promise = self
+            .inner
+            .connection
+            .add_ice_candidate_with_opt_rtc_ice_candidate_init(Some(init));
+        JsFuture::from(promise).await.map(|_| ())
+    }
+}
+
+async fn user_media() -> Result<MediaStream, JsValue> {
+    let devices = web_sys::window()
+        .ok_or("no window")?
+        .navigator()
+        .media_devices()?;
+    let mut constraints = MediaStreamConstraints::new();
+    constraints.audio(&JsValue::TRUE).video(&JsValue::TRUE);
+    let stream = JsFuture::from(devices.get_user_media_with_constraints(&constraints)?).await?;
+    Ok(stream.unchecked_into())
+}
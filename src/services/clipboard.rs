@@ -0,0 +1,56 @@
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlDocument, HtmlTextAreaElement};
+
+/// Puts `text` on the clipboard. Uses the async Clipboard API where there
+/// is one, which needs a secure context, and the older `execCommand("copy")`
+/// elsewhere.
+pub async fn write_text(text: &str) -> Result<(), JsValue> {
+    match clipboard_write(text) {
+        Some(promise) => JsFuture::from(promise).await.map(|_| ()),
+        None => exec_command_copy(text),
+    }
+}
+
+/// Starts `navigator.clipboard.writeText`, if the browser has it. It's
+/// behind web-sys's unstable APIs, so this goes through JS reflection.
+fn clipboard_write(text: &str) -> Option<Promise> {
+    let navigator = web_sys::window()?.navigator();
+    let clipboard = Reflect::get(&navigator, &"clipboard".into()).ok()?;
+    if clipboard.is_undefined() {
+        return None;
+    }
+    let write_text = Reflect::get(&clipboard, &"writeText".into()).ok()?;
+    let promise = write_text
+        .dyn_ref::<Function>()?
+        .call1(&clipboard, &text.into())
+        .ok()?;
+    promise.dyn_into().ok()
+}
+
+/// Copies by selecting `text` in an off-screen text area.
+fn exec_command_copy(text: &str) -> Result<(), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let body = document
+        .body()
+        .ok_or_else(|| JsValue::from_str("no body"))?;
+    let area: HtmlTextAreaElement = document.create_element("textarea")?.dyn_into()?;
+    area.set_value(text);
+    area.set_attribute("readonly", "")?;
+    area.set_attribute("style", "position: fixed; top: -1000px; opacity: 0;")?;
+    body.append_child(&area)?;
+    area.select();
+    let copied = document
+        .dyn_into::<HtmlDocument>()
+        .map_err(JsValue::from)
+        .and_then(|document| document.exec_command("copy"));
+    body.remove_child(&area)?;
+    match copied {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(JsValue::from_str("copy refused")),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,357 @@
+use crate::i18n::Locale;
+
+/// What the chat input turned out to hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Text to send as is.
+    Text(String),
+    Command {
+        name: String,
+        args: Args,
+    },
+}
+
+/// A command's arguments, both as typed and split into words. Quotes group
+/// words, so `/x "a b" c` has the words `a b` and `c`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub raw: String,
+    pub words: Vec<String>,
+}
+
+/// Reads the chat input. Leading spaces are ignored, and `//` escapes a
+/// message that should start with a literal `/`.
+pub fn parse(input: &str) -> Input {
+    let trimmed = input.trim_start();
+    let rest = match trimmed.strip_prefix('/') {
+        Some(rest) if rest.starts_with('/') => return Input::Text(rest.to_string()),
+        Some(rest) if !rest.is_empty() && !rest.starts_with(char::is_whitespace) => rest,
+        _ => return Input::Text(input.to_string()),
+    };
+    let (name, raw) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let raw = raw.trim();
+    Input::Command {
+        name: name.to_lowercase(),
+        args: Args {
+            raw: raw.to_string(),
+            words: split_words(raw),
+        },
+    }
+}
+
+fn split_words(raw: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in raw.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// What running a command asks the chat to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Send {
+        text: String,
+        action: bool,
+    },
+    /// Shown only to us, never sent.
+    Notice(String),
+    Clear,
+    /// Opens a shared whiteboard.
+    Whiteboard,
+    /// Sets off confetti for everyone.
+    Confetti,
+    /// Sets the room's topic, or clears it if empty; moderators only.
+    SetTopic(String),
+    /// Sends an announcement to everyone; admins only.
+    Announce {
+        title: Option<String>,
+        text: String,
+    },
+}
+
+pub type Handler = fn(&Args, &Commands, &Locale) -> Outcome;
+
+pub struct Command {
+    pub name: &'static str,
+    /// Shown after the name in `/help`, e.g. `<action>`.
+    pub usage: &'static str,
+    /// Locale key of the one-line description.
+    pub summary: &'static str,
+    handler: Handler,
+}
+
+/// The slash commands the chat input understands, in `/help` order.
+#[derive(Default)]
+pub struct Commands {
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    /// `/help`, `/me`, `/clear`, `/whiteboard`, `/confetti`, `/announce` and
+    /// `/topic`.
+    pub fn builtin() -> Self {
+        Self::default()
+            .register("help", "", "commands.help", help)
+            .register("me", "<action>", "commands.me", me)
+            .register("clear", "", "commands.clear", |_, _, _| Outcome::Clear)
+            .register("whiteboard", "", "commands.whiteboard", |_, _, _| {
+                Outcome::Whiteboard
+            })
+            .register("confetti", "", "commands.confetti", |_, _, _| {
+                Outcome::Confetti
+            })
+            .register(
+                "announce",
+                "[<title> |] <message>",
+                "commands.announce",
+                announce,
+            )
+            .register("topic", "[<text>]", "commands.topic", |args, _, _| {
+                Outcome::SetTopic(args.raw.clone())
+            })
+    }
+
+    pub fn register(
+        mut self,
+        name: &'static str,
+        usage: &'static str,
+        summary: &'static str,
+        handler: Handler,
+    ) -> Self {
+        self.commands.push(Command {
+            name,
+            usage,
+            summary,
+            handler,
+        });
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+
+    pub fn run(&self, name: &str, args: &Args, locale: &Locale) -> Outcome {
+        match self.commands.iter().find(|c| c.name == name) {
+            Some(command) => (command.handler)(args, self, locale),
+            None => Outcome::Notice(locale.format("commands.unknown", &[("name", name)])),
+        }
+    }
+}
+
+fn help(_: &Args, commands: &Commands, locale: &Locale) -> Outcome {
+    let lines: Vec<String> = commands
+        .iter()
+        .map(|c| {
+            let usage = format!("/{} {}", c.name, c.usage);
+            format!("{} — {}", usage.trim_end(), locale.t(c.summary))
+        })
+        .collect();
+    Outcome::Notice(lines.join("\n"))
+}
+
+fn me(args: &Args, _: &Commands, locale: &Locale) -> Outcome {
+    if args.raw.is_empty() {
+        return Outcome::Notice(locale.format("commands.usage", &[("usage", "/me <action>")]));
+    }
+    Outcome::Send {
+        text: args.raw.clone(),
+        action: true,
+    }
+}
+
+/// `/announce Title | text`, or just `/announce text` for no title.
+fn announce(args: &Args, _: &Commands, locale: &Locale) -> Outcome {
+    let (title, text) = match args.raw.split_once('|') {
+        Some((title, text)) => (Some(title.trim()).filter(|t| !t.is_empty()), text.trim()),
+        None => (None, args.raw.as_str()),
+    };
+    if text.is_empty() {
+        return Outcome::Notice(locale.format(
+            "commands.usage",
+            &[("usage", "/announce [<title> |] <message>")],
+        ));
+    }
+    Outcome::Announce {
+        title: title.map(str::to_string),
+        text: text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Language;
+
+    fn command(name: &str, raw: &str, words: &[&str]) -> Input {
+        Input::Command {
+            name: name.into(),
+            args: Args {
+                raw: raw.into(),
+                words: words.iter().map(|w| w.to_string()).collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn plain_text_is_left_alone() {
+        assert_eq!(parse("hello /me"), Input::Text("hello /me".into()));
+        assert_eq!(parse("/"), Input::Text("/".into()));
+        assert_eq!(parse("/ spaced"), Input::Text("/ spaced".into()));
+    }
+
+    #[test]
+    fn leading_spaces_are_ignored() {
+        assert_eq!(parse("   /HELP  "), command("help", "", &[]));
+        assert_eq!(parse("\t/me waves"), command("me", "waves", &["waves"]));
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        assert_eq!(
+            parse(r#"/invite "ops team" 'bob smith' carol"#),
+            command(
+                "invite",
+                r#""ops team" 'bob smith' carol"#,
+                &["ops team", "bob smith", "carol"]
+            )
+        );
+        assert_eq!(
+            parse(r#"/x "" it's"#),
+            command("x", r#""" it's"#, &["", "its"])
+        );
+        assert_eq!(
+            parse(r#"/x "unterminated"#),
+            command("x", r#""unterminated"#, &["unterminated"])
+        );
+    }
+
+    #[test]
+    fn double_slash_escapes() {
+        assert_eq!(parse("//shrug"), Input::Text("/shrug".into()));
+        assert_eq!(parse("  //me"), Input::Text("/me".into()));
+    }
+
+    #[test]
+    fn runs_registered_commands() {
+        let locale = Locale::new(Language::En);
+        let commands = Commands::builtin();
+        let Input::Command { name, args } = parse("/me waves hello") else {
+            panic!("not a command");
+        };
+        assert_eq!(
+            commands.run(&name, &args, &locale),
+            Outcome::Send {
+                text: "waves hello".into(),
+                action: true
+            }
+        );
+        assert_eq!(
+            commands.run("clear", &Args::default(), &locale),
+            Outcome::Clear
+        );
+        assert_eq!(
+            commands.run("whiteboard", &Args::default(), &locale),
+            Outcome::Whiteboard
+        );
+        assert_eq!(
+            commands.run("confetti", &Args::default(), &locale),
+            Outcome::Confetti
+        );
+        assert!(matches!(
+            commands.run("me", &Args::default(), &locale),
+            Outcome::Notice(_)
+        ));
+    }
+
+    #[test]
+    fn announce_takes_an_optional_title() {
+        let locale = Locale::new(Language::En);
+        let commands = Commands::builtin();
+        let run = |input: &str| {
+            let Input::Command { name, args } = parse(input) else {
+                panic!("not a command");
+            };
+            commands.run(&name, &args, &locale)
+        };
+        assert_eq!(
+            run("/announce Maintenance | Down at 10pm"),
+            Outcome::Announce {
+                title: Some("Maintenance".into()),
+                text: "Down at 10pm".into()
+            }
+        );
+        assert_eq!(
+            run("/announce Pizza in the kitchen"),
+            Outcome::Announce {
+                title: None,
+                text: "Pizza in the kitchen".into()
+            }
+        );
+        assert!(matches!(run("/announce Title |"), Outcome::Notice(_)));
+    }
+
+    #[test]
+    fn topic_takes_the_rest_of_the_line() {
+        let locale = Locale::new(Language::En);
+        let Input::Command { name, args } = parse("/topic  Release day: be nice ") else {
+            panic!("not a command");
+        };
+        assert_eq!(
+            Commands::builtin().run(&name, &args, &locale),
+            Outcome::SetTopic("Release day: be nice".into())
+        );
+        assert_eq!(
+            Commands::builtin().run("topic", &Args::default(), &locale),
+            Outcome::SetTopic(String::new())
+        );
+    }
+
+    #[test]
+    fn help_lists_every_command() {
+        let locale = Locale::new(Language::En);
+        let commands =
+            Commands::builtin().register("shrug", "", "commands.help", |_, _, _| Outcome::Clear);
+        let Outcome::Notice(help) = commands.run("help", &Args::default(), &locale) else {
+            panic!("help should be a notice");
+        };
+        let lines: Vec<&str> = help.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[1].starts_with("/me <action> — "));
+        assert!(lines[7].starts_with("/shrug — "));
+    }
+
+    #[test]
+    fn unknown_commands_get_feedback() {
+        let locale = Locale::new(Language::En);
+        let Outcome::Notice(text) = Commands::builtin().run("nope", &Args::default(), &locale)
+        else {
+            panic!("expected a notice");
+        };
+        assert!(text.contains("/nope"), "{}", text);
+    }
+}
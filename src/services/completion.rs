@@ -0,0 +1,229 @@
+/// IRC-style Tab completion of usernames in the chat input.
+///
+/// The first Tab completes the word before the caret as far as the matching
+/// names agree; once it can't go further, each Tab cycles to the next name.
+/// Positions are in UTF-16 code units, as the DOM reports the caret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// Where the word being completed starts, in bytes.
+    start: usize,
+    /// What we last put there, so we can tell if the input changed under us.
+    inserted: String,
+    candidates: Vec<String>,
+    /// The candidate shown, or `None` while showing the common prefix.
+    index: Option<usize>,
+}
+
+/// The input after a completion step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub value: String,
+    pub caret: u32,
+}
+
+impl Completion {
+    /// Starts completing the word before `caret`. Returns `None` when there's
+    /// no word there or no name starts with it.
+    pub fn begin<'a>(
+        value: &str,
+        caret: u32,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Option<(Self, Edit)> {
+        let caret = byte_index(value, caret);
+        let start = value[..caret].rfind(char::is_whitespace).map_or(0, |i| {
+            i + value[i..].chars().next().map_or(1, char::len_utf8)
+        });
+        // `@name` completes the name after the `@`.
+        let start = if value[start..].starts_with('@') {
+            start + 1
+        } else {
+            start
+        };
+        let word = &value[start..caret];
+        if word.is_empty() {
+            return None;
+        }
+        let word_lower = word.to_lowercase();
+        let mut candidates: Vec<String> = names
+            .into_iter()
+            .filter(|name| name.to_lowercase().starts_with(&word_lower))
+            .map(str::to_string)
+            .collect();
+        candidates.sort_by_key(|name| name.to_lowercase());
+        candidates.dedup();
+
+        let mut completion = Self {
+            start,
+            inserted: word.to_string(),
+            candidates,
+            index: None,
+        };
+        let edit = match completion.candidates.as_slice() {
+            [] => return None,
+            [only] => {
+                let only = only.clone();
+                completion.index = Some(0);
+                completion.replace(value, caret, &only, true)
+            }
+            _ => {
+                let prefix = common_prefix(&completion.candidates);
+                if prefix.chars().count() > word.chars().count() {
+                    completion.replace(value, caret, &prefix, false)
+                } else {
+                    return completion
+                        .step(value, caret, true)
+                        .map(|edit| (completion, edit));
+                }
+            }
+        };
+        Some((completion, edit))
+    }
+
+    /// Moves to the next candidate, or the previous one when `forward` is
+    /// false. Returns `None` if the input no longer holds what we inserted,
+    /// in which case the caller should start over.
+    pub fn cycle(&mut self, value: &str, caret: u32, forward: bool) -> Option<Edit> {
+        let caret = byte_index(value, caret);
+        if value.get(self.start..caret) != Some(self.inserted.as_str()) {
+            return None;
+        }
+        self.step(value, caret, forward)
+    }
+
+    fn step(&mut self, value: &str, caret: usize, forward: bool) -> Option<Edit> {
+        let len = self.candidates.len();
+        let index = match (self.index, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        self.index = Some(index);
+        let name = self.candidates[index].clone();
+        Some(self.replace(value, caret, &name, true))
+    }
+
+    /// Puts `text` in place of the word, followed by a separator if it is a
+    /// whole name: `": "` at the start of the line, a space elsewhere.
+    fn replace(&mut self, value: &str, caret: usize, text: &str, whole: bool) -> Edit {
+        let suffix = match (whole, self.start) {
+            (false, _) => "",
+            (true, 0) => ": ",
+            (true, _) => " ",
+        };
+        self.inserted = format!("{}{}", text, suffix);
+        let before = &value[..self.start];
+        let after = value[caret..]
+            .strip_prefix(' ')
+            .filter(|_| whole)
+            .unwrap_or(&value[caret..]);
+        let value = format!("{}{}{}", before, self.inserted, after);
+        let caret = utf16_len(before) + utf16_len(&self.inserted);
+        Edit { value, caret }
+    }
+}
+
+/// The longest prefix every candidate shares, ignoring case, spelled as in
+/// the first candidate.
+fn common_prefix(candidates: &[String]) -> String {
+    let first = &candidates[0];
+    let mut end = first.len();
+    for other in &candidates[1..] {
+        let shared = first
+            .char_indices()
+            .zip(other.chars())
+            .find(|((_, a), b)| !a.to_lowercase().eq(b.to_lowercase()))
+            .map_or(first.len().min(other.len()), |((i, _), _)| i);
+        end = end.min(shared);
+    }
+    first[..end].to_string()
+}
+
+pub(crate) fn byte_index(value: &str, utf16: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in value.char_indices() {
+        if units >= utf16 as usize {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    value.len()
+}
+
+pub(crate) fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 4] = ["alice", "Alicia", "bob", "Zoë"];
+
+    fn begin(value: &str, caret: u32) -> Option<(Completion, Edit)> {
+        Completion::begin(value, caret, NAMES)
+    }
+
+    fn edit(value: &str, caret: u32) -> Edit {
+        Edit {
+            value: value.into(),
+            caret,
+        }
+    }
+
+    #[test]
+    fn unique_prefix_completes_the_name() {
+        assert_eq!(begin("BO", 2).unwrap().1, edit("bob: ", 5));
+        assert_eq!(begin("hi bo", 5).unwrap().1, edit("hi bob ", 7));
+        assert_eq!(begin("hi @b", 5).unwrap().1, edit("hi @bob ", 8));
+    }
+
+    #[test]
+    fn ambiguous_prefix_completes_the_common_part_then_cycles() {
+        let (mut completion, first) = begin("hey a", 5).unwrap();
+        assert_eq!(first, edit("hey alic", 8));
+        let second = completion.cycle(&first.value, first.caret, true).unwrap();
+        assert_eq!(second, edit("hey alice ", 10));
+        let third = completion.cycle(&second.value, second.caret, true).unwrap();
+        assert_eq!(third, edit("hey Alicia ", 11));
+        let fourth = completion.cycle(&third.value, third.caret, true).unwrap();
+        assert_eq!(fourth, second);
+        let back = completion
+            .cycle(&fourth.value, fourth.caret, false)
+            .unwrap();
+        assert_eq!(back, third);
+    }
+
+    #[test]
+    fn cycles_at_once_when_the_prefix_cannot_grow() {
+        let (_, first) = begin("alic", 4).unwrap();
+        assert_eq!(first, edit("alice: ", 7));
+    }
+
+    #[test]
+    fn keeps_text_after_the_caret() {
+        let (mut completion, first) = begin("a how are you", 1).unwrap();
+        assert_eq!(first, edit("alic how are you", 4));
+        let next = completion.cycle(&first.value, first.caret, true).unwrap();
+        assert_eq!(next, edit("alice: how are you", 7));
+    }
+
+    #[test]
+    fn counts_the_caret_in_utf16() {
+        assert_eq!(begin("😀 z", 4).unwrap().1, edit("😀 Zoë ", 7));
+    }
+
+    #[test]
+    fn nothing_to_complete() {
+        assert_eq!(begin("", 0), None);
+        assert_eq!(begin("hi ", 3), None);
+        assert_eq!(begin("carol", 5), None);
+    }
+
+    #[test]
+    fn stops_when_the_input_changed() {
+        let (mut completion, first) = begin("a", 1).unwrap();
+        assert_eq!(completion.cycle("xyz", 3, true), None);
+        assert!(completion.cycle(&first.value, first.caret, true).is_some());
+    }
+}
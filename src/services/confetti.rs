@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+/// How long a burst stays on screen.
+pub const DURATION_MS: f64 = 3_000.0;
+/// How often one user may set it off.
+pub const COOLDOWN_MS: f64 = 30_000.0;
+
+const PARTICLES: usize = 150;
+/// Downward pull, in pixels per second squared.
+const GRAVITY: f64 = 600.0;
+const COLORS: [&str; 6] = [
+    "#ef4444", "#f59e0b", "#10b981", "#3b82f6", "#8b5cf6", "#ec4899",
+];
+
+/// One falling paper rectangle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Particle {
+    pub x: f64,
+    pub y: f64,
+    /// Pixels per second.
+    pub vx: f64,
+    pub vy: f64,
+    /// Radians.
+    pub angle: f64,
+    /// Radians per second.
+    pub spin: f64,
+    pub width: f64,
+    pub height: f64,
+    pub color: &'static str,
+}
+
+impl Particle {
+    pub fn step(&mut self, dt_secs: f64) {
+        self.vy += GRAVITY * dt_secs;
+        self.x += self.vx * dt_secs;
+        self.y += self.vy * dt_secs;
+        self.angle += self.spin * dt_secs;
+    }
+}
+
+/// A burst from just above the top edge of a viewport `width` wide.
+/// `random` returns numbers in `[0, 1)`, like `Math.random`.
+pub fn burst(width: f64, mut random: impl FnMut() -> f64) -> Vec<Particle> {
+    (0..PARTICLES)
+        .map(|i| Particle {
+            x: random() * width,
+            y: -20.0 * random(),
+            vx: (random() - 0.5) * 300.0,
+            vy: 100.0 + random() * 250.0,
+            angle: random() * std::f64::consts::TAU,
+            spin: (random() - 0.5) * 4.0 * std::f64::consts::PI,
+            width: 6.0 + random() * 6.0,
+            height: 4.0 + random() * 4.0,
+            color: COLORS[i % COLORS.len()],
+        })
+        .collect()
+}
+
+/// When each user last set off confetti, so nobody can fill the screen
+/// with it.
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    last: HashMap<String, f64>,
+}
+
+impl Cooldowns {
+    /// How long `who` still has to wait, if at all.
+    pub fn remaining(&self, who: &str, now: f64) -> Option<f64> {
+        let last = self.last.get(who)?;
+        let left = last + COOLDOWN_MS - now;
+        (left > 0.0).then_some(left)
+    }
+
+    /// Records a burst from `who`, unless they are still cooling down.
+    pub fn fire(&mut self, who: &str, now: f64) -> bool {
+        if self.remaining(who, now).is_some() {
+            return false;
+        }
+        self.last.insert(who.to_string(), now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_fall_and_spin() {
+        let mut particles = burst(800.0, || 0.5);
+        assert_eq!(particles.len(), PARTICLES);
+        let p = &mut particles[0];
+        assert_eq!((p.x, p.vx, p.spin), (400.0, 0.0, 0.0));
+        let (y, vy) = (p.y, p.vy);
+        p.step(0.5);
+        assert_eq!(p.vy, vy + GRAVITY * 0.5);
+        assert_eq!(p.y, y + p.vy * 0.5);
+    }
+
+    #[test]
+    fn bursts_stay_in_the_viewport_width() {
+        let mut seed = 0.0;
+        let particles = burst(300.0, || {
+            seed = (seed + 0.37) % 1.0;
+            seed
+        });
+        assert!(particles.iter().all(|p| (0.0..300.0).contains(&p.x)));
+        assert!(particles.iter().all(|p| p.y <= 0.0 && p.vy > 0.0));
+    }
+
+    #[test]
+    fn one_burst_per_cooldown() {
+        let mut cooldowns = Cooldowns::default();
+        assert!(cooldowns.fire("ana", 1_000.0));
+        assert!(!cooldowns.fire("ana", 20_000.0));
+        assert_eq!(cooldowns.remaining("ana", 21_000.0), Some(10_000.0));
+        assert!(cooldowns.fire("bo", 20_000.0));
+        assert!(cooldowns.fire("ana", 31_000.0));
+    }
+}
@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use reqwasm::http::{Request, Response};
+use serde::Deserialize;
+
+const TOKEN_URL: &str = "http://127.0.0.1:8080/api/csrf-token";
+const HEADER: &str = "X-CSRF-Token";
+/// What the server answers a 403 with when the token is stale or missing.
+const INVALID_BODY: &str = "csrf_invalid";
+
+/// The server's anti-CSRF token, fetched once at startup and shared through
+/// context. Every request that changes something goes out through
+/// [`CsrfToken::send`] so it carries the token.
+#[derive(Debug, Clone, Default)]
+pub struct CsrfToken(Rc<RefCell<Option<String>>>);
+
+impl PartialEq for CsrfToken {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The token endpoint's answer: either the bare token or `{"token": ...}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenResponse {
+    Bare(String),
+    Wrapped { token: String },
+}
+
+fn parse_token(body: &str) -> Result<String, String> {
+    match serde_json::from_str(body).map_err(|e| e.to_string())? {
+        TokenResponse::Bare(token) | TokenResponse::Wrapped { token } => Ok(token),
+    }
+}
+
+/// Whether a 403 body says our token was refused, as opposed to anything
+/// else the server forbids. It may come quoted, as JSON.
+fn is_token_rejection(body: &str) -> bool {
+    body.trim().trim_matches('"') == INVALID_BODY
+}
+
+impl CsrfToken {
+    pub fn get(&self) -> Option<String> {
+        self.0.borrow().clone()
+    }
+
+    /// Fetches a fresh token from the server.
+    pub async fn refresh(&self) -> Result<(), String> {
+        let response = Request::get(TOKEN_URL)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.ok() {
+            return Err(format!(
+                "csrf token failed with status {}",
+                response.status()
+            ));
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        *self.0.borrow_mut() = Some(parse_token(&body)?);
+        Ok(())
+    }
+
+    fn attach(&self, request: Request) -> Request {
+        match self.get() {
+            Some(token) => request.header(HEADER, &token),
+            None => request,
+        }
+    }
+
+    /// Sends the request `build` makes with the token attached. If the
+    /// server refuses the token, fetches a new one and sends it once more;
+    /// `build` is called again for that, as a request can only be sent once.
+    pub async fn send(&self, build: impl Fn() -> Request) -> Result<Response, String> {
+        let response = self
+            .attach(build())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status() != 403 {
+            return Ok(response);
+        }
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if !is_token_rejection(&body) {
+            return Err(format!("request failed with status 403: {}", body));
+        }
+        self.refresh().await?;
+        self.attach(build()).send().await.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bare_and_wrapped_tokens() {
+        assert_eq!(parse_token(r#""abc123""#).unwrap(), "abc123");
+        assert_eq!(parse_token(r#"{"token": "abc123"}"#).unwrap(), "abc123");
+        assert!(parse_token("abc123").is_err());
+    }
+
+    #[test]
+    fn recognizes_token_rejections() {
+        assert!(is_token_rejection("csrf_invalid"));
+        assert!(is_token_rejection("\"csrf_invalid\"\n"));
+        assert!(!is_token_rejection("banned"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::services::message::PinKey;
+
+/// The same text sent again within this many milliseconds is taken for a
+/// double click or a bouncing Enter rather than meant.
+pub const REPEAT_WINDOW_MS: f64 = 2_000.0;
+/// How many received messages are remembered for spotting retransmissions.
+const SEEN_LIMIT: usize = 1_000;
+
+/// Remembers the last message we sent, to drop an accidental second copy.
+#[derive(Debug, Default)]
+pub struct SendGuard {
+    last: Option<(String, f64)>,
+}
+
+impl SendGuard {
+    /// Whether sending `payload` at `now` would repeat the last send.
+    pub fn is_repeat(&self, payload: &str, now: f64) -> bool {
+        self.last
+            .as_ref()
+            .is_some_and(|(last, at)| last == payload && now - at < REPEAT_WINDOW_MS)
+    }
+
+    pub fn record(&mut self, payload: String, now: f64) {
+        self.last = Some((payload, now));
+    }
+}
+
+/// The messages received lately, so one a server sends twice, e.g. when it
+/// retransmits after a reconnect, only shows once.
+#[derive(Debug, Default)]
+pub struct SeenMessages {
+    keys: HashSet<PinKey>,
+    order: VecDeque<PinKey>,
+}
+
+impl SeenMessages {
+    pub fn contains(&self, key: &PinKey) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Notes a message. Returns `false` if it was already seen.
+    pub fn insert(&mut self, key: PinKey) -> bool {
+        if !self.keys.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+        if self.order.len() > SEEN_LIMIT {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_quick_identical_send_is_a_repeat() {
+        let mut guard = SendGuard::default();
+        assert!(!guard.is_repeat("hi", 0.0));
+        guard.record("hi".into(), 0.0);
+        assert!(guard.is_repeat("hi", 1_999.0));
+        assert!(!guard.is_repeat("hi", REPEAT_WINDOW_MS));
+        assert!(!guard.is_repeat("hello", 100.0));
+    }
+
+    #[test]
+    fn retransmitted_messages_are_seen_once() {
+        let mut seen = SeenMessages::default();
+        assert!(seen.insert(PinKey::new("alice", 1.0)));
+        assert!(seen.contains(&PinKey::new("alice", 1.0)));
+        assert!(!seen.insert(PinKey::new("alice", 1.0)));
+        assert!(seen.insert(PinKey::new("alice", 2.0)));
+        assert!(seen.insert(PinKey::new("bob", 1.0)));
+    }
+
+    #[test]
+    fn forgets_the_oldest_past_the_limit() {
+        let mut seen = SeenMessages::default();
+        for i in 0..=SEEN_LIMIT {
+            seen.insert(PinKey::new("alice", i as f64));
+        }
+        assert!(seen.insert(PinKey::new("alice", 0.0)));
+        assert!(!seen.insert(PinKey::new("alice", SEEN_LIMIT as f64)));
+    }
+}
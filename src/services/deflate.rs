@@ -0,0 +1,431 @@
+//! Raw DEFLATE (RFC 1951) without an extra crate. `compress` emits a single
+//! fixed-Huffman block, which is small to write and does well on chat JSON;
+//! `decompress` reads any valid stream, so the server is free to use zlib.
+
+use std::fmt;
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions to try per match; more compresses a little
+/// better and a lot slower.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order code length code lengths are stored in a dynamic block header.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+#[derive(Debug, PartialEq)]
+pub enum InflateError {
+    /// The stream ended before its final block did.
+    Truncated,
+    /// A reserved block type, a bad code or a distance before the start.
+    Corrupt,
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InflateError::Truncated => write!(f, "deflate stream is truncated"),
+            InflateError::Corrupt => write!(f, "deflate stream is corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for InflateError {}
+
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::default();
+    // BFINAL, then BTYPE 01 for fixed codes.
+    out.bits(1, 1);
+    out.bits(1, 2);
+
+    // Hash chains over three-byte prefixes: `head` holds the latest position
+    // for each hash, `prev` links each position to the one before it.
+    let mut head = vec![u32::MAX; 1 << HASH_BITS];
+    let mut prev = vec![u32::MAX; input.len()];
+    let insert = |pos: usize, head: &mut [u32], prev: &mut [u32]| {
+        if pos + MIN_MATCH <= input.len() {
+            let h = hash(&input[pos..]);
+            prev[pos] = head[h];
+            head[h] = pos as u32;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < input.len() {
+        let (length, distance) = longest_match(input, pos, &head, &prev);
+        let step = if length >= MIN_MATCH {
+            write_match(&mut out, length, distance);
+            length
+        } else {
+            write_literal(&mut out, input[pos] as u16);
+            1
+        };
+        for p in pos..pos + step {
+            insert(p, &mut head, &mut prev);
+        }
+        pos += step;
+    }
+    write_literal(&mut out, 256);
+    out.finish()
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let key = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+    (key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// The longest earlier repeat of the bytes at `pos` within the window, as
+/// `(length, distance)`.
+fn longest_match(input: &[u8], pos: usize, head: &[u32], prev: &[u32]) -> (usize, usize) {
+    if pos + MIN_MATCH > input.len() {
+        return (0, 0);
+    }
+    let max = (input.len() - pos).min(MAX_MATCH);
+    let mut best = (0, 0);
+    let mut candidate = head[hash(&input[pos..])];
+    for _ in 0..MAX_CHAIN {
+        if candidate == u32::MAX || pos - candidate as usize > WINDOW {
+            break;
+        }
+        let start = candidate as usize;
+        let length = input[start..]
+            .iter()
+            .zip(&input[pos..pos + max])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if length > best.0 {
+            best = (length, pos - start);
+            if length == max {
+                break;
+            }
+        }
+        candidate = prev[start];
+    }
+    best
+}
+
+fn write_literal(out: &mut BitWriter, symbol: u16) {
+    let (code, length) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xC0 + symbol - 280, 8),
+    };
+    out.code(code, length);
+}
+
+fn write_match(out: &mut BitWriter, length: usize, distance: usize) {
+    let index = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_literal(out, 257 + index as u16);
+    out.bits(
+        (length - LENGTH_BASE[index] as usize) as u32,
+        LENGTH_EXTRA[index],
+    );
+    let index = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    out.code(index as u16, 5);
+    out.bits(
+        (distance - DISTANCE_BASE[index] as usize) as u32,
+        DISTANCE_EXTRA[index],
+    );
+}
+
+/// Packs bits least significant first, as DEFLATE stores everything but
+/// Huffman codes.
+#[derive(Default)]
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u8) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes go most significant bit first.
+    fn code(&mut self, code: u16, length: u8) {
+        let reversed = code.reverse_bits() >> (16 - length);
+        self.bits(reversed as u32, length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+pub fn decompress(input: &[u8]) -> Result<Vec<u8>, InflateError> {
+    let mut reader = BitReader {
+        input,
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => stored(&mut reader, &mut out)?,
+            1 => {
+                let (literals, distances) = fixed_tables();
+                codes(&mut reader, &mut out, &literals, &distances)?
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                codes(&mut reader, &mut out, &literals, &distances)?
+            }
+            _ => return Err(InflateError::Corrupt),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u8,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u8) -> Result<u32, InflateError> {
+        while self.count < count {
+            let byte = *self.input.get(self.pos).ok_or(InflateError::Truncated)?;
+            self.pos += 1;
+            self.buffer |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+}
+
+fn stored(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), InflateError> {
+    // Stored blocks start on a byte boundary.
+    reader.buffer = 0;
+    reader.count = 0;
+    let header = reader
+        .input
+        .get(reader.pos..reader.pos + 4)
+        .ok_or(InflateError::Truncated)?;
+    let length = u16::from_le_bytes([header[0], header[1]]);
+    if length != !u16::from_le_bytes([header[2], header[3]]) {
+        return Err(InflateError::Corrupt);
+    }
+    let start = reader.pos + 4;
+    let data = reader
+        .input
+        .get(start..start + length as usize)
+        .ok_or(InflateError::Truncated)?;
+    out.extend_from_slice(data);
+    reader.pos = start + length as usize;
+    Ok(())
+}
+
+/// A canonical Huffman code: how many codes there are of each length, and
+/// the symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16)
+            .filter(|&s| lengths[s as usize] != 0)
+            .collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, InflateError> {
+        let (mut code, mut first, mut index) = (0_u32, 0_u32, 0_u32);
+        for &count in &self.counts[1..] {
+            code |= reader.bits(1)?;
+            let count = u32::from(count);
+            if code < first + count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(InflateError::Corrupt)
+    }
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), InflateError> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+    let mut code_lengths = [0; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or(InflateError::Corrupt)?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count || lengths[256] == 0 {
+        return Err(InflateError::Corrupt);
+    }
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn codes(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), InflateError> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                let extra = *LENGTH_EXTRA.get(index).ok_or(InflateError::Corrupt)?;
+                let length = LENGTH_BASE[index] as usize + reader.bits(extra)? as usize;
+                let index = distances.decode(reader)? as usize;
+                let extra = *DISTANCE_EXTRA.get(index).ok_or(InflateError::Corrupt)?;
+                let distance = DISTANCE_BASE[index] as usize + reader.bits(extra)? as usize;
+                if distance > out.len() {
+                    return Err(InflateError::Corrupt);
+                }
+                // The copy may overlap what it writes, so go byte by byte.
+                let start = out.len() - distance;
+                for i in 0..length {
+                    out.push(out[start + i]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(input: &[u8]) {
+        assert_eq!(decompress(&compress(input)).unwrap(), input);
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip(b"");
+        round_trip(b"a");
+        round_trip(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+        let all_bytes: Vec<u8> = (0..=255).cycle().take(2_000).collect();
+        round_trip(&all_bytes);
+        let history =
+            r#"{"from":"alice","message":"hi there","timestamp":1700000000000}"#.repeat(500);
+        round_trip(history.as_bytes());
+    }
+
+    #[test]
+    fn repetitive_json_shrinks() {
+        let history =
+            r#"{"from":"alice","message":"hi there","timestamp":1700000000000}"#.repeat(100);
+        let compressed = compress(history.as_bytes());
+        assert!(
+            compressed.len() * 10 < history.len(),
+            "{}",
+            compressed.len()
+        );
+    }
+
+    #[test]
+    fn reads_stored_blocks() {
+        // BFINAL + BTYPE 00, then LEN 5 and its complement.
+        let stream = [1, 5, 0, !5, !0, b'h', b'e', b'l', b'l', b'o'];
+        assert_eq!(decompress(&stream).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn reads_dynamic_blocks() {
+        // zlib's raw deflate, level 9, of "0,1,2,…,39".
+        let stream = [
+            0x0d, 0xcc, 0xc1, 0x01, 0xc0, 0x30, 0x08, 0x03, 0xb1, 0x85, 0xee, 0x81, 0x6d, 0x92,
+            0xc0, 0xfe, 0x8b, 0xb5, 0x03, 0x48, 0x85, 0x30, 0xa1, 0x39, 0x5c, 0x1e, 0xc3, 0xa2,
+            0x42, 0x42, 0x46, 0x41, 0x8d, 0x0e, 0xba, 0xe8, 0xa1, 0x41, 0x8b, 0x0b, 0xff, 0xc6,
+            0x38, 0xb8, 0xf1, 0xc1, 0x17, 0x3f, 0x3c, 0x78, 0x49, 0x11, 0x91, 0xbf, 0x0c, 0x69,
+            0x72, 0xc8, 0x25, 0x8f, 0x0c, 0xd9, 0x0f,
+        ];
+        let expected: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+        assert_eq!(decompress(&stream).unwrap(), expected.join(",").as_bytes());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(decompress(&[]), Err(InflateError::Truncated));
+        assert_eq!(decompress(&[0b111]), Err(InflateError::Corrupt));
+        let compressed = compress(b"hello hello hello");
+        assert_eq!(
+            decompress(&compressed[..compressed.len() - 2]),
+            Err(InflateError::Truncated)
+        );
+        // A match reaching back before the start of the output.
+        let mut out = BitWriter::default();
+        out.bits(1, 1);
+        out.bits(1, 2);
+        write_match(&mut out, 3, 1);
+        assert_eq!(decompress(&out.finish()), Err(InflateError::Corrupt));
+    }
+}
@@ -0,0 +1,185 @@
+use js_sys::{Array, Uint8Array};
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Json,
+    Text,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Json, ExportFormat::Text];
+
+    /// `date` is the export day as `YYYY-MM-DD`.
+    pub fn file_name(&self, date: &str) -> String {
+        let extension = match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Text => "txt",
+        };
+        format!("chat_export_{}.{}", date, extension)
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Text => "text/plain",
+        }
+    }
+}
+
+/// What a transcript needs from a message. The JSON export is the message's
+/// own serialization.
+pub trait TranscriptEntry: Serialize {
+    fn sender(&self) -> &str;
+    fn timestamp(&self) -> f64;
+    fn text(&self) -> &str;
+}
+
+/// Renders `messages` in `format`. Plain text is one `[time] sender: text`
+/// line per message, with continuation lines of multi-line messages
+/// indented so every new entry still starts with `[`.
+pub fn transcript<'a, M: TranscriptEntry + 'a>(
+    messages: impl IntoIterator<Item = &'a M>,
+    format: ExportFormat,
+    format_time: impl Fn(f64) -> String,
+) -> String {
+    match format {
+        ExportFormat::Json => {
+            let messages: Vec<&M> = messages.into_iter().collect();
+            serde_json::to_string_pretty(&messages).unwrap()
+        }
+        ExportFormat::Text => messages
+            .into_iter()
+            .map(|m| {
+                format!(
+                    "[{}] {}: {}\n",
+                    format_time(m.timestamp()),
+                    m.sender(),
+                    m.text().lines().collect::<Vec<_>>().join("\n    ")
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Today's local date as `YYYY-MM-DD`.
+pub fn today() -> String {
+    format_date(js_sys::Date::now())
+}
+
+/// Formats a millisecond timestamp as a local `YYYY-MM-DD` date, the format
+/// `<input type="date">` uses.
+pub fn format_date(timestamp: f64) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp));
+    format!(
+        "{}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
+
+/// Formats a millisecond timestamp as local `HH:MM`.
+pub fn format_time(timestamp: f64) -> String {
+    let date = js_sys::Date::new(&JsValue::from_f64(timestamp));
+    format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
+
+/// Hands `contents` to the browser as a file download by clicking a hidden
+/// `<a download>` pointing at an object URL.
+pub fn download(file_name: &str, mime_type: &str, contents: &str) -> Result<(), JsValue> {
+    let bytes = Uint8Array::from(contents.as_bytes());
+    let parts = Array::of1(&bytes);
+    let mut props = BlobPropertyBag::new();
+    props.type_(mime_type);
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &props)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let body = document
+        .body()
+        .ok_or_else(|| JsValue::from_str("no body"))?;
+    let anchor: HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.set_hidden(true);
+
+    body.append_child(&anchor)?;
+    anchor.click();
+    body.remove_child(&anchor)?;
+    Url::revoke_object_url(&url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Entry {
+        from: &'static str,
+        timestamp: f64,
+        message: &'static str,
+    }
+
+    impl TranscriptEntry for Entry {
+        fn sender(&self) -> &str {
+            self.from
+        }
+
+        fn timestamp(&self) -> f64 {
+            self.timestamp
+        }
+
+        fn text(&self) -> &str {
+            self.message
+        }
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry {
+                from: "zoë",
+                timestamp: 1.0,
+                message: "héllo 👋 世界",
+            },
+            Entry {
+                from: "bob",
+                timestamp: 2.0,
+                message: "line one\nline two",
+            },
+        ]
+    }
+
+    fn time(timestamp: f64) -> String {
+        format!("t{}", timestamp)
+    }
+
+    #[test]
+    fn text_transcript_indents_continuation_lines() {
+        assert_eq!(
+            transcript(&entries(), ExportFormat::Text, time),
+            "[t1] zoë: héllo 👋 世界\n[t2] bob: line one\n    line two\n"
+        );
+    }
+
+    #[test]
+    fn json_transcript_round_trips_unicode_and_newlines() {
+        let json = transcript(&entries(), ExportFormat::Json, time);
+        assert!(json.contains("héllo 👋 世界"));
+        let parsed: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[1]["message"], "line one\nline two");
+        assert_eq!(parsed[0]["from"], "zoë");
+    }
+
+    #[test]
+    fn file_name_carries_the_date() {
+        assert_eq!(
+            ExportFormat::Text.file_name("2024-03-09"),
+            "chat_export_2024-03-09.txt"
+        );
+    }
+}
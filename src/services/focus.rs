@@ -0,0 +1,36 @@
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+use yew::prelude::*;
+
+/// Moves focus into a modal while it is open, so keyboard and screen reader
+/// users land in it, and gives focus back to whatever had it on close. Put
+/// the returned ref on the dialog, with `tabindex="-1"`.
+pub fn use_modal_focus() -> NodeRef {
+    let dialog = use_node_ref();
+    {
+        let dialog = dialog.clone();
+        use_effect_with_deps(
+            move |_| {
+                let previous = focused_element();
+                if let Some(dialog) = dialog.cast::<HtmlElement>() {
+                    let _ = dialog.focus();
+                }
+                move || {
+                    if let Some(previous) = previous {
+                        let _ = previous.focus();
+                    }
+                }
+            },
+            (),
+        );
+    }
+    dialog
+}
+
+fn focused_element() -> Option<HtmlElement> {
+    web_sys::window()?
+        .document()?
+        .active_element()?
+        .dyn_into()
+        .ok()
+}
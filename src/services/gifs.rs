@@ -0,0 +1,262 @@
+use reqwasm::http::Request;
+use serde::Deserialize;
+
+use crate::services::avatars::encode_query_value;
+
+/// Tenor's v2 API. Set `YEWCHAT_GIF_URL` at build time for a proxy or a
+/// compatible service, and `YEWCHAT_GIF_API_KEY` to turn the picker on.
+const DEFAULT_ENDPOINT: &str = "https://tenor.googleapis.com/v2";
+const PAGE_SIZE: usize = 20;
+
+/// Where GIF searches go.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GifConfig {
+    pub endpoint: String,
+    pub api_key: String,
+}
+
+impl GifConfig {
+    /// The build-time configuration; `None` without an API key.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            endpoint: option_env!("YEWCHAT_GIF_URL")
+                .unwrap_or(DEFAULT_ENDPOINT)
+                .to_string(),
+            api_key: option_env!("YEWCHAT_GIF_API_KEY")?.to_string(),
+        })
+    }
+
+    /// Searches for `query`, or lists what is trending when it is blank.
+    /// `pos` continues from an earlier page.
+    pub fn search_url(&self, query: &str, pos: Option<&str>) -> String {
+        let query = query.trim();
+        let mut url = match query {
+            "" => format!("{}/featured?", self.endpoint.trim_end_matches('/')),
+            _ => format!(
+                "{}/search?q={}&",
+                self.endpoint.trim_end_matches('/'),
+                encode_query_value(query)
+            ),
+        };
+        url.push_str(&format!(
+            "key={}&client_key=yewchat&media_filter=gif,tinygif&limit={}",
+            encode_query_value(&self.api_key),
+            PAGE_SIZE
+        ));
+        if let Some(pos) = pos {
+            url.push_str(&format!("&pos={}", encode_query_value(pos)));
+        }
+        url
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gif {
+    pub id: String,
+    pub description: String,
+    /// A small version for the picker grid.
+    pub preview_url: String,
+    /// What gets sent.
+    pub url: String,
+}
+
+/// One page of results, and where the next one starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub gifs: Vec<Gif>,
+    pub next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TenorResponse {
+    results: Vec<TenorResult>,
+    #[serde(default)]
+    next: String,
+}
+
+#[derive(Deserialize)]
+struct TenorResult {
+    id: String,
+    #[serde(default)]
+    content_description: String,
+    media_formats: TenorFormats,
+}
+
+#[derive(Deserialize)]
+struct TenorFormats {
+    gif: TenorMedia,
+    tinygif: Option<TenorMedia>,
+}
+
+#[derive(Deserialize)]
+struct TenorMedia {
+    url: String,
+}
+
+/// Reads a search response.
+pub fn parse_page(json: &str) -> Result<Page, String> {
+    let response: TenorResponse = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let gifs = response
+        .results
+        .into_iter()
+        .map(|result| Gif {
+            id: result.id,
+            description: result.content_description,
+            preview_url: result
+                .media_formats
+                .tinygif
+                .map_or_else(|| result.media_formats.gif.url.clone(), |tiny| tiny.url),
+            url: result.media_formats.gif.url,
+        })
+        .collect();
+    // Tenor sends an empty or zero position once there is nothing more.
+    let next = Some(response.next).filter(|next| !next.is_empty() && next != "0");
+    Ok(Page { gifs, next })
+}
+
+pub async fn search(config: &GifConfig, query: &str, pos: Option<&str>) -> Result<Page, String> {
+    let response = Request::get(&config.search_url(query, pos))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(format!("search failed with status {}", response.status()));
+    }
+    parse_page(&response.text().await.map_err(|e| e.to_string())?)
+}
+
+/// What the picker shows, built up from the responses to one query. Each
+/// query gets a new generation so late answers to an old one are dropped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Results {
+    pub generation: u32,
+    pub gifs: Vec<Gif>,
+    pub next: Option<String>,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultsAction {
+    /// A new query went out.
+    Reset(u32),
+    /// The next page of the current query went out.
+    LoadMore,
+    Loaded(u32, Page),
+    Failed(u32, String),
+}
+
+impl Results {
+    pub fn apply(&mut self, action: ResultsAction) {
+        match action {
+            ResultsAction::Reset(generation) => {
+                *self = Results {
+                    generation,
+                    loading: true,
+                    ..Results::default()
+                };
+            }
+            ResultsAction::LoadMore => {
+                self.loading = true;
+                self.error = None;
+            }
+            ResultsAction::Loaded(generation, page) if generation == self.generation => {
+                self.gifs.extend(page.gifs);
+                self.next = page.next;
+                self.loading = false;
+            }
+            ResultsAction::Failed(generation, error) if generation == self.generation => {
+                self.error = Some(error);
+                self.loading = false;
+            }
+            ResultsAction::Loaded(..) | ResultsAction::Failed(..) => {}
+        }
+    }
+
+    /// Whether scrolling to the bottom should fetch another page.
+    pub fn has_more(&self) -> bool {
+        self.next.is_some() && !self.loading && self.error.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> GifConfig {
+        GifConfig {
+            endpoint: "https://gifs.example/v2/".into(),
+            api_key: "k&y".into(),
+        }
+    }
+
+    fn page(ids: &[&str], next: Option<&str>) -> Page {
+        Page {
+            gifs: ids
+                .iter()
+                .map(|id| Gif {
+                    id: id.to_string(),
+                    description: String::new(),
+                    preview_url: String::new(),
+                    url: String::new(),
+                })
+                .collect(),
+            next: next.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn builds_search_urls() {
+        assert_eq!(
+            config().search_url(" happy cat ", None),
+            "https://gifs.example/v2/search?q=happy%20cat&key=k%26y&client_key=yewchat&media_filter=gif,tinygif&limit=20"
+        );
+        let featured = config().search_url("", Some("CAg="));
+        assert!(featured.starts_with("https://gifs.example/v2/featured?key="));
+        assert!(featured.ends_with("&pos=CAg%3D"));
+    }
+
+    #[test]
+    fn parses_tenor_responses() {
+        let json = r#"{
+            "results": [
+                {"id": "1", "content_description": "A cat", "media_formats": {
+                    "gif": {"url": "https://media.example/1.gif"},
+                    "tinygif": {"url": "https://media.example/1-tiny.gif"}
+                }},
+                {"id": "2", "media_formats": {"gif": {"url": "https://media.example/2.gif"}}}
+            ],
+            "next": "20"
+        }"#;
+        let page = parse_page(json).unwrap();
+        assert_eq!(page.next.as_deref(), Some("20"));
+        assert_eq!(page.gifs[0].description, "A cat");
+        assert_eq!(page.gifs[0].preview_url, "https://media.example/1-tiny.gif");
+        assert_eq!(page.gifs[1].preview_url, "https://media.example/2.gif");
+        assert_eq!(page.gifs[1].url, "https://media.example/2.gif");
+
+        let last = parse_page(r#"{"results": [], "next": ""}"#).unwrap();
+        assert_eq!(last.next, None);
+        assert!(parse_page("not json").is_err());
+    }
+
+    #[test]
+    fn pages_accumulate_and_stale_answers_are_dropped() {
+        let mut results = Results::default();
+        results.apply(ResultsAction::Reset(1));
+        assert!(results.loading && !results.has_more());
+        results.apply(ResultsAction::Loaded(1, page(&["a", "b"], Some("2"))));
+        assert!(results.has_more());
+        results.apply(ResultsAction::LoadMore);
+        results.apply(ResultsAction::Loaded(1, page(&["c"], None)));
+        assert_eq!(results.gifs.len(), 3);
+        assert!(!results.has_more());
+
+        results.apply(ResultsAction::Reset(2));
+        results.apply(ResultsAction::Loaded(1, page(&["old"], None)));
+        assert!(results.gifs.is_empty() && results.loading);
+        results.apply(ResultsAction::Failed(2, "offline".into()));
+        assert_eq!(results.error.as_deref(), Some("offline"));
+        assert!(!results.loading);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::vec_deque::{Iter, IterMut};
+use std::collections::VecDeque;
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 500;
+/// Below this the trimmed notice would be on screen more than the messages.
+pub const MIN_HISTORY_LIMIT: usize = 50;
+
+/// The messages kept on screen. Bounded so a long session in a busy room
+/// doesn't keep growing memory: past `limit`, the oldest entries are dropped.
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    limit: usize,
+    trimmed: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            items: VecDeque::new(),
+            limit: limit.max(MIN_HISTORY_LIMIT),
+            trimmed: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push_back(item);
+        self.trim();
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes the cap, dropping the oldest entries right away if the new
+    /// one is smaller.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(MIN_HISTORY_LIMIT);
+        self.trim();
+    }
+
+    /// Drops every entry. Unlike trimming this is asked for, so it doesn't
+    /// count towards `trimmed`.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.trimmed = 0;
+    }
+
+    /// Keeps only the entries `keep` accepts. Like `clear`, this doesn't
+    /// count towards `trimmed`.
+    pub fn retain(&mut self, keep: impl FnMut(&T) -> bool) {
+        self.items.retain(keep);
+    }
+
+    /// How many entries have been dropped so far.
+    pub fn trimmed(&self) -> usize {
+        self.trimmed
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
+    fn trim(&mut self) {
+        let excess = self.items.len().saturating_sub(self.limit);
+        self.items.drain(..excess);
+        self.trimmed += excess;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(limit: usize, count: usize) -> RingBuffer<usize> {
+        let mut history = RingBuffer::new(limit);
+        for i in 0..count {
+            history.push(i);
+        }
+        history
+    }
+
+    #[test]
+    fn keeps_newest_entries() {
+        let history = filled(MIN_HISTORY_LIMIT, MIN_HISTORY_LIMIT + 3);
+        assert_eq!(history.iter().len(), MIN_HISTORY_LIMIT);
+        assert_eq!(history.trimmed(), 3);
+        assert_eq!(history.iter().next(), Some(&3));
+        assert_eq!(history.iter().last(), Some(&(MIN_HISTORY_LIMIT + 2)));
+    }
+
+    #[test]
+    fn lowering_the_limit_trims_immediately() {
+        let mut history = filled(DEFAULT_HISTORY_LIMIT, 200);
+        assert_eq!(history.trimmed(), 0);
+        history.set_limit(100);
+        assert_eq!(history.iter().len(), 100);
+        assert_eq!(history.trimmed(), 100);
+        assert_eq!(history.iter().next(), Some(&100));
+    }
+
+    #[test]
+    fn clearing_forgets_trimmed_entries() {
+        let mut history = filled(MIN_HISTORY_LIMIT, MIN_HISTORY_LIMIT + 3);
+        history.clear();
+        assert_eq!(history.iter().len(), 0);
+        assert_eq!(history.trimmed(), 0);
+    }
+
+    #[test]
+    fn retaining_is_not_trimming() {
+        let mut history = filled(MIN_HISTORY_LIMIT, 10);
+        history.retain(|&i| i % 2 == 0);
+        assert_eq!(history.iter().len(), 5);
+        assert_eq!(history.trimmed(), 0);
+    }
+
+    #[test]
+    fn limit_has_a_floor() {
+        let history: RingBuffer<usize> = RingBuffer::new(1);
+        assert_eq!(history.limit(), MIN_HISTORY_LIMIT);
+    }
+}
@@ -0,0 +1,94 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gloo_events::EventListener;
+use gloo_timers::callback::Interval;
+use yew::Callback;
+
+/// How often we look at the clock; going away can lag by this much.
+const CHECK_MS: u32 = 10_000;
+/// Activity is noted at most this often, so a stream of mouse moves costs
+/// one clock read a second.
+const THROTTLE_MS: f64 = 1_000.0;
+const ACTIVITY_EVENTS: [&str; 5] = [
+    "mousemove",
+    "keydown",
+    "click",
+    "touchstart",
+    "visibilitychange",
+];
+
+/// Tells whether the user has gone idle: `on_change` gets `true` after
+/// `timeout_ms` without a mouse move, key press or click on the page, and
+/// `false` at the next one. A hidden tab gets none of those, so it goes idle
+/// too; coming back to it counts as activity. Only changes are reported.
+/// Dropping it stops listening.
+pub struct IdleTracker {
+    _listeners: Vec<EventListener>,
+    _check: Interval,
+}
+
+impl IdleTracker {
+    pub fn new(timeout_ms: u32, on_change: Callback<bool>) -> Option<Self> {
+        let document = web_sys::window()?.document()?;
+        let last_active = Rc::new(Cell::new(js_sys::Date::now()));
+        let idle = Rc::new(Cell::new(false));
+        let listeners = ACTIVITY_EVENTS
+            .iter()
+            .map(|event| {
+                let last_active = last_active.clone();
+                let idle = idle.clone();
+                let on_change = on_change.clone();
+                EventListener::new(&document, *event, move |_| {
+                    if super::alerts::page_hidden() {
+                        return;
+                    }
+                    let now = js_sys::Date::now();
+                    if !idle.get() && !throttle_passed(last_active.get(), now) {
+                        return;
+                    }
+                    last_active.set(now);
+                    if idle.replace(false) {
+                        on_change.emit(false);
+                    }
+                })
+            })
+            .collect();
+        let check = Interval::new(CHECK_MS, move || {
+            let now = js_sys::Date::now();
+            if !idle.get() && is_idle(last_active.get(), now, timeout_ms) {
+                idle.set(true);
+                on_change.emit(true);
+            }
+        });
+        Some(Self {
+            _listeners: listeners,
+            _check: check,
+        })
+    }
+}
+
+fn is_idle(last_active: f64, now: f64, timeout_ms: u32) -> bool {
+    now - last_active >= f64::from(timeout_ms)
+}
+
+fn throttle_passed(last_active: f64, now: f64) -> bool {
+    now - last_active >= THROTTLE_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_idle, throttle_passed};
+
+    #[test]
+    fn idle_once_the_timeout_passes() {
+        assert!(!is_idle(1_000.0, 60_999.0, 60_000));
+        assert!(is_idle(1_000.0, 61_000.0, 60_000));
+    }
+
+    #[test]
+    fn activity_is_noted_once_a_second() {
+        assert!(!throttle_passed(1_000.0, 1_999.0));
+        assert!(throttle_passed(1_000.0, 2_000.0));
+    }
+}
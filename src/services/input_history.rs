@@ -0,0 +1,119 @@
+use std::collections::VecDeque;
+
+use crate::services::session;
+
+const HISTORY_KEY: &str = "input_history";
+/// How many sends are remembered.
+pub const HISTORY_LIMIT: usize = 50;
+
+/// What we've typed into the message box before, recalled with the arrow
+/// keys like a shell's history.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    /// Oldest first.
+    entries: VecDeque<String>,
+    /// The entry being shown, while browsing.
+    position: Option<usize>,
+    /// The draft from before browsing began, to go back to.
+    stash: String,
+    /// Where the entries are saved, under the user's name.
+    key: String,
+}
+
+impl InputHistory {
+    pub fn restore(username: &str) -> Self {
+        let key = session::user_key(username, HISTORY_KEY);
+        Self {
+            entries: session::load(&key).unwrap_or_default(),
+            key,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_browsing(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Remembers a send as the latest entry and stops browsing.
+    pub fn record(&mut self, text: &str) {
+        self.position = None;
+        self.stash.clear();
+        if text.trim().is_empty() || self.entries.back().is_some_and(|last| last == text) {
+            return;
+        }
+        self.entries.push_back(text.to_string());
+        while self.entries.len() > HISTORY_LIMIT {
+            self.entries.pop_front();
+        }
+        session::save(&self.key, &self.entries);
+    }
+
+    /// The entry before the one shown, starting from the latest. `draft` is
+    /// kept to come back to.
+    pub fn older(&mut self, draft: &str) -> Option<&str> {
+        let position = match self.position {
+            None => {
+                self.stash = draft.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(position) => position.checked_sub(1)?,
+        };
+        self.position = Some(position);
+        self.entries.get(position).map(String::as_str)
+    }
+
+    /// The entry after the one shown; past the latest, the stashed draft.
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position? + 1;
+        if position < self.entries.len() {
+            self.position = Some(position);
+            return self.entries.get(position).cloned();
+        }
+        self.cancel()
+    }
+
+    /// Stops browsing, giving back the draft from before it began.
+    pub fn cancel(&mut self) -> Option<String> {
+        self.position.take()?;
+        Some(std::mem::take(&mut self.stash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> InputHistory {
+        InputHistory {
+            entries: entries.iter().map(|e| e.to_string()).collect(),
+            ..InputHistory::default()
+        }
+    }
+
+    #[test]
+    fn walks_back_and_forth_from_the_latest() {
+        let mut history = history(&["one", "two"]);
+        assert_eq!(history.newer(), None);
+        assert_eq!(history.older("draft"), Some("two"));
+        assert_eq!(history.older("two"), Some("one"));
+        assert_eq!(history.older("one"), None);
+        assert_eq!(history.newer().as_deref(), Some("two"));
+        assert_eq!(history.newer().as_deref(), Some("draft"));
+        assert!(!history.is_browsing());
+    }
+
+    #[test]
+    fn cancelling_gives_back_the_draft() {
+        let mut history = history(&["one"]);
+        history.older("half typed");
+        assert_eq!(history.cancel().as_deref(), Some("half typed"));
+        assert_eq!(history.cancel(), None);
+    }
+
+    #[test]
+    fn an_empty_history_has_nothing_to_recall() {
+        let mut history = InputHistory::default();
+        assert_eq!(history.older(""), None);
+        assert!(!history.is_browsing());
+    }
+}
@@ -0,0 +1,170 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::components::chat::{MessageData, UserProfile};
+use crate::services::session;
+
+/// Bump when the stored shape of messages or profiles changes; older entries
+/// are then ignored rather than misread.
+const FORMAT_VERSION: u32 = 1;
+/// Saved under the user's name, so a different login starts afresh.
+const PREFIX: &str = "history.";
+const ROOMS_KEY: &str = "history.rooms";
+const USERS_KEY: &str = "history.users";
+/// Messages kept per room.
+pub const PERSISTED_MESSAGES: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+fn room_key(room: Option<&str>) -> String {
+    match room {
+        Some(room) => format!("{}room.{}", PREFIX, room),
+        None => format!("{}lobby", PREFIX),
+    }
+}
+
+fn decode<T: DeserializeOwned>(stored: serde_json::Value) -> Option<T> {
+    let stored: Versioned<serde_json::Value> = serde_json::from_value(stored).ok()?;
+    if stored.version != FORMAT_VERSION {
+        return None;
+    }
+    serde_json::from_value(stored.data).ok()
+}
+
+fn load<T: DeserializeOwned>(username: &str, key: &str) -> Option<T> {
+    decode(session::load(&session::user_key(username, key))?)
+}
+
+/// Saves the newest of `messages`, halving them until they fit if storage
+/// is full.
+fn save_messages(username: &str, key: &str, messages: &[&MessageData]) {
+    let key = &session::user_key(username, key);
+    let mut keep = messages.len().min(PERSISTED_MESSAGES);
+    loop {
+        let stored = Versioned {
+            version: FORMAT_VERSION,
+            data: &messages[messages.len() - keep..],
+        };
+        match session::try_save(key, &stored) {
+            Ok(()) => return,
+            Err(e) if keep == 0 => {
+                log::warn!("could not store {}: {:?}", key, e);
+                return;
+            }
+            Err(_) => keep /= 2,
+        }
+    }
+}
+
+/// Stores the newest messages of each room, and the profiles needed to
+/// render them, for `username`.
+pub fn save<'a>(
+    username: &str,
+    messages: impl IntoIterator<Item = &'a MessageData>,
+    rooms: &[String],
+    users: impl IntoIterator<Item = &'a UserProfile>,
+) {
+    let messages: Vec<&MessageData> = messages.into_iter().collect();
+    for room in std::iter::once(None).chain(rooms.iter().map(|r| Some(r.as_str()))) {
+        let in_room: Vec<&MessageData> = messages
+            .iter()
+            .copied()
+            .filter(|m| m.room.as_deref() == room)
+            .collect();
+        save_messages(username, &room_key(room), &in_room);
+    }
+    let users: Vec<&UserProfile> = users.into_iter().collect();
+    for (key, value) in [
+        (ROOMS_KEY, serde_json::to_value(rooms)),
+        (USERS_KEY, serde_json::to_value(users)),
+    ] {
+        let stored = Versioned {
+            version: FORMAT_VERSION,
+            data: value.expect("history serializes"),
+        };
+        if let Err(e) = session::try_save(&session::user_key(username, key), &stored) {
+            log::warn!("could not store {}: {:?}", key, e);
+        }
+    }
+}
+
+/// What a previous visit left behind.
+#[derive(Default)]
+pub struct Restored {
+    pub rooms: Vec<String>,
+    /// Oldest first, lobby and rooms interleaved by timestamp.
+    pub messages: Vec<MessageData>,
+    pub users: Vec<UserProfile>,
+}
+
+pub fn restore(username: &str) -> Restored {
+    let rooms: Vec<String> = load(username, ROOMS_KEY).unwrap_or_default();
+    let mut messages: Vec<MessageData> = std::iter::once(None)
+        .chain(rooms.iter().map(|r| Some(r.as_str())))
+        .flat_map(|room| load::<Vec<MessageData>>(username, &room_key(room)).unwrap_or_default())
+        .collect();
+    messages.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    Restored {
+        rooms,
+        messages,
+        users: load(username, USERS_KEY).unwrap_or_default(),
+    }
+}
+
+pub fn clear(username: &str) {
+    session::remove_all(&session::user_key(username, PREFIX));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn reads_current_version() {
+        let stored = serde_json::json!({ "version": FORMAT_VERSION, "data": ["ops"] });
+        assert_eq!(decode::<Vec<String>>(stored), Some(vec!["ops".to_string()]));
+    }
+
+    #[test]
+    fn ignores_other_versions_and_shapes() {
+        let old = serde_json::json!({ "version": 0, "data": ["ops"] });
+        assert_eq!(decode::<Vec<String>>(old), None);
+        let bare = serde_json::json!(["ops"]);
+        assert_eq!(decode::<Vec<String>>(bare), None);
+        let wrong = serde_json::json!({ "version": FORMAT_VERSION, "data": 3 });
+        assert_eq!(decode::<Vec<String>>(wrong), None);
+    }
+
+    #[test]
+    fn room_keys_are_distinct() {
+        assert_ne!(room_key(None), room_key(Some("lobby")));
+        assert!(room_key(Some("ops")).starts_with(PREFIX));
+    }
+
+    // Local storage is only there in a browser.
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn logging_in_keeps_the_history() {
+        save(
+            "dave",
+            std::iter::empty(),
+            &["ops".to_string()],
+            std::iter::empty(),
+        );
+        session::log_in("dave", false);
+        session::log_in("erin", false);
+        assert_eq!(restore("dave").rooms, vec!["ops".to_string()]);
+        assert!(restore("erin").rooms.is_empty());
+        clear("dave");
+        assert!(restore("dave").rooms.is_empty());
+    }
+}
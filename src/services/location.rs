@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Position, PositionError, PositionOptions};
+use yew::Callback;
+
+use crate::services::message::Location;
+
+/// Zoom level of the map preview: a few streets around the spot.
+pub const PREVIEW_ZOOM: u32 = 15;
+/// OpenStreetMap tiles are this many pixels square.
+pub const TILE_SIZE: f64 = 256.0;
+/// Don't leave the user waiting on a GPS fix that never comes.
+const TIMEOUT_MS: u32 = 15_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocationError {
+    Denied,
+    Unavailable,
+    /// The browser has no geolocation at all.
+    Unsupported,
+}
+
+impl LocationError {
+    /// Locale key of the message to show.
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            LocationError::Denied => "location.denied",
+            LocationError::Unavailable => "location.unavailable",
+            LocationError::Unsupported => "location.unsupported",
+        }
+    }
+}
+
+/// Asks the browser where we are, prompting for permission the first time.
+pub fn current_position(on_done: Callback<Result<Location, LocationError>>) {
+    let geolocation = web_sys::window().and_then(|w| w.navigator().geolocation().ok());
+    let Some(geolocation) = geolocation else {
+        on_done.emit(Err(LocationError::Unsupported));
+        return;
+    };
+    // Exactly one of these runs; the other is leaked, which for a one-off
+    // request is a couple of small closures.
+    let on_success = {
+        let on_done = on_done.clone();
+        Closure::once_into_js(move |position: Position| {
+            let coords = position.coords();
+            on_done.emit(Ok(Location {
+                lat: coords.latitude(),
+                lon: coords.longitude(),
+            }));
+        })
+    };
+    let on_error = {
+        let on_done = on_done.clone();
+        Closure::once_into_js(move |error: PositionError| {
+            on_done.emit(Err(match error.code() {
+                PositionError::PERMISSION_DENIED => LocationError::Denied,
+                _ => LocationError::Unavailable,
+            }));
+        })
+    };
+    let mut options = PositionOptions::new();
+    options.timeout(TIMEOUT_MS);
+    let requested = geolocation.get_current_position_with_error_callback_and_options(
+        on_success.unchecked_ref(),
+        Some(on_error.unchecked_ref()),
+        &options,
+    );
+    if requested.is_err() {
+        on_done.emit(Err(LocationError::Unsupported));
+    }
+}
+
+/// The map tile holding a spot, and where in it the spot is, in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub url: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// The OpenStreetMap tile showing `location` at `zoom`, as in
+/// <https://wiki.openstreetmap.org/wiki/Slippy_map_tilenames>.
+pub fn tile(location: &Location, zoom: u32) -> Tile {
+    let n = f64::from(1u32 << zoom);
+    let lat = location.lat.clamp(-85.0511, 85.0511).to_radians();
+    let x = (location.lon + 180.0) / 360.0 * n;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * n;
+    let (x, y) = (x.clamp(0.0, n - 1e-9), y.clamp(0.0, n - 1e-9));
+    Tile {
+        url: format!(
+            "https://tile.openstreetmap.org/{}/{}/{}.png",
+            zoom,
+            x.floor(),
+            y.floor()
+        ),
+        x: x.fract() * TILE_SIZE,
+        y: y.fract() * TILE_SIZE,
+    }
+}
+
+/// Opens the spot in Google Maps.
+pub fn maps_url(location: &Location) -> String {
+    format!(
+        "https://www.google.com/maps/search/?api=1&query={},{}",
+        location.lat, location.lon
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_tile() {
+        let big_ben = Location {
+            lat: 51.500_729,
+            lon: -0.124_625,
+        };
+        let t = tile(&big_ben, 15);
+        assert_eq!(t.url, "https://tile.openstreetmap.org/15/16372/10897.png");
+        assert!((0.0..TILE_SIZE).contains(&t.x));
+        assert!((0.0..TILE_SIZE).contains(&t.y));
+    }
+
+    #[test]
+    fn stays_on_the_map_at_the_edges() {
+        let corner = tile(
+            &Location {
+                lat: 90.0,
+                lon: 180.0,
+            },
+            1,
+        );
+        assert_eq!(corner.url, "https://tile.openstreetmap.org/1/1/0.png");
+    }
+
+    #[test]
+    fn links_to_google_maps() {
+        let spot = Location {
+            lat: 48.8584,
+            lon: 2.2945,
+        };
+        assert_eq!(
+            maps_url(&spot),
+            "https://www.google.com/maps/search/?api=1&query=48.8584,2.2945"
+        );
+    }
+}
@@ -0,0 +1,1305 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::services::avatars::AvatarStyle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MsgTypes {
+    Users,
+    Register,
+    Message,
+    Status,
+    FileShare,
+    VideoCall,
+    Rename,
+    Diff,
+    ServerError,
+    Mute,
+    RoomInvite,
+    JoinRoom,
+    DeclineInvite,
+    AudioMessage,
+    Pin,
+    Unpin,
+    Whiteboard,
+    WhiteboardStroke,
+    Location,
+    Presence,
+    PinnedMessage,
+    UnpinMessage,
+    Confetti,
+    ThreadReply,
+    Read,
+    Announcement,
+    CodeReview,
+    CodeReviewComment,
+    Kick,
+    Stats,
+    StatsRequest,
+    Topic,
+    Survey,
+    SurveyResponse,
+    LinkPreview,
+    Broadcast,
+    Edit,
+    Delete,
+    Image,
+    Reaction,
+}
+
+impl MsgTypes {
+    fn takes_data_array(&self) -> bool {
+        matches!(self, MsgTypes::Users)
+    }
+
+    /// Frames that are posted to the history as a message. The server
+    /// stamps these with its `timestamp`.
+    pub fn is_posted(&self) -> bool {
+        matches!(
+            self,
+            MsgTypes::Message
+                | MsgTypes::ThreadReply
+                | MsgTypes::FileShare
+                | MsgTypes::AudioMessage
+                | MsgTypes::Image
+                | MsgTypes::Location
+                | MsgTypes::Diff
+                | MsgTypes::Whiteboard
+                | MsgTypes::CodeReview
+                | MsgTypes::Survey
+                | MsgTypes::Announcement
+        )
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketMessage {
+    pub message_type: MsgTypes,
+    pub data_array: Option<Vec<String>>,
+    pub data: Option<String>,
+    /// When the server relayed a posted frame, by its clock, in
+    /// milliseconds since the epoch. With the sender, it names the message
+    /// on every client alike. Never sent by clients.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<f64>,
+}
+
+impl WebSocketMessage {
+    /// Reads `data` as the frame's payload. A frame without one, or whose
+    /// payload doesn't parse, is logged and read as `None`: one bad frame
+    /// from the server shouldn't take the chat down.
+    pub fn payload<T: DeserializeOwned>(&self) -> Option<T> {
+        let Some(data) = &self.data else {
+            log::warn!("{:?} frame without data", self.message_type);
+            return None;
+        };
+        match serde_json::from_str(data) {
+            Ok(payload) => Some(payload),
+            Err(e) => {
+                log::warn!("bad {:?} frame: {}", self.message_type, e);
+                None
+            }
+        }
+    }
+}
+
+/// A shared file. Files are always offered as a download, never rendered
+/// inline. Small ones carry their content; the rest were uploaded.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileShare {
+    pub filename: String,
+    pub size_bytes: u64,
+    pub mime_type: String,
+    /// Empty when the content comes along.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub download_url: String,
+    /// The file itself, base64 encoded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// A pasted image sent inline rather than uploaded: the `Image` payload.
+/// The server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageShare {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    /// A `data:image/…` URL.
+    pub data_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+/// A recorded voice message: the `AudioMessage` payload. The server relays
+/// it with a `from` field added.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioClip {
+    pub url: String,
+    pub duration_secs: f64,
+}
+
+/// A shared location: the `Location` payload. The server relays it with a
+/// `from` field added.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Location {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Whether a user is around, shown as the dot on their avatar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Presence {
+    #[default]
+    Online,
+    /// Set automatically after a while without input.
+    Away,
+    /// Set by hand; silences alerts and stays until changed.
+    DoNotDisturb,
+}
+
+/// Payload of a `Presence` frame. The server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PresenceUpdate {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub presence: Presence,
+}
+
+/// Who a client is: sent as the `Register` payload and relayed back as each
+/// `Users` entry.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Registration {
+    pub name: String,
+    #[serde(default)]
+    pub avatar_style: AvatarStyle,
+    /// Set by the server on each entry; ignored when registering.
+    #[serde(default, skip_serializing_if = "Role::is_member")]
+    pub role: Role,
+}
+
+/// What a user may do to others. Moderators can mute and kick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Member,
+    Moderator,
+}
+
+impl Role {
+    pub fn is_member(&self) -> bool {
+        *self == Role::Member
+    }
+}
+
+impl Registration {
+    /// Reads a `Users` entry. Servers that predate avatar styles send bare
+    /// names, which get the default style.
+    pub fn parse(entry: &str) -> Self {
+        serde_json::from_str(entry).unwrap_or_else(|_| Self {
+            name: entry.to_string(),
+            avatar_style: AvatarStyle::default(),
+            role: Role::Member,
+        })
+    }
+}
+
+/// A unified diff pasted into the chat. The server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffShare {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub unified_diff: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+/// Payload of a `Rename` frame.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rename {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// An invitation to a private room. Declining sends it back unchanged as
+/// the `DeclineInvite` payload.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoomInvite {
+    pub room_name: String,
+    pub inviter: String,
+    pub invitee: String,
+}
+
+/// Payload of `JoinRoom`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JoinRoom {
+    pub room_name: String,
+}
+
+/// `Message` data we send. A plain lobby message still goes out as bare
+/// text; one posted to a room, or sent as a `/me` action, goes as JSON.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OutgoingMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub action: bool,
+    /// Set on a `ThreadReply`: the message it answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_id: Option<ThreadId>,
+}
+
+impl OutgoingMessage {
+    pub fn to_data(&self) -> String {
+        if self.room.is_none() && !self.action && self.thread_id.is_none() {
+            self.message.clone()
+        } else {
+            serde_json::to_string(self).unwrap()
+        }
+    }
+}
+
+/// The message a thread hangs off. Like a pin, it is named by its sender
+/// and the server's timestamp, as message ids are assigned by each client.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ThreadId {
+    pub from: String,
+    pub timestamp: f64,
+}
+
+impl ThreadId {
+    pub fn key(&self) -> PinKey {
+        PinKey::new(&self.from, self.timestamp)
+    }
+}
+
+/// A message pinned for everyone: the `Pin` and `Unpin` payload. Message
+/// ids are assigned by each client, so the message is named by its sender
+/// and the server's timestamp, and a copy of the text comes along for
+/// clients that have since trimmed it from their history.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pin {
+    pub from: String,
+    pub timestamp: f64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+impl Pin {
+    pub fn key(&self) -> PinKey {
+        PinKey::new(&self.from, self.timestamp)
+    }
+}
+
+/// Payload of an `UnpinMessage` frame: takes down the message a moderator
+/// pinned to `room`. The `PinnedMessage` payload is the message itself.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct UnpinMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+/// A sender's correction of their own message: the `Edit` payload. Like a
+/// pin, the message is named by its sender and timestamp. The server fills
+/// in `from`, and only passes on edits to the sender's own messages.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Edit {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub timestamp: f64,
+    /// The new text.
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+impl Edit {
+    /// The message edited, when `from` is its sender.
+    pub fn key(&self) -> PinKey {
+        PinKey::new(&self.from, self.timestamp)
+    }
+}
+
+/// A sender taking back their own message: the `Delete` payload. Named
+/// like an edit; the server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delete {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub timestamp: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+impl Delete {
+    pub fn key(&self) -> PinKey {
+        PinKey::new(&self.from, self.timestamp)
+    }
+}
+
+/// An emoji reaction put on or taken off a message: the `Reaction`
+/// payload. The message is named like a pin, by its sender and timestamp;
+/// the server fills in `from`, who reacted.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reaction {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    /// Who sent the message reacted to.
+    pub sender: String,
+    pub timestamp: f64,
+    pub emoji: String,
+    /// Takes the reaction back off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remove: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+impl Reaction {
+    pub fn key(&self) -> PinKey {
+        PinKey::new(&self.sender, self.timestamp)
+    }
+
+    /// Counts the reaction in `tallies`, or takes it out. Whether anything
+    /// changed.
+    pub fn apply(&self, tallies: &mut Vec<ReactionTally>) -> bool {
+        let index = tallies.iter().position(|t| t.emoji == self.emoji);
+        match (index, self.remove) {
+            (Some(index), false) if tallies[index].names.contains(&self.from) => false,
+            (Some(index), false) => {
+                tallies[index].names.push(self.from.clone());
+                true
+            }
+            (None, false) => {
+                tallies.push(ReactionTally {
+                    emoji: self.emoji.clone(),
+                    names: vec![self.from.clone()],
+                });
+                true
+            }
+            (Some(index), true) => {
+                let names = &mut tallies[index].names;
+                let before = names.len();
+                names.retain(|name| *name != self.from);
+                let changed = names.len() != before;
+                if names.is_empty() {
+                    tallies.remove(index);
+                }
+                changed
+            }
+            (None, true) => false,
+        }
+    }
+}
+
+/// Everyone who reacted to a message with one emoji, in the order they did.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReactionTally {
+    pub emoji: String,
+    pub names: Vec<String>,
+}
+
+/// What identifies a message on every client alike, and across reloads:
+/// its sender and the timestamp the server stamped it with. Pins and stars
+/// refer to messages by it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PinKey {
+    from: String,
+    timestamp_bits: u64,
+}
+
+impl PinKey {
+    pub fn new(from: &str, timestamp: f64) -> Self {
+        Self {
+            from: from.to_string(),
+            timestamp_bits: timestamp.to_bits(),
+        }
+    }
+}
+
+/// One freehand stroke: the `WhiteboardStroke` payload. The server fills in
+/// `from`. Undoing a stroke sends it again with `erase` set.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhiteboardStroke {
+    pub board: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    /// Unique among the author's strokes.
+    pub id: u32,
+    /// An SVG path, as in `<path d="…">`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub d: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub erase: bool,
+}
+
+/// A code snippet shared for review, with remarks on its lines. `language`
+/// is a fence tag such as `rust`; one we can't highlight shows as plain text.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CodeReview {
+    /// Chosen by the sender, so comments can name the review.
+    pub id: String,
+    #[serde(default)]
+    pub language: String,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<LineComment>,
+}
+
+/// A remark on one line of a [`CodeReview`], counting lines from 1. The
+/// server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LineComment {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub line: u32,
+    pub text: String,
+}
+
+/// Adds a comment to a review: the `CodeReviewComment` payload.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CodeReviewComment {
+    pub review: String,
+    #[serde(flatten)]
+    pub comment: LineComment,
+}
+
+/// How urgently a [`Broadcast`] has to be seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BroadcastSeverity {
+    /// Passes by as a toast.
+    #[default]
+    Info,
+    /// Stays in a banner until dismissed.
+    Warning,
+    /// Blocks the chat until acknowledged.
+    Critical,
+    /// The server is going away: everyone is disconnected and signed out.
+    ForcedDisconnect,
+}
+
+/// A system message from the operators to every client, such as warning of
+/// maintenance: the `Broadcast` payload. Unlike an [`Announcement`] it is
+/// not part of the chat.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Broadcast {
+    #[serde(default)]
+    pub severity: BroadcastSeverity,
+    pub message: String,
+}
+
+/// What a link leads to, from the OpenGraph tags of its page: the
+/// `LinkPreview` payload. We send just the `url`; the server fetches the
+/// page and answers with whatever of the rest it says.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct LinkPreview {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_name: Option<String>,
+}
+
+impl LinkPreview {
+    /// Whether there's nothing worth a card.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none()
+    }
+}
+
+/// Questions the server asks everyone: the `Survey` payload. Each survey
+/// is answered once, so `survey_id` must be unique.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Survey {
+    pub survey_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub questions: Vec<Question>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Question {
+    #[serde(rename = "textQuestion")]
+    Text { prompt: String },
+    /// Answered from 1 to `max`.
+    #[serde(rename = "ratingQuestion")]
+    Rating { prompt: String, max: u8 },
+    MultipleChoice {
+        prompt: String,
+        options: Vec<String>,
+    },
+}
+
+impl Question {
+    pub fn prompt(&self) -> &str {
+        match self {
+            Question::Text { prompt }
+            | Question::Rating { prompt, .. }
+            | Question::MultipleChoice { prompt, .. } => prompt,
+        }
+    }
+}
+
+/// One answer per question, in the survey's order: the `SurveyResponse`
+/// payload. The server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurveyResponse {
+    pub survey_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub answers: Vec<Answer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum Answer {
+    /// Left empty if the question was skipped.
+    Text(String),
+    Rating(u8),
+    /// The chosen option.
+    Choice(String),
+}
+
+/// Opens a shared canvas: the `Whiteboard` payload. The server fills in
+/// `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Whiteboard {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+/// A room's topic: the `Topic` payload, sent by a moderator to change it
+/// and by the server to everyone. An empty topic clears it. The server
+/// fills in `from`, leaving it empty when telling a newcomer the topic.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Topic {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    #[serde(default)]
+    pub topic: String,
+}
+
+/// Sets off confetti on everyone's screen: the `Confetti` payload. The
+/// server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ConfettiBurst {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+}
+
+/// A notice to everyone from the server or an admin: the `Announcement`
+/// payload. The server fills in `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Announcement {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub message: String,
+}
+
+/// How far someone has read in a room: the `Read` payload, naming the newest
+/// message they have seen by its sender and timestamp. The server fills in
+/// `from`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReadReceipt {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub room: Option<String>,
+    pub sender: String,
+    pub timestamp: f64,
+}
+
+/// A moderator muting `target`. Sent by the moderator, then relayed to
+/// everyone by the server, which fills in `from` and drops the target's
+/// messages until the time is up. A zero duration lifts the mute.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mute {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub target: String,
+    pub duration_secs: u64,
+}
+
+/// A moderator removing `target` from the chat: the `Kick` payload. Relayed
+/// to everyone like [`Mute`], after which the server closes the target's
+/// connection.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Kick {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub target: String,
+}
+
+/// Activity across the whole chat: the `Stats` payload, sent in answer to
+/// a `StatsRequest`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatStats {
+    /// Messages sent in each hour of the day, from midnight.
+    pub hourly: Vec<u64>,
+    /// The five busiest senders, busiest first.
+    pub top_senders: Vec<SenderCount>,
+    pub total: u64,
+    /// The date with the most messages, as `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub busiest_day: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SenderCount {
+    pub name: String,
+    pub count: u64,
+}
+
+/// A structured error from the server, such as a rate limit or a full room.
+/// Codes follow HTTP: 4xx means the request was refused, 5xx that the server
+/// itself failed.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ServerError {
+    pub code: u32,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Alert,
+}
+
+impl ServerError {
+    pub fn severity(&self) -> Severity {
+        match self.code {
+            400..=499 => Severity::Info,
+            _ => Severity::Alert,
+        }
+    }
+}
+
+/// WebRTC signaling carried in a `VideoCall` frame. Media flows peer to peer;
+/// only the session descriptions and ICE candidates go through the server.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CallSignal {
+    Offer {
+        sdp: String,
+    },
+    Answer {
+        sdp: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    IceCandidate {
+        candidate: String,
+        sdp_mid: Option<String>,
+        sdp_m_line_index: Option<u16>,
+    },
+    Hangup,
+}
+
+/// Every client sees every `VideoCall` frame; `to` says who it is for. The
+/// server fills in `from` on the way out.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VideoCall {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub signal: CallSignal,
+}
+
+impl WebSocketMessage {
+    pub fn builder(message_type: MsgTypes) -> WebSocketMessageBuilder<NoPayload> {
+        WebSocketMessageBuilder {
+            message_type,
+            data: None,
+            _payload: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// `data` was set on a message type that carries `data_array`.
+    UnexpectedData(MsgTypes),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::UnexpectedData(t) => {
+                write!(f, "{:?} messages take data_array, not data", t)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Builder state before any payload has been set; `build` is not available.
+pub struct NoPayload;
+/// Builder state once `data` has been set.
+pub struct WithData;
+
+/// Builds a [`WebSocketMessage`]. The payload type parameter makes it a
+/// compile error to call `build` without setting `data`. Only the server
+/// sends `data_array`, in `Users` frames, so there is no setter for it;
+/// building a `Users` frame is reported by `build` as a [`BuildError`].
+pub struct WebSocketMessageBuilder<P> {
+    message_type: MsgTypes,
+    data: Option<String>,
+    _payload: PhantomData<P>,
+}
+
+impl WebSocketMessageBuilder<NoPayload> {
+    pub fn data(self, data: impl Into<String>) -> WebSocketMessageBuilder<WithData> {
+        WebSocketMessageBuilder {
+            message_type: self.message_type,
+            data: Some(data.into()),
+            _payload: PhantomData,
+        }
+    }
+}
+
+impl WebSocketMessageBuilder<WithData> {
+    pub fn build(self) -> Result<WebSocketMessage, BuildError> {
+        if self.message_type.takes_data_array() {
+            return Err(BuildError::UnexpectedData(self.message_type));
+        }
+        Ok(WebSocketMessage {
+            message_type: self.message_type,
+            data_array: None,
+            data: self.data,
+            timestamp: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `npm test` runs these natively and again in a headless browser.
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn builds_data_message() {
+        let message = WebSocketMessage::builder(MsgTypes::Message)
+            .data("hello")
+            .build()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_string(&message).unwrap(),
+            r#"{"messageType":"message","dataArray":null,"data":"hello"}"#
+        );
+    }
+
+    #[test]
+    fn rejects_data_on_users_frames() {
+        let err = WebSocketMessage::builder(MsgTypes::Users)
+            .data("alice")
+            .build()
+            .unwrap_err();
+        assert_eq!(err, BuildError::UnexpectedData(MsgTypes::Users));
+    }
+
+    #[test]
+    fn every_data_type_builds_with_data() {
+        for t in [
+            MsgTypes::Register,
+            MsgTypes::Message,
+            MsgTypes::Status,
+            MsgTypes::FileShare,
+            MsgTypes::VideoCall,
+            MsgTypes::Rename,
+            MsgTypes::Diff,
+            MsgTypes::ServerError,
+            MsgTypes::Mute,
+            MsgTypes::RoomInvite,
+            MsgTypes::JoinRoom,
+            MsgTypes::DeclineInvite,
+            MsgTypes::Pin,
+            MsgTypes::Unpin,
+            MsgTypes::Whiteboard,
+            MsgTypes::WhiteboardStroke,
+            MsgTypes::Location,
+            MsgTypes::Presence,
+        ] {
+            let message = WebSocketMessage::builder(t).data("x").build().unwrap();
+            assert_eq!(message.message_type, t);
+            assert_eq!(message.data.as_deref(), Some("x"));
+            assert_eq!(message.data_array, None);
+        }
+    }
+
+    #[test]
+    fn build_errors_explain_the_mismatch() {
+        assert_eq!(
+            BuildError::UnexpectedData(MsgTypes::Users).to_string(),
+            "Users messages take data_array, not data"
+        );
+    }
+
+    #[test]
+    fn message_types_use_lowercase_wire_names() {
+        assert_eq!(
+            serde_json::to_string(&MsgTypes::FileShare).unwrap(),
+            r#""fileshare""#
+        );
+        assert_eq!(
+            serde_json::from_str::<MsgTypes>(r#""videocall""#).unwrap(),
+            MsgTypes::VideoCall
+        );
+    }
+
+    #[test]
+    fn reads_frame_payloads() {
+        let frame = |data: Option<&str>| WebSocketMessage {
+            message_type: MsgTypes::ServerError,
+            data_array: None,
+            data: data.map(String::from),
+            timestamp: None,
+        };
+        assert_eq!(
+            frame(Some(r#"{"code":429,"detail":"slow down"}"#)).payload(),
+            Some(ServerError {
+                code: 429,
+                detail: "slow down".into(),
+            })
+        );
+        assert_eq!(
+            frame(Some(r#"{"code":"oops"}"#)).payload::<ServerError>(),
+            None
+        );
+        assert_eq!(frame(None).payload::<ServerError>(), None);
+    }
+
+    #[test]
+    fn reads_server_users_frame() {
+        let message: WebSocketMessage = serde_json::from_str(
+            r#"{"messageType":"users","dataArray":["alice","bob"],"data":null}"#,
+        )
+        .unwrap();
+        assert_eq!(message.message_type, MsgTypes::Users);
+        assert_eq!(message.data_array.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn posted_frames_carry_the_server_timestamp() {
+        let message: WebSocketMessage = serde_json::from_str(
+            r#"{"messageType":"message","data":"{\"from\":\"alice\",\"message\":\"hi\"}","timestamp":1700000000000}"#,
+        )
+        .unwrap();
+        assert!(message.message_type.is_posted());
+        assert_eq!(message.timestamp, Some(1_700_000_000_000.0));
+        assert!(!MsgTypes::Read.is_posted());
+    }
+
+    #[test]
+    fn parses_registration_entries() {
+        assert_eq!(
+            Registration::parse(r#"{"name":"alice","avatarStyle":"pixel-art"}"#),
+            Registration {
+                name: "alice".into(),
+                avatar_style: AvatarStyle::PixelArt,
+                role: Role::Member,
+            }
+        );
+        assert_eq!(
+            Registration::parse(r#"{"name":"mod","role":"moderator"}"#).role,
+            Role::Moderator
+        );
+        assert_eq!(
+            Registration::parse("bob").avatar_style,
+            AvatarStyle::default()
+        );
+        assert_eq!(Registration::parse("bob").name, "bob");
+    }
+
+    #[test]
+    fn video_call_wire_format() {
+        let call = VideoCall {
+            from: String::new(),
+            to: "bob".into(),
+            signal: CallSignal::IceCandidate {
+                candidate: "candidate:1".into(),
+                sdp_mid: Some("0".into()),
+                sdp_m_line_index: Some(0),
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&call).unwrap(),
+            r#"{"to":"bob","kind":"iceCandidate","candidate":"candidate:1","sdpMid":"0","sdpMLineIndex":0}"#
+        );
+
+        let incoming: VideoCall =
+            serde_json::from_str(r#"{"from":"alice","to":"bob","kind":"hangup"}"#).unwrap();
+        assert_eq!(incoming.from, "alice");
+        assert_eq!(incoming.signal, CallSignal::Hangup);
+    }
+
+    #[test]
+    fn server_error_parses_and_grades() {
+        let error: ServerError =
+            serde_json::from_str(r#"{"code":429,"detail":"slow down"}"#).unwrap();
+        assert_eq!(error.detail, "slow down");
+        assert_eq!(error.severity(), Severity::Info);
+        let error = ServerError {
+            code: 503,
+            detail: "room full".into(),
+        };
+        assert_eq!(error.severity(), Severity::Alert);
+    }
+
+    #[test]
+    fn mute_wire_format() {
+        let mute = Mute {
+            from: String::new(),
+            target: "bob".into(),
+            duration_secs: 600,
+        };
+        let json = serde_json::to_string(&mute).unwrap();
+        assert_eq!(json, r#"{"target":"bob","durationSecs":600}"#);
+        assert_eq!(serde_json::from_str::<Mute>(&json).unwrap(), mute);
+    }
+
+    #[test]
+    fn read_receipt_wire_format() {
+        let receipt = ReadReceipt {
+            from: String::new(),
+            room: None,
+            sender: "alice".into(),
+            timestamp: 1.5,
+        };
+        let json = serde_json::to_string(&receipt).unwrap();
+        assert_eq!(json, r#"{"sender":"alice","timestamp":1.5}"#);
+        assert_eq!(serde_json::from_str::<ReadReceipt>(&json).unwrap(), receipt);
+    }
+
+    #[test]
+    fn outgoing_message_wire_format() {
+        let plain = OutgoingMessage {
+            room: None,
+            message: "hi".into(),
+            action: false,
+            thread_id: None,
+        };
+        assert_eq!(plain.to_data(), "hi");
+        let action = OutgoingMessage {
+            action: true,
+            ..plain.clone()
+        };
+        assert_eq!(action.to_data(), r#"{"message":"hi","action":true}"#);
+        let room = OutgoingMessage {
+            room: Some("ops".into()),
+            ..plain
+        };
+        assert_eq!(room.to_data(), r#"{"room":"ops","message":"hi"}"#);
+        let reply = OutgoingMessage {
+            room: None,
+            message: "hi".into(),
+            action: false,
+            thread_id: Some(ThreadId {
+                from: "alice".into(),
+                timestamp: 1.5,
+            }),
+        };
+        assert_eq!(
+            reply.to_data(),
+            r#"{"message":"hi","thread_id":{"from":"alice","timestamp":1.5}}"#
+        );
+    }
+
+    #[test]
+    fn code_review_comment_wire_format() {
+        let comment = CodeReviewComment {
+            review: "alice-1".into(),
+            comment: LineComment {
+                from: String::new(),
+                line: 3,
+                text: "off by one?".into(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_string(&comment).unwrap(),
+            r#"{"review":"alice-1","line":3,"text":"off by one?"}"#
+        );
+        // As relayed, with the sender filled in.
+        let relayed: CodeReviewComment = serde_json::from_str(
+            r#"{"from":"bob","review":"alice-1","line":3,"text":"off by one?"}"#,
+        )
+        .unwrap();
+        assert_eq!(relayed.comment.from, "bob");
+        assert_eq!(relayed.comment.line, 3);
+    }
+
+    #[test]
+    fn broadcast_wire_format() {
+        let broadcast: Broadcast = serde_json::from_str(
+            r#"{"severity":"forcedDisconnect","message":"Back in 10 minutes"}"#,
+        )
+        .unwrap();
+        assert_eq!(broadcast.severity, BroadcastSeverity::ForcedDisconnect);
+        let plain: Broadcast = serde_json::from_str(r#"{"message":"hello"}"#).unwrap();
+        assert_eq!(plain.severity, BroadcastSeverity::Info);
+    }
+
+    #[test]
+    fn link_preview_wire_format() {
+        let request = LinkPreview {
+            url: "https://example.com".into(),
+            ..LinkPreview::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"url":"https://example.com"}"#
+        );
+        let answer: LinkPreview = serde_json::from_str(
+            r#"{"url":"https://example.com","title":"Example","image_url":"https://example.com/og.png","site_name":"Example Inc"}"#,
+        )
+        .unwrap();
+        assert_eq!(answer.site_name.as_deref(), Some("Example Inc"));
+        assert_eq!(answer.description, None);
+        assert!(!answer.is_empty());
+        assert!(request.is_empty());
+    }
+
+    #[test]
+    fn survey_wire_format() {
+        let survey: Survey = serde_json::from_str(
+            r#"{"surveyId":"s1","questions":[
+                {"type":"textQuestion","prompt":"Anything else?"},
+                {"type":"ratingQuestion","prompt":"How was it?","max":5},
+                {"type":"multipleChoice","prompt":"Which day?","options":["Mon","Tue"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(survey.survey_id, "s1");
+        assert_eq!(
+            survey.questions[1],
+            Question::Rating {
+                prompt: "How was it?".into(),
+                max: 5
+            }
+        );
+        assert_eq!(survey.questions[2].prompt(), "Which day?");
+
+        let response = SurveyResponse {
+            survey_id: "s1".into(),
+            from: String::new(),
+            answers: vec![
+                Answer::Text(String::new()),
+                Answer::Rating(4),
+                Answer::Choice("Tue".into()),
+            ],
+        };
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"surveyId":"s1","answers":[{"type":"text","value":""},{"type":"rating","value":4},{"type":"choice","value":"Tue"}]}"#
+        );
+    }
+
+    #[test]
+    fn audio_clip_wire_format() {
+        let clip = AudioClip {
+            url: "http://host/voice.webm".into(),
+            duration_secs: 7.5,
+        };
+        let json = serde_json::to_string(&clip).unwrap();
+        assert_eq!(
+            json,
+            r#"{"url":"http://host/voice.webm","durationSecs":7.5}"#
+        );
+        assert_eq!(serde_json::from_str::<AudioClip>(&json).unwrap(), clip);
+    }
+
+    #[test]
+    fn inline_files_carry_content_and_no_url() {
+        let file = FileShare {
+            filename: "notes.txt".into(),
+            size_bytes: 5,
+            mime_type: "text/plain".into(),
+            download_url: String::new(),
+            content: Some("aGVsbG8=".into()),
+        };
+        let json = serde_json::to_string(&file).unwrap();
+        assert_eq!(
+            json,
+            r#"{"filename":"notes.txt","sizeBytes":5,"mimeType":"text/plain","content":"aGVsbG8="}"#
+        );
+        assert_eq!(serde_json::from_str::<FileShare>(&json).unwrap(), file);
+    }
+
+    #[test]
+    fn image_share_wire_format() {
+        let image = ImageShare {
+            from: String::new(),
+            data_url: "data:image/jpeg;base64,AAAA".into(),
+            room: None,
+        };
+        let json = serde_json::to_string(&image).unwrap();
+        assert_eq!(json, r#"{"dataUrl":"data:image/jpeg;base64,AAAA"}"#);
+        assert_eq!(serde_json::from_str::<ImageShare>(&json).unwrap(), image);
+    }
+
+    #[test]
+    fn room_invite_wire_format() {
+        let invite = RoomInvite {
+            room_name: "ops".into(),
+            inviter: "alice".into(),
+            invitee: "bob".into(),
+        };
+        let json = serde_json::to_string(&invite).unwrap();
+        assert_eq!(
+            json,
+            r#"{"roomName":"ops","inviter":"alice","invitee":"bob"}"#
+        );
+        assert_eq!(serde_json::from_str::<RoomInvite>(&json).unwrap(), invite);
+        assert_eq!(
+            serde_json::to_string(&MsgTypes::DeclineInvite).unwrap(),
+            r#""declineinvite""#
+        );
+    }
+
+    #[test]
+    fn presence_wire_format() {
+        let update = PresenceUpdate {
+            from: String::new(),
+            presence: Presence::DoNotDisturb,
+        };
+        let json = serde_json::to_string(&update).unwrap();
+        assert_eq!(json, r#"{"presence":"do-not-disturb"}"#);
+        let relayed: PresenceUpdate =
+            serde_json::from_str(r#"{"from":"bob","presence":"away"}"#).unwrap();
+        assert_eq!(relayed.presence, Presence::Away);
+    }
+
+    #[test]
+    fn pin_wire_format() {
+        let pin = Pin {
+            from: "alice".into(),
+            timestamp: 1_700_000_000_000.0,
+            message: "standup at 10".into(),
+            room: None,
+        };
+        let json = serde_json::to_string(&pin).unwrap();
+        assert_eq!(
+            json,
+            r#"{"from":"alice","timestamp":1700000000000.0,"message":"standup at 10"}"#
+        );
+        let parsed = serde_json::from_str::<Pin>(&json).unwrap();
+        assert_eq!(parsed.key(), PinKey::new("alice", 1_700_000_000_000.0));
+        assert_ne!(parsed.key(), PinKey::new("bob", 1_700_000_000_000.0));
+    }
+
+    #[test]
+    fn edit_wire_format() {
+        let edit = Edit {
+            from: String::new(),
+            timestamp: 1_700_000_000_000.0,
+            message: "standup at 11".into(),
+            room: Some("ops".into()),
+        };
+        let json = serde_json::to_string(&edit).unwrap();
+        assert_eq!(
+            json,
+            r#"{"timestamp":1700000000000.0,"message":"standup at 11","room":"ops"}"#
+        );
+        let echoed: Edit = serde_json::from_str(
+            r#"{"from":"alice","timestamp":1700000000000.0,"message":"standup at 11"}"#,
+        )
+        .unwrap();
+        assert_eq!(echoed.key(), PinKey::new("alice", 1_700_000_000_000.0));
+    }
+
+    #[test]
+    fn delete_wire_format() {
+        let delete = Delete {
+            from: String::new(),
+            timestamp: 1_700_000_000_000.0,
+            room: None,
+        };
+        let json = serde_json::to_string(&delete).unwrap();
+        assert_eq!(json, r#"{"timestamp":1700000000000.0}"#);
+        let echoed: Delete =
+            serde_json::from_str(r#"{"from":"alice","timestamp":1700000000000.0}"#).unwrap();
+        assert_eq!(echoed.key(), PinKey::new("alice", 1_700_000_000_000.0));
+    }
+
+    #[test]
+    fn reactions_tally_once_per_person() {
+        let mut reaction: Reaction = serde_json::from_str(
+            r#"{"from":"bob","sender":"alice","timestamp":1700000000000.0,"emoji":"👍"}"#,
+        )
+        .unwrap();
+        assert_eq!(reaction.key(), PinKey::new("alice", 1_700_000_000_000.0));
+        let mut tallies = Vec::new();
+        assert!(reaction.apply(&mut tallies));
+        assert!(!reaction.apply(&mut tallies));
+        reaction.from = "carol".into();
+        assert!(reaction.apply(&mut tallies));
+        assert_eq!(tallies[0].names, ["bob", "carol"]);
+
+        reaction.remove = true;
+        assert!(reaction.apply(&mut tallies));
+        assert!(!reaction.apply(&mut tallies));
+        reaction.from = "bob".into();
+        assert!(reaction.apply(&mut tallies));
+        assert!(tallies.is_empty());
+    }
+
+    #[test]
+    fn unpin_message_names_the_room() {
+        assert_eq!(
+            serde_json::to_string(&UnpinMessage::default()).unwrap(),
+            "{}"
+        );
+        let unpin: UnpinMessage = serde_json::from_str(r#"{"room":"ops"}"#).unwrap();
+        assert_eq!(unpin.room.as_deref(), Some("ops"));
+        assert_eq!(
+            serde_json::to_string(&MsgTypes::PinnedMessage).unwrap(),
+            r#""pinnedmessage""#
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+/// Outgoing frames held back while the socket is down. Bounded so a long
+/// outage cannot grow memory without limit: once full, the oldest frame is
+/// dropped to make room.
+pub struct MessageQueue {
+    items: VecDeque<String>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl MessageQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, message: String) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+            self.dropped += 1;
+        }
+        self.items.push_back(message);
+    }
+
+    /// Puts a frame that failed to send back at the head of the queue. If
+    /// that overfills it, the newest frame is the one dropped.
+    pub fn requeue(&mut self, message: String) {
+        self.items.push_front(message);
+        if self.items.len() > self.capacity {
+            self.items.pop_back();
+            self.dropped += 1;
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.items.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// How many frames have been dropped since the last `reset_dropped`.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn reset_dropped(&mut self) {
+        self.dropped = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageQueue;
+
+    #[test]
+    fn pops_in_order() {
+        let mut queue = MessageQueue::new(3);
+        queue.push("a".into());
+        queue.push("b".into());
+        assert_eq!(queue.pop().as_deref(), Some("a"));
+        assert_eq!(queue.pop().as_deref(), Some("b"));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn drops_oldest_when_full() {
+        let mut queue = MessageQueue::new(2);
+        for m in ["a", "b", "c"] {
+            queue.push(m.into());
+        }
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pop().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn requeue_goes_to_front() {
+        let mut queue = MessageQueue::new(2);
+        queue.push("b".into());
+        queue.requeue("a".into());
+        assert_eq!(queue.pop().as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn requeue_into_a_full_queue_counts_the_drop() {
+        let mut queue = MessageQueue::new(2);
+        queue.push("b".into());
+        queue.push("c".into());
+        queue.requeue("a".into());
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.dropped(), 1);
+        assert_eq!(queue.pop().as_deref(), Some("a"));
+        assert_eq!(queue.pop().as_deref(), Some("b"));
+    }
+}
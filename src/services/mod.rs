@@ -1,2 +1,37 @@
 pub mod websocket;
 pub mod event_bus;
+pub mod export;
+pub mod history;
+pub mod session;
+pub mod names;
+pub mod message;
+pub mod message_queue;
+pub mod sse;
+pub mod transport;
+pub mod upload;
+pub mod csrf;
+pub mod receipts;
+pub mod call;
+pub mod avatars;
+pub mod settings;
+pub mod alerts;
+pub mod bot;
+pub mod local_history;
+pub mod commands;
+pub mod deflate;
+pub mod completion;
+pub mod recorder;
+pub mod whiteboard;
+pub mod location;
+pub mod clipboard;
+pub mod focus;
+pub mod idle;
+pub mod shortcuts;
+pub mod confetti;
+pub mod gifs;
+pub mod stickers;
+pub mod translate;
+pub mod rate_limit;
+pub mod dedupe;
+pub mod unread;
+pub mod input_history;
@@ -0,0 +1,72 @@
+const ADJECTIVES: &[&str] = &[
+    "brave", "calm", "clever", "cosy", "eager", "fuzzy", "gentle", "happy", "jolly", "lucky",
+    "mellow", "nimble", "proud", "quiet", "swift", "witty",
+];
+
+const NOUNS: &[&str] = &[
+    "badger", "beaver", "falcon", "ferret", "gecko", "heron", "koala", "lynx", "marmot", "otter",
+    "panda", "puffin", "raven", "seal", "walrus", "wombat",
+];
+
+const MAX_USERNAME_LEN: usize = 32;
+
+/// The rules every username has to follow, whether typed or generated.
+pub fn is_valid_username(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= MAX_USERNAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Builds a guest name like `brave-otter-42`. The same seed always yields the
+/// same name.
+pub fn generate_name(seed: u64) -> String {
+    let mut state = seed;
+    let adjective = ADJECTIVES[(next(&mut state) % ADJECTIVES.len() as u64) as usize];
+    let noun = NOUNS[(next(&mut state) % NOUNS.len() as u64) as usize];
+    let number = next(&mut state) % 100;
+    format!("{}-{}-{}", adjective, noun, number)
+}
+
+// splitmix64: tiny, dependency-free, and good enough to pick words.
+fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_name() {
+        assert_eq!(generate_name(7), generate_name(7));
+    }
+
+    #[test]
+    fn different_seeds_vary() {
+        let names: std::collections::HashSet<_> = (0..20).map(generate_name).collect();
+        assert!(names.len() > 1);
+    }
+
+    #[test]
+    fn generated_names_are_valid() {
+        for seed in 0..1000 {
+            let name = generate_name(seed);
+            assert!(is_valid_username(&name), "{} is not valid", name);
+            assert_eq!(name.split('-').count(), 3);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_usernames() {
+        assert!(!is_valid_username(""));
+        assert!(!is_valid_username("has space"));
+        assert!(!is_valid_username(&"a".repeat(MAX_USERNAME_LEN + 1)));
+        assert!(is_valid_username("alice_99"));
+    }
+}
@@ -0,0 +1,105 @@
+/// Messages that may go out back to back.
+pub const BURST: f64 = 5.0;
+/// How long an empty bucket takes to fill up again, in milliseconds, so a
+/// steady `BURST` messages per this long.
+pub const REFILL_MS: f64 = 10_000.0;
+
+/// Keeps accidental floods, like a held-down Enter or a pasted wall of
+/// lines, from reaching the server. Each send takes a token; tokens come
+/// back steadily up to `BURST`.
+#[derive(Debug)]
+pub struct TokenBucket {
+    tokens: f64,
+    /// When `tokens` was last brought up to date.
+    updated_at: f64,
+}
+
+impl TokenBucket {
+    /// A full bucket.
+    pub fn new(now: f64) -> Self {
+        Self {
+            tokens: BURST,
+            updated_at: now,
+        }
+    }
+
+    /// Takes a token for a send. Without one, returns how many milliseconds
+    /// until the next comes back.
+    pub fn take(&mut self, now: f64) -> Result<(), f64> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        Err((1.0 - self.tokens) * REFILL_MS / BURST)
+    }
+
+    fn refill(&mut self, now: f64) {
+        let elapsed = (now - self.updated_at).max(0.0);
+        self.tokens = (self.tokens + elapsed * BURST / REFILL_MS).min(BURST);
+        self.updated_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    const PER_TOKEN_MS: f64 = REFILL_MS / BURST;
+
+    fn drained() -> TokenBucket {
+        let mut bucket = TokenBucket::new(0.0);
+        for _ in 0..BURST as usize {
+            bucket.take(0.0).unwrap();
+        }
+        bucket
+    }
+
+    #[test]
+    fn a_burst_goes_through_then_waits() {
+        let mut bucket = drained();
+        assert_eq!(bucket.take(0.0), Err(PER_TOKEN_MS));
+        assert_eq!(bucket.take(PER_TOKEN_MS / 2.0), Err(PER_TOKEN_MS / 2.0));
+    }
+
+    #[test]
+    fn a_sixth_message_within_three_seconds_is_blocked() {
+        let mut bucket = TokenBucket::new(0.0);
+        for i in 0..5 {
+            assert!(bucket.take(i as f64 * 100.0).is_ok());
+        }
+        assert!(bucket.take(1_500.0).is_err());
+    }
+
+    #[test]
+    fn tokens_come_back_one_at_a_time() {
+        let mut bucket = drained();
+        assert!(bucket.take(PER_TOKEN_MS).is_ok());
+        assert!(bucket.take(PER_TOKEN_MS).is_err());
+    }
+
+    #[test]
+    fn refilling_stops_at_a_full_burst() {
+        let mut bucket = drained();
+        let later = REFILL_MS * 10.0;
+        for _ in 0..BURST as usize {
+            assert!(bucket.take(later).is_ok());
+        }
+        assert!(bucket.take(later).is_err());
+    }
+
+    #[test]
+    fn a_clock_going_backwards_adds_nothing() {
+        let mut bucket = TokenBucket::new(1_000.0);
+        for _ in 0..BURST as usize {
+            bucket.take(1_000.0).unwrap();
+        }
+        assert!(bucket.take(0.0).is_err());
+    }
+}
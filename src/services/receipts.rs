@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use crate::services::message::ReadReceipt;
+
+/// How long the view has to settle before a receipt goes out, in
+/// milliseconds, so scrolling through the history doesn't send a stream.
+pub const DEBOUNCE_MS: u32 = 1_000;
+
+/// Who has read how far in each room. A receipt names the newest message
+/// its sender has seen, which stands for everything before it too, so only
+/// the newest timestamp per reader is kept.
+#[derive(Debug, Default)]
+pub struct Receipts {
+    read_up_to: HashMap<(Option<String>, String), f64>,
+}
+
+impl Receipts {
+    /// Records a receipt. Returns whether the reader got further than
+    /// before.
+    pub fn record(&mut self, receipt: &ReadReceipt) -> bool {
+        let read = self
+            .read_up_to
+            .entry((receipt.room.clone(), receipt.from.clone()))
+            .or_insert(f64::NEG_INFINITY);
+        if receipt.timestamp <= *read {
+            return false;
+        }
+        *read = receipt.timestamp;
+        true
+    }
+
+    /// Everyone who has read the message `from` sent at `timestamp`, in name
+    /// order. The sender isn't counted as reading their own message.
+    pub fn seen_by(&self, room: &Option<String>, from: &str, timestamp: f64) -> Vec<&str> {
+        let mut readers: Vec<&str> = self
+            .read_up_to
+            .iter()
+            .filter(|((r, reader), read)| r == room && reader != from && **read >= timestamp)
+            .map(|((_, reader), _)| reader.as_str())
+            .collect();
+        readers.sort_unstable();
+        readers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(from: &str, room: Option<&str>, timestamp: f64) -> ReadReceipt {
+        ReadReceipt {
+            from: from.into(),
+            room: room.map(Into::into),
+            sender: "alice".into(),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn a_receipt_covers_older_messages() {
+        let mut receipts = Receipts::default();
+        assert!(receipts.record(&receipt("bob", None, 20.0)));
+        assert!(!receipts.record(&receipt("bob", None, 10.0)));
+        assert_eq!(receipts.seen_by(&None, "alice", 15.0), ["bob"]);
+        assert!(receipts.seen_by(&None, "alice", 25.0).is_empty());
+    }
+
+    #[test]
+    fn senders_and_other_rooms_are_not_counted() {
+        let mut receipts = Receipts::default();
+        receipts.record(&receipt("alice", None, 20.0));
+        receipts.record(&receipt("carol", Some("games"), 20.0));
+        receipts.record(&receipt("bob", None, 20.0));
+        assert_eq!(receipts.seen_by(&None, "alice", 10.0), ["bob"]);
+        assert_eq!(
+            receipts.seen_by(&Some("games".into()), "alice", 10.0),
+            ["carol"]
+        );
+    }
+}
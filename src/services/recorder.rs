@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    Blob, BlobEvent, BlobPropertyBag, MediaRecorder, MediaRecorderOptions, MediaStream,
+    MediaStreamConstraints, MediaStreamTrack,
+};
+use yew::Callback;
+
+const PREFERRED_TYPE: &str = "audio/webm";
+
+/// A finished voice recording.
+pub struct Recording {
+    pub blob: Blob,
+    /// Measured while recording: WebM files from `MediaRecorder` often don't
+    /// say how long they are, so players can't tell on their own.
+    pub duration_secs: f64,
+}
+
+/// A microphone recording in progress. Keep it until `on_done` has fired;
+/// dropping it earlier discards the audio and releases the microphone.
+pub struct Recorder {
+    recorder: MediaRecorder,
+    stream: MediaStream,
+    started: f64,
+    _on_data: Closure<dyn FnMut(BlobEvent)>,
+    _on_stop: Closure<dyn FnMut()>,
+}
+
+impl Recorder {
+    /// Asks for the microphone and starts recording.
+    pub async fn start(on_done: Callback<Recording>) -> Result<Self, JsValue> {
+        let devices = web_sys::window()
+            .ok_or("no window")?
+            .navigator()
+            .media_devices()?;
+        let mut constraints = MediaStreamConstraints::new();
+        constraints.audio(&JsValue::TRUE);
+        let stream: MediaStream =
+            JsFuture::from(devices.get_user_media_with_constraints(&constraints)?)
+                .await?
+                .unchecked_into();
+
+        // Safari can't record WebM; let it pick its own format.
+        let recorder = if MediaRecorder::is_type_supported(PREFERRED_TYPE) {
+            let mut options = MediaRecorderOptions::new();
+            options.mime_type(PREFERRED_TYPE);
+            MediaRecorder::new_with_media_stream_and_media_recorder_options(&stream, &options)?
+        } else {
+            MediaRecorder::new_with_media_stream(&stream)?
+        };
+
+        let chunks = Rc::new(RefCell::new(js_sys::Array::new()));
+        let on_data = {
+            let chunks = chunks.clone();
+            Closure::wrap(Box::new(move |e: BlobEvent| {
+                if let Some(data) = e.data() {
+                    chunks.borrow().push(&data);
+                }
+            }) as Box<dyn FnMut(BlobEvent)>)
+        };
+        let started = js_sys::Date::now();
+        let on_stop = {
+            let recorder = recorder.clone();
+            let stream = stream.clone();
+            Closure::wrap(Box::new(move || {
+                stop_tracks(&stream);
+                let mut options = BlobPropertyBag::new();
+                options.type_(&recorder.mime_type());
+                let blob = Blob::new_with_blob_sequence_and_options(&chunks.borrow(), &options);
+                match blob {
+                    Ok(blob) => {
+                        let recording = Recording {
+                            blob,
+                            duration_secs: (js_sys::Date::now() - started) / 1000.0,
+                        };
+                        // The receiver is likely to drop us, closure and all,
+                        // so hand over once this handler has returned.
+                        let on_done = on_done.clone();
+                        spawn_local(async move { on_done.emit(recording) });
+                    }
+                    Err(e) => log::error!("recording: {:?}", e),
+                }
+            }) as Box<dyn FnMut()>)
+        };
+        recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+        recorder.set_onstop(Some(on_stop.as_ref().unchecked_ref()));
+        recorder.start()?;
+
+        Ok(Self {
+            recorder,
+            stream,
+            started,
+            _on_data: on_data,
+            _on_stop: on_stop,
+        })
+    }
+
+    pub fn elapsed_secs(&self) -> f64 {
+        (js_sys::Date::now() - self.started) / 1000.0
+    }
+
+    /// Finishes the recording; `on_done` gets it shortly after.
+    pub fn stop(&self) {
+        if let Err(e) = self.recorder.stop() {
+            log::error!("recording: {:?}", e);
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // The handlers are freed with us, so make sure they can't fire.
+        self.recorder.set_ondataavailable(None);
+        self.recorder.set_onstop(None);
+        let _ = self.recorder.stop();
+        stop_tracks(&self.stream);
+    }
+}
+
+/// A file extension for a recording of the given MIME type, e.g.
+/// `audio/webm;codecs=opus` is `webm`.
+pub fn extension(mime_type: &str) -> &str {
+    match mime_type.split(';').next().unwrap_or_default().trim() {
+        "audio/mp4" => "m4a",
+        "audio/mpeg" => "mp3",
+        mime => mime
+            .strip_prefix("audio/")
+            .filter(|ext| !ext.is_empty())
+            .unwrap_or("audio"),
+    }
+}
+
+fn stop_tracks(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        track.unchecked_into::<MediaStreamTrack>().stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::extension;
+
+    #[test]
+    fn extensions() {
+        assert_eq!(extension("audio/webm;codecs=opus"), "webm");
+        assert_eq!(extension("audio/ogg"), "ogg");
+        assert_eq!(extension("audio/mp4"), "m4a");
+        assert_eq!(extension(""), "audio");
+    }
+}
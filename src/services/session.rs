@@ -0,0 +1,140 @@
+use gloo_storage::errors::StorageError;
+use gloo_storage::{LocalStorage, Storage};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Every key this app persists starts with this prefix.
+const KEY_PREFIX: &str = "yewchat.";
+const USERNAME_KEY: &str = "yewchat.username";
+/// State that is one user's own is saved under `user.<name>.`, so forgetting
+/// them removes just that, and a different login never picks it up.
+/// Anything else, such as settings, belongs to the device.
+const USER_PREFIX: &str = "user.";
+
+/// The username saved by a previous "remember me" login, if any.
+pub fn stored_username() -> Option<String> {
+    LocalStorage::get(USERNAME_KEY).ok()
+}
+
+/// The name under which `name` is saved for `username`.
+pub fn user_key(username: &str, name: &str) -> String {
+    format!("{}{}.{}", USER_PREFIX, username, name)
+}
+
+/// Whether [`forget`] for `username` removes what is saved under `name`.
+pub fn belongs_to(username: &str, name: &str) -> bool {
+    name.starts_with(&user_key(username, ""))
+}
+
+pub fn remember(username: &str) {
+    if let Err(e) = LocalStorage::set(USERNAME_KEY, username) {
+        log::error!("could not store username: {:?}", e);
+    }
+}
+
+/// Reads a piece of persisted client state saved with [`save`].
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    LocalStorage::get(format!("{}{}", KEY_PREFIX, name)).ok()
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    if let Err(e) = try_save(name, value) {
+        log::error!("could not store {}: {:?}", name, e);
+    }
+}
+
+/// Like [`save`], but leaves the error to the caller, e.g. to retry with
+/// less data when storage is full.
+pub fn try_save<T: Serialize>(name: &str, value: &T) -> Result<(), StorageError> {
+    LocalStorage::set(format!("{}{}", KEY_PREFIX, name), value)
+}
+
+/// The names of every entry saved, without the app's prefix.
+fn names() -> Vec<String> {
+    let storage = LocalStorage::raw();
+    (0..LocalStorage::length())
+        .filter_map(|i| storage.key(i).ok().flatten())
+        .filter_map(|k| k.strip_prefix(KEY_PREFIX).map(String::from))
+        .collect()
+}
+
+/// Removes every entry saved under a name starting with `prefix`.
+pub fn remove_all(prefix: &str) {
+    for name in names().into_iter().filter(|n| n.starts_with(prefix)) {
+        LocalStorage::delete(format!("{}{}", KEY_PREFIX, name));
+    }
+}
+
+/// Records a login. Without "remember me" the name just isn't stored;
+/// nothing saved before is touched.
+pub fn log_in(username: &str, remember_me: bool) {
+    if remember_me {
+        remember(username);
+    }
+}
+
+/// Removes the stored username along with everything saved for that user.
+/// Settings and other device-wide state stay.
+pub fn forget(username: &str) {
+    LocalStorage::delete(USERNAME_KEY);
+    remove_all(&user_key(username, ""));
+}
+
+/// Carries what was saved for `old` over to `new`, after a rename.
+pub fn move_user(old: &str, new: &str) {
+    let prefix = user_key(old, "");
+    for name in names() {
+        let Some(rest) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let from = format!("{}{}", KEY_PREFIX, name);
+        if let Ok(value) = LocalStorage::get::<serde_json::Value>(&from) {
+            save(&user_key(new, rest), &value);
+        }
+        LocalStorage::delete(from);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn user_keys_are_scoped_by_name() {
+        assert_eq!(user_key("alice", "muted"), "user.alice.muted");
+        assert!(belongs_to("alice", &user_key("alice", "muted")));
+        assert!(!belongs_to("al", &user_key("alice", "muted")));
+        assert!(!belongs_to("alice", "settings"));
+    }
+
+    // Local storage is only there in a browser.
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn forgetting_a_user_keeps_everyone_elses_state() {
+        save(&user_key("alice", "muted"), &["bob"]);
+        save(&user_key("bob", "muted"), &["alice"]);
+        save("settings", &1);
+        remember("alice");
+        forget("alice");
+        assert_eq!(stored_username(), None);
+        assert_eq!(load::<Vec<String>>(&user_key("alice", "muted")), None);
+        assert!(load::<Vec<String>>(&user_key("bob", "muted")).is_some());
+        assert_eq!(load::<u32>("settings"), Some(1));
+        forget("bob");
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn renaming_moves_a_users_state() {
+        save(&user_key("carol", "starred"), &[1]);
+        move_user("carol", "caz");
+        assert_eq!(load::<Vec<u32>>(&user_key("caz", "starred")), Some(vec![1]));
+        assert_eq!(load::<Vec<u32>>(&user_key("carol", "starred")), None);
+        forget("caz");
+    }
+}
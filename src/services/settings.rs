@@ -0,0 +1,402 @@
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use yew::{use_context, Callback};
+
+use crate::colors::Palette;
+use crate::i18n::Language;
+use crate::services::history::DEFAULT_HISTORY_LIMIT;
+use crate::services::session;
+
+/// Settings belong to the device, not to whoever logs in, so forgetting a
+/// user keeps them.
+const SETTINGS_KEY: &str = "settings";
+
+/// The choices for going away when idle, in minutes; 0 never does.
+pub const AWAY_AFTER_CHOICES: [u32; 5] = [1, 5, 10, 30, 0];
+
+/// The choices for how many characters a message holds before it is
+/// collapsed behind "Show more".
+pub const LONG_MESSAGE_CHOICES: [usize; 5] = [300, 500, 600, 1000, 2000];
+
+/// The choices for how long announcements stay pinned, in minutes.
+pub const ANNOUNCEMENT_PIN_CHOICES: [u32; 4] = [15, 60, 240, 1440];
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub fn toggled(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    /// Class on the app root; `styles.css` keys dark overrides off it.
+    pub fn class(self) -> &'static str {
+        match self {
+            Theme::Light => "",
+            Theme::Dark => "dark-mode",
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Light => Palette::Light,
+            Theme::Dark => Palette::Dark,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+    #[default]
+    TwentyFourHour,
+    TwelveHour,
+    Hidden,
+}
+
+impl TimestampFormat {
+    pub const ALL: [TimestampFormat; 3] = [
+        TimestampFormat::TwentyFourHour,
+        TimestampFormat::TwelveHour,
+        TimestampFormat::Hidden,
+    ];
+
+    /// Locale key for the format's name.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            TimestampFormat::TwentyFourHour => "settings.timestamps_24h",
+            TimestampFormat::TwelveHour => "settings.timestamps_12h",
+            TimestampFormat::Hidden => "settings.timestamps_hidden",
+        }
+    }
+
+    fn format_parts(self, hours: u32, minutes: u32) -> Option<String> {
+        match self {
+            TimestampFormat::TwentyFourHour => Some(format!("{:02}:{:02}", hours, minutes)),
+            TimestampFormat::TwelveHour => {
+                let suffix = if hours < 12 { "AM" } else { "PM" };
+                let hours = match hours % 12 {
+                    0 => 12,
+                    h => h,
+                };
+                Some(format!("{}:{:02} {}", hours, minutes, suffix))
+            }
+            TimestampFormat::Hidden => None,
+        }
+    }
+
+    /// Formats a millisecond timestamp in local time, or `None` when
+    /// timestamps are hidden.
+    pub fn format(self, timestamp: f64) -> Option<String> {
+        let date = js_sys::Date::new(&JsValue::from_f64(timestamp));
+        self.format_parts(date.get_hours(), date.get_minutes())
+    }
+}
+
+/// Client-side preferences, persisted as one entry in localStorage. Fields
+/// missing from an older save take their defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub theme: Theme,
+    pub language: Language,
+    /// Desktop notifications for messages that arrive while the tab is hidden.
+    pub notifications: bool,
+    pub sounds: bool,
+    /// Tell others which messages we have read.
+    pub read_receipts: bool,
+    /// Silences sounds and notifications and shows us as busy.
+    pub do_not_disturb: bool,
+    /// Masks swear words in the messages shown.
+    pub profanity_filter: bool,
+    pub history_limit: usize,
+    pub timestamp_format: TimestampFormat,
+    /// Minutes without input before we show as away; 0 for never.
+    pub away_after_minutes: u32,
+    /// Characters of text, code aside, before a message is collapsed.
+    pub long_message_chars: usize,
+    /// Minutes an announcement stays pinned above the messages.
+    pub announcement_pin_minutes: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            language: Language::default(),
+            notifications: false,
+            sounds: true,
+            read_receipts: true,
+            do_not_disturb: false,
+            profanity_filter: false,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            timestamp_format: TimestampFormat::default(),
+            away_after_minutes: 5,
+            long_message_chars: 500,
+            announcement_pin_minutes: 60,
+        }
+    }
+}
+
+pub fn load() -> Settings {
+    session::load(SETTINGS_KEY).unwrap_or_default()
+}
+
+pub fn save(settings: &Settings) {
+    session::save(SETTINGS_KEY, settings);
+}
+
+/// The current settings, shared through context. Setters hand the updated
+/// copy to the provider, which saves it and re-renders every consumer.
+#[derive(Clone, PartialEq)]
+pub struct SettingsHandle {
+    settings: Rc<Settings>,
+    on_change: Callback<Settings>,
+}
+
+impl SettingsHandle {
+    pub fn new(settings: Rc<Settings>, on_change: Callback<Settings>) -> Self {
+        Self {
+            settings,
+            on_change,
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Settings)) {
+        let mut settings = (*self.settings).clone();
+        f(&mut settings);
+        if settings != *self.settings {
+            self.on_change.emit(settings);
+        }
+    }
+
+    /// The theme itself is read through [`ThemeContext`].
+    pub fn set_theme(&self, theme: Theme) {
+        self.update(|s| s.theme = theme);
+    }
+
+    pub fn language(&self) -> Language {
+        self.settings.language
+    }
+
+    pub fn set_language(&self, language: Language) {
+        self.update(|s| s.language = language);
+    }
+
+    pub fn notifications(&self) -> bool {
+        self.settings.notifications
+    }
+
+    pub fn set_notifications(&self, enabled: bool) {
+        self.update(|s| s.notifications = enabled);
+    }
+
+    pub fn sounds(&self) -> bool {
+        self.settings.sounds
+    }
+
+    pub fn set_sounds(&self, enabled: bool) {
+        self.update(|s| s.sounds = enabled);
+    }
+
+    pub fn read_receipts(&self) -> bool {
+        self.settings.read_receipts
+    }
+
+    pub fn set_read_receipts(&self, enabled: bool) {
+        self.update(|s| s.read_receipts = enabled);
+    }
+
+    pub fn do_not_disturb(&self) -> bool {
+        self.settings.do_not_disturb
+    }
+
+    pub fn set_do_not_disturb(&self, enabled: bool) {
+        self.update(|s| s.do_not_disturb = enabled);
+    }
+
+    pub fn profanity_filter(&self) -> bool {
+        self.settings.profanity_filter
+    }
+
+    pub fn set_profanity_filter(&self, enabled: bool) {
+        self.update(|s| s.profanity_filter = enabled);
+    }
+
+    pub fn history_limit(&self) -> usize {
+        self.settings.history_limit
+    }
+
+    pub fn set_history_limit(&self, limit: usize) {
+        self.update(|s| s.history_limit = limit);
+    }
+
+    pub fn timestamp_format(&self) -> TimestampFormat {
+        self.settings.timestamp_format
+    }
+
+    pub fn set_timestamp_format(&self, format: TimestampFormat) {
+        self.update(|s| s.timestamp_format = format);
+    }
+
+    pub fn away_after_minutes(&self) -> u32 {
+        self.settings.away_after_minutes
+    }
+
+    pub fn set_away_after_minutes(&self, minutes: u32) {
+        self.update(|s| s.away_after_minutes = minutes);
+    }
+
+    pub fn long_message_chars(&self) -> usize {
+        self.settings.long_message_chars
+    }
+
+    pub fn set_long_message_chars(&self, chars: usize) {
+        self.update(|s| s.long_message_chars = chars);
+    }
+
+    pub fn announcement_pin_minutes(&self) -> u32 {
+        self.settings.announcement_pin_minutes
+    }
+
+    pub fn set_announcement_pin_minutes(&self, minutes: u32) {
+        self.update(|s| s.announcement_pin_minutes = minutes);
+    }
+
+    pub fn reset(&self) {
+        self.update(|s| *s = Settings::default());
+    }
+}
+
+/// Just the theme, provided separately so components that only care about
+/// colors don't re-render on unrelated settings changes.
+#[derive(Clone, PartialEq)]
+pub struct ThemeContext {
+    theme: Theme,
+    on_change: Callback<Theme>,
+}
+
+impl ThemeContext {
+    pub fn new(theme: Theme, on_change: Callback<Theme>) -> Self {
+        Self { theme, on_change }
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme
+    }
+
+    pub fn set(&self, theme: Theme) {
+        if theme != self.theme {
+            self.on_change.emit(theme);
+        }
+    }
+
+    pub fn toggle(&self) {
+        self.on_change.emit(self.theme.toggled());
+    }
+}
+
+pub fn use_settings() -> SettingsHandle {
+    use_context::<SettingsHandle>().expect("settings context to be set")
+}
+
+pub fn use_theme() -> ThemeContext {
+    use_context::<ThemeContext>().expect("theme context to be set")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[test]
+    fn missing_fields_take_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{"theme":"dark"}"#).unwrap();
+        assert_eq!(settings.theme, Theme::Dark);
+        assert_eq!(settings.history_limit, DEFAULT_HISTORY_LIMIT);
+        assert!(settings.sounds);
+        assert!(settings.read_receipts);
+        assert!(!settings.do_not_disturb);
+        assert!(!settings.profanity_filter);
+        assert_eq!(settings.away_after_minutes, 5);
+        assert_eq!(settings.long_message_chars, 500);
+        // The old default, which some have saved, is still offered.
+        assert!(LONG_MESSAGE_CHOICES.contains(&600));
+        assert_eq!(settings.announcement_pin_minutes, 60);
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        use TimestampFormat::*;
+        assert_eq!(TwentyFourHour.format_parts(9, 5).as_deref(), Some("09:05"));
+        assert_eq!(TwelveHour.format_parts(0, 30).as_deref(), Some("12:30 AM"));
+        assert_eq!(TwelveHour.format_parts(12, 0).as_deref(), Some("12:00 PM"));
+        assert_eq!(TwelveHour.format_parts(23, 59).as_deref(), Some("11:59 PM"));
+        assert_eq!(Hidden.format_parts(9, 5), None);
+    }
+
+    #[test]
+    fn settings_are_not_per_user() {
+        assert!(!session::belongs_to("alice", SETTINGS_KEY));
+    }
+
+    // Local storage is only there in a browser.
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn settings_outlive_logins_and_forgetting() {
+        let dark = Settings {
+            theme: Theme::Dark,
+            ..Settings::default()
+        };
+        save(&dark);
+        session::log_in("frank", false);
+        session::forget("frank");
+        assert_eq!(load(), dark);
+        save(&Settings::default());
+    }
+
+    #[test]
+    fn setters_emit_only_on_change() {
+        use std::cell::RefCell;
+        let emitted = Rc::new(RefCell::new(vec![]));
+        let handle = SettingsHandle::new(Rc::default(), {
+            let emitted = emitted.clone();
+            Callback::from(move |s| emitted.borrow_mut().push(s))
+        });
+        handle.set_theme(Theme::Light);
+        handle.set_theme(Theme::Dark);
+        assert_eq!(emitted.borrow().len(), 1);
+        assert_eq!(emitted.borrow()[0].theme, Theme::Dark);
+    }
+
+    #[test]
+    fn toggling_the_theme_context_flips_the_root_class() {
+        use std::cell::Cell;
+        let theme = Rc::new(Cell::new(Theme::Light));
+        let context = |current: Theme| {
+            let theme = theme.clone();
+            ThemeContext::new(current, Callback::from(move |t| theme.set(t)))
+        };
+        assert_eq!(context(theme.get()).theme().class(), "");
+        context(theme.get()).toggle();
+        assert_eq!(context(theme.get()).theme().class(), "dark-mode");
+        assert_eq!(context(theme.get()).theme().palette(), Palette::Dark);
+        context(theme.get()).toggle();
+        assert_eq!(theme.get(), Theme::Light);
+    }
+}
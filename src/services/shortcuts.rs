@@ -0,0 +1,313 @@
+/// What a keyboard shortcut does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Clears and leaves the message input, or closes the shortcut sheet.
+    Escape,
+    Search,
+    FocusUserFilter,
+    PreviousRoom,
+    NextRoom,
+    FocusInput,
+    ShowShortcuts,
+}
+
+/// A key with the modifiers it needs. Ctrl also matches Cmd on macOS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    /// As in `KeyboardEvent.key`, compared ignoring case.
+    pub key: &'static str,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+pub struct Shortcut {
+    pub chord: Chord,
+    /// How the cheat sheet writes the keys.
+    pub label: &'static str,
+    /// Locale key of the description.
+    pub description: &'static str,
+    pub action: Action,
+    /// Whether it works while typing in a text field.
+    pub in_fields: bool,
+}
+
+const fn shortcut(
+    key: &'static str,
+    ctrl: bool,
+    alt: bool,
+    label: &'static str,
+    description: &'static str,
+    action: Action,
+    in_fields: bool,
+) -> Shortcut {
+    Shortcut {
+        chord: Chord { key, ctrl, alt },
+        label,
+        description,
+        action,
+        in_fields,
+    }
+}
+
+/// Every shortcut, in cheat sheet order.
+pub const SHORTCUTS: [Shortcut; 7] = [
+    shortcut(
+        "/",
+        false,
+        false,
+        "/",
+        "shortcuts.focus_input",
+        Action::FocusInput,
+        false,
+    ),
+    shortcut(
+        "Escape",
+        false,
+        false,
+        "Esc",
+        "shortcuts.escape",
+        Action::Escape,
+        true,
+    ),
+    shortcut(
+        "f",
+        true,
+        false,
+        "Ctrl+F",
+        "shortcuts.search",
+        Action::Search,
+        true,
+    ),
+    shortcut(
+        "k",
+        true,
+        false,
+        "Ctrl+K",
+        "shortcuts.user_filter",
+        Action::FocusUserFilter,
+        true,
+    ),
+    shortcut(
+        "ArrowUp",
+        false,
+        true,
+        "Alt+↑",
+        "shortcuts.previous_room",
+        Action::PreviousRoom,
+        true,
+    ),
+    shortcut(
+        "ArrowDown",
+        false,
+        true,
+        "Alt+↓",
+        "shortcuts.next_room",
+        Action::NextRoom,
+        true,
+    ),
+    shortcut(
+        "?",
+        false,
+        false,
+        "?",
+        "shortcuts.show",
+        Action::ShowShortcuts,
+        false,
+    ),
+];
+
+/// A key press as the shortcuts see it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyPress {
+    pub key: String,
+    /// Ctrl or Cmd.
+    pub ctrl: bool,
+    pub alt: bool,
+    /// Typed into a text field.
+    pub in_field: bool,
+}
+
+/// The shortcut `press` triggers, if any.
+pub fn find(press: &KeyPress) -> Option<Action> {
+    SHORTCUTS
+        .iter()
+        .find(|s| {
+            s.chord.key.eq_ignore_ascii_case(&press.key)
+                && s.chord.ctrl == press.ctrl
+                && s.chord.alt == press.alt
+                && (s.in_fields || !press.in_field)
+        })
+        .map(|s| s.action)
+}
+
+/// Keys of the message box itself, listed apart in the cheat sheet: how
+/// they're written and the locale key of what they do.
+pub const INPUT_SHORTCUTS: [(&str, &str); 2] = [
+    ("↑", "shortcuts.edit_last"),
+    ("Ctrl+↑ Ctrl+↓", "shortcuts.history"),
+];
+
+/// What a key does in the message box, beyond typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAction {
+    /// Loads our latest message to edit.
+    EditLast,
+    /// Recalls what we sent before the one shown.
+    Older,
+    Newer,
+    /// Stops editing or recalling, putting the draft back.
+    Cancel,
+}
+
+/// The message box as a key press finds it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputState {
+    pub empty: bool,
+    /// Going through what we sent before.
+    pub recalling: bool,
+    /// Editing a message already sent.
+    pub editing: bool,
+}
+
+/// What `press` does in the message box, if anything. Up edits our last
+/// message, from an empty box; going through what we sent takes Ctrl, so
+/// the two never compete for a key.
+pub fn input_action(press: &KeyPress, state: InputState) -> Option<InputAction> {
+    let busy = state.recalling || state.editing;
+    match (press.key.as_str(), press.ctrl, press.alt) {
+        ("ArrowUp", false, false) if state.empty && !busy => Some(InputAction::EditLast),
+        ("ArrowUp", true, false) if !state.editing && (state.empty || state.recalling) => {
+            Some(InputAction::Older)
+        }
+        ("ArrowDown", true, false) if state.recalling => Some(InputAction::Newer),
+        ("Escape", false, false) if busy => Some(InputAction::Cancel),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key: &str, ctrl: bool, alt: bool, in_field: bool) -> KeyPress {
+        KeyPress {
+            key: key.into(),
+            ctrl,
+            alt,
+            in_field,
+        }
+    }
+
+    #[test]
+    fn finds_shortcuts() {
+        assert_eq!(
+            find(&press("K", true, false, false)),
+            Some(Action::FocusUserFilter)
+        );
+        assert_eq!(
+            find(&press("ArrowDown", false, true, true)),
+            Some(Action::NextRoom)
+        );
+        assert_eq!(
+            find(&press("?", false, false, false)),
+            Some(Action::ShowShortcuts)
+        );
+        assert_eq!(find(&press("k", false, false, false)), None);
+        assert_eq!(find(&press("ArrowDown", false, false, false)), None);
+    }
+
+    #[test]
+    fn typing_keys_are_left_to_text_fields() {
+        assert_eq!(find(&press("/", false, false, true)), None);
+        assert_eq!(find(&press("?", false, false, true)), None);
+        assert_eq!(
+            find(&press("/", false, false, false)),
+            Some(Action::FocusInput)
+        );
+        assert_eq!(
+            find(&press("Escape", false, false, true)),
+            Some(Action::Escape)
+        );
+        assert_eq!(find(&press("f", true, false, true)), Some(Action::Search));
+    }
+
+    #[test]
+    fn up_edits_and_ctrl_up_recalls() {
+        let empty = InputState {
+            empty: true,
+            ..InputState::default()
+        };
+        assert_eq!(
+            input_action(&press("ArrowUp", false, false, true), empty),
+            Some(InputAction::EditLast)
+        );
+        assert_eq!(
+            input_action(&press("ArrowUp", true, false, true), empty),
+            Some(InputAction::Older)
+        );
+        assert_eq!(
+            input_action(&press("ArrowDown", true, false, true), empty),
+            None
+        );
+        // Alt+Up is the previous room.
+        assert_eq!(
+            input_action(&press("ArrowUp", false, true, true), empty),
+            None
+        );
+        let typing = InputState::default();
+        assert_eq!(
+            input_action(&press("ArrowUp", false, false, true), typing),
+            None
+        );
+        assert_eq!(
+            input_action(&press("ArrowUp", true, false, true), typing),
+            None
+        );
+        assert_eq!(
+            input_action(&press("Escape", false, false, true), typing),
+            None
+        );
+    }
+
+    #[test]
+    fn recalling_and_editing_each_keep_their_keys() {
+        let recalling = InputState {
+            recalling: true,
+            ..InputState::default()
+        };
+        assert_eq!(
+            input_action(&press("ArrowUp", true, false, true), recalling),
+            Some(InputAction::Older)
+        );
+        assert_eq!(
+            input_action(&press("ArrowDown", true, false, true), recalling),
+            Some(InputAction::Newer)
+        );
+        assert_eq!(
+            input_action(&press("Escape", false, false, true), recalling),
+            Some(InputAction::Cancel)
+        );
+        let editing = InputState {
+            editing: true,
+            ..InputState::default()
+        };
+        assert_eq!(
+            input_action(&press("ArrowUp", true, false, true), editing),
+            None
+        );
+        assert_eq!(
+            input_action(&press("Escape", false, false, true), editing),
+            Some(InputAction::Cancel)
+        );
+    }
+
+    #[test]
+    fn every_action_has_one_shortcut() {
+        for (i, a) in SHORTCUTS.iter().enumerate() {
+            for b in &SHORTCUTS[i + 1..] {
+                assert_ne!(a.action, b.action);
+                assert_ne!(a.chord, b.chord);
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use futures::{
+    channel::mpsc::{self, Receiver, Sender},
+    select, StreamExt,
+};
+use reqwasm::http::Request as HttpRequest;
+use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{EventSource, MessageEvent};
+use yew::Callback;
+use yew_agent::Dispatched;
+
+use crate::services::csrf::CsrfToken;
+use crate::services::event_bus::{EventBus, Request};
+use crate::services::message_queue::MessageQueue;
+use crate::services::websocket::{ConnectionState, ConnectionStatus};
+
+const SERVER_URL: &str = "http://127.0.0.1:8080";
+const QUEUE_CAPACITY: usize = 50;
+
+enum SseEvent {
+    Open,
+    Message(String),
+    Error,
+}
+
+/// Receives frames over an `EventSource` and sends them with plain POST
+/// requests, for networks that block WebSocket upgrades. Behaves like
+/// [`WebsocketService`](crate::services::websocket::WebsocketService):
+/// frames go in through `tx`, incoming frames come out of the event bus.
+pub struct SseService {
+    pub tx: Sender<String>,
+}
+
+impl SseService {
+    pub fn new(handshake: String, csrf: CsrfToken, on_state: Callback<ConnectionState>) -> Self {
+        let (in_tx, in_rx) = mpsc::channel::<String>(1000);
+        spawn_local(run(handshake, csrf, in_rx, on_state));
+        Self { tx: in_tx }
+    }
+}
+
+async fn run(
+    handshake: String,
+    csrf: CsrfToken,
+    mut in_rx: Receiver<String>,
+    on_state: Callback<ConnectionState>,
+) {
+    // The server pairs our POSTs with our event stream by this id.
+    let client_id = format!("{:x}", (js_sys::Math::random() * u32::MAX as f64) as u32);
+    let source = match EventSource::new(&format!("{}/events?client={}", SERVER_URL, client_id)) {
+        Ok(source) => source,
+        Err(e) => {
+            log::error!("sse: {:?}", e);
+            return;
+        }
+    };
+    let send_url = format!("{}/send?client={}", SERVER_URL, client_id);
+
+    // Funnel the EventSource callbacks into one stream. The closures must
+    // stay alive for as long as the source does, which is this function.
+    let (event_tx, mut events) = mpsc::unbounded::<SseEvent>();
+    let on_open = {
+        let event_tx = event_tx.clone();
+        Closure::wrap(Box::new(move || {
+            let _ = event_tx.unbounded_send(SseEvent::Open);
+        }) as Box<dyn FnMut()>)
+    };
+    let on_message = {
+        let event_tx = event_tx.clone();
+        Closure::wrap(Box::new(move |e: MessageEvent| {
+            if let Some(data) = e.data().as_string() {
+                let _ = event_tx.unbounded_send(SseEvent::Message(data));
+            }
+        }) as Box<dyn FnMut(MessageEvent)>)
+    };
+    let on_error = Closure::wrap(Box::new(move || {
+        let _ = event_tx.unbounded_send(SseEvent::Error);
+    }) as Box<dyn FnMut()>);
+    source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    source.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    let mut event_bus = EventBus::dispatcher();
+    let mut queue = MessageQueue::new(QUEUE_CAPACITY);
+    let mut open = false;
+    let report = |status: ConnectionStatus, queue: &MessageQueue| {
+        on_state.emit(ConnectionState::new(status, queue))
+    };
+    report(ConnectionStatus::Connecting, &queue);
+
+    loop {
+        select! {
+            event = events.next() => match event {
+                Some(SseEvent::Open) => {
+                    // Every new stream is a new session as far as the server
+                    // is concerned, so register again before flushing.
+                    open = post(&csrf, &send_url, handshake.clone()).await;
+                    if !open {
+                        report(ConnectionStatus::Offline, &queue);
+                        continue;
+                    }
+                    if !queue.is_empty() {
+                        report(ConnectionStatus::Flushing, &queue);
+                    }
+                    while let Some(s) = queue.pop() {
+                        if !post(&csrf, &send_url, s.clone()).await {
+                            queue.requeue(s);
+                            open = false;
+                            break;
+                        }
+                    }
+                    if open {
+                        queue.reset_dropped();
+                        report(ConnectionStatus::Online, &queue);
+                    }
+                }
+                Some(SseEvent::Message(data)) => {
+                    log::debug!("from sse: {}", data);
+                    event_bus.send(Request::EventBusMsg(data));
+                }
+                Some(SseEvent::Error) => {
+                    // EventSource reconnects by itself; we just wait for the
+                    // next `open`.
+                    open = false;
+                    report(ConnectionStatus::Offline, &queue);
+                }
+                None => break,
+            },
+            out = in_rx.next() => match out {
+                Some(s) => {
+                    if !open || !post(&csrf, &send_url, s.clone()).await {
+                        queue.push(s);
+                        report(ConnectionStatus::Offline, &queue);
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+    source.close();
+}
+
+async fn post(csrf: &CsrfToken, url: &str, body: String) -> bool {
+    let response = csrf
+        .send(|| {
+            HttpRequest::post(url)
+                .header("Content-Type", "application/json")
+                .body(body.clone())
+        })
+        .await;
+    match response {
+        Ok(response) => response.ok(),
+        Err(e) => {
+            log::error!("sse send: {:?}", e);
+            false
+        }
+    }
+}
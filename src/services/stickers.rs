@@ -0,0 +1,106 @@
+use reqwasm::http::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::services::session;
+
+const CACHE_KEY: &str = "sticker_manifest";
+/// How long a fetched manifest is reused, in milliseconds.
+const CACHE_TTL_MS: f64 = 60.0 * 60.0 * 1000.0;
+
+/// The sticker packs on offer, as served at the manifest URL.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Manifest {
+    pub packs: Vec<Pack>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Pack {
+    pub name: String,
+    /// Prepended to each sticker's file name to get its URL.
+    pub url_prefix: String,
+    pub stickers: Vec<Sticker>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Sticker {
+    pub id: String,
+    pub filename: String,
+}
+
+impl Pack {
+    pub fn url(&self, sticker: &Sticker) -> String {
+        format!(
+            "{}/{}",
+            self.url_prefix.trim_end_matches('/'),
+            sticker.filename.trim_start_matches('/')
+        )
+    }
+}
+
+/// A manifest saved to local storage, with where and when it came from.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Cached {
+    url: String,
+    fetched_at: f64,
+    manifest: Manifest,
+}
+
+impl Cached {
+    fn is_fresh(&self, url: &str, now: f64) -> bool {
+        self.url == url && now - self.fetched_at < CACHE_TTL_MS
+    }
+}
+
+/// The manifest at `url`, from the cache if it was fetched within the hour.
+pub async fn load(url: &str) -> Result<Manifest, String> {
+    let now = js_sys::Date::now();
+    if let Some(cached) = session::load::<Cached>(CACHE_KEY).filter(|c| c.is_fresh(url, now)) {
+        return Ok(cached.manifest);
+    }
+    let response = Request::get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(format!("manifest failed with status {}", response.status()));
+    }
+    let manifest: Manifest = response.json().await.map_err(|e| e.to_string())?;
+    let cached = Cached {
+        url: url.to_string(),
+        fetched_at: now,
+        manifest,
+    };
+    session::save(CACHE_KEY, &cached);
+    Ok(cached.manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_manifest_schema() {
+        let json = r#"{"packs": [{"name": "Cats", "url_prefix": "https://cdn.example/cats/",
+            "stickers": [{"id": "wave", "filename": "wave.png"}]}]}"#;
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let pack = &manifest.packs[0];
+        assert_eq!(pack.name, "Cats");
+        assert_eq!(
+            pack.url(&pack.stickers[0]),
+            "https://cdn.example/cats/wave.png"
+        );
+    }
+
+    #[test]
+    fn cache_expires_after_an_hour() {
+        let cached = Cached {
+            url: "https://cdn.example/stickers.json".into(),
+            fetched_at: 1_000.0,
+            manifest: Manifest { packs: Vec::new() },
+        };
+        assert!(cached.is_fresh(
+            "https://cdn.example/stickers.json",
+            1_000.0 + CACHE_TTL_MS - 1.0
+        ));
+        assert!(!cached.is_fresh("https://cdn.example/stickers.json", 1_000.0 + CACHE_TTL_MS));
+        assert!(!cached.is_fresh("https://other.example/stickers.json", 2_000.0));
+    }
+}
@@ -0,0 +1,133 @@
+use reqwasm::http::Request;
+use serde::{Deserialize, Serialize};
+
+/// The public LibreTranslate instance. Set `YEWCHAT_TRANSLATE_URL` at build
+/// time for a self-hosted one, and `YEWCHAT_TRANSLATE_API_KEY` where the
+/// instance asks for a key.
+///
+/// The key is compiled into the wasm bundle, so anyone who loads the app
+/// can read it: only use one that is fine to publish. To keep a billed key
+/// secret, point `YEWCHAT_TRANSLATE_URL` at a route on the chat server that
+/// adds the key and forwards to the instance, and leave the key unset here.
+const DEFAULT_ENDPOINT: &str = "https://libretranslate.com";
+
+/// Where translations come from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslateConfig {
+    pub endpoint: String,
+    pub api_key: Option<String>,
+}
+
+impl TranslateConfig {
+    /// The build-time configuration.
+    pub fn from_env() -> Self {
+        Self {
+            endpoint: option_env!("YEWCHAT_TRANSLATE_URL")
+                .unwrap_or(DEFAULT_ENDPOINT)
+                .to_string(),
+            api_key: option_env!("YEWCHAT_TRANSLATE_API_KEY").map(str::to_string),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/translate", self.endpoint.trim_end_matches('/'))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TranslateRequest<'a> {
+    q: &'a str,
+    source: &'a str,
+    target: &'a str,
+    format: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    translated_text: Option<String>,
+    error: Option<String>,
+}
+
+fn request_body(config: &TranslateConfig, text: &str, target: &str) -> String {
+    serde_json::to_string(&TranslateRequest {
+        q: text,
+        source: "auto",
+        target,
+        format: "text",
+        api_key: config.api_key.as_deref(),
+    })
+    .unwrap()
+}
+
+/// Reads a response, which carries either the translation or an error.
+fn parse_response(json: &str) -> Result<String, String> {
+    let response: TranslateResponse = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    match (response.translated_text, response.error) {
+        (Some(text), _) => Ok(text),
+        (None, Some(error)) => Err(error),
+        (None, None) => Err("empty translation".to_string()),
+    }
+}
+
+/// Translates `text` into the language tagged `target`, detecting the
+/// language it is written in.
+pub async fn translate(
+    config: &TranslateConfig,
+    text: &str,
+    target: &str,
+) -> Result<String, String> {
+    let response = Request::post(&config.url())
+        .header("Content-Type", "application/json")
+        .body(request_body(config, text, target))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(parse_response(&body)
+            .err()
+            .unwrap_or_else(|| format!("translation failed with status {}", response.status())));
+    }
+    parse_response(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_the_request() {
+        let config = TranslateConfig {
+            endpoint: "https://translate.example/".into(),
+            api_key: None,
+        };
+        assert_eq!(config.url(), "https://translate.example/translate");
+        assert_eq!(
+            request_body(&config, "hola", "en"),
+            r#"{"q":"hola","source":"auto","target":"en","format":"text"}"#
+        );
+        let keyed = TranslateConfig {
+            api_key: Some("k".into()),
+            ..config
+        };
+        assert!(request_body(&keyed, "hola", "en").ends_with(r#""api_key":"k"}"#));
+    }
+
+    #[test]
+    fn reads_translations_and_errors() {
+        assert_eq!(
+            parse_response(r#"{"translatedText": "hello"}"#).unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            parse_response(
+                r#"{"error": "Visit https://portal.libretranslate.com to get an API key"}"#
+            )
+            .unwrap_err(),
+            "Visit https://portal.libretranslate.com to get an API key"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use futures::channel::mpsc::Sender;
+use yew::Callback;
+
+use crate::services::csrf::CsrfToken;
+use crate::services::sse::SseService;
+use crate::services::websocket::{ConnectionState, WebsocketConfig, WebsocketService};
+
+/// How the client talks to the chat server.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Transport {
+    #[default]
+    WebSocket,
+    /// `EventSource` for receiving plus POST for sending, for networks that
+    /// block WebSocket upgrades.
+    ServerSentEvents,
+}
+
+impl Transport {
+    /// Reads the transport from a `?transport=sse` query parameter, so a
+    /// deployment can opt into SSE without a rebuild.
+    pub fn from_query() -> Self {
+        if query_has("transport=sse") {
+            Transport::ServerSentEvents
+        } else {
+            Transport::WebSocket
+        }
+    }
+
+    /// Starts the matching service and returns the sender for outgoing frames.
+    /// Only SSE sends over plain HTTP, so only it needs the CSRF token.
+    pub fn connect(
+        self,
+        handshake: String,
+        csrf: CsrfToken,
+        on_state: Callback<ConnectionState>,
+    ) -> Sender<String> {
+        match self {
+            Transport::WebSocket => {
+                WebsocketService::new(handshake, WebsocketConfig::from_query(), on_state).tx
+            }
+            Transport::ServerSentEvents => SseService::new(handshake, csrf, on_state).tx,
+        }
+    }
+}
+
+/// Whether the page URL's query string contains `pair`, e.g. `transport=sse`.
+pub fn query_has(pair: &str) -> bool {
+    let search = web_sys::window()
+        .and_then(|w| w.location().search().ok())
+        .unwrap_or_default();
+    search.trim_start_matches('?').split('&').any(|p| p == pair)
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::components::chat::MessageData;
+use crate::services::session;
+
+const READ_KEY: &str = "room_read";
+
+/// How far each room has been read, and how many messages have come in
+/// since. Rooms are keyed by name, the lobby by "".
+#[derive(Debug, Default)]
+pub struct RoomUnread {
+    /// Timestamp of the newest message read in each room.
+    read_up_to: HashMap<String, f64>,
+    counts: HashMap<String, usize>,
+    /// Rooms where an unread message mentions us.
+    mentioned: HashSet<String>,
+}
+
+fn room_key(room: &Option<String>) -> &str {
+    room.as_deref().unwrap_or("")
+}
+
+/// Whether `message` is one to count: someone else's, in the room itself
+/// rather than a thread.
+fn is_counted(message: &MessageData, me: &str) -> bool {
+    !message.notice && message.thread_id.is_none() && message.from != me
+}
+
+/// Whether `text` mentions `me` as `@me`, ignoring case.
+pub fn mentions(text: &str, me: &str) -> bool {
+    let me = me.to_lowercase();
+    let text = text.to_lowercase();
+    text.match_indices('@').any(|(at, _)| {
+        let rest = &text[at + 1..];
+        rest.starts_with(&me)
+            && !rest[me.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+impl RoomUnread {
+    /// Picks up where the last session left off, counting the restored
+    /// messages each room hadn't been read up to.
+    pub fn restore<'a>(messages: impl IntoIterator<Item = &'a MessageData>, me: &str) -> Self {
+        let read_up_to = session::load(&session::user_key(me, READ_KEY)).unwrap_or_default();
+        Self::with_read(read_up_to, messages, me)
+    }
+
+    fn with_read<'a>(
+        read_up_to: HashMap<String, f64>,
+        messages: impl IntoIterator<Item = &'a MessageData>,
+        me: &str,
+    ) -> Self {
+        let mut counts = HashMap::new();
+        let mut mentioned = HashSet::new();
+        for message in messages.into_iter().filter(|m| is_counted(m, me)) {
+            let key = room_key(&message.room);
+            if read_up_to
+                .get(key)
+                .is_some_and(|read| message.timestamp > *read)
+            {
+                *counts.entry(key.to_string()).or_default() += 1;
+                if mentions(&message.message, me) {
+                    mentioned.insert(key.to_string());
+                }
+            }
+        }
+        Self {
+            read_up_to,
+            counts,
+            mentioned,
+        }
+    }
+
+    /// Notes a message arriving; it's read straight away if we are looking
+    /// at its room. Returns whether a count changed.
+    pub fn arrived(&mut self, message: &MessageData, me: &str, viewing: bool) -> bool {
+        if !is_counted(message, me) {
+            return false;
+        }
+        if viewing {
+            return self.mark_read(&message.room, message.timestamp);
+        }
+        let key = room_key(&message.room).to_string();
+        // A room never read so far: everything in it counts after a reload.
+        self.read_up_to.entry(key.clone()).or_insert(0.0);
+        if mentions(&message.message, me) {
+            self.mentioned.insert(key.clone());
+        }
+        *self.counts.entry(key).or_default() += 1;
+        true
+    }
+
+    /// Marks `room` read up to the message at `up_to`. Returns whether it
+    /// had anything unread.
+    pub fn mark_read(&mut self, room: &Option<String>, up_to: f64) -> bool {
+        let key = room_key(room);
+        let read = self.read_up_to.entry(key.to_string()).or_insert(up_to);
+        *read = read.max(up_to);
+        self.mentioned.remove(key);
+        self.counts.remove(key).is_some()
+    }
+
+    /// The unread count of every room that has any.
+    pub fn by_room(&self) -> HashMap<Option<String>, usize> {
+        self.counts
+            .iter()
+            .map(|(key, count)| ((!key.is_empty()).then(|| key.clone()), *count))
+            .collect()
+    }
+
+    /// The rooms with an unread mention of us.
+    pub fn mentioned(&self) -> HashSet<Option<String>> {
+        self.mentioned
+            .iter()
+            .map(|key| (!key.is_empty()).then(|| key.clone()))
+            .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    pub fn save(&self, me: &str) {
+        session::save(&session::user_key(me, READ_KEY), &self.read_up_to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(from: &str, room: Option<&str>, timestamp: f64) -> MessageData {
+        MessageData {
+            from: from.into(),
+            message: format!("hi from {}", from),
+            timestamp,
+            room: room.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_rooms_we_are_not_looking_at() {
+        let mut unread = RoomUnread::default();
+        assert!(unread.arrived(&message("bob", Some("games"), 1.0), "me", false));
+        assert!(!unread.arrived(&message("bob", None, 2.0), "me", true));
+        assert!(!unread.arrived(&message("me", Some("games"), 3.0), "me", false));
+        assert_eq!(unread.by_room(), HashMap::from([(Some("games".into()), 1)]));
+        assert_eq!(unread.total(), 1);
+        assert!(unread.mark_read(&Some("games".into()), 3.0));
+        assert_eq!(unread.total(), 0);
+    }
+
+    #[test]
+    fn mentions_need_the_whole_name() {
+        assert!(mentions("@Me look", "me"));
+        assert!(mentions("thanks, @me!", "me"));
+        assert!(!mentions("@meg look", "me"));
+        assert!(!mentions("me@example.com", "me"));
+    }
+
+    #[test]
+    fn a_mention_marks_the_room_until_read() {
+        let mut unread = RoomUnread::default();
+        let mut mention = message("bob", Some("games"), 1.0);
+        mention.message = "@me your turn".into();
+        unread.arrived(&message("bob", None, 1.0), "me", false);
+        unread.arrived(&mention, "me", false);
+        assert_eq!(unread.mentioned(), HashSet::from([Some("games".into())]));
+        unread.mark_read(&Some("games".into()), 1.0);
+        assert!(unread.mentioned().is_empty());
+    }
+
+    #[test]
+    fn restores_counts_from_what_was_read() {
+        let read = HashMap::from([("".to_string(), 2.0), ("games".to_string(), 0.0)]);
+        let history = [
+            message("bob", None, 1.0),
+            message("bob", None, 3.0),
+            message("bob", Some("games"), 1.0),
+            message("bob", Some("never"), 1.0),
+        ];
+        let unread = RoomUnread::with_read(read, &history, "me");
+        assert_eq!(
+            unread.by_room(),
+            HashMap::from([(None, 1), (Some("games".into()), 1)])
+        );
+    }
+}
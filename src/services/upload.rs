@@ -0,0 +1,225 @@
+use reqwasm::http::Request;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    Blob, BlobPropertyBag, CanvasRenderingContext2d, DataTransfer, File, FormData,
+    HtmlCanvasElement, HtmlImageElement, Url,
+};
+
+use crate::services::csrf::CsrfToken;
+use crate::services::message::FileShare;
+
+const UPLOAD_URL: &str = "http://127.0.0.1:8080/upload";
+
+/// Files up to this size, in bytes, are sent inline rather than uploaded.
+pub const INLINE_FILE_LIMIT: u64 = 64 * 1024;
+/// The largest file that can be shared by default, in bytes.
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The image types a pasted image may be.
+const SUPPORTED_IMAGE_TYPES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+/// The most a pasted image sent inline may weigh, in bytes.
+pub const INLINE_IMAGE_LIMIT: usize = 256 * 1024;
+/// The longest side an inline image starts out scaled to, in pixels.
+const INLINE_IMAGE_SIDE: u32 = 1600;
+
+/// An image pasted into the message box, held until the user sends or
+/// drops it. The preview's object URL is released along with it.
+pub struct PastedImage {
+    pub file: File,
+    pub preview_url: String,
+}
+
+impl PastedImage {
+    /// The first image on the clipboard, if there is one, or its type if we
+    /// can't send that kind.
+    pub fn from_clipboard(data: &DataTransfer) -> Option<Result<Self, String>> {
+        let items = data.items();
+        let item = (0..items.length())
+            .filter_map(|i| items.get(i))
+            .find(|item| item.kind() == "file" && item.type_().starts_with("image/"))?;
+        let file = item.get_as_file().ok().flatten()?;
+        Some(Self::from_file(file))
+    }
+
+    /// `file`, such as one dropped on the chat, or its type if we can't
+    /// send that kind.
+    pub fn from_file(file: File) -> Result<Self, String> {
+        if !is_supported(&file.type_()) {
+            return Err(file.type_());
+        }
+        let preview_url = Url::create_object_url_with_blob(&file).map_err(|_| file.type_())?;
+        Ok(Self { file, preview_url })
+    }
+
+    /// The image as a JPEG data URL of at most `INLINE_IMAGE_LIMIT` bytes,
+    /// scaled down on a canvas until it fits.
+    pub async fn to_data_url(&self) -> Result<String, String> {
+        let document = web_sys::window()
+            .and_then(|w| w.document())
+            .ok_or("no document")?;
+        let image = HtmlImageElement::new().map_err(|_| "no image element")?;
+        image.set_src(&self.preview_url);
+        JsFuture::from(image.decode())
+            .await
+            .map_err(|_| "the image could not be read")?;
+        let canvas: HtmlCanvasElement = document
+            .create_element("canvas")
+            .map_err(|_| "no canvas")?
+            .unchecked_into();
+        let context: CanvasRenderingContext2d = canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .ok_or("no canvas context")?
+            .unchecked_into();
+        let mut side = INLINE_IMAGE_SIDE;
+        while side >= 64 {
+            let (width, height) = scaled(image.natural_width(), image.natural_height(), side);
+            canvas.set_width(width);
+            canvas.set_height(height);
+            // JPEG has no transparency; show it against white, not black.
+            context.set_fill_style(&"white".into());
+            context.fill_rect(0.0, 0.0, width as f64, height as f64);
+            context
+                .draw_image_with_html_image_element_and_dw_and_dh(
+                    &image,
+                    0.0,
+                    0.0,
+                    width as f64,
+                    height as f64,
+                )
+                .map_err(|_| "the image could not be drawn")?;
+            let url = canvas
+                .to_data_url_with_type_and_encoder_options("image/jpeg", &0.85.into())
+                .map_err(|_| "the image could not be encoded")?;
+            // The data URL itself is what's sent, so that's what's weighed.
+            if url.len() <= INLINE_IMAGE_LIMIT {
+                return Ok(url);
+            }
+            side = side * 3 / 4;
+        }
+        Err("the image is too large".into())
+    }
+}
+
+/// Whether a pasted image of `mime_type` can be sent.
+pub fn is_supported(mime_type: &str) -> bool {
+    SUPPORTED_IMAGE_TYPES.contains(&mime_type.to_ascii_lowercase().as_str())
+}
+
+/// `width` by `height` shrunk, keeping its shape, so neither side is over
+/// `side`. Smaller images are left as they are.
+fn scaled(width: u32, height: u32, side: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= side {
+        return (width.max(1), height.max(1));
+    }
+    let shrink = |n: u32| ((n as u64 * side as u64 / longest as u64) as u32).max(1);
+    (shrink(width), shrink(height))
+}
+
+impl Drop for PastedImage {
+    fn drop(&mut self) {
+        let _ = Url::revoke_object_url(&self.preview_url);
+    }
+}
+
+/// `file` ready to share: its content inline if it is small, otherwise
+/// uploaded.
+pub async fn share(csrf: &CsrfToken, file: &File) -> Result<FileShare, String> {
+    let size_bytes = file.size() as u64;
+    let (download_url, content) = if size_bytes <= INLINE_FILE_LIMIT {
+        let buffer = JsFuture::from(file.array_buffer())
+            .await
+            .map_err(|_| "the file could not be read")?;
+        let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+        (String::new(), Some(to_base64(&bytes)?))
+    } else {
+        (upload(csrf, file).await?, None)
+    };
+    Ok(FileShare {
+        filename: file.name(),
+        size_bytes,
+        mime_type: file.type_(),
+        download_url,
+        content,
+    })
+}
+
+/// `btoa` takes a string of bytes, one per character.
+fn to_base64(bytes: &[u8]) -> Result<String, String> {
+    let binary: String = bytes.iter().map(|&b| char::from(b)).collect();
+    web_sys::window()
+        .ok_or("no window")?
+        .btoa(&binary)
+        .map_err(|_| "the file could not be encoded".to_string())
+}
+
+/// An object URL for a file sent inline, to download it from. Released
+/// along with it.
+pub struct ObjectUrl(String);
+
+impl ObjectUrl {
+    pub fn from_base64(content: &str, mime_type: &str) -> Option<Self> {
+        let binary = web_sys::window()?.atob(content).ok()?;
+        let bytes: Vec<u8> = binary.chars().map(|c| c as u8).collect();
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes.as_slice()));
+        let mut options = BlobPropertyBag::new();
+        options.type_(mime_type);
+        let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).ok()?;
+        Url::create_object_url_with_blob(&blob).ok().map(Self)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for ObjectUrl {
+    fn drop(&mut self) {
+        let _ = Url::revoke_object_url(&self.0);
+    }
+}
+
+/// Uploads `file` as a multipart form and returns the download URL the
+/// server answers with.
+pub async fn upload(csrf: &CsrfToken, file: &File) -> Result<String, String> {
+    upload_blob(csrf, file, &file.name()).await
+}
+
+/// Like `upload`, for data that isn't a picked file, such as a recording.
+pub async fn upload_blob(csrf: &CsrfToken, blob: &Blob, filename: &str) -> Result<String, String> {
+    // The browser sets the multipart content type, boundary and all.
+    let form = FormData::new().map_err(|_| "no form data")?;
+    form.append_with_blob_and_filename("file", blob, filename)
+        .map_err(|_| "the file could not be attached")?;
+    let response = csrf
+        .send(|| Request::post(UPLOAD_URL).body(form.clone()))
+        .await?;
+    if !response.ok() {
+        return Err(format!("upload failed with status {}", response.status()));
+    }
+    response.text().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_image_types_are_supported() {
+        assert!(is_supported("image/png"));
+        assert!(is_supported("IMAGE/JPEG"));
+        assert!(!is_supported("image/tiff"));
+        assert!(!is_supported("image/svg+xml"));
+    }
+
+    #[test]
+    fn scaling_keeps_the_shape() {
+        assert_eq!(scaled(3200, 1600, 1600), (1600, 800));
+        assert_eq!(scaled(900, 3000, 1500), (450, 1500));
+        assert_eq!(scaled(640, 480, 1600), (640, 480));
+        assert_eq!(scaled(10_000, 1, 100), (100, 1));
+    }
+}
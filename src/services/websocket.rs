@@ -1,53 +1,312 @@
-use futures::{channel::mpsc::Sender, SinkExt, StreamExt};
+use futures::{
+    channel::mpsc::{Receiver, Sender},
+    select, FutureExt, SinkExt, StreamExt,
+};
+use gloo_timers::future::TimeoutFuture;
 use reqwasm::websocket::{futures::WebSocket, Message};
 
 use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
 use yew_agent::Dispatched;
 
+use crate::services::deflate;
 use crate::services::event_bus::{EventBus, Request};
+use crate::services::message_queue::MessageQueue;
+use crate::services::transport::query_has;
+
+const SERVER_URL: &str = "ws://127.0.0.1:8080";
+const QUEUE_CAPACITY: usize = 50;
+const RECONNECT_MIN_MS: u32 = 1_000;
+const RECONNECT_MAX_MS: u32 = 30_000;
+/// Starts a binary frame holding a deflated UTF-8 payload. Frames are JSON
+/// or chat text, which never start with a NUL, so nothing else matches.
+const COMPRESSED_MAGIC: &[u8] = b"\0DF";
+/// Below this, deflating saves too little to be worth it.
+const COMPRESS_MIN_BYTES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Online,
+    /// Connected and sending what was queued while offline.
+    Flushing,
+    Offline,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionState {
+    pub status: ConnectionStatus,
+    pub queued: usize,
+    /// Messages thrown away because the offline queue was full.
+    pub dropped: usize,
+}
+
+impl ConnectionState {
+    pub fn new(status: ConnectionStatus, queue: &MessageQueue) -> Self {
+        Self {
+            status,
+            queued: queue.len(),
+            dropped: queue.dropped(),
+        }
+    }
+}
+
+impl Default for ConnectionState {
+    fn default() -> Self {
+        Self {
+            status: ConnectionStatus::Connecting,
+            queued: 0,
+            dropped: 0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WebsocketConfig {
+    /// Deflate large outgoing frames. Compressed incoming frames are always
+    /// understood, so only turn this on for servers that accept them too.
+    pub compress: bool,
+}
+
+impl WebsocketConfig {
+    /// `?compress=deflate` turns on compression.
+    pub fn from_query() -> Self {
+        Self {
+            compress: query_has("compress=deflate"),
+        }
+    }
+}
 
 pub struct WebsocketService {
     pub tx: Sender<String>,
 }
 
 impl WebsocketService {
-    pub fn new() -> Self {
-        let ws = WebSocket::open("ws://127.0.0.1:8080").unwrap();
-
-        let (mut write, mut read) = ws.split();
+    /// Connects to the chat server and keeps reconnecting until the service is
+    /// dropped. `handshake` is sent first on every (re)connect so the server
+    /// knows who we are; anything sent while offline is queued and flushed
+    /// after it.
+    pub fn new(
+        handshake: String,
+        config: WebsocketConfig,
+        on_state: Callback<ConnectionState>,
+    ) -> Self {
+        let (in_tx, in_rx) = futures::channel::mpsc::channel::<String>(1000);
+        spawn_local(run(handshake, config, in_rx, on_state));
+        Self { tx: in_tx }
+    }
+}
 
-        let (in_tx, mut in_rx) = futures::channel::mpsc::channel::<String>(1000);
-        let mut event_bus = EventBus::dispatcher();
+/// Wraps an outgoing payload, deflating it when that's on and it helps.
+fn encode(payload: String, config: WebsocketConfig) -> Message {
+    if config.compress && payload.len() >= COMPRESS_MIN_BYTES {
+        let mut frame = COMPRESSED_MAGIC.to_vec();
+        frame.extend(deflate::compress(payload.as_bytes()));
+        if frame.len() < payload.len() {
+            return Message::Bytes(frame);
+        }
+    }
+    Message::Text(payload)
+}
 
-        spawn_local(async move {
-            while let Some(s) = in_rx.next().await {
-                log::debug!("got event from channel! {}", s);
-                write.send(Message::Text(s)).await.unwrap();
+/// Reads a binary frame, inflating it if it carries the magic prefix.
+fn decode(frame: &[u8]) -> Option<String> {
+    let bytes = match frame.strip_prefix(COMPRESSED_MAGIC) {
+        Some(compressed) => match deflate::decompress(compressed) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::error!("ws: {}", e);
+                return None;
             }
-        });
-
-        spawn_local(async move {
-            while let Some(msg) = read.next().await {
-                match msg {
-                    Ok(Message::Text(data)) => {
-                        log::debug!("from websocket: {}", data);
-                        event_bus.send(Request::EventBusMsg(data));
+        },
+        None => frame.to_vec(),
+    };
+    String::from_utf8(bytes).ok()
+}
+
+async fn run(
+    handshake: String,
+    config: WebsocketConfig,
+    mut in_rx: Receiver<String>,
+    on_state: Callback<ConnectionState>,
+) {
+    let mut event_bus = EventBus::dispatcher();
+    let mut queue = MessageQueue::new(QUEUE_CAPACITY);
+    let mut delay = RECONNECT_MIN_MS;
+    let report = |status: ConnectionStatus, queue: &MessageQueue| {
+        on_state.emit(ConnectionState::new(status, queue))
+    };
+
+    loop {
+        report(ConnectionStatus::Connecting, &queue);
+        // A socket that can't even be opened is retried like one that
+        // closed, so queued messages wait rather than being lost.
+        match WebSocket::open(SERVER_URL) {
+            Ok(ws) => {
+                let (mut write, read) = ws.split();
+                let mut read = read.fuse();
+
+                // The sink only becomes ready once the socket opens, and never wakes
+                // up if opening fails, so race the handshake against the close event.
+                let opened = select! {
+                    sent = write.send(encode(handshake.clone(), config)).fuse() => sent.is_ok(),
+                    _ = read.next() => false,
+                };
+
+                if opened {
+                    delay = RECONNECT_MIN_MS;
+                    if !queue.is_empty() {
+                        report(ConnectionStatus::Flushing, &queue);
                     }
-                    Ok(Message::Bytes(b)) => {
-                        let decoded = std::str::from_utf8(&b);
-                        if let Ok(val) = decoded {
-                            log::debug!("from websocket: {}", val);
-                            event_bus.send(Request::EventBusMsg(val.into()));
+                    let mut flushed = true;
+                    while let Some(s) = queue.pop() {
+                        if write.send(encode(s.clone(), config)).await.is_err() {
+                            queue.requeue(s);
+                            flushed = false;
+                            break;
                         }
                     }
-                    Err(e) => {
-                        log::error!("ws: {:?}", e)
+
+                    if flushed {
+                        queue.reset_dropped();
+                        report(ConnectionStatus::Online, &queue);
+                        loop {
+                            select! {
+                                msg = read.next() => match msg {
+                                    Some(Ok(Message::Text(data))) => {
+                                        log::debug!("from websocket: {}", data);
+                                        event_bus.send(Request::EventBusMsg(data));
+                                    }
+                                    Some(Ok(Message::Bytes(b))) => {
+                                        if let Some(val) = decode(&b) {
+                                            log::debug!("from websocket: {}", val);
+                                            event_bus.send(Request::EventBusMsg(val));
+                                        }
+                                    }
+                                    Some(Err(e)) => {
+                                        log::error!("ws: {:?}", e)
+                                    }
+                                    None => break,
+                                },
+                                out = in_rx.next() => match out {
+                                    Some(s) => {
+                                        log::debug!("got event from channel! {}", s);
+                                        if write.send(encode(s.clone(), config)).await.is_err() {
+                                            queue.push(s);
+                                            break;
+                                        }
+                                    }
+                                    None => return,
+                                },
+                            }
+                        }
                     }
                 }
             }
-            log::debug!("WebSocket Closed");
-        });
+            Err(e) => log::error!("ws: {:?}", e),
+        }
+        log::debug!("WebSocket Closed");
 
-        Self { tx: in_tx }
+        // Keep accepting messages while waiting to reconnect.
+        report(ConnectionStatus::Offline, &queue);
+        let mut retry = TimeoutFuture::new(delay).fuse();
+        loop {
+            select! {
+                _ = retry => break,
+                out = in_rx.next() => match out {
+                    Some(s) => {
+                        queue.push(s);
+                        report(ConnectionStatus::Offline, &queue);
+                    }
+                    None => return,
+                },
+            }
+        }
+        delay = (delay * 2).min(RECONNECT_MAX_MS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(count: usize) -> String {
+        let messages: Vec<String> = (0..count)
+            .map(|i| {
+                format!(
+                    r#"{{"from":"user{}","message":"message number {}","timestamp":{}}}"#,
+                    i % 20,
+                    i,
+                    1_700_000_000_000_u64 + i as u64 * 1_000
+                )
+            })
+            .collect();
+        format!("[{}]", messages.join(","))
+    }
+
+    const ON: WebsocketConfig = WebsocketConfig { compress: true };
+
+    #[test]
+    fn large_frames_round_trip_compressed() {
+        let payload = history(200);
+        let Message::Bytes(frame) = encode(payload.clone(), ON) else {
+            panic!("expected a compressed frame");
+        };
+        assert!(frame.starts_with(COMPRESSED_MAGIC));
+        assert!(frame.len() < payload.len() / 3, "{}", frame.len());
+        assert_eq!(decode(&frame), Some(payload));
+    }
+
+    #[test]
+    fn small_or_disabled_frames_stay_text() {
+        let payload = history(200);
+        assert_eq!(
+            encode(payload.clone(), WebsocketConfig::default()),
+            Message::Text(payload)
+        );
+        assert_eq!(encode("hi".into(), ON), Message::Text("hi".into()));
+    }
+
+    #[test]
+    fn plain_binary_frames_still_decode() {
+        assert_eq!(decode(b"hello"), Some("hello".into()));
+        let mut corrupt = COMPRESSED_MAGIC.to_vec();
+        corrupt.push(0xff);
+        assert_eq!(decode(&corrupt), None);
+    }
+
+    /// Throughput of the compressed path against plain frames. Not a
+    /// correctness check; run with
+    /// `cargo test --release -- --ignored --nocapture compression_throughput`.
+    #[test]
+    #[ignore]
+    fn compression_throughput() {
+        use std::time::Instant;
+
+        for count in [10, 100, 1_000] {
+            let payload = history(count);
+            let rounds = 2_000_000 / payload.len() + 1;
+            let bytes = (payload.len() * rounds) as f64 / 1e6;
+            for (label, config) in [("plain", WebsocketConfig::default()), ("deflate", ON)] {
+                let start = Instant::now();
+                let mut size = 0;
+                for _ in 0..rounds {
+                    let frame = match encode(payload.clone(), config) {
+                        Message::Text(text) => text.into_bytes(),
+                        Message::Bytes(bytes) => bytes,
+                    };
+                    size = frame.len();
+                    assert!(decode(&frame).is_some());
+                }
+                let secs = start.elapsed().as_secs_f64();
+                println!(
+                    "{:>5} messages, {:<7}: {:>7} bytes/frame, {:>8.1} MB/s",
+                    count,
+                    label,
+                    size,
+                    bytes / secs
+                );
+            }
+        }
     }
 }
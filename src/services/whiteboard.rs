@@ -0,0 +1,113 @@
+use crate::services::message::WhiteboardStroke;
+
+/// The canvas is drawn in these units whatever its size on screen, so
+/// strokes land in the same place for everyone.
+pub const WIDTH: f64 = 400.0;
+pub const HEIGHT: f64 = 240.0;
+
+/// The strokes on one whiteboard, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Board {
+    strokes: Vec<WhiteboardStroke>,
+}
+
+impl Board {
+    pub fn strokes(&self) -> impl Iterator<Item = &WhiteboardStroke> {
+        self.strokes.iter()
+    }
+
+    /// Draws or erases a stroke. We apply our own strokes as we draw them,
+    /// so the server's echo is a repeat; returns whether anything changed.
+    pub fn apply(&mut self, stroke: WhiteboardStroke) -> bool {
+        let existing = self
+            .strokes
+            .iter()
+            .position(|s| s.from == stroke.from && s.id == stroke.id);
+        match (existing, stroke.erase) {
+            (Some(i), true) => {
+                self.strokes.remove(i);
+                true
+            }
+            (None, false) => {
+                self.strokes.push(stroke);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `from`'s newest stroke still on the board, for undo.
+    pub fn last_by(&self, from: &str) -> Option<&WhiteboardStroke> {
+        self.strokes.iter().rev().find(|s| s.from == from)
+    }
+}
+
+/// An SVG path through `points`, to a tenth of a unit.
+pub fn path_data(points: &[(f64, f64)]) -> String {
+    let mut d = String::new();
+    for (i, (x, y)) in points.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        if i > 0 {
+            d.push(' ');
+        }
+        d.push_str(&format!("{}{} {}", command, round(*x), round(*y)));
+    }
+    d
+}
+
+fn round(n: f64) -> f64 {
+    (n * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(from: &str, id: u32, erase: bool) -> WhiteboardStroke {
+        WhiteboardStroke {
+            board: "b".into(),
+            from: from.into(),
+            id,
+            d: "M0 0 L1 1".into(),
+            erase,
+        }
+    }
+
+    #[test]
+    fn echoes_are_ignored() {
+        let mut board = Board::default();
+        assert!(board.apply(stroke("alice", 0, false)));
+        assert!(!board.apply(stroke("alice", 0, false)));
+        assert!(board.apply(stroke("bob", 0, false)));
+        assert_eq!(board.strokes().count(), 2);
+    }
+
+    #[test]
+    fn undo_erases_the_newest_own_stroke() {
+        let mut board = Board::default();
+        board.apply(stroke("alice", 0, false));
+        board.apply(stroke("alice", 1, false));
+        board.apply(stroke("bob", 0, false));
+        assert_eq!(board.last_by("alice").map(|s| s.id), Some(1));
+
+        assert!(board.apply(stroke("alice", 1, true)));
+        assert!(!board.apply(stroke("alice", 1, true)));
+        assert_eq!(board.last_by("alice").map(|s| s.id), Some(0));
+        assert_eq!(board.last_by("carol"), None);
+    }
+
+    #[test]
+    fn paths_are_rounded() {
+        assert_eq!(
+            path_data(&[(1.0, 2.04), (3.25, 4.0), (5.0, 6.0)]),
+            "M1 2 L3.3 4 L5 6"
+        );
+        assert_eq!(path_data(&[]), "");
+    }
+
+    #[test]
+    fn stroke_wire_format() {
+        let json = serde_json::to_string(&stroke("", 3, true)).unwrap();
+        assert_eq!(json, r#"{"board":"b","id":3,"d":"M0 0 L1 1","erase":true}"#);
+    }
+}
@@ -0,0 +1,447 @@
+//! Hand-written lexers for highlighting fenced code blocks in messages.
+//!
+//! Only `core` is used, so everything here borrows from the input and can be
+//! unit-tested natively without a browser.
+
+use core::str::CharIndices;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Rust,
+    JavaScript,
+    Python,
+}
+
+impl Language {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "rust" | "rs" => Some(Language::Rust),
+            "javascript" | "js" => Some(Language::JavaScript),
+            "python" | "py" => Some(Language::Python),
+            _ => None,
+        }
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+                "enum", "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+                "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct",
+                "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+            ],
+            Language::JavaScript => &[
+                "async",
+                "await",
+                "break",
+                "case",
+                "catch",
+                "class",
+                "const",
+                "continue",
+                "default",
+                "delete",
+                "do",
+                "else",
+                "export",
+                "extends",
+                "false",
+                "finally",
+                "for",
+                "function",
+                "if",
+                "import",
+                "in",
+                "instanceof",
+                "let",
+                "new",
+                "null",
+                "return",
+                "super",
+                "switch",
+                "this",
+                "throw",
+                "true",
+                "try",
+                "typeof",
+                "undefined",
+                "var",
+                "void",
+                "while",
+                "yield",
+            ],
+            Language::Python => &[
+                "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+                "continue", "def", "del", "elif", "else", "except", "finally", "for", "from",
+                "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass",
+                "raise", "return", "try", "while", "with", "yield",
+            ],
+        }
+    }
+
+    fn line_comment(&self) -> &'static str {
+        match self {
+            Language::Rust | Language::JavaScript => "//",
+            Language::Python => "#",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Plain,
+}
+
+impl TokenKind {
+    /// CSS class for the token, or `None` for text that is left unstyled.
+    pub fn class(&self) -> Option<&'static str> {
+        match self {
+            TokenKind::Keyword => Some("token-keyword"),
+            TokenKind::String => Some("token-string"),
+            TokenKind::Number => Some("token-number"),
+            TokenKind::Comment => Some("token-comment"),
+            TokenKind::Plain => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+/// Splits `source` into tokens. Concatenating every token's text gives back
+/// the original source.
+pub fn tokenize(language: Language, source: &str) -> Tokens<'_> {
+    Tokens {
+        language,
+        source,
+        pos: 0,
+    }
+}
+
+pub struct Tokens<'a> {
+    language: Language,
+    source: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = &self.source[self.pos..];
+        let first = rest.chars().next()?;
+
+        let (kind, len) = if rest.starts_with(self.language.line_comment()) {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if self.language != Language::Python && rest.starts_with("/*") {
+            let len = rest[2..].find("*/").map_or(rest.len(), |i| i + 4);
+            (TokenKind::Comment, len)
+        } else if let Some(len) = self.string_len(rest) {
+            (TokenKind::String, len)
+        } else if first.is_ascii_digit() {
+            let len = end_of(rest.char_indices(), |c| {
+                c.is_ascii_alphanumeric() || c == '_' || c == '.'
+            });
+            (TokenKind::Number, len)
+        } else if first.is_alphabetic() || first == '_' {
+            let len = end_of(rest.char_indices(), |c| c.is_alphanumeric() || c == '_');
+            let kind = if self.language.keywords().contains(&&rest[..len]) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Plain
+            };
+            (kind, len)
+        } else {
+            (TokenKind::Plain, first.len_utf8())
+        };
+
+        self.pos += len;
+        Some(Token {
+            kind,
+            text: &rest[..len],
+        })
+    }
+}
+
+impl<'a> Tokens<'a> {
+    /// Length of the string literal at the start of `rest`, if there is one.
+    fn string_len(&self, rest: &str) -> Option<usize> {
+        if self.language == Language::Python {
+            for quote in ["\"\"\"", "'''"] {
+                if let Some(body) = rest.strip_prefix(quote) {
+                    return Some(body.find(quote).map_or(rest.len(), |i| i + 6));
+                }
+            }
+        }
+
+        let quote = rest.chars().next()?;
+        let is_quote = match self.language {
+            Language::Rust => quote == '"' || is_rust_char_literal(rest),
+            Language::JavaScript => quote == '"' || quote == '\'' || quote == '`',
+            Language::Python => quote == '"' || quote == '\'',
+        };
+        if !is_quote {
+            return None;
+        }
+
+        let multiline = quote == '`' || (self.language == Language::Rust && quote == '"');
+        let mut escaped = false;
+        for (i, c) in rest.char_indices().skip(1) {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                return Some(i + 1);
+            } else if c == '\n' && !multiline {
+                return Some(i);
+            }
+        }
+        Some(rest.len())
+    }
+}
+
+/// Tells `'a'` and `'\n'` apart from lifetimes such as `'a`.
+fn is_rust_char_literal(rest: &str) -> bool {
+    let mut chars = rest.chars();
+    matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some('\''), Some('\\'), _) | (Some('\''), Some(_), Some('\''))
+    )
+}
+
+fn end_of(chars: CharIndices<'_>, mut keep: impl FnMut(char) -> bool) -> usize {
+    let mut end = 0;
+    for (i, c) in chars {
+        if i > 0 && !keep(c) {
+            return i;
+        }
+        end = i + c.len_utf8();
+    }
+    end
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Code {
+        language: Option<Language>,
+        code: &'a str,
+    },
+}
+
+/// Splits a message into plain text and ```` ``` ```` fenced code blocks. The
+/// language tag is whatever follows the opening fence on its line; for a
+/// one-line block it is the first word, but only if it names a known
+/// language.
+pub fn segments(message: &str) -> Segments<'_> {
+    Segments { rest: message }
+}
+
+pub struct Segments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let open = match self.rest.find("```") {
+            Some(0) => 0,
+            Some(i) => {
+                let text = &self.rest[..i];
+                self.rest = &self.rest[i..];
+                return Some(Segment::Text(text));
+            }
+            None => {
+                let text = self.rest;
+                self.rest = "";
+                return Some(Segment::Text(text));
+            }
+        };
+
+        let body = &self.rest[open + 3..];
+        let close = match body.find("```") {
+            Some(close) => close,
+            None => {
+                // An unterminated fence is just text.
+                let text = self.rest;
+                self.rest = "";
+                return Some(Segment::Text(text));
+            }
+        };
+        let inner = &body[..close];
+        self.rest = &body[close + 3..];
+
+        let (language, code) = match inner.find('\n') {
+            Some(newline) => (
+                Language::from_tag(inner[..newline].trim()),
+                &inner[newline + 1..],
+            ),
+            None => {
+                let trimmed = inner.trim_start();
+                match trimmed.split_once(char::is_whitespace) {
+                    Some((tag, code)) if Language::from_tag(tag).is_some() => {
+                        (Language::from_tag(tag), code)
+                    }
+                    _ => (None, inner),
+                }
+            }
+        };
+        Some(Segment::Code { language, code })
+    }
+}
+
+/// The start of a message holding at most `limit` characters of text,
+/// cut back to a word break, or `None` if it is no longer than that. Code
+/// blocks don't count towards the limit and are never cut.
+pub fn truncate_prose(message: &str, limit: usize) -> Option<&str> {
+    let mut count = 0;
+    for segment in segments(message) {
+        let Segment::Text(text) = segment else {
+            continue;
+        };
+        let start = text.as_ptr() as usize - message.as_ptr() as usize;
+        let Some((cut, _)) = text.char_indices().nth(limit - count) else {
+            count += text.chars().count();
+            continue;
+        };
+        let at_break = text[cut..].starts_with(char::is_whitespace);
+        let cut = text[..cut]
+            .rfind(char::is_whitespace)
+            .filter(|&space| !at_break && start + space > 0)
+            .unwrap_or(cut);
+        return Some(message[..start + cut].trim_end());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn kinds(language: Language, source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(language, source)
+            .filter(|t| t.kind != TokenKind::Plain)
+            .map(|t| (t.kind, t.text))
+            .collect()
+    }
+
+    #[test]
+    fn tokens_cover_the_source() {
+        let source = "fn main() { let s = \"hi\"; // done\n}";
+        let joined: String = tokenize(Language::Rust, source).map(|t| t.text).collect();
+        assert_eq!(joined, source);
+    }
+
+    #[test]
+    fn lexes_rust() {
+        assert_eq!(
+            kinds(Language::Rust, "let x: &'a str = \"a\\\"b\"; /* c */ 42"),
+            [
+                (TokenKind::Keyword, "let"),
+                (TokenKind::String, "\"a\\\"b\""),
+                (TokenKind::Comment, "/* c */"),
+                (TokenKind::Number, "42"),
+            ]
+        );
+        assert_eq!(
+            kinds(Language::Rust, "'x' '\\n'"),
+            [(TokenKind::String, "'x'"), (TokenKind::String, "'\\n'")]
+        );
+    }
+
+    #[test]
+    fn lexes_javascript() {
+        assert_eq!(
+            kinds(Language::JavaScript, "const s = `hi ${x}`; // hey"),
+            [
+                (TokenKind::Keyword, "const"),
+                (TokenKind::String, "`hi ${x}`"),
+                (TokenKind::Comment, "// hey"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_python() {
+        assert_eq!(
+            kinds(Language::Python, "def f(): return '''doc''' # 1.5"),
+            [
+                (TokenKind::Keyword, "def"),
+                (TokenKind::Keyword, "return"),
+                (TokenKind::String, "'''doc'''"),
+                (TokenKind::Comment, "# 1.5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_fenced_blocks() {
+        let segs: Vec<_> = segments("look: ```rust\nfn x() {}``` and ```py pass``` ok").collect();
+        assert_eq!(
+            segs,
+            [
+                Segment::Text("look: "),
+                Segment::Code {
+                    language: Some(Language::Rust),
+                    code: "fn x() {}"
+                },
+                Segment::Text(" and "),
+                Segment::Code {
+                    language: Some(Language::Python),
+                    code: "pass"
+                },
+                Segment::Text(" ok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_and_unterminated_fences() {
+        assert_eq!(
+            segments("```let x```").collect::<Vec<_>>(),
+            [Segment::Code {
+                language: None,
+                code: "let x"
+            }]
+        );
+        assert_eq!(
+            segments("```oops").collect::<Vec<_>>(),
+            [Segment::Text("```oops")]
+        );
+    }
+
+    #[test]
+    fn truncates_prose_at_a_word_break() {
+        assert_eq!(truncate_prose("short", 10), None);
+        assert_eq!(truncate_prose("one two three", 9), Some("one two"));
+        assert_eq!(truncate_prose("abcdefghij", 4), Some("abcd"));
+    }
+
+    #[test]
+    fn code_blocks_are_not_counted() {
+        let message = "see ```\nlong code here\n``` and then more";
+        assert_eq!(truncate_prose(message, 18), None);
+        assert_eq!(
+            truncate_prose(message, 8),
+            Some("see ```\nlong code here\n``` and")
+        );
+    }
+}
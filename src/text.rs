@@ -0,0 +1,23 @@
+//! Small helpers for showing user text in a limited space.
+
+/// `text` cut to `max` characters, with an ellipsis if anything was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cuts_at_a_character_and_trims_before_the_ellipsis() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("ünïcödé text", 7), "ünïcödé…");
+        assert_eq!(truncate("cut here please", 9), "cut here…");
+        assert_eq!(truncate("ab cd", 3), "ab…");
+        assert_eq!(truncate("☕☕☕☕", 2), "☕☕…");
+    }
+}